@@ -33,5 +33,9 @@ export const MOCK_APP_SETTINGS: AppSettings = {
 	},
 	reviews: {
 		autoFillPrDescriptionFromCommit: true
+	},
+	hunkDependencies: {
+		algorithm: 'ranges',
+		compare: false
 	}
 };
@@ -67,6 +67,10 @@ export class SettingsService {
 		await this.backend.invoke('update_fetch', { update });
 	}
 
+	async updateHunkDependencies(update: Partial<HunkDependencies>) {
+		await this.backend.invoke('update_hunk_dependencies', { update });
+	}
+
 	private async nudge(): Promise<void> {
 		await this.autoOptInWs3();
 		await this.autoOptInRules();
@@ -154,6 +158,8 @@ export type AppSettings = {
 	claude: Claude;
 	/** Settings related to code reviews and pull requests */
 	reviews: Reviews;
+	/** Settings related to the calculation of hunk dependencies */
+	hunkDependencies: HunkDependencies;
 };
 
 export type TelemetrySettings = {
@@ -206,3 +212,10 @@ export type Reviews = {
 	/** Whether to auto-fill PR title and description from the first commit when a branch has only one commit. */
 	autoFillPrDescriptionFromCommit: boolean;
 };
+
+export type HunkDependencies = {
+	/** The algorithm to calculate which commits the uncommitted changes depend on. */
+	algorithm: 'ranges' | 'lines';
+	/** Whether to also calculate the dependencies with the other algorithm, and log where they disagree. */
+	compare: boolean;
+};
@@ -112,6 +112,20 @@ pub fn absorb(
     Ok(())
 }
 
+/// Absorb those of `changes` that are locked to a single commit into that commit, without involving an LLM.
+///
+/// Changes that aren't locked, or locked to multiple commits, are left as they are.
+pub fn absorb_locked(
+    emitter: std::sync::Arc<Emitter>,
+    ctx: &mut CommandContext,
+    changes: Vec<but_core::TreeChange>,
+) -> anyhow::Result<()> {
+    let repo = ctx.gix_repo()?;
+    let paths = changes.into_iter().map(|change| change.path).collect();
+    let project_status = but_tools::workspace::get_project_status(ctx, &repo, Some(paths))?;
+    absorb_locked_changes(emitter, ctx, &project_status).context("Failed to absorb locked changes")
+}
+
 struct AbsorbGroup {
     commit_id: gix::ObjectId,
     stack_id: StackId,
@@ -25,6 +25,7 @@ pub mod cli;
 mod generate;
 mod grouping;
 mod openai;
+mod prompt;
 pub mod rename_branch;
 pub mod reword;
 mod simple;
@@ -152,6 +153,26 @@ pub fn absorb(
     absorb::absorb(emitter, ctx, openai, changes)
 }
 
+/// Absorb those of `changes` that are locked to a single commit into that commit, without the help of an LLM.
+pub fn absorb_locked(
+    emitter: Arc<Emitter>,
+    ctx: &mut CommandContext,
+    changes: Vec<TreeChange>,
+) -> anyhow::Result<()> {
+    absorb::absorb_locked(emitter, ctx, changes)
+}
+
+/// Perform what `prompt` asks for with `changes`, using the workspace tools, and return the response of the LLM.
+pub fn prompt(
+    emitter: Arc<Emitter>,
+    ctx: &mut CommandContext,
+    openai: &OpenAiProvider,
+    prompt: &str,
+    changes: Vec<TreeChange>,
+) -> anyhow::Result<String> {
+    prompt::prompt(emitter, ctx, openai, prompt, changes)
+}
+
 pub fn branch_changes(
     emitter: Arc<Emitter>,
     ctx: &mut CommandContext,
@@ -0,0 +1,65 @@
+use anyhow::Context;
+use but_tools::{emit::Emitter, workspace::workspace_toolset};
+use gitbutler_command_context::CommandContext;
+
+use crate::OpenAiProvider;
+
+pub fn prompt(
+    emitter: std::sync::Arc<Emitter>,
+    ctx: &mut CommandContext,
+    openai: &OpenAiProvider,
+    prompt: &str,
+    changes: Vec<but_core::TreeChange>,
+) -> anyhow::Result<String> {
+    let repo = ctx.gix_repo()?;
+
+    let paths = changes
+        .iter()
+        .map(|change| change.path.clone())
+        .collect::<Vec<_>>();
+    let path_strings = paths.iter().map(|p| p.to_string()).collect::<Vec<String>>();
+    let path_strings = path_strings.join("\n");
+
+    let project_status = but_tools::workspace::get_project_status(ctx, &repo, Some(paths))?;
+    let serialized_status = serde_json::to_string_pretty(&project_status)
+        .context("Failed to serialize project status")?;
+
+    let mut toolset = workspace_toolset(ctx, emitter, uuid::Uuid::new_v4().to_string());
+
+    let system_message ="
+        You are a GitButler agent that performs actions on a Git project on behalf of the user, without being able to ask them anything.
+        Use the tools provided to you to perform what the user asks for, and respond with a short summary of the actions you've taken.
+        If the instructions are unclear or can't be performed with the given file changes, don't do anything.
+        ";
+
+    let prompt = format!(
+        "
+        Please, perform the following instructions with the file changes listed below.
+        <instructions>
+                {prompt}
+        </instructions>
+
+        <important_note>
+            Only operate on the file changes listed below.
+        </important_note>
+
+        Here are the file changes:
+        <file_changes>
+                {path_strings}
+        </file_changes>
+
+        Here is the project status:
+        <project_status>
+                {serialized_status}
+        </project_status>
+    "
+    );
+
+    crate::openai::tool_calling_loop(
+        openai,
+        system_message,
+        vec![prompt.into()],
+        &mut toolset,
+        None,
+    )
+}
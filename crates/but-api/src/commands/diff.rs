@@ -6,10 +6,14 @@ use but_core::{
     Commit,
     commit::ConflictEntries,
     ui::{TreeChange, TreeChanges},
+    unified_diff::{
+        semantic::HunkKind,
+        word_diff::{Granularity, WordDiff},
+    },
 };
 use but_hunk_assignment::{AssignmentRejection, HunkAssignmentRequest, WorktreeChanges};
 use but_hunk_dependency::ui::{
-    HunkDependencies, hunk_dependencies_for_workspace_changes_by_worktree_dir,
+    HunkDependencies, HunkLockExplanation, hunk_dependencies_for_workspace_changes_by_worktree_dir,
 };
 use but_settings::AppSettings;
 use but_workspace::StackId;
@@ -17,23 +21,81 @@ use gitbutler_command_context::CommandContext;
 use gitbutler_project::ProjectId;
 use gitbutler_reference::Refname;
 use gix::refs::Category;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use tracing::instrument;
 
 /// Provide a unified diff for `change`, but fail if `change` is a [type-change](but_core::ModeFlags::TypeChange)
 /// or if it involves a change to a [submodule](gix::object::Kind::Commit).
+///
+/// If `analysis` is given, each hunk of the diff is also analysed accordingly.
 #[api_cmd]
 #[tauri::command(async)]
 #[instrument(err(Debug))]
 pub fn tree_change_diffs(
     project_id: ProjectId,
     change: TreeChange,
-) -> anyhow::Result<Option<but_core::UnifiedDiff>, Error> {
+    analysis: Option<HunkAnalysisOptions>,
+) -> anyhow::Result<Option<TreeChangeDiff>, Error> {
     let change: but_core::TreeChange = change.into();
     let project = gitbutler_project::get(project_id)?;
     let app_settings = AppSettings::load_from_default_path_creating()?;
     let repo = gix::open(project.path).map_err(anyhow::Error::from)?;
-    Ok(change.unified_diff(&repo, app_settings.context_lines)?)
+    let Some(diff) = change.unified_diff(&repo, app_settings.context_lines)? else {
+        return Ok(None);
+    };
+    let hunks_analysis = match (&diff, analysis) {
+        (but_core::UnifiedDiff::Patch { hunks, .. }, Some(options)) => Some(
+            hunks
+                .iter()
+                .map(|hunk| HunkAnalysis {
+                    kind: options
+                        .semantic
+                        .then(|| hunk.semantic_kind(change.path.as_bstr())),
+                    word_diff: options
+                        .word_diff
+                        .map(|granularity| hunk.word_diff(granularity)),
+                })
+                .collect(),
+        ),
+        _ => None,
+    };
+    Ok(Some(TreeChangeDiff {
+        diff,
+        hunks_analysis,
+    }))
+}
+
+/// What to compute for each hunk of the diff returned by [`tree_change_diffs()`].
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HunkAnalysisOptions {
+    /// If set, compare the changed lines of each hunk with this granularity.
+    #[serde(default)]
+    pub word_diff: Option<Granularity>,
+    /// If `true`, classify each hunk by what its changes amount to.
+    #[serde(default)]
+    pub semantic: bool,
+}
+
+/// A unified diff, along with the analysis of each of its hunks if it was requested.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeChangeDiff {
+    #[serde(flatten)]
+    pub diff: but_core::UnifiedDiff,
+    /// The analysis of each hunk of the patch, in the same order.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hunks_analysis: Option<Vec<HunkAnalysis>>,
+}
+
+/// Information about a hunk that isn't visible in its unified diff.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HunkAnalysis {
+    /// What the changes of the hunk amount to, if requested.
+    pub kind: Option<HunkKind>,
+    /// The parts of the changed lines that actually changed, if requested.
+    pub word_diff: Option<Vec<WordDiff>>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -124,6 +186,7 @@ pub fn changes_in_worktree(project_id: ProjectId) -> anyhow::Result<WorktreeChan
         &ctx.project().path,
         &ctx.project().gb_dir(),
         Some(changes.changes.clone()),
+        ctx.app_settings().hunk_dependencies.into(),
     );
 
     // If the dependencies calculation failed, we still want to try to get assignments
@@ -164,6 +227,27 @@ pub fn changes_in_worktree(project_id: ProjectId) -> anyhow::Result<WorktreeChan
     })
 }
 
+/// Explain why each hunk of the uncommitted changes to the file at `path` is locked to a stack, by listing the commits
+/// whose changes intersect with it, along with the commit it could be amended into.
+#[api_cmd]
+#[tauri::command(async)]
+#[instrument(err(Debug))]
+pub fn explain_hunk_locks(
+    project_id: ProjectId,
+    path: String,
+) -> anyhow::Result<Vec<HunkLockExplanation>, Error> {
+    let project = gitbutler_project::get(project_id)?;
+    let ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
+    let explanations = but_hunk_dependency::ui::explain_hunk_locks(
+        &ctx,
+        &ctx.project().path,
+        &ctx.project().gb_dir(),
+        path.as_str().into(),
+        ctx.app_settings().hunk_dependencies.into(),
+    )?;
+    Ok(explanations)
+}
+
 #[api_cmd]
 #[tauri::command(async)]
 #[instrument(err(Debug))]
@@ -1,4 +1,6 @@
 //! In place of commands.rs
+use std::path::PathBuf;
+
 use anyhow::Context;
 use but_api_macros::api_cmd;
 use but_core::ui::TreeChange;
@@ -6,6 +8,7 @@ use but_settings::AppSettings;
 use but_workspace::StackId;
 use gitbutler_command_context::CommandContext;
 use gitbutler_edit_mode::ConflictEntryPresence;
+use gitbutler_edit_mode::resolve::{ConflictSide, ConflictedPath};
 use gitbutler_operating_modes::{EditModeMetadata, OperatingMode};
 use gitbutler_project::ProjectId;
 use tracing::instrument;
@@ -53,9 +56,9 @@ pub fn abort_edit_and_return_to_workspace(project_id: ProjectId) -> Result<(), E
 #[instrument(err(Debug))]
 pub fn save_edit_and_return_to_workspace(project_id: ProjectId) -> Result<(), Error> {
     let project = gitbutler_project::get(project_id)?;
-    let ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
+    let mut ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
 
-    gitbutler_edit_mode::commands::save_and_return_to_workspace(&ctx)?;
+    gitbutler_edit_mode::commands::save_and_return_to_workspace(&mut ctx)?;
 
     Ok(())
 }
@@ -81,3 +84,57 @@ pub fn edit_changes_from_initial(project_id: ProjectId) -> Result<Vec<TreeChange
 
     gitbutler_edit_mode::commands::changes_from_initial(&ctx).map_err(Into::into)
 }
+
+#[api_cmd]
+#[tauri::command(async)]
+#[instrument(err(Debug))]
+pub fn edit_conflicted_paths(project_id: ProjectId) -> Result<Vec<ConflictedPath>, Error> {
+    let project = gitbutler_project::get(project_id)?;
+    let ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
+
+    gitbutler_edit_mode::commands::conflicted_paths(&ctx).map_err(Into::into)
+}
+
+#[api_cmd]
+#[tauri::command(async)]
+#[instrument(err(Debug))]
+pub fn edit_take_conflict_side(
+    project_id: ProjectId,
+    path: PathBuf,
+    side: ConflictSide,
+    hunk: Option<usize>,
+) -> Result<(), Error> {
+    let project = gitbutler_project::get(project_id)?;
+    let ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
+
+    gitbutler_edit_mode::commands::take_conflict_side(&ctx, &path, side, hunk)?;
+
+    Ok(())
+}
+
+#[api_cmd]
+#[tauri::command(async)]
+#[instrument(err(Debug))]
+pub fn edit_write_conflict_markers(project_id: ProjectId, path: PathBuf) -> Result<(), Error> {
+    let project = gitbutler_project::get(project_id)?;
+    let ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
+
+    gitbutler_edit_mode::commands::write_conflict_markers(&ctx, &path)?;
+
+    Ok(())
+}
+
+#[api_cmd]
+#[tauri::command(async)]
+#[instrument(err(Debug))]
+pub fn finish_edit_resolution_and_return_to_workspace(
+    project_id: ProjectId,
+    force: bool,
+) -> Result<(), Error> {
+    let project = gitbutler_project::get(project_id)?;
+    let mut ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
+
+    gitbutler_edit_mode::commands::finish_resolution_and_return_to_workspace(&mut ctx, force)?;
+
+    Ok(())
+}
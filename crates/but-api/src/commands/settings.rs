@@ -1,7 +1,8 @@
 //! In place of commands.rs
 use but_api_macros::api_cmd;
 use but_settings::api::{
-    ClaudeUpdate, FeatureFlagsUpdate, FetchUpdate, ReviewsUpdate, TelemetryUpdate,
+    ClaudeUpdate, FeatureFlagsUpdate, FetchUpdate, HunkDependenciesUpdate, ReviewsUpdate,
+    TelemetryUpdate,
 };
 use but_settings::{AppSettings, AppSettingsWithDiskSync};
 use serde::Deserialize;
@@ -121,3 +122,18 @@ pub fn update_fetch(
         .update_fetch(params.update)
         .map_err(|e| e.into())
 }
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateHunkDependenciesParams {
+    pub update: HunkDependenciesUpdate,
+}
+
+pub fn update_hunk_dependencies(
+    app_settings_sync: &AppSettingsWithDiskSync,
+    params: UpdateHunkDependenciesParams,
+) -> Result<(), Error> {
+    app_settings_sync
+        .update_hunk_dependencies(params.update)
+        .map_err(|e| e.into())
+}
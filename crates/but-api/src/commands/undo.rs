@@ -3,7 +3,7 @@ use but_api_macros::api_cmd;
 use but_settings::AppSettings;
 use gitbutler_command_context::CommandContext;
 use gitbutler_oplog::entry::OperationKind;
-use gitbutler_oplog::{OplogExt, entry::Snapshot};
+use gitbutler_oplog::{GcOutcome, OplogExt, SnapshotQuery, entry::Snapshot};
 use gitbutler_project::ProjectId;
 use tracing::instrument;
 
@@ -29,6 +29,20 @@ pub fn list_snapshots(
     Ok(snapshots)
 }
 
+/// Return up to `limit` snapshots matching `query`, with the most recent snapshot first.
+#[api_cmd]
+#[tauri::command(async)]
+#[instrument(err(Debug))]
+pub fn query_snapshots(
+    project_id: ProjectId,
+    query: SnapshotQuery,
+    limit: usize,
+) -> Result<Vec<Snapshot>, Error> {
+    let project = gitbutler_project::get(project_id).context("failed to get project")?;
+    let ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
+    Ok(ctx.query_snapshots(&query, limit)?)
+}
+
 #[api_cmd]
 #[tauri::command(async)]
 #[instrument(err(Debug))]
@@ -43,6 +57,41 @@ pub fn restore_snapshot(project_id: ProjectId, sha: String) -> Result<(), Error>
     Ok(())
 }
 
+/// Undo the most recent operation that wasn't undone yet, and return the snapshot that was restored to do so,
+/// or `None` if there is nothing left to undo.
+#[api_cmd]
+#[tauri::command(async)]
+#[instrument(err(Debug))]
+pub fn undo_operation(project_id: ProjectId) -> Result<Option<Snapshot>, Error> {
+    let project = gitbutler_project::get(project_id).context("failed to get project")?;
+    let ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
+    let mut guard = project.exclusive_worktree_access();
+    Ok(ctx.undo(guard.write_permission())?)
+}
+
+/// Revert the most recent undo that wasn't redone yet, and return the snapshot that was restored to do so,
+/// or `None` if there is nothing to redo.
+#[api_cmd]
+#[tauri::command(async)]
+#[instrument(err(Debug))]
+pub fn redo_operation(project_id: ProjectId) -> Result<Option<Snapshot>, Error> {
+    let project = gitbutler_project::get(project_id).context("failed to get project")?;
+    let ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
+    let mut guard = project.exclusive_worktree_access();
+    Ok(ctx.redo(guard.write_permission())?)
+}
+
+/// Remove all snapshots the retention policy of the project doesn't keep, and delete the objects of the removed snapshots.
+#[api_cmd]
+#[tauri::command(async)]
+#[instrument(err(Debug))]
+pub fn compact_oplog(project_id: ProjectId) -> Result<GcOutcome, Error> {
+    let project = gitbutler_project::get(project_id).context("failed to get project")?;
+    let ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
+    let mut guard = project.exclusive_worktree_access();
+    Ok(ctx.compact(&project.oplog_retention(), guard.write_permission())?)
+}
+
 #[api_cmd]
 #[tauri::command(async)]
 #[instrument(err(Debug))]
@@ -7,7 +7,7 @@ use gitbutler_branch::{BranchCreateRequest, BranchUpdateRequest};
 use gitbutler_branch_actions::branch_upstream_integration::IntegrationStrategy;
 use gitbutler_branch_actions::upstream_integration::{
     BaseBranchResolution, BaseBranchResolutionApproach, IntegrationOutcome, Resolution,
-    StackStatuses,
+    StackStatuses, UpstreamIntegrationDryRun,
 };
 use gitbutler_branch_actions::{
     BaseBranch, BranchListing, BranchListingDetails, BranchListingFilter, MoveBranchResult,
@@ -137,13 +137,14 @@ pub fn integrate_upstream_commits(
     integration_strategy: Option<IntegrationStrategy>,
 ) -> Result<(), Error> {
     let project = gitbutler_project::get(project_id)?;
-    let ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
+    let mut ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
     gitbutler_branch_actions::integrate_upstream_commits(
         &ctx,
         stack_id,
         series_name,
         integration_strategy,
     )?;
+    but_rules::process_recorded_events(&mut ctx);
     Ok(())
 }
 
@@ -519,6 +520,22 @@ pub fn update_commit_message(
     Ok(new_commit_id.to_string())
 }
 
+#[api_cmd]
+#[tauri::command(async)]
+#[instrument(err(Debug))]
+pub fn acknowledge_auto_resolution(
+    project_id: ProjectId,
+    stack_id: StackId,
+    commit_id: String,
+) -> Result<String, Error> {
+    let project = gitbutler_project::get(project_id)?;
+    let ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
+    let commit_id = git2::Oid::from_str(&commit_id).map_err(|e| anyhow!(e))?;
+    let new_commit_id =
+        gitbutler_branch_actions::acknowledge_auto_resolution(&ctx, stack_id, commit_id)?;
+    Ok(new_commit_id.to_string())
+}
+
 #[api_cmd]
 #[tauri::command(async)]
 #[instrument(err(Debug))]
@@ -540,12 +557,27 @@ pub fn upstream_integration_statuses(
     target_commit_id: Option<String>,
 ) -> Result<StackStatuses, Error> {
     let project = gitbutler_project::get(project_id)?;
-    let ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
+    let mut ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
     let commit_id = target_commit_id
         .map(|commit_id| git2::Oid::from_str(&commit_id).map_err(|e| anyhow!(e)))
         .transpose()?;
     Ok(gitbutler_branch_actions::upstream_integration_statuses(
-        &ctx, commit_id,
+        &mut ctx, commit_id,
+    )?)
+}
+
+#[api_cmd]
+#[tauri::command(async)]
+#[instrument(err(Debug))]
+pub fn upstream_integration_dry_run(
+    project_id: ProjectId,
+    base_branch_resolution: Option<BaseBranchResolution>,
+) -> Result<UpstreamIntegrationDryRun, Error> {
+    let project = gitbutler_project::get(project_id)?;
+    let mut ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
+    Ok(gitbutler_branch_actions::upstream_integration_dry_run(
+        &mut ctx,
+        base_branch_resolution,
     )?)
 }
 
@@ -558,9 +590,13 @@ pub fn integrate_upstream(
     base_branch_resolution: Option<BaseBranchResolution>,
 ) -> Result<IntegrationOutcome, Error> {
     let project = gitbutler_project::get(project_id)?;
-    let ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
-    let outcome =
-        gitbutler_branch_actions::integrate_upstream(&ctx, &resolutions, base_branch_resolution)?;
+    let mut ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
+    let outcome = gitbutler_branch_actions::integrate_upstream(
+        &mut ctx,
+        &resolutions,
+        base_branch_resolution,
+    )?;
+    but_rules::process_recorded_events(&mut ctx);
 
     Ok(outcome)
 }
@@ -573,10 +609,10 @@ pub fn resolve_upstream_integration(
     resolution_approach: BaseBranchResolutionApproach,
 ) -> Result<String, Error> {
     let project = gitbutler_project::get(project_id)?;
-    let ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
+    let mut ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
 
     let new_target_id =
-        gitbutler_branch_actions::resolve_upstream_integration(&ctx, resolution_approach)?;
+        gitbutler_branch_actions::resolve_upstream_integration(&mut ctx, resolution_approach)?;
     let commit_id = git2::Oid::to_string(&new_target_id);
     Ok(commit_id)
 }
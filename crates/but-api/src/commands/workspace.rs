@@ -197,7 +197,7 @@ pub fn create_commit_from_worktree_changes(
     stack_branch_name: String,
 ) -> Result<commit_engine::ui::CreateCommitOutcome, Error> {
     let project = gitbutler_project::get(project_id)?;
-    let ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
+    let mut ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
     let mut guard = project.exclusive_worktree_access();
     let snapshot_tree = ctx.prepare_snapshot(guard.read_permission());
 
@@ -222,6 +222,8 @@ pub fn create_commit_from_worktree_changes(
     });
 
     let outcome = outcome?;
+    drop(guard);
+    but_rules::process_recorded_events(&mut ctx);
     Ok(outcome.into())
 }
 
@@ -48,6 +48,26 @@ pub fn get_anonymous_graph_path(
         .map_err(Into::into)
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetOplogBundlePathParams {
+    pub project_id: String,
+}
+
+pub fn get_oplog_bundle_path(
+    app: &App,
+    params: GetOplogBundlePathParams,
+) -> Result<PathBuf, Error> {
+    let project_id = params
+        .project_id
+        .parse()
+        .context(error::Context::new_static(
+            Code::Validation,
+            "Malformed project id",
+        ))?;
+    app.archival.bundle_oplog(project_id).map_err(Into::into)
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GetLogsArchivePathParams {}
@@ -6,6 +6,9 @@ use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 use std::{collections::HashSet, path::PathBuf};
 
+/// Parse and edit commit message trailers, and apply the trailer policy of a repository.
+pub mod trailer;
+
 /// A unique ID to track any commit.
 pub type ChangeId = crate::Id<'C'>;
 
@@ -132,6 +135,9 @@ const HEADERS_VERSION_FIELD: &str = "gitbutler-headers-version";
 const HEADERS_CHANGE_ID_FIELD: &str = "gitbutler-change-id";
 /// The name of the header field that stores the amount of conflicted files.
 pub const HEADERS_CONFLICTED_FIELD: &str = "gitbutler-conflicted";
+/// The name of the header field that stores the amount of conflicting hunks that were resolved automatically
+/// with recorded resolutions, so the user can review them.
+pub const HEADERS_AUTO_RESOLVED_FIELD: &str = "gitbutler-auto-resolved";
 const HEADERS_VERSION: &str = "2";
 
 impl From<&HeadersV2> for Vec<(BString, BString)> {
@@ -268,6 +274,14 @@ impl<'repo> Commit<'repo> {
     pub fn headers(&self) -> Option<HeadersV2> {
         HeadersV2::try_from_commit(&self.inner)
     }
+
+    /// Return the amount of conflicting hunks that were resolved automatically with recorded resolutions
+    /// when this commit was rebased, or `None` if there was no such resolution.
+    pub fn auto_resolved_hunks(&self) -> Option<u64> {
+        self.extra_headers()
+            .find(HEADERS_AUTO_RESOLVED_FIELD)
+            .and_then(|value| value.to_str().ok()?.parse().ok())
+    }
 }
 
 /// Conflict specific details
@@ -0,0 +1,377 @@
+//! Trailers are the `Key: value` lines at the very end of a commit message, like `Signed-off-by: Name <email>`.
+//!
+//! This module parses them, edits them without disturbing the rest of the message, and applies the
+//! [trailer policy](Policy) configured for a repository.
+use anyhow::Context;
+use bstr::{BStr, BString, ByteSlice, ByteVec};
+use std::borrow::Cow;
+
+const SIGN_OFF: &str = "gitbutler.signOff";
+const CO_AUTHOR: &str = "gitbutler.coAuthor";
+const TICKET_PATTERN: &str = "gitbutler.ticketPattern";
+const TICKET_TRAILER: &str = "gitbutler.ticketTrailer";
+
+/// The key of the trailer that certifies the [Developer Certificate of Origin](https://developercertificate.org).
+pub const SIGNED_OFF_BY: &str = "Signed-off-by";
+/// The key of the trailers naming additional authors of a commit.
+pub const CO_AUTHORED_BY: &str = "Co-authored-by";
+/// The key of the trailer with the ticket id, unless configured otherwise.
+pub const DEFAULT_TICKET_TRAILER: &str = "Refs";
+
+/// A single `key: value` trailer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trailer {
+    /// The key, like `Signed-off-by`, which is compared case-insensitively.
+    pub key: BString,
+    /// The value, with continuation lines separated by newlines.
+    pub value: BString,
+}
+
+impl Trailer {
+    /// Create a new trailer from `key` and `value`.
+    pub fn new(key: impl Into<BString>, value: impl Into<BString>) -> Self {
+        Trailer {
+            key: key.into(),
+            value: value.into(),
+        }
+    }
+
+    /// Return `true` if this trailer has the given `key`, ignoring its case.
+    pub fn has_key(&self, key: impl AsRef<[u8]>) -> bool {
+        self.key.eq_ignore_ascii_case(key.as_ref())
+    }
+
+    /// Parse `line` as trailer, or return `None` if it isn't one.
+    ///
+    /// Keys consist of alphanumeric characters and dashes, and are followed by a colon and whitespace,
+    /// which prevents URLs from being mistaken for trailers.
+    fn parse(line: &[u8]) -> Option<Self> {
+        let (key, value) = line.split_once_str(":")?;
+        let is_key = key
+            .first()
+            .is_some_and(|first| first.is_ascii_alphanumeric())
+            && key.iter().all(|b| b.is_ascii_alphanumeric() || *b == b'-');
+        let is_separated = value.first().is_none_or(|b| b.is_ascii_whitespace());
+        (is_key && is_separated).then(|| Trailer::new(key, value.trim()))
+    }
+
+    fn write_to(&self, out: &mut BString) {
+        out.push_str(&self.key);
+        out.push_str(": ");
+        out.push_str(&self.value);
+    }
+}
+
+/// A commit message split into the text before its trailers, and the trailers themselves.
+///
+/// Like with `git interpret-trailers`, the trailers are the last paragraph of the message, which must be
+/// separated from the text by an empty line, start with a trailer and otherwise consist only of trailers and
+/// their continuation lines. The first paragraph is the title, and never contains trailers.
+///
+/// Unlike Git, paragraphs that are only mostly made of trailers aren't considered trailers.
+#[derive(Debug, Clone)]
+pub struct Message {
+    original: BString,
+    text: BString,
+    trailers: Vec<Trailer>,
+    modified: bool,
+}
+
+impl Message {
+    /// Parse `message` into its text and trailers.
+    pub fn parse(message: &BStr) -> Self {
+        let mut line_starts = Vec::new();
+        let mut lines = Vec::new();
+        let mut offset = 0;
+        for line in message.lines_with_terminator() {
+            line_starts.push(offset);
+            offset += line.len();
+            lines.push(line.trim_end_with(|c| c == '\n' || c == '\r'));
+        }
+
+        let is_blank = |line: &[u8]| line.trim().is_empty();
+        let mut end = lines.len();
+        while end > 0 && is_blank(lines[end - 1]) {
+            end -= 1;
+        }
+        let mut start = end;
+        while start > 0 && !is_blank(lines[start - 1]) {
+            start -= 1;
+        }
+        let is_title = lines[..start].iter().all(|line| is_blank(line));
+        let is_continuation = |line: &[u8]| line.first().is_some_and(|b| *b == b' ' || *b == b'\t');
+        let is_trailer_paragraph = !is_title
+            && lines[start..end]
+                .first()
+                .is_some_and(|line| Trailer::parse(line).is_some())
+            && lines[start..end]
+                .iter()
+                .all(|line| Trailer::parse(line).is_some() || is_continuation(line));
+        if !is_trailer_paragraph {
+            start = end;
+        }
+
+        let mut trailers = Vec::<Trailer>::new();
+        for line in &lines[start..end] {
+            match Trailer::parse(line) {
+                Some(trailer) => trailers.push(trailer),
+                None => {
+                    let last = trailers
+                        .last_mut()
+                        .expect("trailers start with a trailer line");
+                    last.value.push(b'\n');
+                    last.value.push_str(line);
+                }
+            }
+        }
+
+        let text_end = line_starts.get(start).copied().unwrap_or(message.len());
+        Message {
+            original: message.to_owned(),
+            text: message[..text_end].trim_end().into(),
+            trailers,
+            modified: false,
+        }
+    }
+
+    /// Return the message without its trailers, and without trailing whitespace.
+    pub fn text(&self) -> &BStr {
+        self.text.as_ref()
+    }
+
+    /// Return all trailers in order of appearance.
+    pub fn trailers(&self) -> &[Trailer] {
+        &self.trailers
+    }
+
+    /// Return `true` if there is at least one trailer with `key`, ignoring its case.
+    pub fn has_key(&self, key: impl AsRef<[u8]>) -> bool {
+        let key = key.as_ref();
+        self.trailers.iter().any(|trailer| trailer.has_key(key))
+    }
+
+    /// Return `true` if `trailer` is present with the same value.
+    pub fn contains(&self, trailer: &Trailer) -> bool {
+        self.trailers
+            .iter()
+            .any(|existing| existing.has_key(&trailer.key) && existing.value == trailer.value)
+    }
+
+    /// Append `trailer` unless it's already present, and return `true` if it was added.
+    pub fn push(&mut self, trailer: Trailer) -> bool {
+        if self.contains(&trailer) {
+            return false;
+        }
+        self.trailers.push(trailer);
+        self.modified = true;
+        true
+    }
+
+    /// Insert `trailer` right before the first trailer with `key`, or append it if there is none,
+    /// unless it's already present. Return `true` if it was added.
+    pub fn insert_before(&mut self, key: &str, trailer: Trailer) -> bool {
+        if self.contains(&trailer) {
+            return false;
+        }
+        let pos = self
+            .trailers
+            .iter()
+            .position(|existing| existing.has_key(key))
+            .unwrap_or(self.trailers.len());
+        self.trailers.insert(pos, trailer);
+        self.modified = true;
+        true
+    }
+
+    /// Return the message with all of its trailers, which is the original message if nothing was changed.
+    pub fn into_message(self) -> BString {
+        if !self.modified {
+            return self.original;
+        }
+        let mut out = self.text;
+        if !self.trailers.is_empty() {
+            if !out.is_empty() {
+                out.push_str("\n\n");
+            }
+            for (idx, trailer) in self.trailers.iter().enumerate() {
+                if idx > 0 {
+                    out.push(b'\n');
+                }
+                trailer.write_to(&mut out);
+            }
+        }
+        out.push(b'\n');
+        out
+    }
+}
+
+/// Join `messages` into the message of a commit they are squashed into, with the trailers of all of them,
+/// without duplicates, at the end.
+///
+/// The messages are always joined with a newline, and those with trailers contribute their text instead,
+/// ending in a newline if the message did.
+pub fn squash_messages(messages: impl IntoIterator<Item = impl AsRef<[u8]>>) -> BString {
+    let parsed: Vec<_> = messages
+        .into_iter()
+        .map(|msg| Message::parse(msg.as_ref().as_bstr()))
+        .collect();
+    let text: BString = bstr::join(
+        "\n",
+        parsed.iter().map(|msg| {
+            if msg.trailers.is_empty() {
+                return msg.original.clone();
+            }
+            let mut text = msg.text.clone();
+            if msg.original.ends_with(b"\n") {
+                text.push(b'\n');
+            }
+            text
+        }),
+    )
+    .into();
+    if parsed.iter().all(|msg| msg.trailers.is_empty()) {
+        return text;
+    }
+
+    let text: BString = text.trim_end().into();
+    let mut out = Message {
+        original: text.clone(),
+        text,
+        trailers: Vec::new(),
+        modified: true,
+    };
+    for trailer in parsed.into_iter().flat_map(|msg| msg.trailers) {
+        out.push(trailer);
+    }
+    out.into_message()
+}
+
+/// The trailers to add to each commit message GitButler writes, as configured per repository.
+#[derive(Debug, Clone)]
+pub struct Policy {
+    /// If `true`, add a `Signed-off-by` trailer for the committer, configured with `gitbutler.signOff`.
+    pub sign_off: bool,
+    /// The `Name <email>` of each co-author to add a `Co-authored-by` trailer for, configured with
+    /// one `gitbutler.coAuthor` entry each.
+    pub co_authors: Vec<BString>,
+    /// A regular expression to find the ticket id in the short name of the branch a commit is created on,
+    /// configured with `gitbutler.ticketPattern`.
+    /// The ticket id is the first capture group if there is one, or the whole match otherwise.
+    pub ticket_pattern: Option<regex::Regex>,
+    /// The key of the trailer to hold the ticket id, configured with `gitbutler.ticketTrailer`.
+    pub ticket_trailer: BString,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Policy {
+            sign_off: false,
+            co_authors: Vec::new(),
+            ticket_pattern: None,
+            ticket_trailer: DEFAULT_TICKET_TRAILER.into(),
+        }
+    }
+}
+
+impl Policy {
+    /// Read the policy from `config`.
+    pub fn from_config(config: &gix::config::Snapshot<'_>) -> anyhow::Result<Self> {
+        let ticket_pattern = config
+            .string(TICKET_PATTERN)
+            .map(|pattern| -> anyhow::Result<_> {
+                let pattern = pattern
+                    .to_str()
+                    .with_context(|| format!("{TICKET_PATTERN} must be valid UTF-8"))?;
+                regex::Regex::new(pattern).with_context(|| {
+                    format!("Invalid regular expression in {TICKET_PATTERN}: '{pattern}'")
+                })
+            })
+            .transpose()?;
+        Ok(Policy {
+            sign_off: config.boolean(SIGN_OFF).unwrap_or(false),
+            co_authors: config
+                .strings(CO_AUTHOR)
+                .unwrap_or_default()
+                .into_iter()
+                .map(Cow::into_owned)
+                .filter(|co_author| !co_author.trim().is_empty())
+                .collect(),
+            ticket_pattern,
+            ticket_trailer: config
+                .string(TICKET_TRAILER)
+                .map(Cow::into_owned)
+                .unwrap_or_else(|| DEFAULT_TICKET_TRAILER.into()),
+        })
+    }
+
+    /// Return the ticket id found in `branch_name`, if any.
+    pub fn ticket_id<'a>(&self, branch_name: &'a str) -> Option<&'a str> {
+        let captures = self.ticket_pattern.as_ref()?.captures(branch_name)?;
+        captures
+            .get(1)
+            .or_else(|| captures.get(0))
+            .map(|ticket| ticket.as_str())
+            .filter(|ticket| !ticket.is_empty())
+    }
+
+    /// Return the keys of the trailers that this policy maintains.
+    fn managed_keys(&self) -> Vec<&[u8]> {
+        let mut keys = Vec::new();
+        if self.ticket_pattern.is_some() {
+            keys.push(self.ticket_trailer.as_slice());
+        }
+        if !self.co_authors.is_empty() {
+            keys.push(CO_AUTHORED_BY.as_bytes());
+        }
+        if self.sign_off {
+            keys.push(SIGNED_OFF_BY.as_bytes());
+        }
+        keys
+    }
+
+    /// Apply this policy to `message`, which replaces `previous_messages`, for a commit by `committer` (as `Name <email>`)
+    /// created on the branch with `branch_name`, if known, and return the new message.
+    ///
+    /// Trailers this policy maintains are carried over from `previous_messages` for each key that `message` doesn't have trailers for,
+    /// so rewording, amending and squashing keep them.
+    /// Then the ticket id, co-authors and sign-off are added if they are missing, in that order.
+    /// The message is returned unchanged if there is nothing to add.
+    pub fn apply(
+        &self,
+        message: &BStr,
+        previous_messages: &[&BStr],
+        committer: &BStr,
+        branch_name: Option<&str>,
+    ) -> BString {
+        let mut out = Message::parse(message);
+        let previous: Vec<_> = previous_messages
+            .iter()
+            .map(|msg| Message::parse(msg))
+            .collect();
+        for key in self.managed_keys() {
+            if out.has_key(key) {
+                continue;
+            }
+            for trailer in previous
+                .iter()
+                .flat_map(|msg| msg.trailers())
+                .filter(|trailer| trailer.has_key(key))
+            {
+                out.push(trailer.clone());
+            }
+        }
+
+        if let Some(ticket) = branch_name.and_then(|name| self.ticket_id(name))
+            && !out.has_key(&self.ticket_trailer)
+        {
+            out.push(Trailer::new(self.ticket_trailer.clone(), ticket));
+        }
+        for co_author in &self.co_authors {
+            out.push(Trailer::new(CO_AUTHORED_BY, co_author.clone()));
+        }
+        if self.sign_off {
+            out.push(Trailer::new(SIGNED_OFF_BY, committer));
+        }
+        out.into_message()
+    }
+}
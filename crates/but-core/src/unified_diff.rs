@@ -5,6 +5,12 @@ use gix::diff::blob::platform::prepare_diff::Operation;
 use gix::diff::blob::unified_diff::{ConsumeBinaryHunk, ContextSize, HunkHeader};
 use serde::Serialize;
 
+/// Classify hunks by what their changes amount to, like only changing whitespace or comments, using per-language
+/// heuristics instead of parsing the code.
+pub mod semantic;
+/// Refine the changed lines of hunks into the words or characters that changed.
+pub mod word_diff;
+
 /// A hunk as used in a [UnifiedDiff], which also contains all added and removed lines.
 #[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
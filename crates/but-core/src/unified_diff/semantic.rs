@@ -0,0 +1,439 @@
+use bstr::{BStr, ByteSlice};
+use serde::{Deserialize, Serialize};
+
+use super::DiffHunk;
+
+/// What the changes of a hunk amount to, as determined by heuristics that know the comment and import syntax of
+/// common languages.
+///
+/// No syntax tree is built for this, so changes are judged line by line, and the classification errs on the side
+/// of [`Code`](HunkKind::Code) whenever the heuristics can't tell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HunkKind {
+    /// Only whitespace changed, like indentation, line breaks or empty lines.
+    Whitespace,
+    /// Only comments changed, possibly along with whitespace.
+    Comment,
+    /// Import statements were only reordered.
+    ImportReordering,
+    /// Anything else, which is assumed to change the code.
+    Code,
+}
+
+impl DiffHunk {
+    /// Determine what the changes of this hunk amount to, using `path` to learn the language of the changed file.
+    pub fn semantic_kind(&self, path: &BStr) -> HunkKind {
+        classify(path, self.diff.as_bstr())
+    }
+}
+
+/// Determine what the changes in `diff`, a hunk in unified diff format with or without its header, amount to,
+/// using `path` to learn the language of the changed file.
+///
+/// Changes are compared as sequences of whitespace-separated tokens, so joining or splitting tokens is a change to
+/// the code. In languages where indentation is significant, like Python and YAML, it's compared as well.
+/// Only the hunk is seen, so comments and strings that start before it may be misjudged.
+///
+/// Only whitespace changes can be detected in files of unknown languages.
+pub fn classify(path: &BStr, diff: &BStr) -> HunkKind {
+    let mut old = Vec::new();
+    let mut new = Vec::new();
+    for line in diff.lines() {
+        match line.split_first() {
+            Some((b'-', content)) => old.push(Line {
+                content,
+                is_change: true,
+            }),
+            Some((b'+', content)) => new.push(Line {
+                content,
+                is_change: true,
+            }),
+            // The hunk header and the marker for a missing newline at the end of the file.
+            Some((b'@' | b'\\', _)) => {}
+            _ => {
+                let content = line.get(1..).unwrap_or_default();
+                old.push(Line {
+                    content,
+                    is_change: false,
+                });
+                new.push(Line {
+                    content,
+                    is_change: false,
+                });
+            }
+        }
+    }
+    let removed: Vec<_> = changed_lines(&old).collect();
+    let added: Vec<_> = changed_lines(&new).collect();
+
+    let language = Language::from_path(path);
+    let significant_indentation = language
+        .as_ref()
+        .is_some_and(|language| language.significant_indentation);
+    if tokens(removed.iter().copied(), significant_indentation)
+        == tokens(added.iter().copied(), significant_indentation)
+    {
+        return HunkKind::Whitespace;
+    }
+    let Some(language) = language else {
+        return HunkKind::Code;
+    };
+    if language.is_import_reordering(&removed, &added) {
+        return HunkKind::ImportReordering;
+    }
+    let (removed_code, removed_comments) = language.changed_code(&old);
+    let (added_code, added_comments) = language.changed_code(&new);
+    if (removed_comments || added_comments)
+        && tokens(
+            removed_code.iter().map(Vec::as_slice),
+            significant_indentation,
+        ) == tokens(
+            added_code.iter().map(Vec::as_slice),
+            significant_indentation,
+        )
+    {
+        return HunkKind::Comment;
+    }
+    HunkKind::Code
+}
+
+/// A line of one side of a hunk.
+struct Line<'a> {
+    content: &'a [u8],
+    /// If `false`, it's a context line that is the same on both sides.
+    is_change: bool,
+}
+
+fn changed_lines<'a>(lines: &'a [Line<'a>]) -> impl Iterator<Item = &'a [u8]> + 'a {
+    lines
+        .iter()
+        .filter(|line| line.is_change)
+        .map(|line| line.content)
+}
+
+/// Return the whitespace-separated tokens of all `lines`, with the indentation of each non-empty line as token of
+/// its own if `significant_indentation` is `true`.
+fn tokens<'a>(
+    lines: impl Iterator<Item = &'a [u8]>,
+    significant_indentation: bool,
+) -> Vec<&'a [u8]> {
+    let mut tokens = Vec::new();
+    for line in lines {
+        let code = line.trim_start();
+        if code.is_empty() {
+            continue;
+        }
+        if significant_indentation {
+            tokens.push(&line[..line.len() - code.len()]);
+        }
+        tokens.extend(
+            code.split(|byte| byte.is_ascii_whitespace())
+                .filter(|token| !token.is_empty()),
+        );
+    }
+    tokens
+}
+
+/// The syntax of a language, as far as it's needed to classify changes.
+struct Language {
+    /// Prefixes of comments that end with the line.
+    line_comments: &'static [&'static str],
+    /// The start and end of comments that may span multiple lines.
+    block_comment: Option<(&'static str, &'static str)>,
+    /// Characters that start and end strings, in which comments can't start.
+    quotes: &'static [u8],
+    /// If `true`, `'` starts a character literal like `'"'` if it's closed after a single (escaped) character,
+    /// and is code otherwise, like in lifetimes.
+    char_literals: bool,
+    /// If `true`, the indentation of a line is part of the code, instead of just being whitespace.
+    significant_indentation: bool,
+    /// Prefixes of lines with import statements.
+    imports: &'static [&'static str],
+}
+
+const C_BLOCK_COMMENT: Option<(&str, &str)> = Some(("/*", "*/"));
+
+impl Language {
+    fn from_path(path: &BStr) -> Option<Self> {
+        let file_name = path.rsplit_str("/").next()?;
+        let extension = file_name[file_name.rfind_byte(b'.')? + 1..].to_ascii_lowercase();
+        let language = match extension.as_slice() {
+            b"rs" => Language {
+                line_comments: &["//"],
+                block_comment: C_BLOCK_COMMENT,
+                quotes: b"\"",
+                char_literals: true,
+                significant_indentation: false,
+                imports: &["use ", "pub use ", "pub(crate) use ", "extern crate "],
+            },
+            b"c" | b"h" | b"cc" | b"cpp" | b"cxx" | b"hh" | b"hpp" | b"m" | b"mm" => Language {
+                line_comments: &["//"],
+                block_comment: C_BLOCK_COMMENT,
+                quotes: b"\"'",
+                char_literals: false,
+                significant_indentation: false,
+                imports: &["#include", "#import"],
+            },
+            b"js" | b"jsx" | b"mjs" | b"cjs" | b"ts" | b"tsx" | b"mts" | b"cts" | b"svelte" => {
+                Language {
+                    line_comments: &["//"],
+                    block_comment: C_BLOCK_COMMENT,
+                    quotes: b"\"'`",
+                    char_literals: false,
+                    significant_indentation: false,
+                    imports: &["import "],
+                }
+            }
+            b"java" | b"kt" | b"kts" | b"scala" | b"swift" | b"groovy" | b"dart" => Language {
+                line_comments: &["//"],
+                block_comment: C_BLOCK_COMMENT,
+                quotes: b"\"",
+                char_literals: true,
+                significant_indentation: false,
+                imports: &["import "],
+            },
+            b"cs" => Language {
+                line_comments: &["//"],
+                block_comment: C_BLOCK_COMMENT,
+                quotes: b"\"",
+                char_literals: true,
+                significant_indentation: false,
+                imports: &["using "],
+            },
+            b"go" => Language {
+                line_comments: &["//"],
+                block_comment: C_BLOCK_COMMENT,
+                quotes: b"\"`",
+                char_literals: true,
+                significant_indentation: false,
+                imports: &["import "],
+            },
+            b"py" | b"pyi" => Language {
+                line_comments: &["#"],
+                block_comment: None,
+                quotes: b"\"'",
+                char_literals: false,
+                significant_indentation: true,
+                imports: &["import ", "from "],
+            },
+            b"rb" => Language {
+                line_comments: &["#"],
+                block_comment: None,
+                quotes: b"\"'",
+                char_literals: false,
+                significant_indentation: false,
+                imports: &["require ", "require_relative "],
+            },
+            b"sh" | b"bash" | b"zsh" | b"fish" | b"toml" => Language {
+                line_comments: &["#"],
+                block_comment: None,
+                quotes: b"\"'",
+                char_literals: false,
+                significant_indentation: false,
+                imports: &[],
+            },
+            b"yaml" | b"yml" => Language {
+                line_comments: &["#"],
+                block_comment: None,
+                quotes: b"\"'",
+                char_literals: false,
+                significant_indentation: true,
+                imports: &[],
+            },
+            b"sql" => Language {
+                line_comments: &["--"],
+                block_comment: C_BLOCK_COMMENT,
+                quotes: b"'",
+                char_literals: false,
+                significant_indentation: false,
+                imports: &[],
+            },
+            b"hs" => Language {
+                line_comments: &["--"],
+                block_comment: Some(("{-", "-}")),
+                quotes: b"\"",
+                char_literals: true,
+                significant_indentation: true,
+                imports: &["import "],
+            },
+            b"lua" => Language {
+                line_comments: &["--"],
+                block_comment: None,
+                quotes: b"\"'",
+                char_literals: false,
+                significant_indentation: false,
+                imports: &[],
+            },
+            b"css" => Language {
+                line_comments: &[],
+                block_comment: C_BLOCK_COMMENT,
+                quotes: b"\"'",
+                char_literals: false,
+                significant_indentation: false,
+                imports: &["@import "],
+            },
+            b"scss" | b"less" => Language {
+                line_comments: &["//"],
+                block_comment: C_BLOCK_COMMENT,
+                quotes: b"\"'",
+                char_literals: false,
+                significant_indentation: false,
+                imports: &["@import ", "@use "],
+            },
+            b"html" | b"htm" | b"xml" | b"svg" | b"md" | b"markdown" | b"vue" => Language {
+                line_comments: &[],
+                block_comment: Some(("<!--", "-->")),
+                quotes: b"",
+                char_literals: false,
+                significant_indentation: false,
+                imports: &[],
+            },
+            _ => return None,
+        };
+        Some(language)
+    }
+
+    /// Return `true` if all non-empty `removed` and `added` lines are import statements, which are the same on
+    /// both sides but in a different order.
+    fn is_import_reordering(&self, removed: &[&[u8]], added: &[&[u8]]) -> bool {
+        let imports = |lines: &[&[u8]]| -> Option<Vec<Vec<u8>>> {
+            let mut imports = lines
+                .iter()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty())
+                .map(|line| {
+                    self.imports
+                        .iter()
+                        .any(|prefix| line.starts_with(prefix.as_bytes()))
+                        .then(|| line.to_owned())
+                })
+                .collect::<Option<Vec<_>>>()?;
+            imports.sort();
+            Some(imports)
+        };
+        match (imports(removed), imports(added)) {
+            (Some(removed), Some(added)) => !removed.is_empty() && removed == added,
+            _ => false,
+        }
+    }
+
+    /// Return the code of all changed `lines` of one side of a hunk without comments, along with `true` if any of
+    /// these lines contained a comment.
+    fn changed_code(&self, lines: &[Line<'_>]) -> (Vec<Vec<u8>>, bool) {
+        let mut in_block = self.starts_in_block_comment(lines);
+        let mut had_comment = false;
+        let mut code = Vec::new();
+        for line in lines {
+            let (line_code, line_had_comment) = self.strip_comments(line.content, &mut in_block);
+            if line.is_change {
+                code.push(line_code);
+                had_comment |= line_had_comment;
+            }
+        }
+        (code, had_comment)
+    }
+
+    /// Guess if `lines` start within a block comment, which is the case if the first block comment delimiter
+    /// that is encountered ends a block comment.
+    fn starts_in_block_comment(&self, lines: &[Line<'_>]) -> bool {
+        let Some((start, end)) = self.block_comment else {
+            return false;
+        };
+        lines
+            .iter()
+            .find_map(
+                |line| match (line.content.find(start), line.content.find(end)) {
+                    (Some(start_pos), Some(end_pos)) => Some(end_pos < start_pos),
+                    (None, Some(_)) => Some(true),
+                    (Some(_), None) => Some(false),
+                    (None, None) => None,
+                },
+            )
+            .unwrap_or(false)
+    }
+
+    /// Return `line` without comments, along with `true` if there was a comment, with `in_block` tracking if the
+    /// line starts and ends within a block comment.
+    fn strip_comments(&self, line: &[u8], in_block: &mut bool) -> (Vec<u8>, bool) {
+        let mut code = Vec::with_capacity(line.len());
+        let mut had_comment = false;
+        let mut quote = None;
+        let mut pos = 0;
+        while pos < line.len() {
+            let rest = &line[pos..];
+            if *in_block {
+                had_comment = true;
+                match self.block_comment {
+                    Some((_, end)) if rest.starts_with(end.as_bytes()) => {
+                        *in_block = false;
+                        pos += end.len();
+                    }
+                    _ => pos += 1,
+                }
+                continue;
+            }
+            let byte = line[pos];
+            if let Some(open) = quote {
+                code.push(byte);
+                if byte == b'\\' {
+                    code.extend(line.get(pos + 1));
+                    pos += 1;
+                } else if byte == open {
+                    quote = None;
+                }
+                pos += 1;
+                continue;
+            }
+            if self.char_literals
+                && let Some(len) = char_literal_len(rest)
+            {
+                code.extend_from_slice(&rest[..len]);
+                pos += len;
+                continue;
+            }
+            if self.quotes.contains(&byte) {
+                quote = Some(byte);
+            } else if self
+                .line_comments
+                .iter()
+                .any(|prefix| rest.starts_with(prefix.as_bytes()))
+            {
+                had_comment = true;
+                break;
+            } else if let Some((start, _)) = self
+                .block_comment
+                .filter(|(start, _)| rest.starts_with(start.as_bytes()))
+            {
+                *in_block = true;
+                had_comment = true;
+                pos += start.len();
+                continue;
+            }
+            code.push(byte);
+            pos += 1;
+        }
+        (code, had_comment)
+    }
+}
+
+/// Return the length of the character literal that `rest` starts with, like `'"'` or `'\n'`, or `None` if it
+/// doesn't start with one.
+fn char_literal_len(rest: &[u8]) -> Option<usize> {
+    if rest.first() != Some(&b'\'') {
+        return None;
+    }
+    if rest.get(1) == Some(&b'\\') {
+        // Escapes are at most as long as `\u{10FFFF}`, and the escaped character may be a quote itself.
+        let end = rest
+            .get(3..)?
+            .iter()
+            .take(9)
+            .position(|byte| *byte == b'\'')?;
+        return Some(3 + end + 1);
+    }
+    let (Some(_), len) = bstr::decode_utf8(rest.get(1..)?) else {
+        return None;
+    };
+    (rest.get(1 + len) == Some(&b'\'')).then_some(1 + len + 1)
+}
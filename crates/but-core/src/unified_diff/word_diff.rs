@@ -0,0 +1,207 @@
+use std::ops::Range;
+
+use bstr::{BString, ByteSlice};
+use gix::diff::blob::Algorithm;
+use gix::diff::blob::intern::{InternedInput, TokenSource};
+use serde::{Deserialize, Serialize};
+
+use super::DiffHunk;
+
+/// How finely the changed lines of a hunk are compared with each other.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Granularity {
+    /// Compare words, runs of whitespace and individual punctuation characters.
+    #[default]
+    Word,
+    /// Compare individual characters.
+    Character,
+}
+
+/// A part of the removed or added lines of a [`WordDiff`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Segment {
+    /// If `true`, this part was removed or added, otherwise it's the same on both sides.
+    pub changed: bool,
+    /// The text of this part, which may span multiple lines and include line separators.
+    #[serde(serialize_with = "gitbutler_serde::bstring_lossy::serialize")]
+    pub text: BString,
+}
+
+/// A run of removed lines along with the added lines that directly follow it, split into the parts that changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WordDiff {
+    /// The 1-based line number of the first removed line in the previous version of the file.
+    pub old_start: u32,
+    /// The 1-based line number of the first added line in the new version of the file.
+    pub new_start: u32,
+    /// All removed lines, which concatenated yield the removed text including line separators.
+    pub removed: Vec<Segment>,
+    /// All added lines, which concatenated yield the added text including line separators.
+    pub added: Vec<Segment>,
+}
+
+impl DiffHunk {
+    /// Compare each run of removed lines in this hunk with the added lines that directly follow it with `granularity`,
+    /// to learn which parts of these lines actually changed.
+    ///
+    /// Runs of lines that were only added or only removed aren't returned as there is nothing to compare them to.
+    pub fn word_diff(&self, granularity: Granularity) -> Vec<WordDiff> {
+        let mut out = Vec::new();
+        let mut lines = self.diff.lines_with_terminator().peekable();
+        if lines.peek().is_some_and(|line| line.starts_with(b"@@")) {
+            lines.next();
+        }
+
+        let (mut old_line, mut new_line) = (self.old_start, self.new_start);
+        let mut run = Run::default();
+        for line in lines {
+            match line.first() {
+                Some(b'-') => {
+                    if !run.added.is_empty() {
+                        run.flush(granularity, &mut out);
+                    }
+                    if run.removed.is_empty() {
+                        run.old_start = old_line;
+                    }
+                    run.removed.extend_from_slice(&line[1..]);
+                    old_line += 1;
+                }
+                Some(b'+') => {
+                    if !run.removed.is_empty() {
+                        if run.added.is_empty() {
+                            run.new_start = new_line;
+                        }
+                        run.added.extend_from_slice(&line[1..]);
+                    }
+                    new_line += 1;
+                }
+                Some(b'\\') => {}
+                _ => {
+                    run.flush(granularity, &mut out);
+                    old_line += 1;
+                    new_line += 1;
+                }
+            }
+        }
+        run.flush(granularity, &mut out);
+        out
+    }
+}
+
+#[derive(Default)]
+struct Run {
+    old_start: u32,
+    new_start: u32,
+    removed: BString,
+    added: BString,
+}
+
+impl Run {
+    fn flush(&mut self, granularity: Granularity, out: &mut Vec<WordDiff>) {
+        let (removed, added) = (
+            std::mem::take(&mut self.removed),
+            std::mem::take(&mut self.added),
+        );
+        if removed.is_empty() || added.is_empty() {
+            return;
+        }
+        let before = tokenize(&removed, granularity);
+        let after = tokenize(&added, granularity);
+        let input = InternedInput::new(Tokens(&before), Tokens(&after));
+        let mut changes = Vec::new();
+        gix::diff::blob::diff(
+            Algorithm::Histogram,
+            &input,
+            |before: Range<u32>, after: Range<u32>| changes.push((before, after)),
+        );
+        out.push(WordDiff {
+            old_start: self.old_start,
+            new_start: self.new_start,
+            removed: segments(&before, changes.iter().map(|(before, _)| before.clone())),
+            added: segments(&after, changes.iter().map(|(_, after)| after.clone())),
+        });
+    }
+}
+
+/// Tokens that were already split, to be diffed as is.
+struct Tokens<'a>(&'a [&'a [u8]]);
+
+impl<'a> TokenSource for Tokens<'a> {
+    type Token = &'a [u8];
+    type Tokenizer = std::iter::Copied<std::slice::Iter<'a, &'a [u8]>>;
+
+    fn tokenize(&self) -> Self::Tokenizer {
+        self.0.iter().copied()
+    }
+
+    fn estimate_tokens(&self) -> u32 {
+        self.0.len() as u32
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum TokenClass {
+    Word,
+    Whitespace,
+    /// Punctuation and line separators, which are tokens on their own.
+    Single,
+}
+
+fn token_class(byte: u8) -> TokenClass {
+    if byte.is_ascii_alphanumeric() || byte == b'_' || !byte.is_ascii() {
+        TokenClass::Word
+    } else if byte.is_ascii_whitespace() && byte != b'\n' {
+        TokenClass::Whitespace
+    } else {
+        TokenClass::Single
+    }
+}
+
+fn tokenize(text: &[u8], granularity: Granularity) -> Vec<&[u8]> {
+    match granularity {
+        Granularity::Character => text
+            .char_indices()
+            .map(|(start, end, _)| &text[start..end])
+            .collect(),
+        Granularity::Word => {
+            let mut out = Vec::new();
+            let mut start = 0;
+            while start < text.len() {
+                let class = token_class(text[start]);
+                let len = if class == TokenClass::Single {
+                    1
+                } else {
+                    text[start..]
+                        .iter()
+                        .position(|byte| token_class(*byte) != class)
+                        .unwrap_or(text.len() - start)
+                };
+                out.push(&text[start..start + len]);
+                start += len;
+            }
+            out
+        }
+    }
+}
+
+/// Join `tokens` into segments, with the tokens at the indices in `changed` being marked as changed.
+fn segments(tokens: &[&[u8]], changed: impl Iterator<Item = Range<u32>>) -> Vec<Segment> {
+    let mut is_changed = vec![false; tokens.len()];
+    for range in changed {
+        is_changed[range.start as usize..range.end as usize].fill(true);
+    }
+    let mut out: Vec<Segment> = Vec::new();
+    for (token, changed) in tokens.iter().zip(is_changed) {
+        match out.last_mut() {
+            Some(last) if last.changed == changed => last.text.extend_from_slice(token),
+            _ => out.push(Segment {
+                changed,
+                text: (*token).into(),
+            }),
+        }
+    }
+    out
+}
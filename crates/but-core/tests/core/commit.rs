@@ -19,3 +19,175 @@ pub fn conflict_repo(name: &str) -> anyhow::Result<gix::Repository> {
         gix::open::Options::isolated(),
     )?)
 }
+
+mod trailer {
+    use bstr::ByteSlice;
+    use but_core::commit::trailer::{Message, Policy, Trailer, squash_messages};
+
+    #[test]
+    fn parse_and_append() {
+        let msg = Message::parse(b"title\n\nbody\n\nSigned-off-by: A <a@example.com>\n".as_bstr());
+        assert_eq!(msg.text(), "title\n\nbody");
+        assert_eq!(
+            msg.trailers(),
+            [Trailer::new("Signed-off-by", "A <a@example.com>")]
+        );
+        assert!(msg.has_key("signed-off-by"), "keys are case-insensitive");
+
+        let mut msg = msg;
+        assert!(!msg.push(Trailer::new("signed-off-by", "A <a@example.com>")));
+        assert!(msg.push(Trailer::new("Refs", "ABC-1")));
+        assert_eq!(
+            msg.into_message(),
+            "title\n\nbody\n\nSigned-off-by: A <a@example.com>\nRefs: ABC-1\n"
+        );
+    }
+
+    #[test]
+    fn unchanged_messages_are_returned_as_is() {
+        let original = b"title\n\n\nKey: value\n\n".as_bstr();
+        let mut msg = Message::parse(original);
+        assert!(!msg.push(Trailer::new("Key", "value")));
+        assert_eq!(msg.into_message().as_bstr(), original);
+    }
+
+    #[test]
+    fn title_and_urls_are_no_trailers() {
+        let msg = Message::parse(b"Fixes: something".as_bstr());
+        assert!(msg.trailers().is_empty(), "the title is never a trailer");
+
+        let msg = Message::parse(b"title\n\nhttps://example.com".as_bstr());
+        assert!(msg.trailers().is_empty());
+
+        let mut msg = Message::parse(b"title\n\nbody".as_bstr());
+        msg.push(Trailer::new("Key", "value"));
+        assert_eq!(
+            msg.into_message(),
+            "title\n\nbody\n\nKey: value\n",
+            "new trailers start a paragraph of their own"
+        );
+    }
+
+    #[test]
+    fn continuation_lines() {
+        let mut msg = Message::parse(b"title\n\nChange-Id: I1\nNote: first\n  second\n".as_bstr());
+        assert_eq!(
+            msg.trailers(),
+            [
+                Trailer::new("Change-Id", "I1"),
+                Trailer::new("Note", "first\n  second")
+            ]
+        );
+        msg.insert_before("note", Trailer::new("Key", "value"));
+        assert_eq!(
+            msg.into_message(),
+            "title\n\nChange-Id: I1\nKey: value\nNote: first\n  second\n"
+        );
+    }
+
+    #[test]
+    fn only_a_final_paragraph_of_trailers_has_trailers() {
+        let mut msg = Message::parse(b"title\nChange-Id: I1\n".as_bstr());
+        assert!(
+            msg.trailers().is_empty(),
+            "trailers must be separated from the text by an empty line"
+        );
+        msg.push(Trailer::new("Key", "value"));
+        assert_eq!(msg.into_message(), "title\nChange-Id: I1\n\nKey: value\n");
+
+        let msg = Message::parse(b"title\n\nbody\nKey: value\n".as_bstr());
+        assert!(
+            msg.trailers().is_empty(),
+            "all lines of the paragraph must be trailers"
+        );
+
+        let msg = Message::parse(b"title\n\n  indented\nKey: value\n".as_bstr());
+        assert!(
+            msg.trailers().is_empty(),
+            "the paragraph has to start with a trailer"
+        );
+
+        let msg = Message::parse(b"title\n\nFixes: a\n\nbody\n".as_bstr());
+        assert!(
+            msg.trailers().is_empty(),
+            "trailers in earlier paragraphs don't count"
+        );
+
+        let msg = Message::parse(b"\n\nKey: value\n".as_bstr());
+        assert!(
+            msg.trailers().is_empty(),
+            "the first paragraph is the title"
+        );
+    }
+
+    #[test]
+    fn squash_keeps_all_trailers_at_the_end() {
+        assert_eq!(
+            squash_messages(["first", "second"]),
+            "first\nsecond",
+            "messages without trailers are joined as they are"
+        );
+        assert_eq!(
+            squash_messages([
+                "first\n\nSigned-off-by: A <a@example.com>\n",
+                "second\n\nSigned-off-by: A <a@example.com>\nRefs: ABC-1\n"
+            ]),
+            "first\n\nsecond\n\nSigned-off-by: A <a@example.com>\nRefs: ABC-1\n"
+        );
+        assert_eq!(
+            squash_messages(["first", "second\n\nRefs: ABC-1"]),
+            "first\nsecond\n\nRefs: ABC-1\n",
+            "messages are joined the same way with and without trailers"
+        );
+        assert_eq!(
+            squash_messages(["first\n\nRefs: ABC-1", "second\n", "third"]),
+            "first\nsecond\n\nthird\n\nRefs: ABC-1\n"
+        );
+    }
+
+    #[test]
+    fn default_policy_leaves_messages_alone() {
+        let msg = b"title\n".as_bstr();
+        let previous = b"old\n\nSigned-off-by: A <a@example.com>".as_bstr();
+        assert_eq!(
+            Policy::default()
+                .apply(msg, &[previous], "B <b@example.com>".into(), Some("ABC-1"))
+                .as_bstr(),
+            msg,
+            "nothing is added or carried over"
+        );
+    }
+
+    #[test]
+    fn policy_from_config_adds_and_carries_over_trailers() -> anyhow::Result<()> {
+        let mut repo = super::conflict_repo("normal-and-artificial")?;
+        {
+            let mut config = repo.config_snapshot_mut();
+            config.set_raw_value(&"gitbutler.signOff", "true")?;
+            config.set_raw_value(&"gitbutler.coAuthor", "C <c@example.com>")?;
+            config.set_raw_value(&"gitbutler.ticketPattern", "([A-Z]+-[0-9]+)")?;
+        }
+        let policy = Policy::from_config(&repo.config_snapshot())?;
+        assert_eq!(policy.ticket_trailer, "Refs");
+        assert_eq!(policy.ticket_id("feat/ABC-12-thing"), Some("ABC-12"));
+
+        let committer = "B <b@example.com>".as_bytes().as_bstr();
+        assert_eq!(
+            policy.apply(
+                b"title\n".as_bstr(),
+                &[],
+                committer,
+                Some("feat/ABC-12-thing")
+            ),
+            "title\n\nRefs: ABC-12\nCo-authored-by: C <c@example.com>\nSigned-off-by: B <b@example.com>\n"
+        );
+
+        let previous = b"old\n\nRefs: XYZ-1\nSigned-off-by: A <a@example.com>\n".as_bstr();
+        assert_eq!(
+            policy.apply(b"reworded".as_bstr(), &[previous], committer, None),
+            "reworded\n\nRefs: XYZ-1\nSigned-off-by: A <a@example.com>\nCo-authored-by: C <c@example.com>\nSigned-off-by: B <b@example.com>\n",
+            "maintained trailers are carried over, and the current committer signs off as well"
+        );
+        Ok(())
+    }
+}
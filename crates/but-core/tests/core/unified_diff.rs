@@ -1,3 +1,5 @@
+use but_core::unified_diff::semantic::{self, HunkKind};
+use but_core::unified_diff::word_diff::{Granularity, Segment, WordDiff};
 use but_core::{ChangeState, UnifiedDiff, unified_diff};
 use gix::object::tree::EntryKind;
 
@@ -231,6 +233,149 @@ fn submodule_added() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn word_diff_of_changed_lines() {
+    let hunk = diff_hunk("@@ -1,4 +1,4 @@\n a\n-let x = 1;\n+let y = 1;\n b\n+added\n");
+    let diffs = hunk.word_diff(Granularity::Word);
+    assert_eq!(
+        diffs,
+        [WordDiff {
+            old_start: 2,
+            new_start: 2,
+            removed: vec![
+                segment(false, "let "),
+                segment(true, "x"),
+                segment(false, " = 1;\n"),
+            ],
+            added: vec![
+                segment(false, "let "),
+                segment(true, "y"),
+                segment(false, " = 1;\n"),
+            ],
+        }],
+        "lines that were only added have nothing to be compared to"
+    );
+
+    let hunk = diff_hunk("@@ -1,1 +1,1 @@\n-color\n+colour\n");
+    let diffs = hunk.word_diff(Granularity::Character);
+    assert_eq!(diffs.len(), 1);
+    assert_eq!(diffs[0].removed, [segment(false, "color\n")]);
+    assert_eq!(
+        diffs[0].added,
+        [
+            segment(false, "colo"),
+            segment(true, "u"),
+            segment(false, "r\n")
+        ]
+    );
+}
+
+#[test]
+fn semantic_kind_of_hunks() {
+    let kind = |path: &str, diff: &str| semantic::classify(path.into(), diff.into());
+    assert_eq!(
+        kind("a.rs", "@@ -1,1 +1,2 @@\n-fn a() {\n+fn a()\n+{\n"),
+        HunkKind::Whitespace
+    );
+    assert_eq!(
+        kind(
+            "a.rs",
+            "@@ -1,1 +1,1 @@\n-let a = 1; // one\n+let a = 1; // two\n"
+        ),
+        HunkKind::Comment
+    );
+    assert_eq!(
+        kind("a.rs", "@@ -1,3 +1,3 @@\n /*\n-  old\n+  new\n */\n"),
+        HunkKind::Comment,
+        "block comments are tracked across lines"
+    );
+    assert_eq!(
+        kind("a.rs", "@@ -1,2 +1,2 @@\n-  old\n+  new\n */\n"),
+        HunkKind::Comment,
+        "hunks may start within a block comment"
+    );
+    assert_eq!(
+        kind(
+            "a.py",
+            "@@ -1,2 +1,2 @@\n-import b\n-import a\n+import a\n+import b\n"
+        ),
+        HunkKind::ImportReordering
+    );
+    assert_eq!(
+        kind("a.py", "@@ -1,1 +1,1 @@\n-import b\n+import a\n"),
+        HunkKind::Code,
+        "changing imports changes the code"
+    );
+    assert_eq!(
+        kind("a.py", "@@ -1,1 +1,1 @@\n-x = \"#fff\"\n+x = \"#000\"\n"),
+        HunkKind::Code,
+        "comments don't start in strings"
+    );
+    assert_eq!(
+        kind("unknown", "@@ -1,1 +1,1 @@\n-a // one\n+a // two\n"),
+        HunkKind::Code,
+        "comments can't be detected in unknown languages"
+    );
+    assert_eq!(
+        kind("unknown", "@@ -1,1 +1,1 @@\n-let ab = 1;\n+let a b = 1;\n"),
+        HunkKind::Code,
+        "splitting tokens changes the code"
+    );
+    assert_eq!(
+        kind("a.rs", "@@ -1,1 +1,1 @@\n-    a();\n+a();\n"),
+        HunkKind::Whitespace
+    );
+    assert_eq!(
+        kind("a.py", "@@ -1,2 +1,2 @@\n if a:\n-    b()\n+c()\n"),
+        HunkKind::Code,
+        "indentation is code in Python"
+    );
+    assert_eq!(
+        kind("a.yaml", "@@ -1,1 +1,1 @@\n-  key: 1\n+key: 1\n"),
+        HunkKind::Code,
+        "indentation is code in YAML"
+    );
+    assert_eq!(
+        kind(
+            "a.rs",
+            "@@ -1,1 +1,1 @@\n-let q = '\"'; // one\n+let q = '\"'; // two\n"
+        ),
+        HunkKind::Comment,
+        "character literals aren't strings"
+    );
+    assert_eq!(
+        kind(
+            "a.rs",
+            "@@ -1,1 +1,1 @@\n-fn a<'a>(a: &'a str) {} // one\n+fn a<'a>(a: &'a str) {} // two\n"
+        ),
+        HunkKind::Comment,
+        "lifetimes aren't character literals"
+    );
+}
+
+fn diff_hunk(diff: &str) -> unified_diff::DiffHunk {
+    let header = diff.lines().next().expect("header");
+    let ranges: Vec<u32> = header
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|num| !num.is_empty())
+        .map(|num| num.parse().expect("valid number"))
+        .collect();
+    unified_diff::DiffHunk {
+        old_start: ranges[0],
+        old_lines: ranges[1],
+        new_start: ranges[2],
+        new_lines: ranges[3],
+        diff: diff.into(),
+    }
+}
+
+fn segment(changed: bool, text: &str) -> Segment {
+    Segment {
+        changed,
+        text: text.into(),
+    }
+}
+
 fn extract_patch(diff: Option<UnifiedDiff>) -> Vec<unified_diff::DiffHunk> {
     match diff {
         None | Some(UnifiedDiff::Binary | UnifiedDiff::TooLarge { .. }) => {
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+CREATE TABLE `conflict_resolutions`(
+	`preimage_id` TEXT NOT NULL PRIMARY KEY,
+	`resolution` BINARY NOT NULL,
+	`created_at` TIMESTAMP NOT NULL,
+	`updated_at` TIMESTAMP NOT NULL
+);
@@ -0,0 +1,65 @@
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+
+use crate::DbHandle;
+use crate::schema::conflict_resolutions::dsl::conflict_resolutions;
+
+use diesel::prelude::{Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+
+/// How a conflicting hunk was resolved by the user, to resolve it the same way when it conflicts again.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::conflict_resolutions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ConflictResolution {
+    /// The hex id of the hash of both sides of the conflicting hunk.
+    pub preimage_id: String,
+    /// The content the conflicting hunk was replaced with.
+    pub resolution: Vec<u8>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+impl DbHandle {
+    pub fn conflict_resolutions(&mut self) -> ConflictResolutionsHandle<'_> {
+        ConflictResolutionsHandle { db: self }
+    }
+}
+
+pub struct ConflictResolutionsHandle<'a> {
+    db: &'a mut DbHandle,
+}
+
+impl ConflictResolutionsHandle<'_> {
+    /// Insert `resolution`, or replace the existing entry with the same preimage id.
+    pub fn upsert(&mut self, resolution: ConflictResolution) -> Result<(), diesel::result::Error> {
+        diesel::replace_into(conflict_resolutions)
+            .values(resolution)
+            .execute(&mut self.db.conn)?;
+        Ok(())
+    }
+
+    pub fn get(
+        &mut self,
+        preimage_id: &str,
+    ) -> Result<Option<ConflictResolution>, diesel::result::Error> {
+        let resolution = conflict_resolutions
+            .filter(crate::schema::conflict_resolutions::preimage_id.eq(preimage_id))
+            .first::<ConflictResolution>(&mut self.db.conn)
+            .optional()?;
+        Ok(resolution)
+    }
+
+    pub fn list(&mut self) -> Result<Vec<ConflictResolution>, diesel::result::Error> {
+        let resolutions = conflict_resolutions.load::<ConflictResolution>(&mut self.db.conn)?;
+        Ok(resolutions)
+    }
+
+    pub fn delete(&mut self, preimage_id: &str) -> Result<(), diesel::result::Error> {
+        diesel::delete(
+            conflict_resolutions
+                .filter(crate::schema::conflict_resolutions::preimage_id.eq(preimage_id)),
+        )
+        .execute(&mut self.db.conn)?;
+        Ok(())
+    }
+}
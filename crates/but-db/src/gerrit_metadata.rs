@@ -0,0 +1,68 @@
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+
+use crate::DbHandle;
+use crate::schema::gerrit_metadata::dsl::gerrit_metadata;
+
+use diesel::prelude::{Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+
+/// What Gerrit told us about a change when it was last pushed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::gerrit_metadata)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct GerritMetadata {
+    /// The Gerrit `Change-Id` trailer value, like `I1234…`.
+    pub change_id: String,
+    /// The hex id of the commit that was last pushed for this change.
+    pub commit_id: String,
+    /// The URL at which the change can be reviewed.
+    pub review_url: String,
+    /// The number Gerrit assigned to the change.
+    pub change_number: i64,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+impl DbHandle {
+    pub fn gerrit_metadata(&mut self) -> GerritMetadataHandle<'_> {
+        GerritMetadataHandle { db: self }
+    }
+}
+
+pub struct GerritMetadataHandle<'a> {
+    db: &'a mut DbHandle,
+}
+
+impl GerritMetadataHandle<'_> {
+    /// Insert `metadata`, or replace the existing entry with the same change id.
+    pub fn upsert(&mut self, metadata: GerritMetadata) -> Result<(), diesel::result::Error> {
+        diesel::replace_into(gerrit_metadata)
+            .values(metadata)
+            .execute(&mut self.db.conn)?;
+        Ok(())
+    }
+
+    pub fn get(
+        &mut self,
+        change_id: &str,
+    ) -> Result<Option<GerritMetadata>, diesel::result::Error> {
+        let metadata = gerrit_metadata
+            .filter(crate::schema::gerrit_metadata::change_id.eq(change_id))
+            .first::<GerritMetadata>(&mut self.db.conn)
+            .optional()?;
+        Ok(metadata)
+    }
+
+    pub fn list(&mut self) -> Result<Vec<GerritMetadata>, diesel::result::Error> {
+        let metadata = gerrit_metadata.load::<GerritMetadata>(&mut self.db.conn)?;
+        Ok(metadata)
+    }
+
+    pub fn delete(&mut self, change_id: &str) -> Result<(), diesel::result::Error> {
+        diesel::delete(
+            gerrit_metadata.filter(crate::schema::gerrit_metadata::change_id.eq(change_id)),
+        )
+        .execute(&mut self.db.conn)?;
+        Ok(())
+    }
+}
@@ -19,6 +19,10 @@ mod file_write_locks;
 pub use file_write_locks::FileWriteLock;
 mod workspace_rules;
 pub use workspace_rules::WorkspaceRule;
+mod gerrit_metadata;
+pub use gerrit_metadata::GerritMetadata;
+mod conflict_resolutions;
+pub use conflict_resolutions::ConflictResolution;
 
 use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("./migrations");
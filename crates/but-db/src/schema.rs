@@ -86,3 +86,23 @@ diesel::table! {
         approved -> Nullable<Bool>,
     }
 }
+
+diesel::table! {
+    gerrit_metadata (change_id) {
+        change_id -> Text,
+        commit_id -> Text,
+        review_url -> Text,
+        change_number -> BigInt,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    conflict_resolutions (preimage_id) {
+        preimage_id -> Text,
+        resolution -> Binary,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
@@ -59,6 +59,22 @@ impl Archival {
         create_zip_file_from_content(&dot_file_contents, "anon-graph.dot", output_file)
     }
 
+    /// Create a git bundle with the operations log of the project behind `project_id`, along with the GitButler state
+    /// needed to restore its snapshots in a scratch repository.
+    pub fn bundle_oplog(&self, project_id: ProjectId) -> Result<PathBuf> {
+        let project = gitbutler_project::get(project_id)?;
+        let mut ctx = gitbutler_command_context::CommandContext::open(
+            &project,
+            AppSettings::load_from_default_path_creating()?,
+        )?;
+        let guard = project.shared_worktree_access();
+        let output_file = self
+            .cache_dir
+            .join(format!("oplog-{date}.bundle", date = filesafe_date_time()));
+        gitbutler_oplog::export_bundle(&mut ctx, &output_file, guard.read_permission())?;
+        Ok(output_file)
+    }
+
     /// Create an archive of all logs in the application log directory.
     pub fn zip_logs(&self) -> Result<PathBuf> {
         let output_file = self
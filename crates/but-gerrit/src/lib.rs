@@ -1,9 +1,14 @@
-use bstr::{BString, ByteSlice};
-use but_core::commit::HeadersV2;
+use bstr::{BString, ByteSlice, ByteVec};
+use but_core::commit::{HeadersV2, trailer};
+use but_db::GerritMetadata;
 use sha1::{Digest, Sha1};
 use std::fmt::Display;
 use uuid::Uuid;
 
+pub mod push;
+
+const CHANGE_ID: &str = "Change-Id";
+
 #[derive(Clone, Debug)]
 pub struct GerritChangeId(String);
 
@@ -26,38 +31,83 @@ pub fn set_trailers(commit: &mut gix::objs::Commit) {
     }
 }
 
-fn with_change_id_trailer(msg: BString, change_id: Uuid) -> BString {
-    let change_id = GerritChangeId::from(change_id);
-    let change_id_line = format!("\nChange-Id: {change_id}\n");
-    let msg_bytes = msg.as_slice();
-
-    if msg_bytes.find(b"\nChange-Id:").is_some() {
-        return msg;
+/// Parse the `output` of pushing `commits` to `refs/for/<target>`, ordered from oldest to newest, and
+/// store the change number and review URL Gerrit assigned to each commit in `db`, keyed by its `Change-Id`.
+///
+/// Return the metadata that was stored, in the order Gerrit listed the changes.
+/// Commits without `Change-Id` trailer or those Gerrit didn't mention are skipped.
+pub fn record_push_output(
+    db: &mut but_db::DbHandle,
+    repo: &gix::Repository,
+    commits: &[gix::ObjectId],
+    output: &str,
+) -> anyhow::Result<Vec<GerritMetadata>> {
+    let changes = push::parse_push_output(output);
+    if changes.is_empty() {
+        return Ok(Vec::new());
     }
+    let messages = commits
+        .iter()
+        .map(|id| Ok(repo.find_commit(*id)?.message_raw()?.to_owned()))
+        .collect::<anyhow::Result<Vec<BString>>>()?;
+    let message_refs: Vec<_> = messages.iter().map(|m| m.as_bstr()).collect();
 
-    let lines: Vec<&[u8]> = msg_bytes.lines().collect();
-    let mut insert_pos = lines.len();
-
-    for (i, line) in lines.iter().enumerate().rev() {
-        if line.starts_with(b"Signed-off-by:") {
-            insert_pos = i;
-        }
+    let now = chrono::Local::now().naive_local();
+    let mut out = Vec::new();
+    for (idx, change) in push::match_changes_to_commits(&changes, &message_refs) {
+        let Some(change_id) = push::change_id_from_message(message_refs[idx]) else {
+            continue;
+        };
+        let change_id = change_id.to_string();
+        let created_at = db
+            .gerrit_metadata()
+            .get(&change_id)?
+            .map_or(now, |existing| existing.created_at);
+        let metadata = GerritMetadata {
+            change_id,
+            commit_id: commits[idx].to_string(),
+            review_url: change.url.clone(),
+            change_number: change.number as i64,
+            created_at,
+            updated_at: now,
+        };
+        db.gerrit_metadata().upsert(metadata.clone())?;
+        out.push(metadata);
     }
+    Ok(out)
+}
 
-    let mut result = BString::from(Vec::new());
-    for (i, line) in lines.iter().enumerate() {
-        if i == insert_pos {
-            result.extend_from_slice(change_id_line.as_bytes());
-        }
-        result.extend_from_slice(line);
-        result.push(b'\n');
-    }
+/// Return what Gerrit reported the last time the change of `commit_id` was pushed, if anything.
+pub fn metadata_for_commit(
+    db: &mut but_db::DbHandle,
+    repo: &gix::Repository,
+    commit_id: gix::ObjectId,
+) -> anyhow::Result<Option<GerritMetadata>> {
+    let commit = repo.find_commit(commit_id)?;
+    let Some(change_id) = push::change_id_from_message(commit.message_raw()?) else {
+        return Ok(None);
+    };
+    Ok(db.gerrit_metadata().get(&change_id.to_string())?)
+}
 
-    if insert_pos == lines.len() {
-        result.extend_from_slice(change_id_line.as_bytes());
+fn with_change_id_trailer(msg: BString, change_id: Uuid) -> BString {
+    // Any `Change-Id` line counts, as commits pushed before trailers were parsed may have it right below the title.
+    if msg.find(b"\nChange-Id:").is_some() {
+        return msg;
     }
-
-    result
+    let change_id = trailer::Trailer::new(CHANGE_ID, GerritChangeId::from(change_id).to_string());
+    let mut message = trailer::Message::parse(msg.as_bstr());
+    if message.trailers().is_empty()
+        && let Some(sign_off) = msg.find(format!("\n{}:", trailer::SIGNED_OFF_BY))
+    {
+        // The sign-off is part of the title paragraph, so keep the `Change-Id` right above it.
+        let mut out = BString::from(&msg[..=sign_off]);
+        out.push_str(format!("{CHANGE_ID}: {}\n", change_id.value));
+        out.push_str(&msg[sign_off + 1..]);
+        return out;
+    }
+    message.insert_before(trailer::SIGNED_OFF_BY, change_id);
+    message.into_message()
 }
 
 #[cfg(test)]
@@ -101,5 +151,17 @@ mod tests {
         let change_id_index = updated_msg_str.find(&change_id_line).unwrap();
         let signed_off_index = updated_msg_str.find("Signed-off-by:").unwrap();
         assert!(change_id_index < signed_off_index);
+
+        // Case 4: Has a paragraph of trailers
+        let msg_with_trailers = BString::from(
+            "Initial commit\n\nRefs: ABC-1\nSigned-off-by: User <alice@example.com>\n",
+        );
+        let updated_msg = with_change_id_trailer(msg_with_trailers, uuid);
+        assert_eq!(
+            updated_msg,
+            format!(
+                "Initial commit\n\nRefs: ABC-1\n{change_id_line}Signed-off-by: User <alice@example.com>\n"
+            )
+        );
     }
 }
@@ -0,0 +1,116 @@
+//! Pushing to Gerrit and understanding what it responds with.
+use bstr::{BStr, BString, ByteSlice};
+
+/// A change as reported by Gerrit after pushing to `refs/for/<target>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushedChange {
+    /// The URL at which the change can be reviewed.
+    pub url: String,
+    /// The number Gerrit assigned to the change.
+    pub number: u64,
+    /// The subject of the commit as printed by Gerrit, which may be truncated.
+    pub subject: String,
+    /// If `true`, the change was created by the push, otherwise a new patchset was added to an existing change.
+    pub is_new: bool,
+}
+
+/// Return the refspec to push `head` for review against `target_branch`, i.e. `main`, optionally
+/// grouping all changes under `topic`.
+pub fn refspec(head: impl std::fmt::Display, target_branch: &str, topic: Option<&str>) -> String {
+    match topic {
+        Some(topic) => format!("{head}:refs/for/{target_branch}%topic={topic}"),
+        None => format!("{head}:refs/for/{target_branch}"),
+    }
+}
+
+/// Parse the `output` of `git push` to a Gerrit remote, which is what Gerrit sends back as `remote:` lines,
+/// and return all changes that were created or updated in the order they were listed.
+///
+/// Both the current format, where new changes are marked with `[NEW]`, as well as the legacy
+/// format with `New Changes:` and `Updated Changes:` sections are understood.
+pub fn parse_push_output(output: &str) -> Vec<PushedChange> {
+    let mut changes = Vec::new();
+    let mut in_new_section = None;
+    for line in output.lines() {
+        let line = line.strip_prefix("remote:").unwrap_or(line).trim();
+        match line {
+            "New Changes:" => {
+                in_new_section = Some(true);
+                continue;
+            }
+            "Updated Changes:" => {
+                in_new_section = Some(false);
+                continue;
+            }
+            _ => {}
+        }
+        if !(line.starts_with("https://") || line.starts_with("http://")) {
+            continue;
+        }
+        let (url, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let Some(number) = url
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .and_then(|n| n.parse().ok())
+        else {
+            continue;
+        };
+        let mut subject = rest.trim();
+        let mut is_new = in_new_section.unwrap_or(false);
+        while let Some((head, marker)) = subject
+            .strip_suffix(']')
+            .and_then(|s| s.rsplit_once('['))
+            .filter(|(_, marker)| marker.chars().all(|c| c.is_ascii_uppercase() || c == ' '))
+        {
+            is_new |= marker == "NEW";
+            subject = head.trim_end();
+        }
+        changes.push(PushedChange {
+            url: url.to_owned(),
+            number,
+            subject: subject.to_owned(),
+            is_new,
+        });
+    }
+    changes
+}
+
+/// Associate each of `changes` with one of `commits` by subject, and return `(commit_index, change)` pairs.
+/// `commits` are the messages of the pushed commits, in the same order in which they were pushed, i.e. the
+/// order in which Gerrit lists them.
+///
+/// Each commit is matched at most once, and subjects that Gerrit truncated with `...` are matched by prefix.
+pub fn match_changes_to_commits<'a>(
+    changes: &'a [PushedChange],
+    commits: &[&BStr],
+) -> Vec<(usize, &'a PushedChange)> {
+    let mut taken = vec![false; commits.len()];
+    let mut out = Vec::new();
+    for change in changes {
+        let wanted = change.subject.as_bytes();
+        let truncated_prefix = wanted.strip_suffix(b"...");
+        let position = commits.iter().enumerate().position(|(idx, message)| {
+            if taken[idx] {
+                return false;
+            }
+            let title = message.lines().next().unwrap_or_default().trim();
+            title == wanted || truncated_prefix.is_some_and(|prefix| title.starts_with(prefix))
+        });
+        if let Some(idx) = position {
+            taken[idx] = true;
+            out.push((idx, change));
+        }
+    }
+    out
+}
+
+/// Return the value of the last `Change-Id` trailer in the commit `message`, if present.
+pub fn change_id_from_message(message: &BStr) -> Option<BString> {
+    message
+        .lines()
+        .rev()
+        .find_map(|line| line.strip_prefix(b"Change-Id:"))
+        .map(|id| id.trim().into())
+        .filter(|id: &BString| !id.is_empty())
+}
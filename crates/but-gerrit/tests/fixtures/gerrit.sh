@@ -0,0 +1,38 @@
+#!/bin/bash
+
+set -eu -o pipefail
+
+git init --bare remote.git
+# Pretend to be Gerrit: pushes to `refs/for/*` print one review URL per new commit, just like Gerrit does.
+cat >remote.git/hooks/pre-receive <<'HOOK'
+#!/bin/bash
+set -eu
+number=100
+while read -r _old new ref; do
+  case "$ref" in
+    refs/for/*) ;;
+    *) continue ;;
+  esac
+  echo ""
+  echo "SUCCESS"
+  echo ""
+  for commit in $(git rev-list --reverse "$new" --not --all); do
+    number=$((number + 1))
+    echo "  https://gerrit.example.com/c/project/+/$number $(git log -1 --format=%s "$commit") [NEW]"
+  done
+  echo ""
+done
+HOOK
+chmod +x remote.git/hooks/pre-receive
+
+git clone remote.git local
+(cd local
+  echo "base" >file && git add . && git commit -m "base"
+  git push origin HEAD:refs/heads/main
+  echo "a" >>file && git commit -am "add a
+
+Change-Id: I1111111111111111111111111111111111111111"
+  echo "b" >>file && git commit -am "add b
+
+Change-Id: I2222222222222222222222222222222222222222"
+)
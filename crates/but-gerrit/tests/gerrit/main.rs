@@ -0,0 +1,158 @@
+mod push {
+    use but_gerrit::push::{PushedChange, parse_push_output, refspec};
+
+    #[test]
+    fn refspec_with_and_without_topic() {
+        assert_eq!(
+            refspec("abc", "main", Some("feature")),
+            "abc:refs/for/main%topic=feature"
+        );
+        assert_eq!(refspec("abc", "main", None), "abc:refs/for/main");
+    }
+
+    #[test]
+    fn parse_current_output_format() {
+        let output = "remote: \nremote: Processing changes: refs: 1, new: 1, updated: 1, done    \nremote: \nremote: SUCCESS\nremote: \nremote:   https://review.example.com/c/project/+/123 first change [NEW]\nremote:   https://review.example.com/c/project/+/124 second change [WIP] [NEW]\nremote:   https://review.example.com/c/project/+/99 updated [change]\nremote: \nTo https://review.example.com/project\n * [new reference]   HEAD -> refs/for/main\n";
+        insta::assert_debug_snapshot!(parse_push_output(output), @r#"
+        [
+            PushedChange {
+                url: "https://review.example.com/c/project/+/123",
+                number: 123,
+                subject: "first change",
+                is_new: true,
+            },
+            PushedChange {
+                url: "https://review.example.com/c/project/+/124",
+                number: 124,
+                subject: "second change",
+                is_new: true,
+            },
+            PushedChange {
+                url: "https://review.example.com/c/project/+/99",
+                number: 99,
+                subject: "updated [change]",
+                is_new: false,
+            },
+        ]
+        "#);
+    }
+
+    #[test]
+    fn parse_legacy_output_format() {
+        let output = "remote: New Changes:\nremote:   https://review.example.com/7 new one\nremote: Updated Changes:\nremote:   https://review.example.com/5/ old one\n";
+        assert_eq!(
+            parse_push_output(output),
+            [
+                PushedChange {
+                    url: "https://review.example.com/7".into(),
+                    number: 7,
+                    subject: "new one".into(),
+                    is_new: true,
+                },
+                PushedChange {
+                    url: "https://review.example.com/5/".into(),
+                    number: 5,
+                    subject: "old one".into(),
+                    is_new: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unrelated_output_yields_nothing() {
+        assert!(parse_push_output("Everything up-to-date\n").is_empty());
+        assert!(parse_push_output("remote: https://example.com/not-a-number\n").is_empty());
+    }
+}
+
+mod record_push_output {
+    use crate::utils::local_and_remote;
+    use but_gerrit::push::refspec;
+
+    #[test]
+    fn each_pushed_commit_is_associated_with_its_change() -> anyhow::Result<()> {
+        let (repo, tmp) = local_and_remote()?;
+        let head = repo.head_id()?.detach();
+        let parent = repo
+            .find_commit(head)?
+            .parent_ids()
+            .next()
+            .expect("two commits on top of base")
+            .detach();
+
+        let out = but_testsupport::git(&repo)
+            .args(["push", "origin", &refspec(head, "main", Some("feature"))])
+            .output()?;
+        assert!(
+            out.status.success(),
+            "{}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        let output = String::from_utf8(out.stderr)?;
+
+        let remote = gix::open_opts(
+            tmp.path().join("remote.git"),
+            gix::open::Options::isolated(),
+        )?;
+        assert!(
+            remote
+                .try_find_reference("refs/for/main%topic=feature")?
+                .is_some(),
+            "the branch name is passed as topic"
+        );
+
+        let mut db = but_db::DbHandle::new_in_directory(tmp.path())?;
+        let recorded = but_gerrit::record_push_output(&mut db, &repo, &[parent, head], &output)?;
+        assert_eq!(
+            recorded
+                .iter()
+                .map(|m| (m.change_id.as_str(), m.change_number))
+                .collect::<Vec<_>>(),
+            [
+                ("I1111111111111111111111111111111111111111", 101),
+                ("I2222222222222222222222222222222222222222", 102)
+            ]
+        );
+
+        let review =
+            but_gerrit::metadata_for_commit(&mut db, &repo, head)?.expect("the change was stored");
+        assert_eq!(review.commit_id, head.to_string());
+        assert_eq!(
+            review.review_url,
+            "https://gerrit.example.com/c/project/+/102"
+        );
+
+        let out = but_testsupport::git(&repo)
+            .args(["push", "origin", &refspec(head, "main", Some("feature"))])
+            .output()?;
+        let recorded = but_gerrit::record_push_output(
+            &mut db,
+            &repo,
+            &[parent, head],
+            &String::from_utf8(out.stderr)?,
+        )?;
+        assert!(
+            recorded.is_empty(),
+            "nothing new was pushed, so Gerrit doesn't report anything"
+        );
+        assert_eq!(
+            db.gerrit_metadata().list()?.len(),
+            2,
+            "previous records are kept"
+        );
+        Ok(())
+    }
+}
+
+mod utils {
+    use but_testsupport::gix_testtools;
+
+    /// Returns the `local` repository whose `origin` is a bare repository that acts like Gerrit.
+    pub fn local_and_remote() -> anyhow::Result<(gix::Repository, tempfile::TempDir)> {
+        let tmp = gix_testtools::scripted_fixture_writable("gerrit.sh")
+            .map_err(anyhow::Error::from_boxed)?;
+        let repo = but_testsupport::open_repo(&tmp.path().join("local"))?;
+        Ok((repo, tmp))
+    }
+}
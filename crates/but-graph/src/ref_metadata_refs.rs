@@ -0,0 +1,465 @@
+use anyhow::Context;
+use bstr::ByteSlice;
+use but_core::RefMetadata;
+use but_core::ref_metadata::{Branch, ValueInfo, Workspace};
+use gix::refs::transaction::PreviousValue;
+use gix::refs::{FullName, FullNameRef};
+use std::any::Any;
+use std::ops::{Deref, DerefMut};
+
+/// The namespace of all references created by [`RefsMetadata`].
+///
+/// Pushing and fetching `refs/gitbutler-metadata/*` shares the metadata with other clones of the repository.
+pub const METADATA_REF_PREFIX: &str = "refs/gitbutler-metadata/";
+
+/// The kind of metadata stored in a reference, which is also the first component of its name below [`METADATA_REF_PREFIX`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Workspace,
+    Branch,
+}
+
+impl Kind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Kind::Workspace => "workspace",
+            Kind::Branch => "branch",
+        }
+    }
+
+    /// Return the name of the reference that stores metadata of this kind for `ref_name`,
+    /// like `refs/gitbutler-metadata/branch/refs/heads/main`.
+    fn storage_ref_name(&self, ref_name: &FullNameRef) -> anyhow::Result<FullName> {
+        Ok(format!(
+            "{METADATA_REF_PREFIX}{}/{}",
+            self.as_str(),
+            ref_name.as_bstr()
+        )
+        .try_into()?)
+    }
+}
+
+/// An implementation to read and write metadata as blobs in the object database, each of which is pointed to by
+/// a reference in the [`METADATA_REF_PREFIX`] namespace named after the kind of metadata and the reference it belongs to.
+///
+/// Unlike [`VirtualBranchesTomlMetadata`](crate::VirtualBranchesTomlMetadata), the metadata is part of the repository,
+/// so teammates can fetch it to reconstruct the same stacks on their clone. Changes are written immediately.
+#[derive(Debug, Clone)]
+pub struct RefsMetadata {
+    repo: gix::Repository,
+}
+
+impl RefsMetadata {
+    /// Initialize a store that keeps its data in `repo`.
+    pub fn new(repo: gix::Repository) -> Self {
+        RefsMetadata { repo }
+    }
+
+    /// Copy all workspace and branch metadata of `source` into this store, overwriting what's there for the same
+    /// references, and return the amount of copied entries.
+    ///
+    /// Use this to migrate from the `virtual_branches.toml` file to a store that can be shared.
+    pub fn import_from(&mut self, source: &impl RefMetadata) -> anyhow::Result<usize> {
+        let mut imported = 0;
+        for item in source.iter() {
+            let (ref_name, value) = item?;
+            if let Some(ws) = value.downcast_ref::<Workspace>() {
+                self.write(
+                    Kind::Workspace,
+                    ref_name.as_ref(),
+                    &storage::Workspace::from(ws),
+                )?;
+            } else if let Some(branch) = value.downcast_ref::<Branch>() {
+                self.write(
+                    Kind::Branch,
+                    ref_name.as_ref(),
+                    &storage::Branch::from(branch),
+                )?;
+            } else {
+                continue;
+            }
+            imported += 1;
+        }
+        Ok(imported)
+    }
+}
+
+/// Storage
+impl RefsMetadata {
+    fn read<T: serde::de::DeserializeOwned>(
+        &self,
+        kind: Kind,
+        ref_name: &FullNameRef,
+    ) -> anyhow::Result<Option<T>> {
+        let Some(reference) = self
+            .repo
+            .try_find_reference(kind.storage_ref_name(ref_name)?.as_ref())?
+        else {
+            return Ok(None);
+        };
+        self.read_blob(&reference).map(Some)
+    }
+
+    fn read_blob<T: serde::de::DeserializeOwned>(
+        &self,
+        reference: &gix::Reference<'_>,
+    ) -> anyhow::Result<T> {
+        let id = reference.try_id().with_context(|| {
+            format!(
+                "Metadata reference '{}' must point to a blob",
+                reference.name().as_bstr()
+            )
+        })?;
+        let blob = self.repo.find_blob(id)?;
+        Ok(toml::from_str(blob.data.to_str()?).with_context(|| {
+            format!(
+                "Failed to parse metadata in '{}'",
+                reference.name().as_bstr()
+            )
+        })?)
+    }
+
+    fn write(
+        &self,
+        kind: Kind,
+        ref_name: &FullNameRef,
+        value: &impl serde::Serialize,
+    ) -> anyhow::Result<()> {
+        let blob_id = self.repo.write_blob(toml::to_string(value)?)?;
+        self.repo.reference(
+            kind.storage_ref_name(ref_name)?,
+            blob_id,
+            PreviousValue::Any,
+            "GitButler: update metadata",
+        )?;
+        Ok(())
+    }
+
+    /// Delete the metadata of `kind` for `ref_name` and return `true` if it existed.
+    fn delete(&self, kind: Kind, ref_name: &FullNameRef) -> anyhow::Result<bool> {
+        let Some(reference) = self
+            .repo
+            .try_find_reference(kind.storage_ref_name(ref_name)?.as_ref())?
+        else {
+            return Ok(false);
+        };
+        reference.delete()?;
+        Ok(true)
+    }
+
+    fn all_values(&self) -> anyhow::Result<Vec<(FullName, Box<dyn Any>)>> {
+        let mut branches = Vec::new();
+        let mut workspaces = Vec::new();
+        for reference in self.repo.references()?.prefixed(METADATA_REF_PREFIX)? {
+            let reference = reference.map_err(|err| anyhow::anyhow!(err))?;
+            let storage_name = reference.name().as_bstr();
+            let Some((kind, ref_name)) = storage_name
+                .strip_prefix(METADATA_REF_PREFIX.as_bytes())
+                .and_then(|rest| rest.split_once_str("/"))
+            else {
+                continue;
+            };
+            let ref_name = FullName::try_from(ref_name.as_bstr())?;
+            match kind {
+                b"workspace" => {
+                    let ws: Workspace = self
+                        .read_blob::<storage::Workspace>(&reference)?
+                        .try_into()?;
+                    workspaces.push((ref_name, Box::new(ws) as Box<dyn Any>));
+                }
+                b"branch" => {
+                    let branch: Branch = self.read_blob::<storage::Branch>(&reference)?.into();
+                    branches.push((ref_name, Box::new(branch) as Box<dyn Any>));
+                }
+                _ => continue,
+            }
+        }
+        // Workspaces last, like the other implementations do.
+        branches.extend(workspaces);
+        Ok(branches)
+    }
+}
+
+impl RefMetadata for RefsMetadata {
+    type Handle<T> = RefsMetadataHandle<T>;
+
+    fn iter(&self) -> impl Iterator<Item = anyhow::Result<(FullName, Box<dyn Any>)>> + '_ {
+        match self.all_values() {
+            Ok(values) => values.into_iter().map(Ok).collect::<Vec<_>>(),
+            Err(err) => vec![Err(err)],
+        }
+        .into_iter()
+    }
+
+    fn workspace(&self, ref_name: &FullNameRef) -> anyhow::Result<Self::Handle<Workspace>> {
+        let value = self.read::<storage::Workspace>(Kind::Workspace, ref_name)?;
+        Ok(RefsMetadataHandle {
+            is_default: value.is_none(),
+            ref_name: ref_name.to_owned(),
+            value: value
+                .map(Workspace::try_from)
+                .transpose()?
+                .unwrap_or_default(),
+        })
+    }
+
+    fn branch(&self, ref_name: &FullNameRef) -> anyhow::Result<Self::Handle<Branch>> {
+        let value = self.read::<storage::Branch>(Kind::Branch, ref_name)?;
+        Ok(RefsMetadataHandle {
+            is_default: value.is_none(),
+            ref_name: ref_name.to_owned(),
+            value: value.map(Branch::from).unwrap_or_default(),
+        })
+    }
+
+    fn set_workspace(&mut self, value: &Self::Handle<Workspace>) -> anyhow::Result<()> {
+        self.write(
+            Kind::Workspace,
+            value.ref_name.as_ref(),
+            &storage::Workspace::from(&value.value),
+        )
+    }
+
+    fn set_branch(&mut self, value: &Self::Handle<Branch>) -> anyhow::Result<()> {
+        self.write(
+            Kind::Branch,
+            value.ref_name.as_ref(),
+            &storage::Branch::from(&value.value),
+        )
+    }
+
+    fn remove(&mut self, ref_name: &FullNameRef) -> anyhow::Result<bool> {
+        let removed_workspace = self.delete(Kind::Workspace, ref_name)?;
+        let removed_branch = self.delete(Kind::Branch, ref_name)?;
+        Ok(removed_workspace || removed_branch)
+    }
+}
+
+pub struct RefsMetadataHandle<T> {
+    is_default: bool,
+    ref_name: FullName,
+    value: T,
+}
+
+impl<T> AsRef<FullNameRef> for RefsMetadataHandle<T> {
+    fn as_ref(&self) -> &FullNameRef {
+        self.ref_name.as_ref()
+    }
+}
+
+impl<T> Deref for RefsMetadataHandle<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for RefsMetadataHandle<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<T> ValueInfo for RefsMetadataHandle<T> {
+    fn is_default(&self) -> bool {
+        self.is_default
+    }
+}
+
+/// The serialized form of the metadata, decoupled from the types in `but_core` so these can change
+/// without breaking the data that was already shared.
+mod storage {
+    use but_core::ref_metadata;
+    use but_core::ref_metadata::StackId;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Time {
+        seconds: gix::date::SecondsSinceUnixEpoch,
+        offset: i32,
+    }
+
+    #[derive(Serialize, Deserialize, Default)]
+    pub struct RefInfo {
+        created_at: Option<Time>,
+        updated_at: Option<Time>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Branch {
+        #[serde(default)]
+        ref_info: RefInfo,
+        description: Option<String>,
+        pull_request: Option<usize>,
+        review_id: Option<String>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Workspace {
+        #[serde(default)]
+        ref_info: RefInfo,
+        target_ref: Option<String>,
+        push_remote: Option<String>,
+        #[serde(default)]
+        stacks: Vec<WorkspaceStack>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct WorkspaceStack {
+        id: StackId,
+        branches: Vec<WorkspaceStackBranch>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct WorkspaceStackBranch {
+        ref_name: String,
+        #[serde(default)]
+        archived: bool,
+    }
+
+    impl From<gix::date::Time> for Time {
+        fn from(gix::date::Time { seconds, offset }: gix::date::Time) -> Self {
+            Time { seconds, offset }
+        }
+    }
+
+    impl From<Time> for gix::date::Time {
+        fn from(Time { seconds, offset }: Time) -> Self {
+            gix::date::Time { seconds, offset }
+        }
+    }
+
+    impl From<&ref_metadata::RefInfo> for RefInfo {
+        fn from(
+            ref_metadata::RefInfo {
+                created_at,
+                updated_at,
+            }: &ref_metadata::RefInfo,
+        ) -> Self {
+            RefInfo {
+                created_at: created_at.map(Into::into),
+                updated_at: updated_at.map(Into::into),
+            }
+        }
+    }
+
+    impl From<RefInfo> for ref_metadata::RefInfo {
+        fn from(
+            RefInfo {
+                created_at,
+                updated_at,
+            }: RefInfo,
+        ) -> Self {
+            ref_metadata::RefInfo {
+                created_at: created_at.map(Into::into),
+                updated_at: updated_at.map(Into::into),
+            }
+        }
+    }
+
+    impl From<&ref_metadata::Branch> for Branch {
+        fn from(
+            ref_metadata::Branch {
+                ref_info,
+                description,
+                review,
+            }: &ref_metadata::Branch,
+        ) -> Self {
+            Branch {
+                ref_info: ref_info.into(),
+                description: description.clone(),
+                pull_request: review.pull_request,
+                review_id: review.review_id.clone(),
+            }
+        }
+    }
+
+    impl From<Branch> for ref_metadata::Branch {
+        fn from(
+            Branch {
+                ref_info,
+                description,
+                pull_request,
+                review_id,
+            }: Branch,
+        ) -> Self {
+            ref_metadata::Branch {
+                ref_info: ref_info.into(),
+                description,
+                review: ref_metadata::Review {
+                    pull_request,
+                    review_id,
+                },
+            }
+        }
+    }
+
+    impl From<&ref_metadata::Workspace> for Workspace {
+        fn from(
+            ref_metadata::Workspace {
+                ref_info,
+                stacks,
+                target_ref,
+                push_remote,
+            }: &ref_metadata::Workspace,
+        ) -> Self {
+            Workspace {
+                ref_info: ref_info.into(),
+                target_ref: target_ref.as_ref().map(|rn| rn.as_bstr().to_string()),
+                push_remote: push_remote.clone(),
+                stacks: stacks
+                    .iter()
+                    .map(|stack| WorkspaceStack {
+                        id: stack.id,
+                        branches: stack
+                            .branches
+                            .iter()
+                            .map(|branch| WorkspaceStackBranch {
+                                ref_name: branch.ref_name.as_bstr().to_string(),
+                                archived: branch.archived,
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            }
+        }
+    }
+
+    impl TryFrom<Workspace> for ref_metadata::Workspace {
+        type Error = anyhow::Error;
+
+        fn try_from(
+            Workspace {
+                ref_info,
+                target_ref,
+                push_remote,
+                stacks,
+            }: Workspace,
+        ) -> Result<Self, Self::Error> {
+            Ok(ref_metadata::Workspace {
+                ref_info: ref_info.into(),
+                stacks: stacks
+                    .into_iter()
+                    .map(|stack| -> anyhow::Result<_> {
+                        Ok(ref_metadata::WorkspaceStack {
+                            id: stack.id,
+                            branches: stack
+                                .branches
+                                .into_iter()
+                                .map(|branch| -> anyhow::Result<_> {
+                                    Ok(ref_metadata::WorkspaceStackBranch {
+                                        ref_name: branch.ref_name.try_into()?,
+                                        archived: branch.archived,
+                                    })
+                                })
+                                .collect::<Result<_, _>>()?,
+                        })
+                    })
+                    .collect::<Result<_, _>>()?,
+                target_ref: target_ref.map(TryInto::try_into).transpose()?,
+                push_remote,
+            })
+        }
+    }
+}
@@ -2,3 +2,4 @@ mod init;
 mod vis;
 
 mod ref_metadata_legacy;
+mod ref_metadata_refs;
@@ -942,7 +942,7 @@ fn vb_fixture(name: &str) -> PathBuf {
     format!("tests/fixtures/legacy/{name}.toml").into()
 }
 
-fn vb_store_rw(name: &str) -> anyhow::Result<(VirtualBranchesTomlMetadata, TempDir)> {
+pub(crate) fn vb_store_rw(name: &str) -> anyhow::Result<(VirtualBranchesTomlMetadata, TempDir)> {
     let tmp = TempDir::new()?;
     let writable_toml_path = tmp.path().join("vb.toml");
     std::fs::copy(vb_fixture(name), &writable_toml_path)?;
@@ -969,7 +969,7 @@ fn default_target() -> Target {
 
 /// Assure everything can round-trip and the data looks consistent, independently of the actual data,
 /// from a store that already contains data.
-fn roundtrip_journey(metadata: &mut impl RefMetadata) -> anyhow::Result<()> {
+pub(crate) fn roundtrip_journey(metadata: &mut impl RefMetadata) -> anyhow::Result<()> {
     // TODO: retrieve and set tests for all items, round-tripping
     let all_items = metadata.iter().map(Result::unwrap).collect::<Vec<_>>();
     for (ref_name, md) in &all_items {
@@ -0,0 +1,118 @@
+use crate::ref_metadata_legacy::{roundtrip_journey, vb_store_rw};
+use but_core::RefMetadata;
+use but_core::ref_metadata::{
+    Branch, StackId, ValueInfo, Workspace, WorkspaceStack, WorkspaceStackBranch,
+};
+use but_graph::{METADATA_REF_PREFIX, RefsMetadata};
+use but_testsupport::gix_testtools::tempfile::{TempDir, tempdir};
+
+#[test]
+fn journey() -> anyhow::Result<()> {
+    let (toml_store, _toml_tmp) = vb_store_rw("virtual-branches-01")?;
+    let (mut store, _tmp) = empty_refs_store()?;
+
+    assert_eq!(
+        store.import_from(&toml_store)?,
+        15,
+        "everything is migrated from the toml file"
+    );
+    assert_eq!(store.iter().count(), 15, "There are items to test on");
+    roundtrip_journey(&mut store)?;
+    Ok(())
+}
+
+#[test]
+fn migration_from_toml_keeps_all_values() -> anyhow::Result<()> {
+    let (toml_store, _toml_tmp) = vb_store_rw("virtual-branches-01")?;
+    let (mut store, _tmp) = empty_refs_store()?;
+    store.import_from(&toml_store)?;
+
+    for item in toml_store.iter() {
+        let (ref_name, value) = item?;
+        if let Some(expected) = value.downcast_ref::<Workspace>() {
+            let ws = store.workspace(ref_name.as_ref())?;
+            assert!(!ws.is_default(), "the workspace was migrated");
+            assert_eq!(&*ws, expected);
+        } else if let Some(expected) = value.downcast_ref::<Branch>() {
+            let branch = store.branch(ref_name.as_ref())?;
+            assert!(!branch.is_default(), "{ref_name} was migrated");
+            assert_eq!(&*branch, expected);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn fetched_metadata_reproduces_stacks_in_another_clone() -> anyhow::Result<()> {
+    let (mut store, tmp) = empty_refs_store()?;
+    let branch_name: &gix::refs::FullNameRef = "refs/heads/feature".try_into()?;
+    let mut branch = store.branch(branch_name)?;
+    assert!(branch.is_default(), "nothing was stored yet");
+    branch.description = Some("A description".into());
+    branch.review.pull_request = Some(42);
+    store.set_branch(&branch)?;
+
+    let ws_name: &gix::refs::FullNameRef = "refs/heads/gitbutler/workspace".try_into()?;
+    let mut ws = store.workspace(ws_name)?;
+    assert!(ws.is_default(), "nothing was stored yet");
+    ws.stacks.push(WorkspaceStack {
+        id: StackId::from_number_for_testing(1),
+        branches: vec![WorkspaceStackBranch {
+            ref_name: branch_name.to_owned(),
+            archived: false,
+        }],
+    });
+    ws.target_ref = Some("refs/remotes/origin/main".try_into()?);
+    ws.push_remote = Some("fork".into());
+    store.set_workspace(&ws)?;
+
+    let other_tmp = tempdir()?;
+    let other_repo = gix::init(other_tmp.path())?;
+    let status = std::process::Command::new("git")
+        .current_dir(other_tmp.path())
+        .arg("fetch")
+        .arg(tmp.path())
+        .arg(format!("{METADATA_REF_PREFIX}*:{METADATA_REF_PREFIX}*"))
+        .status()?;
+    assert!(status.success());
+
+    let other_store = RefsMetadata::new(other_repo);
+    assert_eq!(
+        other_store.iter().count(),
+        2,
+        "the branch and the workspace"
+    );
+    let other_branch = other_store.branch(branch_name)?;
+    assert!(!other_branch.is_default());
+    assert_eq!(&*other_branch, &*branch);
+    let other_ws = other_store.workspace(ws_name)?;
+    assert!(!other_ws.is_default());
+    assert_eq!(&*other_ws, &*ws);
+    Ok(())
+}
+
+#[test]
+fn remove_deletes_all_metadata_of_a_reference() -> anyhow::Result<()> {
+    let (mut store, _tmp) = empty_refs_store()?;
+    let name: &gix::refs::FullNameRef = "refs/heads/both".try_into()?;
+    let ws = store.workspace(name)?;
+    store.set_workspace(&ws)?;
+    let branch = store.branch(name)?;
+    store.set_branch(&branch)?;
+    assert_eq!(
+        store.iter().count(),
+        2,
+        "default values are stored if they are set explicitly"
+    );
+
+    assert!(store.remove(name)?);
+    assert_eq!(store.iter().count(), 0);
+    assert!(!store.remove(name)?, "removing it again is a no-op");
+    Ok(())
+}
+
+fn empty_refs_store() -> anyhow::Result<(RefsMetadata, TempDir)> {
+    let tmp = tempdir()?;
+    let repo = gix::init(tmp.path())?;
+    Ok((RefsMetadata::new(repo), tmp))
+}
@@ -181,6 +181,26 @@ impl From<but_core::WorktreeChanges> for WorktreeChanges {
 }
 
 impl HunkAssignmentRequest {
+    /// Create requests to assign the `removed_lines`, as line numbers in the previous version of the file,
+    /// and the `added_lines`, as line numbers in the worktree version of the file, at `path_bytes` to `stack_id`.
+    ///
+    /// The lines may belong to any hunk of the file, and the remaining lines of their hunks keep their assignment.
+    pub fn for_lines(
+        path_bytes: BString,
+        stack_id: Option<StackId>,
+        removed_lines: &[usize],
+        added_lines: &[usize],
+    ) -> Vec<Self> {
+        HunkHeader::line_selections(removed_lines, added_lines)
+            .into_iter()
+            .map(|hunk_header| HunkAssignmentRequest {
+                hunk_header: Some(hunk_header),
+                path_bytes: path_bytes.clone(),
+                stack_id,
+            })
+            .collect()
+    }
+
     pub fn matches_assignment(&self, assignment: &HunkAssignment) -> bool {
         self.path_bytes == assignment.path_bytes && self.hunk_header == assignment.hunk_header
     }
@@ -193,6 +213,13 @@ impl PartialEq for HunkAssignment {
 }
 
 impl HunkAssignment {
+    /// Return `true` if this assignment is for some of the removed or added lines of a hunk,
+    /// instead of the whole hunk. See [`HunkHeader::is_line_selection()`].
+    pub fn is_line_selection(&self) -> bool {
+        self.hunk_header
+            .is_some_and(|header| header.is_line_selection())
+    }
+
     /// Whether there is overlap between the two hunks.
     fn intersects(&self, other: HunkAssignment) -> bool {
         if self == &other {
@@ -221,8 +248,18 @@ impl HunkAssignment {
 
         // Both have hunk headers - check if the ranges overlap
         if let (Some(header), Some(other_header)) = (self.hunk_header, other.hunk_header) {
-            return header.old_range().intersects(other_header.old_range())
-                && header.new_range().intersects(other_header.new_range());
+            // Line selections only have lines on one side, so only compare the ranges of that side.
+            let old_is_null = header.old_range().is_null() || other_header.old_range().is_null();
+            let new_is_null = header.new_range().is_null() || other_header.new_range().is_null();
+            return match (old_is_null, new_is_null) {
+                (true, true) => false,
+                (true, false) => header.new_range().intersects(other_header.new_range()),
+                (false, true) => header.old_range().intersects(other_header.old_range()),
+                (false, false) => {
+                    header.old_range().intersects(other_header.old_range())
+                        && header.new_range().intersects(other_header.new_range())
+                }
+            };
         }
 
         false
@@ -257,6 +294,7 @@ pub fn assign(
             &ctx.project().path,
             &ctx.project().gb_dir(),
             None,
+            ctx.app_settings().hunk_dependencies.into(),
         )?
     };
 
@@ -272,10 +310,21 @@ pub fn assign(
         ));
     }
 
-    // Reconcile worktree with the persisted assignments
+    // Split hunks whose lines are assigned individually, either previously or by the requests
     let persisted_assignments = state::assignments(ctx)?;
-    let with_worktree = reconcile::assignments(
+    let request_assignments = requests_to_assignments(requests.clone());
+    let split = reconcile::split_by_line_selections(
         &worktree_assignments,
+        &persisted_assignments
+            .iter()
+            .chain(&request_assignments)
+            .cloned()
+            .collect_vec(),
+    );
+
+    // Reconcile worktree with the persisted assignments
+    let with_worktree = reconcile::assignments(
+        &split.assignments,
         &persisted_assignments,
         &applied_stacks,
         MultipleOverlapping::SetMostLines,
@@ -285,7 +334,7 @@ pub fn assign(
     // Reconcile with the requested changes
     let with_requests = reconcile::assignments(
         &with_worktree,
-        &requests_to_assignments(requests.clone()),
+        &request_assignments,
         &applied_stacks,
         MultipleOverlapping::SetMostLines,
         true,
@@ -301,6 +350,9 @@ pub fn assign(
         false,
     )?;
 
+    // Hunks whose lines all ended up with the same stack are assigned as a whole again
+    let with_locks = split.join(with_locks);
+
     state::set_assignments(ctx, with_locks.clone())?;
 
     // Request where the stack_id is different from the outcome are considered rejections - this is due to locking
@@ -347,6 +399,7 @@ pub fn assignments_with_fallback(
             &ctx.project().path,
             &ctx.project().gb_dir(),
             Some(worktree_changes.clone()),
+            ctx.app_settings().hunk_dependencies.into(),
         )?
     };
 
@@ -408,8 +461,9 @@ fn reconcile_with_worktree_and_locks(
         .collect::<Vec<_>>();
 
     let persisted_assignments = state::assignments(ctx)?;
+    let split = reconcile::split_by_line_selections(worktree_assignments, &persisted_assignments);
     let with_worktree = reconcile::assignments(
-        worktree_assignments,
+        &split.assignments,
         &persisted_assignments,
         &applied_stacks,
         MultipleOverlapping::SetMostLines,
@@ -425,7 +479,7 @@ fn reconcile_with_worktree_and_locks(
         set_assignment_from_locks,
     )?;
 
-    Ok(with_locks)
+    Ok(split.join(with_locks))
 }
 
 fn hunk_dependency_assignments(deps: &HunkDependencies) -> Result<Vec<HunkAssignment>> {
@@ -1045,4 +1099,163 @@ mod tests {
             "File should maintain stack assignment when changing from text to binary"
         );
     }
+
+    /// A worktree hunk of `foo.rs` which replaces lines 2 and 3 with three new lines.
+    fn replacing_hunk(id: usize) -> HunkAssignment {
+        HunkAssignment {
+            id: Some(id_seq(id)),
+            hunk_header: Some(HunkHeader {
+                old_start: 2,
+                old_lines: 2,
+                new_start: 2,
+                new_lines: 3,
+            }),
+            path: "foo.rs".to_string(),
+            path_bytes: BString::from("foo.rs"),
+            stack_id: None,
+            hunk_locks: None,
+            line_nums_added: Some(vec![2, 3, 4]),
+            line_nums_removed: Some(vec![2, 3]),
+            diff: Some("@@ -2,2 +2,3 @@\n-a\n-b\n+c\n+d\n+e\n".into()),
+        }
+    }
+
+    fn line_selection(
+        (old_start, old_lines): (u32, u32),
+        (new_start, new_lines): (u32, u32),
+        stack_id: Option<usize>,
+    ) -> HunkAssignment {
+        HunkAssignment {
+            hunk_header: Some(HunkHeader {
+                old_start,
+                old_lines,
+                new_start,
+                new_lines,
+            }),
+            ..HunkAssignment::new("foo.rs", 0, 0, stack_id, None)
+        }
+    }
+
+    #[test]
+    fn test_split_hunk_by_persisted_line_selection() {
+        let applied_stacks = vec![stack_id_seq(1)];
+        let worktree_assignments = vec![replacing_hunk(1)];
+        let previous_assignments = vec![line_selection((0, 0), (3, 1), Some(1))];
+
+        let split =
+            reconcile::split_by_line_selections(&worktree_assignments, &previous_assignments);
+        let result = reconcile::assignments(
+            &split.assignments,
+            &previous_assignments,
+            &applied_stacks,
+            MultipleOverlapping::SetMostLines,
+            true,
+        )
+        .unwrap();
+        let result = split.join(result);
+
+        let headers = result
+            .iter()
+            .map(|assignment| assignment.hunk_header.unwrap())
+            .collect_vec();
+        assert_eq!(
+            headers,
+            [
+                line_selection((2, 2), (0, 0), None),
+                line_selection((0, 0), (2, 1), None),
+                line_selection((0, 0), (3, 1), None),
+                line_selection((0, 0), (4, 1), None),
+            ]
+            .map(|assignment| assignment.hunk_header.unwrap()),
+            "the added lines are split at the boundaries of the persisted selection"
+        );
+        assert_eq!(
+            result
+                .iter()
+                .map(|assignment| assignment.stack_id)
+                .collect_vec(),
+            [None, None, Some(stack_id_seq(1)), None]
+        );
+        assert_eq!(
+            result[0].id,
+            Some(id_seq(1)),
+            "the first line selection keeps the id"
+        );
+        assert!(result.iter().map(|assignment| assignment.id).all_unique());
+        assert_eq!(result[0].diff.as_deref(), Some("-a\n-b\n".into()));
+        assert_eq!(result[2].diff.as_deref(), Some("+d\n".into()));
+        assert_eq!(result[2].line_nums_added, Some(vec![3]));
+        assert_eq!(result[2].line_nums_removed, Some(vec![]));
+    }
+
+    #[test]
+    fn test_selected_diff_skips_no_newline_markers() {
+        let header = HunkHeader {
+            old_start: 1,
+            old_lines: 2,
+            new_start: 1,
+            new_lines: 2,
+        };
+        let diff = "@@ -1,2 +1,2 @@\n-a\n-b\n\\ No newline at end of file\n+c\n+d\n";
+        let selection = HunkHeader {
+            old_start: 0,
+            old_lines: 0,
+            new_start: 1,
+            new_lines: 1,
+        };
+        assert_eq!(
+            reconcile::selected_diff(diff.into(), header, selection),
+            "+c\n"
+        );
+    }
+
+    #[test]
+    fn test_join_line_selections_assigned_to_the_same_stack() {
+        let applied_stacks = vec![stack_id_seq(1), stack_id_seq(2)];
+        let worktree_assignments = vec![replacing_hunk(1)];
+        let previous_assignments = vec![
+            line_selection((2, 1), (0, 0), Some(1)),
+            line_selection((0, 0), (3, 1), Some(2)),
+        ];
+        let requests = vec![HunkAssignment {
+            hunk_header: replacing_hunk(1).hunk_header,
+            ..HunkAssignment::new("foo.rs", 0, 0, Some(1), None)
+        }];
+
+        let split = reconcile::split_by_line_selections(
+            &worktree_assignments,
+            &previous_assignments
+                .iter()
+                .chain(&requests)
+                .cloned()
+                .collect_vec(),
+        );
+        assert_eq!(split.assignments.len(), 5);
+        let with_worktree = reconcile::assignments(
+            &split.assignments,
+            &previous_assignments,
+            &applied_stacks,
+            MultipleOverlapping::SetMostLines,
+            true,
+        )
+        .unwrap();
+        let with_requests = reconcile::assignments(
+            &with_worktree,
+            &requests,
+            &applied_stacks,
+            MultipleOverlapping::SetMostLines,
+            true,
+        )
+        .unwrap();
+        let result = split.join(with_requests);
+
+        assert_eq(
+            result,
+            vec![HunkAssignment {
+                id: Some(id_seq(1)),
+                stack_id: Some(stack_id_seq(1)),
+                ..replacing_hunk(1)
+            }],
+        );
+    }
 }
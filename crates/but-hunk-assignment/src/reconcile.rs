@@ -1,8 +1,12 @@
 use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::ops::Range;
 
 use anyhow::Result;
-use but_workspace::StackId;
+use bstr::{BStr, BString, ByteSlice};
+use but_workspace::{HunkHeader, StackId, commit_engine::HunkRange};
 use itertools::Itertools;
+use uuid::Uuid;
 
 use crate::HunkAssignment;
 
@@ -95,3 +99,205 @@ pub(crate) fn assignments(
     }
     Ok(reconciled)
 }
+
+/// Assignments of worktree hunks, where hunks with lines that are assigned on their own are split into line selections.
+pub(crate) struct Split {
+    /// The assignments of whole hunks and of line selections, to be reconciled.
+    pub assignments: Vec<HunkAssignment>,
+    /// The assignments of the hunks that were split, along with the range of their line selections in `assignments`.
+    split_hunks: Vec<(HunkAssignment, Range<usize>)>,
+}
+
+/// Split each worktree hunk in `new` into [line selections](HunkHeader::is_line_selection()) if any of the assignments
+/// in `old` is a line selection of that hunk, so that the lines of the hunk can be assigned independently.
+///
+/// The hunk is split at the boundaries of all of these selections, so each of them matches one or more of the
+/// new line selections exactly.
+pub(crate) fn split_by_line_selections(new: &[HunkAssignment], old: &[HunkAssignment]) -> Split {
+    let mut assignments = Vec::with_capacity(new.len());
+    let mut split_hunks = Vec::new();
+    for hunk in new {
+        let (Some(header), Some(removed), Some(added)) = (
+            hunk.hunk_header,
+            hunk.line_nums_removed.as_deref(),
+            hunk.line_nums_added.as_deref(),
+        ) else {
+            assignments.push(hunk.clone());
+            continue;
+        };
+        let selections: Vec<HunkHeader> = old
+            .iter()
+            .filter(|assignment| hunk.intersects((*assignment).clone()))
+            .filter_map(|assignment| assignment.hunk_header)
+            .filter(|selection| selection.is_line_selection() && *selection != header)
+            .collect();
+        let boundaries = |range: fn(&HunkHeader) -> HunkRange| -> BTreeSet<usize> {
+            selections
+                .iter()
+                .map(range)
+                .filter(|range| !range.is_null())
+                .flat_map(|range| [range.start as usize, range.end() as usize])
+                .collect()
+        };
+        let pieces: Vec<HunkHeader> = line_runs(removed, &boundaries(HunkHeader::old_range))
+            .into_iter()
+            .map(|range| HunkHeader {
+                old_start: range.start,
+                old_lines: range.lines,
+                new_start: 0,
+                new_lines: 0,
+            })
+            .chain(
+                line_runs(added, &boundaries(HunkHeader::new_range))
+                    .into_iter()
+                    .map(|range| HunkHeader {
+                        old_start: 0,
+                        old_lines: 0,
+                        new_start: range.start,
+                        new_lines: range.lines,
+                    }),
+            )
+            .collect();
+        if selections.is_empty() || pieces.len() < 2 {
+            assignments.push(hunk.clone());
+            continue;
+        }
+
+        let start = assignments.len();
+        for (idx, piece) in pieces.into_iter().enumerate() {
+            let in_range =
+                |range: HunkRange, line: usize| (range.start..range.end()).contains(&(line as u32));
+            assignments.push(HunkAssignment {
+                id: if idx == 0 {
+                    hunk.id
+                } else {
+                    Some(Uuid::new_v4())
+                },
+                hunk_header: Some(piece),
+                path: hunk.path.clone(),
+                path_bytes: hunk.path_bytes.clone(),
+                stack_id: hunk.stack_id,
+                hunk_locks: hunk.hunk_locks.clone(),
+                line_nums_added: Some(
+                    added
+                        .iter()
+                        .copied()
+                        .filter(|line| in_range(piece.new_range(), *line))
+                        .collect(),
+                ),
+                line_nums_removed: Some(
+                    removed
+                        .iter()
+                        .copied()
+                        .filter(|line| in_range(piece.old_range(), *line))
+                        .collect(),
+                ),
+                diff: hunk
+                    .diff
+                    .as_ref()
+                    .map(|diff| selected_diff(diff.as_bstr(), header, piece)),
+            });
+        }
+        split_hunks.push((hunk.clone(), start..assignments.len()));
+    }
+    Split {
+        assignments,
+        split_hunks,
+    }
+}
+
+impl Split {
+    /// Join the line selections of each split hunk in `reconciled`, the reconciled [`assignments`](Self::assignments),
+    /// back into the assignment of the whole hunk if they are all assigned to the same stack.
+    pub(crate) fn join(&self, reconciled: Vec<HunkAssignment>) -> Vec<HunkAssignment> {
+        let mut out = Vec::with_capacity(reconciled.len());
+        let mut split_hunks = self.split_hunks.iter().peekable();
+        let mut idx = 0;
+        while idx < reconciled.len() {
+            let Some((hunk, range)) = split_hunks.next_if(|(_, range)| range.start == idx) else {
+                out.push(reconciled[idx].clone());
+                idx += 1;
+                continue;
+            };
+            let selections = &reconciled[range.clone()];
+            if selections
+                .iter()
+                .map(|selection| selection.stack_id)
+                .all_equal()
+            {
+                let mut hunk = hunk.clone();
+                hunk.id = selections[0].id;
+                hunk.stack_id = selections[0].stack_id;
+                hunk.hunk_locks = selections
+                    .iter()
+                    .filter_map(|selection| selection.hunk_locks.clone())
+                    .reduce(|mut locks, other| {
+                        for lock in other {
+                            if !locks.contains(&lock) {
+                                locks.push(lock);
+                            }
+                        }
+                        locks
+                    });
+                out.push(hunk);
+            } else {
+                // All selections may have adopted the id of the same whole hunk, but each needs its own.
+                let mut seen_ids = BTreeSet::new();
+                out.extend(selections.iter().cloned().map(|mut selection| {
+                    if selection.id.is_some_and(|id| !seen_ids.insert(id)) {
+                        selection.id = Some(Uuid::new_v4());
+                    }
+                    selection
+                }));
+            }
+            idx = range.end;
+        }
+        out
+    }
+}
+
+/// Return the ranges of consecutive line numbers in `lines`, with a new range starting at each of the `boundaries`.
+fn line_runs(lines: &[usize], boundaries: &BTreeSet<usize>) -> Vec<HunkRange> {
+    let mut runs: Vec<HunkRange> = Vec::new();
+    for &line in lines {
+        match runs.last_mut() {
+            Some(run) if run.end() as usize == line && !boundaries.contains(&line) => {
+                run.lines += 1
+            }
+            _ => runs.push(HunkRange {
+                start: line as u32,
+                lines: 1,
+            }),
+        }
+    }
+    runs
+}
+
+/// Return the removed and added lines of `diff`, the diff of the hunk with `header`, that are part of `selection`.
+pub(crate) fn selected_diff(diff: &BStr, header: HunkHeader, selection: HunkHeader) -> BString {
+    let mut out = BString::default();
+    let (mut old_line, mut new_line) = (header.old_start, header.new_start);
+    for line in diff.lines_with_terminator() {
+        match line.first() {
+            // `\ No newline at end of file` annotates the line before it and occupies no line.
+            Some(b'@') | Some(b'\\') => {}
+            Some(b'-') => {
+                if (selection.old_start..selection.old_range().end()).contains(&old_line) {
+                    out.extend_from_slice(line);
+                }
+                old_line += 1;
+            }
+            Some(b'+') => {
+                if (selection.new_start..selection.new_range().end()).contains(&new_line) {
+                    out.extend_from_slice(line);
+                }
+                new_line += 1;
+            }
+            _ => {
+                old_line += 1;
+                new_line += 1;
+            }
+        }
+    }
+    out
+}
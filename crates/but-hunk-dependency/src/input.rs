@@ -28,6 +28,8 @@ pub struct InputCommit {
 pub struct InputFile {
     /// The worktree-relative path to the file.
     pub path: BString,
+    /// The worktree-relative path to the file before it was renamed to `path`, if `change_type` is a rename.
+    pub previous_path: Option<BString>,
     /// The hunks that changed in this file.
     pub hunks: Vec<InputDiffHunk>,
     /// The kind of change of the parent file.
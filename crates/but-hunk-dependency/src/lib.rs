@@ -11,7 +11,8 @@
 //! What follows is research on how one could implement a perfectly *accurate* version of the existing algorithm that *doesn't* use patch context lines,
 //! while producing the result-blobs for each commit as needed 'automatically'.
 //!
-//! For now, this crate just ports `gitbutler-hunk-dependency` to `gix` types.
+//! For now, this crate ports `gitbutler-hunk-dependency` to `gix` types as [`WorkspaceRanges`], and offers [`WorkspaceLines`]
+//! as a first step, which is accurate as it doesn't rely on context lines but doesn't produce result-blobs.
 //!
 //! ### Terminology
 //!
@@ -133,10 +134,14 @@ use gitbutler_oxidize::ObjectIdExt;
 use gix::prelude::ObjectIdExt as _;
 use gix::trace;
 pub use input::{InputCommit, InputDiffHunk, InputFile, InputStack};
+use std::collections::HashMap;
 
 mod ranges;
 pub use ranges::{CalculationError, HunkRange, WorkspaceRanges};
 
+mod lines;
+pub use lines::{LineChange, LineOwner, WorkspaceLines};
+
 /// Types and conversions for use in `tauri`.
 pub mod ui;
 
@@ -179,6 +184,28 @@ pub fn workspace_stacks_to_input_stacks(
     Ok(out)
 }
 
+/// Produce the files that differ between the tip of each of `stacks` and `workspace_commit_id`, by stack id,
+/// for use in [`WorkspaceLines::from_stacks()`].
+pub fn workspace_stacks_to_workspace_files(
+    repo: &gix::Repository,
+    stacks: &[but_workspace::ui::StackEntry],
+    workspace_commit_id: gix::ObjectId,
+) -> anyhow::Result<HashMap<but_workspace::StackId, Vec<InputFile>>> {
+    let mut out = HashMap::new();
+    for stack in stacks {
+        let Some(stack_id) = stack.id else {
+            continue;
+        };
+        if stack.tip == workspace_commit_id {
+            continue;
+        }
+        let (tree_changes, _) =
+            but_core::diff::tree_changes(repo, Some(stack.tip), workspace_commit_id)?;
+        out.insert(stack_id, tree_changes_to_input_files(repo, tree_changes)?);
+    }
+    Ok(out)
+}
+
 /// Turn `changes` with [`TreeChange`] instances into [`InputFile`], one for each input.
 pub fn tree_changes_to_input_files(
     repo: &gix::Repository,
@@ -195,8 +222,10 @@ pub fn tree_changes_to_input_files(
             continue;
         };
         let change_type = change.status.kind();
+        let previous_path = change.previous_path().map(ToOwned::to_owned);
         files.push(InputFile {
             path: change.path,
+            previous_path,
             hunks: hunks.iter().map(InputDiffHunk::from_unified_diff).collect(),
             change_type,
         })
@@ -0,0 +1,305 @@
+use crate::{InputCommit, InputDiffHunk, InputFile, InputStack};
+use but_core::TreeStatusKind;
+use but_workspace::StackId;
+use gix::bstr::BString;
+use std::collections::{BTreeMap, HashMap};
+
+/// The commit that introduced a line or a deletion, along with the stack that contains it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LineOwner {
+    /// The stack that contains [`commit_id`](Self::commit_id).
+    pub stack_id: StackId,
+    /// The commit that introduced the line.
+    pub commit_id: gix::ObjectId,
+}
+
+/// All hunk-dependencies for the entire workspace, computed by tracking which commit introduced each line.
+///
+/// Unlike [`WorkspaceRanges`](crate::WorkspaceRanges), this doesn't consider lines next to a committed change
+/// as part of it, so a worktree hunk only depends on a commit if it changes lines that the commit introduced,
+/// removes lines around a place where the commit deleted lines, or adds lines in between lines that the commit
+/// introduced. Files that were renamed by a commit depend on it as well.
+#[derive(Debug, Default)]
+pub struct WorkspaceLines {
+    paths: HashMap<BString, PathLines>,
+}
+
+/// The lines of a file that were introduced by commits, with line numbers of a specific version of that file.
+#[derive(Debug, Default, Clone)]
+struct PathLines {
+    /// The commits that introduced a line, by 1-based line number.
+    lines: BTreeMap<u32, Vec<LineOwner>>,
+    /// The commits that deleted lines right after the line with the given number, or at the top of the file if it's `0`.
+    deletions: BTreeMap<u32, Vec<LineOwner>>,
+    /// The commits that deleted the whole file, which all hunks in it depend on.
+    file_deletions: Vec<LineOwner>,
+    /// The commits that renamed the file, from the first to the last rename, which all hunks in it depend on
+    /// as the file doesn't exist under its name without them.
+    renames: Vec<LineOwner>,
+}
+
+impl WorkspaceLines {
+    /// Calculates all lines for the workspace, which is identified by `input_stacks`, i.e. all stacks that make up
+    /// that workspace.
+    ///
+    /// As the line numbers are tracked in the version of each file at the tip of a stack, `tips_to_workspace`
+    /// provides the changes between the tip of each stack and the workspace commit, by stack id, to learn the
+    /// line numbers in the workspace commit. Stacks without an entry are assumed to match the workspace commit.
+    pub fn from_stacks(
+        input_stacks: Vec<InputStack>,
+        tips_to_workspace: &HashMap<StackId, Vec<InputFile>>,
+    ) -> WorkspaceLines {
+        let mut paths = HashMap::<BString, PathLines>::new();
+        for InputStack {
+            stack_id,
+            commits_from_base_to_tip,
+        } in input_stacks
+        {
+            let mut stack_paths = HashMap::<BString, PathLines>::new();
+            for InputCommit { commit_id, files } in commits_from_base_to_tip {
+                let owner = LineOwner {
+                    stack_id,
+                    commit_id,
+                };
+                for file in files {
+                    if let Some(previous_path) = file
+                        .previous_path
+                        .as_ref()
+                        .filter(|previous_path| **previous_path != file.path)
+                    {
+                        let mut lines = stack_paths.remove(previous_path).unwrap_or_default();
+                        lines.renames.push(owner);
+                        stack_paths.insert(file.path.clone(), lines);
+                    }
+                    stack_paths.entry(file.path).or_default().apply_commit(
+                        owner,
+                        file.change_type,
+                        &file.hunks,
+                    );
+                }
+            }
+            for file in tips_to_workspace.get(&stack_id).into_iter().flatten() {
+                let previous_path = file.previous_path.as_ref().unwrap_or(&file.path);
+                if let Some(mut lines) = stack_paths.remove(previous_path) {
+                    lines.apply_to_workspace(file.change_type, &file.hunks);
+                    stack_paths.insert(file.path.clone(), lines);
+                }
+            }
+            for (path, lines) in stack_paths {
+                paths.entry(path).or_default().merge(lines);
+            }
+        }
+        WorkspaceLines { paths }
+    }
+
+    /// Finds the commits that the hunk at `path`, removing `lines` starting at `start` in the workspace commit,
+    /// depends on. If `lines` is `0`, the hunk adds lines after the line `start`.
+    pub fn intersection(&self, path: &BString, start: u32, lines: u32) -> Option<Vec<LineOwner>> {
+        let mut unique = Vec::new();
+        for (owner, _) in self.intersecting_changes(path, start, lines) {
+            if !unique.contains(&owner) {
+                unique.push(owner);
+            }
+        }
+        (!unique.is_empty()).then_some(unique)
+    }
+
+    /// Like [`intersection()`](Self::intersection()), but returns each change of a commit that the hunk depends
+    /// on, with consecutive lines introduced by the same commit combined into one change.
+    pub fn intersecting_changes(
+        &self,
+        path: &BString,
+        start: u32,
+        lines: u32,
+    ) -> Vec<(LineOwner, LineChange)> {
+        let Some(path_lines) = self.paths.get(path) else {
+            return Vec::new();
+        };
+        let mut out: Vec<_> = path_lines
+            .file_deletions
+            .iter()
+            .map(|owner| (*owner, LineChange::FileDeletion))
+            .chain(
+                path_lines
+                    .renames
+                    .iter()
+                    .map(|owner| (*owner, LineChange::Rename)),
+            )
+            .collect();
+        if lines == 0 {
+            // Lines added in between lines that were both introduced by the same commit.
+            let before = path_lines.lines.get(&start).into_iter().flatten();
+            let after = path_lines.lines.get(&(start + 1));
+            out.extend(
+                before
+                    .filter(|owner| after.is_some_and(|after| after.contains(owner)))
+                    .map(|owner| (*owner, LineChange::Lines { start, lines: 2 })),
+            );
+        } else {
+            let end = start + lines;
+            for (line, owners) in path_lines.lines.range(start..end) {
+                for owner in owners {
+                    let run = out.iter_mut().find_map(|(run_owner, change)| match change {
+                        LineChange::Lines { start, lines }
+                            if run_owner == owner && *start + *lines == *line =>
+                        {
+                            Some(lines)
+                        }
+                        _ => None,
+                    });
+                    match run {
+                        Some(lines) => *lines += 1,
+                        None => out.push((
+                            *owner,
+                            LineChange::Lines {
+                                start: *line,
+                                lines: 1,
+                            },
+                        )),
+                    }
+                }
+            }
+            // Deletions in between removed lines, but not those right before or after them.
+            out.extend(path_lines.deletions.range(start..end - 1).flat_map(
+                |(after_line, owners)| {
+                    owners.iter().map(|owner| {
+                        (
+                            *owner,
+                            LineChange::Deletion {
+                                after_line: *after_line,
+                            },
+                        )
+                    })
+                },
+            ));
+        }
+        out
+    }
+}
+
+/// A change of a commit that a hunk depends on, as returned by [`WorkspaceLines::intersecting_changes()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineChange {
+    /// The commit introduced `lines` lines starting at the 1-based line `start`.
+    Lines {
+        /// The 1-based number of the first line.
+        start: u32,
+        /// The amount of lines.
+        lines: u32,
+    },
+    /// The commit deleted lines right after the line `after_line`, or at the top of the file if it's `0`.
+    Deletion {
+        /// The 1-based number of the line that preceded the deleted lines.
+        after_line: u32,
+    },
+    /// The commit deleted the whole file.
+    FileDeletion,
+    /// The commit renamed the file.
+    Rename,
+}
+
+impl PathLines {
+    /// Track the changes of the commit of `owner`, whose `hunks` have zero context lines.
+    fn apply_commit(
+        &mut self,
+        owner: LineOwner,
+        change_type: TreeStatusKind,
+        hunks: &[InputDiffHunk],
+    ) {
+        match change_type {
+            TreeStatusKind::Deletion => {
+                *self = PathLines {
+                    file_deletions: vec![owner],
+                    ..Default::default()
+                };
+                return;
+            }
+            TreeStatusKind::Addition => *self = PathLines::default(),
+            TreeStatusKind::Modification | TreeStatusKind::Rename => self.map_through(hunks),
+        }
+        for hunk in hunks {
+            if hunk.new_lines == 0 {
+                self.deletions
+                    .entry(hunk.new_start)
+                    .or_default()
+                    .push(owner);
+            }
+            for line in hunk.new_start..hunk.new_start + hunk.new_lines {
+                self.lines.insert(line, vec![owner]);
+            }
+        }
+    }
+
+    /// Adjust line numbers to the version of the file in the workspace commit, with `hunks` being the changes
+    /// from the version at the tip of the stack.
+    fn apply_to_workspace(&mut self, change_type: TreeStatusKind, hunks: &[InputDiffHunk]) {
+        match change_type {
+            // The other stacks would conflict, but the lines of this one are gone in any case.
+            TreeStatusKind::Deletion | TreeStatusKind::Addition => {
+                self.lines.clear();
+                self.deletions.clear();
+            }
+            TreeStatusKind::Modification | TreeStatusKind::Rename => self.map_through(hunks),
+        }
+    }
+
+    /// Change all line numbers to match the new version of the file after `hunks` were applied to it,
+    /// dropping all lines that were removed and all deletions that were touched by a hunk.
+    fn map_through(&mut self, hunks: &[InputDiffHunk]) {
+        self.lines = std::mem::take(&mut self.lines)
+            .into_iter()
+            .filter(|(line, _)| !hunks.iter().any(|hunk| removes(hunk, *line)))
+            .map(|(line, owners)| (shifted(hunks, line), owners))
+            .collect();
+        self.deletions = std::mem::take(&mut self.deletions)
+            .into_iter()
+            .filter(|(after_line, _)| {
+                !hunks.iter().any(|hunk| {
+                    removes(hunk, *after_line)
+                        || removes(hunk, after_line + 1)
+                        || (hunk.old_lines == 0 && hunk.old_start == *after_line)
+                })
+            })
+            .map(|(after_line, owners)| (shifted(hunks, after_line), owners))
+            .collect();
+    }
+
+    /// Add the lines and deletions of `other`, a different stack, which are expected not to overlap with ours.
+    fn merge(&mut self, other: PathLines) {
+        for (line, owners) in other.lines {
+            self.lines.entry(line).or_default().extend(owners);
+        }
+        for (after_line, owners) in other.deletions {
+            self.deletions.entry(after_line).or_default().extend(owners);
+        }
+        self.file_deletions.extend(other.file_deletions);
+        self.renames.extend(other.renames);
+    }
+}
+
+/// Return `true` if `hunk` removes `line`.
+fn removes(hunk: &InputDiffHunk, line: u32) -> bool {
+    hunk.old_lines > 0 && (hunk.old_start..hunk.old_start + hunk.old_lines).contains(&line)
+}
+
+/// Return the number that `line` has in the new version of the file after all `hunks` were applied,
+/// assuming it wasn't removed by one of them.
+fn shifted(hunks: &[InputDiffHunk], line: u32) -> u32 {
+    let shift: i64 = hunks
+        .iter()
+        .filter(|hunk| {
+            // Added lines follow `old_start` if nothing was removed.
+            let last_old_line = if hunk.old_lines == 0 {
+                hunk.old_start
+            } else {
+                hunk.old_start + hunk.old_lines - 1
+            };
+            last_old_line < line
+        })
+        .map(|hunk| hunk.new_lines as i64 - hunk.old_lines as i64)
+        .sum();
+    (line as i64 + shift).max(0) as u32
+}
+
+#[cfg(test)]
+mod tests;
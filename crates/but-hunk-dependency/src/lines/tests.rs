@@ -0,0 +1,333 @@
+use crate::lines::{LineChange, LineOwner, WorkspaceLines};
+use crate::{InputCommit, InputDiffHunk, InputFile, InputStack};
+use but_core::TreeStatusKind;
+use but_workspace::StackId;
+use gix::bstr::BString;
+use std::collections::HashMap;
+
+/// Create a new object id by repeating the given `hex_char`.
+fn id_from_hex_char(hex_char: char) -> gix::ObjectId {
+    gix::ObjectId::from_hex(String::from_iter(std::iter::repeat_n(hex_char, 40)).as_bytes())
+        .expect("input char is hex-only")
+}
+
+fn hunk(old_start: u32, old_lines: u32, new_start: u32, new_lines: u32) -> InputDiffHunk {
+    InputDiffHunk {
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+    }
+}
+
+fn modification(path: &BString, hunks: Vec<InputDiffHunk>) -> InputFile {
+    InputFile {
+        path: path.clone(),
+        previous_path: None,
+        hunks,
+        change_type: TreeStatusKind::Modification,
+    }
+}
+
+fn commit(hex_char: char, files: Vec<InputFile>) -> InputCommit {
+    InputCommit {
+        commit_id: id_from_hex_char(hex_char),
+        files,
+    }
+}
+
+fn owner(stack_id: StackId, hex_char: char) -> LineOwner {
+    LineOwner {
+        stack_id,
+        commit_id: id_from_hex_char(hex_char),
+    }
+}
+
+#[test]
+fn lines_next_to_committed_lines_are_independent() {
+    let path = BString::from("file");
+    let stack_id = StackId::generate();
+    let lines = WorkspaceLines::from_stacks(
+        vec![InputStack {
+            stack_id,
+            commits_from_base_to_tip: vec![commit(
+                '1',
+                vec![modification(&path, vec![hunk(5, 1, 5, 1)])],
+            )],
+        }],
+        &HashMap::new(),
+    );
+
+    assert_eq!(
+        lines.intersection(&path, 5, 1),
+        Some(vec![owner(stack_id, '1')])
+    );
+    assert_eq!(
+        lines.intersection(&path, 4, 3),
+        Some(vec![owner(stack_id, '1')])
+    );
+    assert_eq!(lines.intersection(&path, 4, 1), None, "the line before");
+    assert_eq!(lines.intersection(&path, 6, 1), None, "the line after");
+    assert_eq!(
+        lines.intersection(&path, 5, 0),
+        None,
+        "adding after the last line"
+    );
+    assert_eq!(
+        lines.intersection(&path, 4, 0),
+        None,
+        "adding before the first line"
+    );
+    assert_eq!(lines.intersection(&BString::from("other"), 5, 1), None);
+}
+
+#[test]
+fn lines_are_tracked_through_commits() {
+    let path = BString::from("file");
+    let stack_id = StackId::generate();
+    let lines = WorkspaceLines::from_stacks(
+        vec![InputStack {
+            stack_id,
+            commits_from_base_to_tip: vec![
+                // Add lines 3 and 4.
+                commit('1', vec![modification(&path, vec![hunk(2, 0, 3, 2)])]),
+                // Remove the first line, and the line that is now line 6.
+                commit(
+                    '2',
+                    vec![modification(
+                        &path,
+                        vec![hunk(1, 1, 0, 0), hunk(6, 1, 4, 0)],
+                    )],
+                ),
+            ],
+        }],
+        &HashMap::new(),
+    );
+
+    assert_eq!(
+        lines.intersection(&path, 2, 2),
+        Some(vec![owner(stack_id, '1')])
+    );
+    assert_eq!(
+        lines.intersection(&path, 2, 0),
+        Some(vec![owner(stack_id, '1')]),
+        "adding in between lines of the same commit"
+    );
+    assert_eq!(lines.intersection(&path, 1, 1), None);
+    assert_eq!(
+        lines.intersection(&path, 4, 2),
+        Some(vec![owner(stack_id, '2')]),
+        "removing the lines around a deletion"
+    );
+    assert_eq!(
+        lines.intersection(&path, 3, 2),
+        Some(vec![owner(stack_id, '1')]),
+        "the deletion is right after the removed lines"
+    );
+    assert_eq!(lines.intersection(&path, 5, 1), None);
+}
+
+#[test]
+fn lines_are_tracked_into_the_workspace_commit() {
+    let path = BString::from("file");
+    let (stack1_id, stack2_id) = (StackId::generate(), StackId::generate());
+    let lines = WorkspaceLines::from_stacks(
+        vec![
+            InputStack {
+                stack_id: stack1_id,
+                // Add two lines at the top.
+                commits_from_base_to_tip: vec![commit(
+                    '1',
+                    vec![modification(&path, vec![hunk(0, 0, 1, 2)])],
+                )],
+            },
+            InputStack {
+                stack_id: stack2_id,
+                commits_from_base_to_tip: vec![commit(
+                    '2',
+                    vec![modification(&path, vec![hunk(10, 1, 10, 1)])],
+                )],
+            },
+        ],
+        &HashMap::from([
+            (
+                stack1_id,
+                vec![modification(&path, vec![hunk(12, 1, 12, 1)])],
+            ),
+            (stack2_id, vec![modification(&path, vec![hunk(0, 0, 1, 2)])]),
+        ]),
+    );
+
+    assert_eq!(
+        lines.intersection(&path, 1, 2),
+        Some(vec![owner(stack1_id, '1')])
+    );
+    assert_eq!(
+        lines.intersection(&path, 12, 1),
+        Some(vec![owner(stack2_id, '2')])
+    );
+    assert_eq!(lines.intersection(&path, 10, 1), None);
+}
+
+#[test]
+fn deleted_files_are_a_dependency_of_all_hunks() {
+    let path = BString::from("file");
+    let stack_id = StackId::generate();
+    let lines = WorkspaceLines::from_stacks(
+        vec![InputStack {
+            stack_id,
+            commits_from_base_to_tip: vec![commit(
+                '1',
+                vec![InputFile {
+                    path: path.clone(),
+                    previous_path: None,
+                    hunks: vec![hunk(1, 5, 0, 0)],
+                    change_type: TreeStatusKind::Deletion,
+                }],
+            )],
+        }],
+        &HashMap::new(),
+    );
+
+    assert_eq!(
+        lines.intersection(&path, 0, 0),
+        Some(vec![owner(stack_id, '1')])
+    );
+}
+
+#[test]
+fn lines_follow_a_chain_of_renames() {
+    let (a, b, c) = (BString::from("a"), BString::from("b"), BString::from("c"));
+    let stack_id = StackId::generate();
+    let rename = |from: &BString, to: &BString, hunks: Vec<InputDiffHunk>| InputFile {
+        path: to.clone(),
+        previous_path: Some(from.clone()),
+        hunks,
+        change_type: TreeStatusKind::Rename,
+    };
+    let lines = WorkspaceLines::from_stacks(
+        vec![InputStack {
+            stack_id,
+            commits_from_base_to_tip: vec![
+                commit('1', vec![modification(&a, vec![hunk(2, 1, 2, 1)])]),
+                commit('2', vec![rename(&a, &b, vec![])]),
+                // Add a line at the top, which moves the line of the first commit down.
+                commit('3', vec![rename(&b, &c, vec![hunk(0, 0, 1, 1)])]),
+            ],
+        }],
+        &HashMap::new(),
+    );
+
+    assert_eq!(
+        lines.intersection(&c, 3, 1),
+        Some(vec![
+            owner(stack_id, '2'),
+            owner(stack_id, '3'),
+            owner(stack_id, '1')
+        ])
+    );
+    assert_eq!(
+        lines.intersection(&c, 10, 1),
+        Some(vec![owner(stack_id, '2'), owner(stack_id, '3')]),
+        "all hunks depend on the renames"
+    );
+    assert_eq!(lines.intersection(&a, 2, 1), None);
+    assert_eq!(lines.intersection(&b, 2, 1), None);
+}
+
+#[test]
+fn intersecting_changes_combine_consecutive_lines_of_a_commit() {
+    let path = BString::from("file");
+    let stack_id = StackId::generate();
+    let lines = WorkspaceLines::from_stacks(
+        vec![InputStack {
+            stack_id,
+            commits_from_base_to_tip: vec![
+                // Replace lines 2 to 4.
+                commit('1', vec![modification(&path, vec![hunk(2, 3, 2, 3)])]),
+                // Change the line in the middle, and delete the line after the last one.
+                commit(
+                    '2',
+                    vec![modification(
+                        &path,
+                        vec![hunk(3, 1, 3, 1), hunk(5, 1, 4, 0)],
+                    )],
+                ),
+            ],
+        }],
+        &HashMap::new(),
+    );
+
+    assert_eq!(
+        lines.intersecting_changes(&path, 1, 5),
+        [
+            (
+                owner(stack_id, '1'),
+                LineChange::Lines { start: 2, lines: 1 }
+            ),
+            (
+                owner(stack_id, '2'),
+                LineChange::Lines { start: 3, lines: 1 }
+            ),
+            (
+                owner(stack_id, '1'),
+                LineChange::Lines { start: 4, lines: 1 }
+            ),
+            (owner(stack_id, '2'), LineChange::Deletion { after_line: 4 }),
+        ]
+    );
+    assert_eq!(
+        lines.intersecting_changes(&path, 3, 2),
+        [
+            (
+                owner(stack_id, '2'),
+                LineChange::Lines { start: 3, lines: 1 }
+            ),
+            (
+                owner(stack_id, '1'),
+                LineChange::Lines { start: 4, lines: 1 }
+            ),
+        ],
+        "the deletion is right after the removed lines"
+    );
+    assert_eq!(
+        lines.intersecting_changes(&path, 2, 0),
+        [],
+        "the lines around the addition are of different commits"
+    );
+    assert_eq!(
+        lines.intersection(&path, 1, 5).map(|owners| owners.len()),
+        Some(2)
+    );
+}
+
+#[test]
+fn intersecting_changes_of_additions_are_the_lines_around_them() {
+    let path = BString::from("file");
+    let stack_id = StackId::generate();
+    let lines = WorkspaceLines::from_stacks(
+        vec![InputStack {
+            stack_id,
+            commits_from_base_to_tip: vec![commit(
+                '1',
+                vec![modification(&path, vec![hunk(2, 1, 2, 3)])],
+            )],
+        }],
+        &HashMap::new(),
+    );
+
+    assert_eq!(
+        lines.intersecting_changes(&path, 1, 4),
+        [(
+            owner(stack_id, '1'),
+            LineChange::Lines { start: 2, lines: 3 }
+        )]
+    );
+    assert_eq!(
+        lines.intersecting_changes(&path, 3, 0),
+        [(
+            owner(stack_id, '1'),
+            LineChange::Lines { start: 3, lines: 2 }
+        )]
+    );
+}
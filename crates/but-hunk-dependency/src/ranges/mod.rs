@@ -16,6 +16,8 @@ use paths::PathRanges;
 #[derive(Debug)]
 pub struct WorkspaceRanges {
     paths: HashMap<BString, Vec<HunkRange>>,
+    /// The commits that renamed the file at a path, see [`StackRanges::renames`].
+    renames: HashMap<BString, Vec<HunkRange>>,
     /// Errors that occurred while computing the fields in this instance.
     pub errors: Vec<CalculationError>,
 }
@@ -35,6 +37,9 @@ pub struct CalculationError {
 #[derive(Debug, Default)]
 struct StackRanges {
     paths: HashMap<BString, PathRanges>,
+    /// The commits that renamed the file at a path, one for each rename, as ranges without lines that intersect
+    /// with all hunks in the file as the file doesn't exist under its name without these commits.
+    renames: HashMap<BString, Vec<HunkRange>>,
 }
 
 /// A struct for collecting hunk ranges by path, before they get merged into a single dimension
@@ -48,6 +53,12 @@ impl StackRanges {
         change_type: TreeStatusKind,
         diffs: Vec<InputDiffHunk>,
     ) -> anyhow::Result<()> {
+        if matches!(
+            change_type,
+            TreeStatusKind::Addition | TreeStatusKind::Deletion
+        ) {
+            self.renames.remove(&path);
+        }
         self.paths
             .entry(path)
             .or_default()
@@ -56,6 +67,31 @@ impl StackRanges {
         Ok(())
     }
 
+    /// Let the ranges of the file at `previous_path` continue at `path`, as `commit_id` renamed it.
+    ///
+    /// This also works for chains of renames, with each of the renaming commits being a dependency of `path`.
+    fn rename(
+        &mut self,
+        stack_id: StackId,
+        commit_id: gix::ObjectId,
+        previous_path: &BString,
+        path: BString,
+    ) {
+        if let Some(ranges) = self.paths.remove(previous_path) {
+            self.paths.insert(path.clone(), ranges);
+        }
+        let mut renames = self.renames.remove(previous_path).unwrap_or_default();
+        renames.push(HunkRange {
+            change_type: TreeStatusKind::Rename,
+            stack_id,
+            commit_id,
+            start: 0,
+            lines: 0,
+            line_shift: 0,
+        });
+        self.renames.insert(path, renames);
+    }
+
     pub fn unique_paths(&self) -> HashSet<BString> {
         self.paths
             .keys()
@@ -91,6 +127,13 @@ impl WorkspaceRanges {
             for commit in commits {
                 let InputCommit { commit_id, files } = commit;
                 for file in files {
+                    if let Some(previous_path) = file
+                        .previous_path
+                        .as_ref()
+                        .filter(|previous_path| **previous_path != file.path)
+                    {
+                        stack_ranges.rename(stack_id, commit_id, previous_path, file.path.clone());
+                    }
                     if let Some(error) = stack_ranges
                         .add(
                             stack_id,
@@ -117,27 +160,42 @@ impl WorkspaceRanges {
             .flat_map(StackRanges::unique_paths)
             .unique()
             .collect_vec();
+        let mut renames = HashMap::<BString, Vec<HunkRange>>::new();
+        for stack in &mut stacks {
+            for (path, ranges) in stack.renames.drain() {
+                renames.entry(path).or_default().extend(ranges);
+            }
+        }
         Ok(WorkspaceRanges {
             paths: paths
                 .iter()
                 .map(|path| (path.clone(), combine_path_ranges(path, &stacks)))
                 .collect(),
+            renames,
             errors,
         })
     }
 
     /// Finds commits that intersect with a given path and range combination.
+    ///
+    /// If the file at `path` was renamed, the commits that renamed it are included as well, after the commits
+    /// whose changes intersect.
     pub fn intersection(&self, path: &BString, start: u32, lines: u32) -> Option<Vec<&HunkRange>> {
-        if let Some(hunk_range) = self.paths.get(path) {
-            let intersection = hunk_range
-                .iter()
-                .filter(|hunk| hunk.intersects(start, lines).unwrap_or(false))
-                .collect_vec();
-            if !intersection.is_empty() {
-                return Some(intersection);
-            }
-        }
-        None
+        let mut intersection = self
+            .paths
+            .get(path)
+            .into_iter()
+            .flatten()
+            .filter(|hunk| hunk.intersects(start, lines).unwrap_or(false))
+            .collect_vec();
+        intersection.extend(self.renames.get(path).into_iter().flatten());
+        (!intersection.is_empty()).then_some(intersection)
+    }
+
+    /// Return the commits that renamed the file at `path`, which are part of each [`intersection`](Self::intersection())
+    /// with it.
+    pub fn renames(&self, path: &BString) -> &[HunkRange] {
+        self.renames.get(path).map_or(&[], Vec::as_slice)
     }
 
     /// Return a reference to the internal mapping that is used for [`Self::intersection()`]
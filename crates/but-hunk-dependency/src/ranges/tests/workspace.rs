@@ -23,6 +23,7 @@ fn workspace_simple() -> anyhow::Result<()> {
                 commit_id: commit1_id,
                 files: vec![InputFile {
                     path: path.clone(),
+                    previous_path: None,
                     change_type: TreeStatusKind::Modification,
                     hunks: vec![InputDiffHunk {
                         old_start: 2,
@@ -40,6 +41,7 @@ fn workspace_simple() -> anyhow::Result<()> {
                 files: vec![InputFile {
                     change_type: TreeStatusKind::Modification,
                     path: path.clone(),
+                    previous_path: None,
                     hunks: vec![
                         input_hunk_from_unified_diff(
                             "@@ -6,8 +6,6 @@
@@ -106,6 +108,7 @@ fn gracefully_handle_invalid_input_commits() -> anyhow::Result<()> {
                 commit_id: commit_a_id, // Delete file
                 files: vec![InputFile {
                     path: path.clone(),
+                    previous_path: None,
                     change_type: TreeStatusKind::Deletion,
                     hunks: vec![InputDiffHunk {
                         old_start: 1,
@@ -119,6 +122,7 @@ fn gracefully_handle_invalid_input_commits() -> anyhow::Result<()> {
                 commit_id: commit_b_id, // Delete file, again
                 files: vec![InputFile {
                     path: path.clone(),
+                    previous_path: None,
                     change_type: TreeStatusKind::Deletion,
                     hunks: vec![InputDiffHunk {
                         old_start: 1,
@@ -132,6 +136,7 @@ fn gracefully_handle_invalid_input_commits() -> anyhow::Result<()> {
                 commit_id: commit_c_id, // Re-add file
                 files: vec![InputFile {
                     path: path.clone(),
+                    previous_path: None,
                     change_type: TreeStatusKind::Addition,
                     hunks: vec![InputDiffHunk {
                         old_start: 0,
@@ -161,3 +166,81 @@ fn gracefully_handle_invalid_input_commits() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn ranges_follow_a_chain_of_renames() -> anyhow::Result<()> {
+    let (a, b, c) = (BString::from("a"), BString::from("b"), BString::from("c"));
+    let stack_id = StackId::generate();
+    let rename = |from: &BString, to: &BString, hunks: Vec<InputDiffHunk>| InputFile {
+        path: to.clone(),
+        previous_path: Some(from.clone()),
+        change_type: TreeStatusKind::Rename,
+        hunks,
+    };
+
+    let workspace_ranges = WorkspaceRanges::try_from_stacks(vec![InputStack {
+        stack_id,
+        commits_from_base_to_tip: vec![
+            InputCommit {
+                commit_id: id_from_hex_char('1'),
+                files: vec![InputFile {
+                    path: a.clone(),
+                    previous_path: None,
+                    change_type: TreeStatusKind::Modification,
+                    hunks: vec![InputDiffHunk {
+                        old_start: 2,
+                        old_lines: 1,
+                        new_start: 2,
+                        new_lines: 1,
+                    }],
+                }],
+            },
+            InputCommit {
+                commit_id: id_from_hex_char('2'),
+                files: vec![rename(&a, &b, vec![])],
+            },
+            InputCommit {
+                commit_id: id_from_hex_char('3'),
+                files: vec![rename(
+                    &b,
+                    &c,
+                    vec![InputDiffHunk {
+                        old_start: 8,
+                        old_lines: 1,
+                        new_start: 8,
+                        new_lines: 1,
+                    }],
+                )],
+            },
+        ],
+    }])?;
+
+    let dependencies = workspace_ranges.intersection(&c, 2, 1).unwrap();
+    assert_eq!(
+        dependencies
+            .iter()
+            .map(|range| (range.commit_id, range.change_type))
+            .collect::<Vec<_>>(),
+        [
+            (id_from_hex_char('1'), TreeStatusKind::Modification),
+            (id_from_hex_char('2'), TreeStatusKind::Rename),
+            (id_from_hex_char('3'), TreeStatusKind::Rename),
+        ],
+        "the range of the commit before the renames is found under the new name"
+    );
+
+    let dependencies = workspace_ranges.intersection(&c, 20, 1).unwrap();
+    assert_eq!(
+        dependencies
+            .iter()
+            .map(|range| range.commit_id)
+            .collect::<Vec<_>>(),
+        [id_from_hex_char('2'), id_from_hex_char('3')],
+        "all hunks depend on the renames"
+    );
+    assert_eq!(workspace_ranges.renames(&c).len(), 2);
+
+    assert!(workspace_ranges.intersection(&a, 2, 1).is_none());
+    assert!(workspace_ranges.intersection(&b, 2, 1).is_none());
+    Ok(())
+}
@@ -1,43 +1,300 @@
 use but_core::UnifiedDiff;
 use but_core::unified_diff::DiffHunk;
+use but_settings::app_settings;
 use gitbutler_command_context::{CommandContext, gix_repo_for_merging};
 use gitbutler_oxidize::OidExt;
 use gitbutler_stack::StackId;
+use gix::bstr::{BStr, BString, ByteSlice};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::Path;
 
-/// Compute the hunk dependencies of a set of tree changes.
+use crate::LineChange;
+
+/// The way hunk dependencies are calculated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Algorithm {
+    /// Track the ranges of committed hunks through the commits of each stack with [`crate::WorkspaceRanges`].
+    #[default]
+    Ranges,
+    /// Track the commit that introduced each line through the commits of each stack with [`crate::WorkspaceLines`],
+    /// so changes right next to committed lines don't depend on them.
+    Lines,
+}
+
+/// Options for use in [`hunk_dependencies_for_workspace_changes_by_worktree_dir()`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Options {
+    /// The algorithm to calculate the hunk dependencies with.
+    pub algorithm: Algorithm,
+    /// If `true`, also calculate the hunk dependencies with the other algorithm and record each hunk for which
+    /// the outcomes differ in [`HunkDependencies::disagreements`].
+    pub compare: bool,
+}
+
+impl From<app_settings::HunkDependencies> for Options {
+    fn from(
+        app_settings::HunkDependencies { algorithm, compare }: app_settings::HunkDependencies,
+    ) -> Self {
+        Options {
+            algorithm: match algorithm {
+                app_settings::HunkDependencyAlgorithm::Ranges => Algorithm::Ranges,
+                app_settings::HunkDependencyAlgorithm::Lines => Algorithm::Lines,
+            },
+            compare,
+        }
+    }
+}
+
+/// Compute the hunk dependencies of a set of tree changes, as configured by `options`.
 pub fn hunk_dependencies_for_changes(
     ctx: &CommandContext,
     worktree_dir: &Path,
     gitbutler_dir: &Path,
     changes: Vec<but_core::TreeChange>,
+    options: Options,
 ) -> anyhow::Result<HunkDependencies> {
     // accelerate tree-tree-diffs
     let repo = gix_repo_for_merging(worktree_dir)?.with_object_memory();
-    let stacks = but_workspace::stacks(ctx, gitbutler_dir, &repo, Default::default())?;
+    let (stacks, input_stacks) = workspace_input_stacks(ctx, &repo, gitbutler_dir)?;
+    let with_ranges = |input_stacks: Vec<crate::InputStack>| {
+        let ranges = crate::WorkspaceRanges::try_from_stacks(input_stacks)?;
+        HunkDependencies::try_from_workspace_ranges(&repo, ranges, changes.clone())
+    };
+    let with_lines = |input_stacks: Vec<crate::InputStack>| {
+        let lines = workspace_lines(&repo, &stacks, input_stacks)?;
+        HunkDependencies::try_from_workspace_lines(&repo, &lines, changes.clone())
+    };
+
+    let (mut deps, other) = match (options.algorithm, options.compare) {
+        (Algorithm::Ranges, false) => (with_ranges(input_stacks)?, None),
+        (Algorithm::Lines, false) => (with_lines(input_stacks)?, None),
+        (Algorithm::Ranges, true) => (
+            with_ranges(input_stacks.clone())?,
+            Some(with_lines(input_stacks)?),
+        ),
+        (Algorithm::Lines, true) => (
+            with_lines(input_stacks.clone())?,
+            Some(with_ranges(input_stacks)?),
+        ),
+    };
+    if let Some(other) = other {
+        deps.disagreements = deps.disagreements_with(&other);
+        for Disagreement {
+            path,
+            hunk,
+            locks,
+            other_locks,
+        } in &deps.disagreements
+        {
+            gix::trace::warn!(
+                "Hunk dependency algorithms disagree on hunk -{},{} +{},{} in '{path}': {locks:?} with {:?}, {other_locks:?} otherwise",
+                hunk.old_start,
+                hunk.old_lines,
+                hunk.new_start,
+                hunk.new_lines,
+                options.algorithm
+            );
+        }
+    }
+    Ok(deps)
+}
+
+/// Return the stacks of the workspace in `repo`, using `gitbutler_dir` for obtaining stack information, along with
+/// their commits as input for the calculation of hunk dependencies.
+fn workspace_input_stacks(
+    ctx: &CommandContext,
+    repo: &gix::Repository,
+    gitbutler_dir: &Path,
+) -> anyhow::Result<(Vec<but_workspace::ui::StackEntry>, Vec<crate::InputStack>)> {
+    let stacks = but_workspace::stacks(ctx, gitbutler_dir, repo, Default::default())?;
     let common_merge_base = gitbutler_stack::VirtualBranchesHandle::new(gitbutler_dir)
         .get_default_target()?
         .sha;
     let input_stacks =
-        crate::workspace_stacks_to_input_stacks(&repo, &stacks, common_merge_base.to_gix())?;
-    let ranges = crate::WorkspaceRanges::try_from_stacks(input_stacks)?;
-    HunkDependencies::try_from_workspace_ranges(&repo, ranges, changes)
+        crate::workspace_stacks_to_input_stacks(repo, &stacks, common_merge_base.to_gix())?;
+    Ok((stacks, input_stacks))
+}
+
+/// Track the lines of `input_stacks`, made from `stacks`, into the workspace commit of `repo`.
+fn workspace_lines(
+    repo: &gix::Repository,
+    stacks: &[but_workspace::ui::StackEntry],
+    input_stacks: Vec<crate::InputStack>,
+) -> anyhow::Result<crate::WorkspaceLines> {
+    let workspace_commit_id = repo.head_id()?.detach();
+    let tips_to_workspace =
+        crate::workspace_stacks_to_workspace_files(repo, stacks, workspace_commit_id)?;
+    Ok(crate::WorkspaceLines::from_stacks(
+        input_stacks,
+        &tips_to_workspace,
+    ))
+}
+
+/// Explain why each hunk of the worktree change at `path` is locked, knowing the `worktree_dir` for changes
+/// and `gitbutler_dir` for obtaining stack information, using the algorithm of `options`.
+///
+/// Hunks that aren't locked are included as well, and there is no explanation if `path` didn't change.
+/// [`Options::compare`] is ignored.
+pub fn explain_hunk_locks(
+    ctx: &CommandContext,
+    worktree_dir: &Path,
+    gitbutler_dir: &Path,
+    path: &BStr,
+    options: Options,
+) -> anyhow::Result<Vec<HunkLockExplanation>> {
+    let repo = gix_repo_for_merging(worktree_dir)?.with_object_memory();
+    let Some(change) = but_core::diff::worktree_changes(&repo)?
+        .changes
+        .into_iter()
+        .find(|change| change.path == path)
+    else {
+        return Ok(Vec::new());
+    };
+    let Some(UnifiedDiff::Patch { hunks, .. }) =
+        change.unified_diff(&repo, 0 /* zero context lines */)?
+    else {
+        return Ok(Vec::new());
+    };
+
+    let (stacks, input_stacks) = workspace_input_stacks(ctx, &repo, gitbutler_dir)?;
+    // The position of each commit in the workspace, to order them from the base to the tip of each stack.
+    let commit_order: HashMap<gix::ObjectId, (usize, usize)> = input_stacks
+        .iter()
+        .enumerate()
+        .flat_map(|(stack_idx, stack)| {
+            stack
+                .commits_from_base_to_tip
+                .iter()
+                .enumerate()
+                .map(move |(commit_idx, commit)| (commit.commit_id, (stack_idx, commit_idx)))
+        })
+        .collect();
+    let path_in_workspace = workspace_path_of(&change);
+    let hunk_commits: Vec<Vec<LockingCommit>> = match options.algorithm {
+        Algorithm::Ranges => {
+            let ranges = crate::WorkspaceRanges::try_from_stacks(input_stacks)?;
+            let renames = ranges.renames(&path_in_workspace);
+            hunks
+                .iter()
+                .map(|hunk| {
+                    let mut commits = Vec::new();
+                    for range in ranges
+                        .intersection(&path_in_workspace, hunk.old_start, hunk.old_lines)
+                        .unwrap_or_default()
+                    {
+                        let commit = locking_commit(
+                            &mut commits,
+                            HunkLock {
+                                stack_id: range.stack_id,
+                                commit_id: range.commit_id,
+                            },
+                        );
+                        if renames.contains(range) {
+                            commit.renamed_file = true;
+                        } else {
+                            commit.ranges.push(LineRange {
+                                start: range.start,
+                                lines: range.lines,
+                            });
+                        }
+                    }
+                    commits
+                })
+                .collect()
+        }
+        Algorithm::Lines => {
+            let lines = workspace_lines(&repo, &stacks, input_stacks)?;
+            hunks
+                .iter()
+                .map(|hunk| {
+                    let mut commits = Vec::new();
+                    for (owner, change) in lines.intersecting_changes(
+                        &path_in_workspace,
+                        hunk.old_start,
+                        hunk.old_lines,
+                    ) {
+                        let commit = locking_commit(
+                            &mut commits,
+                            HunkLock {
+                                stack_id: owner.stack_id,
+                                commit_id: owner.commit_id,
+                            },
+                        );
+                        match change {
+                            LineChange::Lines { start, lines } => {
+                                commit.ranges.push(LineRange { start, lines })
+                            }
+                            LineChange::Deletion { after_line } => commit.ranges.push(LineRange {
+                                start: after_line,
+                                lines: 0,
+                            }),
+                            LineChange::FileDeletion => {
+                                commit.ranges.push(LineRange { start: 0, lines: 0 })
+                            }
+                            LineChange::Rename => commit.renamed_file = true,
+                        }
+                    }
+                    commits
+                })
+                .collect()
+        }
+    };
+
+    let mut out = Vec::new();
+    for (hunk, mut commits) in hunks.into_iter().zip(hunk_commits) {
+        commits.sort_by_key(|commit| commit_order.get(&commit.lock.commit_id).copied());
+
+        let is_locked_to_one_stack = commits
+            .iter()
+            .all(|commit| commits[0].lock.stack_id == commit.lock.stack_id);
+        let amendable_into = commits
+            .last()
+            .filter(|_| is_locked_to_one_stack)
+            .map(|commit| commit.lock);
+        out.push(HunkLockExplanation {
+            path: change.path.to_string(),
+            hunk,
+            commits,
+            amendable_into,
+        });
+    }
+    Ok(out)
+}
+
+/// Return the entry for the commit of `lock` in `commits`, adding it if it doesn't exist yet.
+fn locking_commit(commits: &mut Vec<LockingCommit>, lock: HunkLock) -> &mut LockingCommit {
+    match commits
+        .iter()
+        .position(|commit| commit.lock.commit_id == lock.commit_id)
+    {
+        Some(idx) => &mut commits[idx],
+        None => {
+            commits.push(LockingCommit {
+                lock,
+                ranges: Vec::new(),
+                renamed_file: false,
+            });
+            commits.last_mut().expect("just pushed")
+        }
+    }
 }
 
 /// Compute hunk-dependencies for the UI knowing the `worktree_dir` for changes
-/// and `gitbutler_dir` for obtaining stack information.
+/// and `gitbutler_dir` for obtaining stack information, as configured by `options`.
 pub fn hunk_dependencies_for_workspace_changes_by_worktree_dir(
     ctx: &CommandContext,
     worktree_dir: &Path,
     gitbutler_dir: &Path,
     worktree_changes: Option<Vec<but_core::TreeChange>>,
+    options: Options,
 ) -> anyhow::Result<HunkDependencies> {
     let repo = ctx.gix_repo_for_merging_non_persisting()?;
     let worktree_changes = worktree_changes
         .map(Ok)
         .unwrap_or_else(|| but_core::diff::worktree_changes(&repo).map(|wtc| wtc.changes))?;
-    hunk_dependencies_for_changes(ctx, worktree_dir, gitbutler_dir, worktree_changes)
+    hunk_dependencies_for_changes(ctx, worktree_dir, gitbutler_dir, worktree_changes, options)
 }
 
 /// A way to represent all hunk dependencies that would make it possible to know what can be applied, and were.
@@ -51,6 +308,24 @@ pub struct HunkDependencies {
     /// Errors that occurred during the calculation that should be presented in some way.
     // TODO: Does the UI really use whatever partial result that there may be? Should this be a real error?
     pub errors: Vec<crate::CalculationError>,
+    /// The hunks for which the algorithm that wasn't used yields different dependencies,
+    /// if [`Options::compare`] was set.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub disagreements: Vec<Disagreement>,
+}
+
+/// A hunk for which the [algorithms](Algorithm) to calculate hunk dependencies yield different dependencies.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Disagreement {
+    /// The path of the file that contains `hunk`.
+    pub path: String,
+    /// The hunk with zero context lines.
+    pub hunk: DiffHunk,
+    /// The dependencies of `hunk` with the algorithm that was used.
+    pub locks: Vec<HunkLock>,
+    /// The dependencies of `hunk` with the other algorithm.
+    pub other_locks: Vec<HunkLock>,
 }
 
 impl HunkDependencies {
@@ -66,9 +341,10 @@ impl HunkDependencies {
             let Some(UnifiedDiff::Patch { hunks, .. }) = unidiff else {
                 continue;
             };
+            let path_in_workspace = workspace_path_of(&change);
             for hunk in hunks {
                 if let Some(intersections) =
-                    ranges.intersection(&change.path, hunk.old_start, hunk.old_lines)
+                    ranges.intersection(&path_in_workspace, hunk.old_start, hunk.old_lines)
                 {
                     let locks: Vec<_> = intersections
                         .into_iter()
@@ -85,8 +361,143 @@ impl HunkDependencies {
         Ok(HunkDependencies {
             diffs,
             errors: ranges.errors,
+            disagreements: Vec::new(),
+        })
+    }
+
+    /// Calculate all hunk dependencies using prepared [`crate::WorkspaceLines`].
+    pub fn try_from_workspace_lines(
+        repo: &gix::Repository,
+        lines: &crate::WorkspaceLines,
+        worktree_changes: Vec<but_core::TreeChange>,
+    ) -> anyhow::Result<HunkDependencies> {
+        let mut diffs = Vec::<(String, DiffHunk, Vec<HunkLock>)>::new();
+        for change in worktree_changes {
+            let unidiff = change.unified_diff(repo, 0 /* zero context lines */)?;
+            let Some(UnifiedDiff::Patch { hunks, .. }) = unidiff else {
+                continue;
+            };
+            let path_in_workspace = workspace_path_of(&change);
+            for hunk in hunks {
+                if let Some(owners) =
+                    lines.intersection(&path_in_workspace, hunk.old_start, hunk.old_lines)
+                {
+                    let locks: Vec<_> = owners
+                        .into_iter()
+                        .map(|owner| HunkLock {
+                            commit_id: owner.commit_id,
+                            stack_id: owner.stack_id,
+                        })
+                        .collect();
+                    diffs.push((change.path.to_string(), hunk, locks));
+                }
+            }
+        }
+
+        Ok(HunkDependencies {
+            diffs,
+            errors: Vec::new(),
+            disagreements: Vec::new(),
         })
     }
+
+    /// Return all hunks whose dependencies differ from the ones in `other`, including those that only have
+    /// dependencies in one of them.
+    pub fn disagreements_with(&self, other: &HunkDependencies) -> Vec<Disagreement> {
+        let find = |deps: &HunkDependencies, path: &str, hunk: &DiffHunk| -> Vec<HunkLock> {
+            deps.diffs
+                .iter()
+                .find(|(other_path, other_hunk, _)| {
+                    other_path == path
+                        && (other_hunk.old_start, other_hunk.old_lines)
+                            == (hunk.old_start, hunk.old_lines)
+                        && (other_hunk.new_start, other_hunk.new_lines)
+                            == (hunk.new_start, hunk.new_lines)
+                })
+                .map(|(_, _, locks)| locks.clone())
+                .unwrap_or_default()
+        };
+        let same = |a: &[HunkLock], b: &[HunkLock]| {
+            a.len() == b.len() && a.iter().all(|lock| b.contains(lock))
+        };
+        let mut out: Vec<Disagreement> = self
+            .diffs
+            .iter()
+            .filter_map(|(path, hunk, locks)| {
+                let other_locks = find(other, path, hunk);
+                (!same(locks, &other_locks)).then(|| Disagreement {
+                    path: path.clone(),
+                    hunk: hunk.clone(),
+                    locks: locks.clone(),
+                    other_locks,
+                })
+            })
+            .collect();
+        out.extend(
+            other
+                .diffs
+                .iter()
+                .filter(|(path, hunk, _)| find(self, path, hunk).is_empty())
+                .map(|(path, hunk, other_locks)| Disagreement {
+                    path: path.clone(),
+                    hunk: hunk.clone(),
+                    locks: Vec::new(),
+                    other_locks: other_locks.clone(),
+                }),
+        );
+        out
+    }
+}
+
+/// Return the path of `change` in the workspace commit, which is the path before a rename in the worktree
+/// as the line numbers of the previous version of its hunks refer to it.
+fn workspace_path_of(change: &but_core::TreeChange) -> BString {
+    change
+        .previous_path()
+        .unwrap_or(change.path.as_bstr())
+        .to_owned()
+}
+
+/// Why a worktree hunk is locked, as returned by [`explain_hunk_locks()`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HunkLockExplanation {
+    /// The path of the file that contains `hunk`.
+    pub path: String,
+    /// The worktree hunk with zero context lines.
+    pub hunk: DiffHunk,
+    /// The commits whose changes intersect with `hunk` and thus lock it, ordered from the base to the tip
+    /// of their stack. If empty, the hunk isn't locked.
+    pub commits: Vec<LockingCommit>,
+    /// The commit that `hunk` can be amended into without conflicting with the commits that follow it,
+    /// which is the top-most of `commits`.
+    ///
+    /// It's `None` if the hunk isn't locked, in which case it can go anywhere, or if it's locked to more than one stack,
+    /// in which case it would have to be split first.
+    pub amendable_into: Option<HunkLock>,
+}
+
+/// A commit that locks a worktree hunk, as part of a [`HunkLockExplanation`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockingCommit {
+    /// The commit and its stack.
+    #[serde(flatten)]
+    pub lock: HunkLock,
+    /// The line ranges that the commit changed and that intersect with the hunk, with line numbers in the workspace commit.
+    pub ranges: Vec<LineRange>,
+    /// If `true`, the commit renamed the file, which locks all of its hunks.
+    pub renamed_file: bool,
+}
+
+/// A range of lines in a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineRange {
+    /// The 1-based number of the first line.
+    pub start: u32,
+    /// The amount of lines, which is `0` if the commit only deleted lines at `start`.
+    pub lines: u32,
 }
 
 /// A commit that owns this lock, along with the stack that owns it.
@@ -470,6 +470,39 @@ git init remote2
 )
 
 
+# Add a line right after a committed line, which doesn't change the committed line.
+git clone remote2 addition-next-to-committed-line
+(cd addition-next-to-committed-line
+  git branch existing-branch
+  $CLI project add --switch-to-workspace "$(git rev-parse --symbolic-full-name @{u})"
+
+  $CLI branch create --set-default my_stack
+  echo "1
+2
+3
+4
+update line 5
+6
+7
+8
+9
+" > file
+  commit_stack "my_stack" "update line 5"
+
+  echo "1
+2
+3
+4
+update line 5
+add a line after 5
+6
+7
+8
+9
+" > file
+)
+
+
 # Add the project, make some changes.
 git clone remote2 merge-commit
 (cd merge-commit
@@ -36,20 +36,41 @@ fn change_2_to_two_in_second_commit() -> anyhow::Result<()> {
 #[test]
 fn change_2_to_two_in_second_commit_after_file_rename() -> anyhow::Result<()> {
     let repo = repo("1-2-3-10_renamed-two")?;
+    let rename_commit_id = repo.head_id()?.detach();
     let digest = workspace_ranges_digest_for_worktree_changes(&repo)?;
-    // TODO: It should find a commit, probably due to rename tracking - it would have to start searching for the new path name.
-    insta::assert_debug_snapshot!(digest.partial(), @r#"
+    // The ranges of the commits before the rename are tracked under the new name, and the rename itself is a dependency.
+    insta::assert_snapshot!(format!("{:#?}", digest.partial()).replace(&rename_commit_id.to_string(), "rename-commit"), @r#"
     WorkspaceWithoutRanges {
-        intersections_by_path: [],
-        missed_hunks: [
+        intersections_by_path: [
             (
                 "file-renamed",
-                DiffHunk("@@ -2,1 +2,1 @@
-                -2
-                +two
-                "),
+                [
+                    HunkIntersection {
+                        hunk: DiffHunk("@@ -2,1 +2,1 @@
+                        -2
+                        +two
+                        "),
+                        commit_intersections: [
+                            StableHunkRange {
+                                change_type: Modification,
+                                commit_id: Sha1(ca5567e4be81f1ee69b3d5ac5410d5010bcea756),
+                                start: 2,
+                                lines: 1,
+                                line_shift: 1,
+                            },
+                            StableHunkRange {
+                                change_type: Rename,
+                                commit_id: Sha1(rename-commit),
+                                start: 0,
+                                lines: 0,
+                                line_shift: 0,
+                            },
+                        ],
+                    },
+                ],
             ),
         ],
+        missed_hunks: [],
     }
     "#);
     Ok(())
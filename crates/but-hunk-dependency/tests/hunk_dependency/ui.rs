@@ -184,6 +184,100 @@ fn complex_file_manipulation_multiple_hunks_with_uncommitted_changes() -> anyhow
     Ok(())
 }
 
+#[test]
+fn explain_hunk_locks_of_multiple_hunks() -> anyhow::Result<()> {
+    let (explanations, _ctx) = explain_hunk_locks_for_workspace(
+        "complex-file-manipulation-multiple-hunks-with-changes",
+        "file",
+        Options::default(),
+    )?;
+    let commit_ids = explanations
+        .iter()
+        .map(|explanation| {
+            explanation
+                .commits
+                .iter()
+                .map(|commit| commit.lock.commit_id.to_hex_with_len(7).to_string())
+                .sorted()
+                .collect_vec()
+        })
+        .collect_vec();
+    assert_eq!(
+        commit_ids,
+        [
+            vec!["375e35b"],
+            vec!["250b92b", "e954269", "fba21e9"],
+            vec!["fba21e9"]
+        ],
+        "each hunk is explained with the commits that lock it, as in `complex_file_manipulation_multiple_hunks_with_uncommitted_changes`"
+    );
+    for explanation in &explanations {
+        assert!(
+            explanation
+                .commits
+                .iter()
+                .all(|commit| !commit.ranges.is_empty()),
+            "every commit comes with the ranges that intersect with the hunk"
+        );
+        assert_eq!(
+            explanation.amendable_into,
+            explanation.commits.last().map(|commit| commit.lock),
+            "all locks are in the same stack, so the top-most commit can be amended"
+        );
+    }
+
+    let (explanations, _ctx) = explain_hunk_locks_for_workspace(
+        "complex-file-manipulation-multiple-hunks-with-changes",
+        "unchanged",
+        Options::default(),
+    )?;
+    assert!(explanations.is_empty());
+    Ok(())
+}
+
+#[test]
+fn explain_hunk_locks_with_each_algorithm() -> anyhow::Result<()> {
+    let (explanations, _ctx) = explain_hunk_locks_for_workspace(
+        "addition-next-to-committed-line",
+        "file",
+        Options {
+            algorithm: Algorithm::Ranges,
+            compare: false,
+        },
+    )?;
+    assert_eq!(explanations.len(), 1);
+    let explanation = &explanations[0];
+    assert_eq!(
+        explanation
+            .commits
+            .iter()
+            .map(|commit| commit.ranges.clone())
+            .collect_vec(),
+        [vec![LineRange { start: 5, lines: 1 }]],
+        "the ranges algorithm locks the addition to the commit of the line before it"
+    );
+    assert_eq!(
+        explanation.amendable_into,
+        Some(explanation.commits[0].lock)
+    );
+
+    let (explanations, _ctx) = explain_hunk_locks_for_workspace(
+        "addition-next-to-committed-line",
+        "file",
+        Options {
+            algorithm: Algorithm::Lines,
+            compare: false,
+        },
+    )?;
+    assert_eq!(explanations.len(), 1);
+    assert!(
+        explanations[0].commits.is_empty(),
+        "the lines algorithm doesn't lock additions next to committed lines"
+    );
+    assert_eq!(explanations[0].amendable_into, None);
+    Ok(())
+}
+
 #[test]
 fn dependencies_ignore_merge_commits() -> anyhow::Result<()> {
     let (actual, _ctx) = hunk_dependencies_for_workspace("merge-commit")?;
@@ -210,10 +304,244 @@ fn dependencies_ignore_merge_commits() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn algorithms_disagree_on_additions_next_to_committed_lines() -> anyhow::Result<()> {
+    let name = "addition-next-to-committed-line";
+    let (ranges, ctx) = hunk_dependencies_for_workspace_with_options(
+        name,
+        Options {
+            algorithm: Algorithm::Ranges,
+            compare: true,
+        },
+    )?;
+    let lock = only_commit_lock(&ctx);
+    assert_eq!(ranges.diffs.len(), 1);
+    let (path, hunk, locks) = &ranges.diffs[0];
+    assert_eq!(path, "file");
+    assert_eq!(
+        (
+            hunk.old_start,
+            hunk.old_lines,
+            hunk.new_start,
+            hunk.new_lines
+        ),
+        (5, 0, 6, 1),
+        "the line is added right after the committed one"
+    );
+    assert_eq!(
+        locks,
+        &[lock],
+        "the range of the committed line includes the place after it"
+    );
+    assert_eq!(ranges.disagreements.len(), 1);
+    assert_eq!(ranges.disagreements[0].path, "file");
+    assert_eq!(ranges.disagreements[0].locks, [lock]);
+    assert_eq!(
+        ranges.disagreements[0].other_locks,
+        [],
+        "the committed line isn't changed"
+    );
+
+    let (lines, _ctx) = hunk_dependencies_for_workspace_with_options(
+        name,
+        Options {
+            algorithm: Algorithm::Lines,
+            compare: true,
+        },
+    )?;
+    assert!(
+        lines.diffs.is_empty(),
+        "only changes to committed lines depend on them"
+    );
+    assert_eq!(lines.disagreements.len(), 1);
+    assert_eq!(lines.disagreements[0].locks, []);
+    assert_eq!(lines.disagreements[0].other_locks, [lock]);
+
+    let (lines, _ctx) = hunk_dependencies_for_workspace_with_options(
+        name,
+        Options {
+            algorithm: Algorithm::Lines,
+            compare: false,
+        },
+    )?;
+    assert!(lines.diffs.is_empty());
+    assert!(
+        lines.disagreements.is_empty(),
+        "disagreements are only computed when comparing"
+    );
+    Ok(())
+}
+
+#[test]
+fn dependencies_from_workspace_lines() -> anyhow::Result<()> {
+    let ctx = test_ctx("addition-next-to-committed-line")?;
+    let lock = only_commit_lock(&ctx);
+    let workspace_commit_id = ctx.repo.head_id()?.detach();
+    let tips_to_workspace = but_hunk_dependency::workspace_stacks_to_workspace_files(
+        &ctx.repo,
+        &ctx.stacks_entries,
+        workspace_commit_id,
+    )?;
+    assert!(
+        tips_to_workspace
+            .get(&lock.stack_id)
+            .is_none_or(Vec::is_empty),
+        "the workspace commit of a single stack doesn't change its files"
+    );
+
+    let common_merge_base = gitbutler_stack::VirtualBranchesHandle::new(&ctx.gitbutler_dir)
+        .get_default_target()?
+        .sha;
+    let input_stacks = but_hunk_dependency::workspace_stacks_to_input_stacks(
+        &ctx.repo,
+        &ctx.stacks_entries,
+        common_merge_base.to_gix(),
+    )?;
+    let lines = but_hunk_dependency::WorkspaceLines::from_stacks(input_stacks, &tips_to_workspace);
+    let path = "file".into();
+    assert_eq!(
+        lines.intersection(&path, 5, 1),
+        Some(vec![LineOwner {
+            stack_id: lock.stack_id,
+            commit_id: lock.commit_id,
+        }]),
+        "changing the committed line depends on its commit"
+    );
+
+    let worktree_changes = but_core::diff::worktree_changes(&ctx.repo)?.changes;
+    let deps = HunkDependencies::try_from_workspace_lines(&ctx.repo, &lines, worktree_changes)?;
+    assert!(deps.diffs.is_empty());
+    assert_eq!(deps.errors.len(), 0);
+    Ok(())
+}
+
+#[test]
+fn workspace_files_are_the_changes_of_the_other_stacks() -> anyhow::Result<()> {
+    let ctx = test_ctx("independent-commits-multi-stack")?;
+    let workspace_commit_id = ctx.repo.head_id()?.detach();
+    let tips_to_workspace = but_hunk_dependency::workspace_stacks_to_workspace_files(
+        &ctx.repo,
+        &ctx.stacks_entries,
+        workspace_commit_id,
+    )?;
+    assert_eq!(tips_to_workspace.len(), 2, "one entry per stack");
+    let added_paths = tips_to_workspace
+        .values()
+        .map(|files| {
+            assert!(
+                files
+                    .iter()
+                    .all(|file| file.change_type == TreeStatusKind::Addition)
+            );
+            files
+                .iter()
+                .map(|file| file.path.to_string())
+                .sorted()
+                .collect_vec()
+        })
+        .sorted()
+        .collect_vec();
+    assert_eq!(
+        added_paths,
+        [
+            ["a", "b", "c", "d", "e", "f"],
+            ["g", "h", "i", "j", "k", "l"]
+        ],
+        "each stack lacks the files of the other one"
+    );
+    Ok(())
+}
+
+#[test]
+fn disagreements_are_hunks_with_different_locks() {
+    let (stack_id, other_stack_id) = (StackId::generate(), StackId::generate());
+    let lock = |stack_id, hex_char| HunkLock {
+        stack_id,
+        commit_id: id_from_hex_char(hex_char),
+    };
+    let deps = |diffs: Vec<(&str, DiffHunk, Vec<HunkLock>)>| HunkDependencies {
+        diffs: diffs
+            .into_iter()
+            .map(|(path, hunk, locks)| (path.to_owned(), hunk, locks))
+            .collect(),
+        ..Default::default()
+    };
+    let ours = deps(vec![
+        (
+            "a",
+            hunk(1, 1, 1, 1),
+            vec![lock(stack_id, '1'), lock(stack_id, '2')],
+        ),
+        ("a", hunk(5, 1, 5, 1), vec![lock(stack_id, '1')]),
+        ("b", hunk(1, 1, 1, 1), vec![lock(stack_id, '1')]),
+    ]);
+    let theirs = deps(vec![
+        (
+            "a",
+            hunk(1, 1, 1, 1),
+            vec![lock(stack_id, '2'), lock(stack_id, '1')],
+        ),
+        ("a", hunk(5, 1, 5, 1), vec![lock(other_stack_id, '1')]),
+        ("c", hunk(1, 1, 1, 1), vec![lock(stack_id, '3')]),
+    ]);
+
+    let disagreements = ours.disagreements_with(&theirs);
+    assert_eq!(
+        disagreements
+            .iter()
+            .map(|d| (
+                d.path.as_str(),
+                d.hunk.old_start,
+                d.locks.clone(),
+                d.other_locks.clone()
+            ))
+            .collect_vec(),
+        [
+            (
+                "a",
+                5,
+                vec![lock(stack_id, '1')],
+                vec![lock(other_stack_id, '1')]
+            ),
+            ("b", 1, vec![lock(stack_id, '1')], vec![]),
+            ("c", 1, vec![], vec![lock(stack_id, '3')]),
+        ],
+        "the order of locks doesn't matter, and hunks that are only locked on one side disagree"
+    );
+    assert!(ours.disagreements_with(&ours).is_empty());
+}
+
+fn hunk(old_start: u32, old_lines: u32, new_start: u32, new_lines: u32) -> DiffHunk {
+    DiffHunk {
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+        diff: Default::default(),
+    }
+}
+
+/// Create a new object id by repeating the given `hex_char`.
+fn id_from_hex_char(hex_char: char) -> gix::ObjectId {
+    gix::ObjectId::from_hex(String::from_iter(std::iter::repeat_n(hex_char, 40)).as_bytes())
+        .expect("input char is hex-only")
+}
+
+/// The lock of the only commit in the only stack of `ctx`.
+fn only_commit_lock(ctx: &TestContext) -> HunkLock {
+    assert_eq!(ctx.stacks_entries.len(), 1);
+    let stack = &ctx.stacks_entries[0];
+    HunkLock {
+        stack_id: stack.id.expect("stacks in the workspace have an id"),
+        commit_id: stack.tip,
+    }
+}
+
 mod util {
     use but_core::unified_diff::DiffHunk;
     use but_hunk_dependency::ui::{
-        HunkDependencies, HunkLock, hunk_dependencies_for_workspace_changes_by_worktree_dir,
+        HunkDependencies, HunkLock, HunkLockExplanation, Options, explain_hunk_locks,
+        hunk_dependencies_for_workspace_changes_by_worktree_dir,
     };
     use gitbutler_command_context::CommandContext;
     use gitbutler_stack::StackId;
@@ -221,6 +549,8 @@ mod util {
     use std::collections::HashSet;
     use std::path::PathBuf;
 
+    const SCRIPT_NAME: &str = "../../../but-hunk-dependency/tests/fixtures/dependencies.sh";
+
     pub fn to_stable_string(deps: HunkDependencies) -> String {
         let stack_ids = stack_ids_by_diffs(&deps);
         let deps: StableHunkDependencies = deps.into();
@@ -249,22 +579,51 @@ mod util {
     pub fn hunk_dependencies_for_workspace(
         name: &str,
     ) -> anyhow::Result<(HunkDependencies, TestContext)> {
-        let script_name = "../../../but-hunk-dependency/tests/fixtures/dependencies.sh";
-        let ctx = test_ctx_at(script_name, name)?;
-        let command_context = gitbutler_testsupport::read_only::fixture(script_name, name)?;
-        let deps = hunk_dependencies_for_workspace_by_ctx(&ctx, &command_context)?;
+        hunk_dependencies_for_workspace_with_options(name, Default::default())
+    }
+
+    pub fn hunk_dependencies_for_workspace_with_options(
+        name: &str,
+        options: Options,
+    ) -> anyhow::Result<(HunkDependencies, TestContext)> {
+        let ctx = test_ctx(name)?;
+        let command_context = gitbutler_testsupport::read_only::fixture(SCRIPT_NAME, name)?;
+        let deps = hunk_dependencies_for_workspace_by_ctx(&ctx, &command_context, options)?;
         Ok((deps, ctx))
     }
 
+    pub fn test_ctx(name: &str) -> anyhow::Result<TestContext> {
+        test_ctx_at(SCRIPT_NAME, name)
+    }
+
+    pub fn explain_hunk_locks_for_workspace(
+        name: &str,
+        path: &str,
+        options: Options,
+    ) -> anyhow::Result<(Vec<HunkLockExplanation>, TestContext)> {
+        let ctx = test_ctx(name)?;
+        let command_context = gitbutler_testsupport::read_only::fixture(SCRIPT_NAME, name)?;
+        let explanations = explain_hunk_locks(
+            &command_context,
+            ctx.repo.workdir().expect("We don't support bare repos"),
+            &ctx.gitbutler_dir,
+            path.into(),
+            options,
+        )?;
+        Ok((explanations, ctx))
+    }
+
     fn hunk_dependencies_for_workspace_by_ctx(
         ctx: &TestContext,
         command_context: &CommandContext,
+        options: Options,
     ) -> anyhow::Result<HunkDependencies> {
         hunk_dependencies_for_workspace_changes_by_worktree_dir(
             command_context,
             ctx.repo.workdir().expect("We don't support bare repos"),
             &ctx.gitbutler_dir,
             None,
+            options,
         )
     }
 
@@ -276,7 +635,7 @@ mod util {
     }
 
     impl From<HunkDependencies> for StableHunkDependencies {
-        fn from(HunkDependencies { diffs, errors }: HunkDependencies) -> Self {
+        fn from(HunkDependencies { diffs, errors, .. }: HunkDependencies) -> Self {
             StableHunkDependencies {
                 diffs: diffs
                     .into_iter()
@@ -333,7 +692,15 @@ mod util {
             .collect()
     }
 }
+use but_core::TreeStatusKind;
+use but_core::unified_diff::DiffHunk;
+use but_hunk_dependency::LineOwner;
+use but_hunk_dependency::ui::{Algorithm, HunkDependencies, HunkLock, LineRange, Options};
+use gitbutler_oxidize::OidExt;
+use gitbutler_stack::StackId;
+use itertools::Itertools;
 use util::{
-    hunk_dependencies_for_workspace, simplify_stack_ids_in_string, stack_ids_by_diffs,
-    to_stable_string,
+    TestContext, explain_hunk_locks_for_workspace, hunk_dependencies_for_workspace,
+    hunk_dependencies_for_workspace_with_options, simplify_stack_ids_in_string, stack_ids_by_diffs,
+    test_ctx, to_stable_string,
 };
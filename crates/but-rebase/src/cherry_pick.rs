@@ -25,9 +25,12 @@ pub enum EmptyCommit {
 pub(crate) mod function {
     use crate::cherry_pick::{EmptyCommit, PickMode};
     use crate::commit::DateMode;
+    use crate::rerere::{self, Resolutions};
     use anyhow::{Context, bail};
     use bstr::BString;
-    use but_core::commit::{HEADERS_CONFLICTED_FIELD, HeadersV2, TreeKind};
+    use but_core::commit::{
+        HEADERS_AUTO_RESOLVED_FIELD, HEADERS_CONFLICTED_FIELD, HeadersV2, TreeKind,
+    };
     use gix::object::tree::EntryKind;
     use gix::prelude::ObjectIdExt;
     use serde::Serialize;
@@ -46,10 +49,32 @@ pub(crate) mod function {
         commit_to_rebase: gix::ObjectId,
         pick_mode: PickMode,
         empty_commit: EmptyCommit,
+    ) -> anyhow::Result<gix::ObjectId> {
+        cherry_pick_one_with_resolutions(
+            repo,
+            base,
+            commit_to_rebase,
+            pick_mode,
+            empty_commit,
+            None,
+        )
+    }
+
+    /// Like [`cherry_pick_one()`], but if the pick conflicts and all conflicting hunks have a recorded resolution
+    /// in `resolutions`, these are used instead of creating a conflicted commit.
+    /// Such a commit is marked as auto-resolved with the [`HEADERS_AUTO_RESOLVED_FIELD`] header so the user can
+    /// review it, until it's [acknowledged](crate::rerere::acknowledge_auto_resolution()) or conflicts again.
+    pub fn cherry_pick_one_with_resolutions(
+        repo: &gix::Repository,
+        base: gix::ObjectId,
+        commit_to_rebase: gix::ObjectId,
+        pick_mode: PickMode,
+        empty_commit: EmptyCommit,
+        resolutions: Option<&Resolutions>,
     ) -> anyhow::Result<gix::ObjectId> {
         let base = but_core::Commit::from_id(base.attach(repo))?;
         let to_rebase = but_core::Commit::from_id(commit_to_rebase.attach(repo))?;
-        Ok(cherry_pick_one_inner(base, to_rebase, pick_mode, empty_commit)?.detach())
+        Ok(cherry_pick_one_inner(base, to_rebase, pick_mode, empty_commit, resolutions)?.detach())
     }
 
     fn cherry_pick_one_inner<'repo>(
@@ -57,6 +82,7 @@ pub(crate) mod function {
         commit_to_rebase: but_core::Commit<'repo>,
         pick_mode: PickMode,
         empty_commit: EmptyCommit,
+        resolutions: Option<&Resolutions>,
     ) -> anyhow::Result<gix::Id<'repo>> {
         if commit_to_rebase.parents.len() > 1 {
             bail!("Cannot yet cherry-pick merge-commits - use rebasing for that")
@@ -72,9 +98,40 @@ pub(crate) mod function {
 
         let conflict_kind = gix::merge::tree::TreatAsUnresolved::forced_resolution();
         if cherry_pick.has_unresolved_conflicts(conflict_kind) {
+            if let Some(resolutions) = resolutions.filter(|r| !r.is_empty()) {
+                let paths = cherry_pick
+                    .conflicts
+                    .iter()
+                    .filter(|c| c.is_unresolved(conflict_kind))
+                    .map(|c| {
+                        // Renamed files can't be resolved by path.
+                        let (ours, theirs) = c.changes_in_resolution();
+                        let path = ours.location();
+                        (path == theirs.location()).then(|| path.to_owned())
+                    })
+                    .collect::<Option<Vec<_>>>();
+                if let Some(paths) = paths {
+                    let trees = find_cherry_pick_trees(&base, &commit_to_rebase)?;
+                    if let Some((resolved_tree_id, resolved_hunks)) = rerere::resolve_conflicts(
+                        base.id.repo,
+                        (trees.0.detach(), trees.1.detach(), trees.2.detach()),
+                        tree_id.detach(),
+                        &paths,
+                        resolutions,
+                    )? {
+                        return commit_from_unconflicted_tree(
+                            base,
+                            commit_to_rebase,
+                            resolved_tree_id.attach(tree_id.repo),
+                            empty_commit,
+                            Some(resolved_hunks),
+                        );
+                    }
+                }
+            }
             commit_from_conflicted_tree(base, commit_to_rebase, tree_id, cherry_pick, conflict_kind)
         } else {
-            commit_from_unconflicted_tree(base, commit_to_rebase, tree_id, empty_commit)
+            commit_from_unconflicted_tree(base, commit_to_rebase, tree_id, empty_commit, None)
         }
     }
 
@@ -156,6 +213,7 @@ pub(crate) mod function {
         to_rebase: but_core::Commit<'repo>,
         resolved_tree_id: gix::Id<'repo>,
         empty_commit: EmptyCommit,
+        auto_resolved_hunks: Option<usize>,
     ) -> anyhow::Result<gix::Id<'repo>> {
         let repo = head.id.repo;
         // Remove empty commits
@@ -183,6 +241,17 @@ pub(crate) mod function {
                 .extra_headers
                 .extend(Vec::<(BString, BString)>::from(&HeadersV2::default()));
         }
+        if let Some(hunks) = auto_resolved_hunks {
+            if let Some(pos) = new_commit
+                .extra_headers()
+                .find_pos(HEADERS_AUTO_RESOLVED_FIELD)
+            {
+                new_commit.extra_headers.remove(pos);
+            }
+            new_commit
+                .extra_headers
+                .push((HEADERS_AUTO_RESOLVED_FIELD.into(), hunks.to_string().into()));
+        }
         set_parent(&mut new_commit, head.id.detach())?;
         Ok(
             crate::commit::create(repo, new_commit, DateMode::CommitterUpdateAuthorKeep)?
@@ -198,10 +267,7 @@ pub(crate) mod function {
         treat_as_unresolved: gix::merge::tree::TreatAsUnresolved,
     ) -> anyhow::Result<gix::Id<'repo>> {
         let repo = resolved_tree_id.repo;
-        // in case someone checks this out with vanilla Git, we should warn why it looks like this
-        let readme_content =
-            b"You have checked out a GitButler Conflicted commit. You probably didn't mean to do this.";
-        let readme_blob = repo.write_blob(readme_content)?;
+        let readme_blob = repo.write_blob(CONFLICTED_COMMIT_README)?;
 
         let conflicted_files =
             extract_conflicted_files(resolved_tree_id, cherry_pick, treat_as_unresolved)?;
@@ -240,6 +306,13 @@ pub(crate) mod function {
 
         let mut headers = to_rebase.headers().unwrap_or_default();
         headers.conflicted = conflicted_files.conflicted_header_field();
+        // The resolutions of an earlier pick don't apply to the new conflict.
+        if let Some(pos) = to_rebase
+            .extra_headers()
+            .find_pos(HEADERS_AUTO_RESOLVED_FIELD)
+        {
+            to_rebase.extra_headers.remove(pos);
+        }
         to_rebase.tree = tree.write().context("failed to write tree")?.detach();
         set_parent(&mut to_rebase, head.id.detach())?;
 
@@ -316,12 +389,17 @@ pub(crate) mod function {
         Ok(out)
     }
 
+    /// The content of the `README.txt` file in the tree of conflicted commits.
+    /// In case someone checks this out with vanilla Git, we should warn why it looks like this.
+    pub(crate) const CONFLICTED_COMMIT_README: &[u8] =
+        b"You have checked out a GitButler Conflicted commit. You probably didn't mean to do this.";
+
     #[derive(Default, Debug, Clone, Serialize, PartialEq)]
     #[serde(rename_all = "camelCase")]
     pub struct ConflictEntries {
-        ancestor_entries: Vec<PathBuf>,
-        our_entries: Vec<PathBuf>,
-        their_entries: Vec<PathBuf>,
+        pub(crate) ancestor_entries: Vec<PathBuf>,
+        pub(crate) our_entries: Vec<PathBuf>,
+        pub(crate) their_entries: Vec<PathBuf>,
     }
 
     impl ConflictEntries {
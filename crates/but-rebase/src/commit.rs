@@ -5,9 +5,8 @@ use but_core::{GitConfigSettings, RepositoryExt};
 use gitbutler_error::error::Code;
 use gix::config::Source;
 use gix::objs::WriteTo;
-use std::borrow::Cow;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 
 /// What to do with the committer (actor) and the commit time when [creating a new commit](create()).
@@ -151,6 +150,31 @@ pub fn create(
     Ok(repo.write_object(&commit)?.detach())
 }
 
+/// Apply the [trailer policy](but_core::commit::trailer::Policy) configured in `repo` to the message of `commit`,
+/// which GitButler created or changed on behalf of the user.
+///
+/// `previous_messages` are the messages that the message of `commit` replaces, to carry over the trailers
+/// the policy maintains, and `branch` is the short name of the branch `commit` is on, if known, to extract a ticket id from.
+pub fn apply_trailer_policy(
+    repo: &gix::Repository,
+    commit: &mut gix::objs::Commit,
+    previous_messages: &[&BStr],
+    branch: Option<&str>,
+) -> anyhow::Result<()> {
+    let policy = but_core::commit::trailer::Policy::from_config(&repo.config_snapshot())?;
+    let committer = match repo.committer().transpose()? {
+        Some(committer) => format!("{} <{}>", committer.name, committer.email),
+        None => format!("{} <{}>", commit.committer.name, commit.committer.email),
+    };
+    commit.message = policy.apply(
+        commit.message.as_ref(),
+        previous_messages,
+        committer.as_str().into(),
+        branch,
+    );
+    Ok(())
+}
+
 /// Update the commiter of `commit` to be the current one.
 pub(crate) fn update_committer(
     repo: &gix::Repository,
@@ -177,40 +201,149 @@ pub(crate) fn update_author_time(
     Ok(())
 }
 
+/// The kind of signatures to create, as configured with `gpg.format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureFormat {
+    /// Sign with OpenPGP keys using `gpg`, the default.
+    OpenPgp,
+    /// Sign with X.509 certificates using `gpgsm`.
+    X509,
+    /// Sign with SSH keys using `ssh-keygen`.
+    Ssh,
+}
+
+impl SignatureFormat {
+    /// Read the format from `gpg.format` in `config`, defaulting to OpenPGP just like Git.
+    pub fn from_config(config: &gix::config::Snapshot<'_>) -> anyhow::Result<Self> {
+        let format = config.string("gpg.format");
+        match format.as_ref().map(|format| format.as_bytes()) {
+            None | Some(b"openpgp") => Ok(SignatureFormat::OpenPgp),
+            Some(b"x509") => Ok(SignatureFormat::X509),
+            Some(b"ssh") => Ok(SignatureFormat::Ssh),
+            Some(other) => bail!(
+                "Unsupported signature format in gpg.format: '{}'",
+                other.as_bstr()
+            ),
+        }
+    }
+
+    /// Detect the format of the armored `signature`, or return `None` if it's not one we know.
+    fn of_signature(signature: &[u8]) -> Option<Self> {
+        let signature = signature.trim_start();
+        if signature.starts_with(b"-----BEGIN PGP SIGNATURE-----")
+            || signature.starts_with(b"-----BEGIN PGP MESSAGE-----")
+        {
+            Some(SignatureFormat::OpenPgp)
+        } else if signature.starts_with(b"-----BEGIN SIGNED MESSAGE-----") {
+            Some(SignatureFormat::X509)
+        } else if signature.starts_with(b"-----BEGIN SSH SIGNATURE-----") {
+            Some(SignatureFormat::Ssh)
+        } else {
+            None
+        }
+    }
+
+    /// Return the configuration keys for the program that signs and verifies, in order of precedence.
+    fn program_keys(&self) -> &'static [&'static str] {
+        match self {
+            SignatureFormat::OpenPgp => &["gpg.openpgp.program", "gpg.program"],
+            SignatureFormat::X509 => &["gpg.x509.program"],
+            SignatureFormat::Ssh => &["gpg.ssh.program"],
+        }
+    }
+
+    /// Return the program to sign and verify with as configured in `config`, or the default one.
+    fn program(&self, config: &gix::config::Snapshot<'_>) -> PathBuf {
+        self.program_keys()
+            .iter()
+            .find_map(|key| {
+                config
+                    .trusted_program(*key)
+                    .filter(|program| !program.is_empty())
+            })
+            .map_or_else(
+                || {
+                    match self {
+                        SignatureFormat::OpenPgp => "gpg",
+                        SignatureFormat::X509 => "gpgsm",
+                        SignatureFormat::Ssh => "ssh-keygen",
+                    }
+                    .into()
+                },
+                |program| program.into_owned().into(),
+            )
+    }
+}
+
 /// Sign the given `buffer` using configuration from `repo`, just like Git would.
+///
+/// The signing key is `user.signingKey`, or for SSH the first key printed by `gpg.ssh.defaultKeyCommand`.
+/// Literal SSH keys, i.e. public keys or those prefixed with `key::`, are expected to be held by the ssh-agent.
 pub fn sign_buffer(repo: &gix::Repository, buffer: &[u8]) -> anyhow::Result<BString> {
-    // TODO: support gpg.ssh.defaultKeyCommand to get the signing key if this value doesn't exist
     let config = repo.config_snapshot();
+    let format = SignatureFormat::from_config(&config)?;
+    if format == SignatureFormat::Ssh {
+        return sign_buffer_ssh(&config, buffer);
+    }
+
     let signing_key = signing_key(repo)?;
-    let sign_format = config.string("gpg.format");
-    let is_ssh = if let Some(sign_format) = sign_format {
-        sign_format.as_ref() == "ssh"
-    } else {
-        false
+    let gpg_program = format.program(&config);
+    let mut cmd = into_command(
+        prepare_with_shell_on_windows(gpg_program.as_os_str())
+            .args(["--status-fd=2", "-bsau"])
+            .arg(gix::path::from_bstring(signing_key))
+            .arg("-"),
+    );
+    cmd.stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdin(Stdio::piped());
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            bail!(
+                "Could not find '{}'. Please make sure it is in your `PATH` or configure the full path using `{}` in the Git configuration",
+                gpg_program.display(),
+                format.program_keys()[0]
+            )
+        }
+        Err(err) => {
+            return Err(err).context(format!("Could not execute GPG program using {cmd:?}"));
+        }
     };
+    child.stdin.take().expect("configured").write_all(buffer)?;
 
-    if is_ssh {
-        // write commit data to a temp file so we can sign it
-        let mut signature_storage = tempfile::NamedTempFile::new()?;
-        signature_storage.write_all(buffer)?;
-        let buffer_file_to_sign_path = signature_storage.into_temp_path();
+    let output = child.wait_with_output()?;
+    if output.status.success() {
+        // read stdout
+        let signature = BString::new(output.stdout);
+        Ok(signature)
+    } else {
+        let stderr = BString::new(output.stderr);
+        let stdout = BString::new(output.stdout);
+        let std_both = format!("{stdout} {stderr}");
+        bail!("Failed to sign GPG: {}", std_both);
+    }
+}
 
-        let gpg_program = config
-            .trusted_program("gpg.ssh.program")
-            .filter(|program| !program.is_empty())
-            .map_or_else(
-                || Path::new("ssh-keygen").into(),
-                |program| Cow::Owned(program.into_owned().into()),
-            );
-
-        let mut signing_cmd = prepare_with_shell_on_windows(gpg_program.into_owned())
-            .args(["-Y", "sign", "-n", "git", "-f"]);
-
-        // Write the key to a temp file. This is needs to be created in the
-        // same scope where its used; IE: in the command, otherwise the
-        // tmpfile will get removed too early.
-        let _key_storage;
-        signing_cmd = if let Some(signing_key) = as_literal_key(signing_key.as_bstr()) {
+fn sign_buffer_ssh(config: &gix::config::Snapshot<'_>, buffer: &[u8]) -> anyhow::Result<BString> {
+    let signing_key = ssh_signing_key(config)?;
+
+    // write commit data to a temp file so we can sign it
+    let mut signature_storage = tempfile::NamedTempFile::new()?;
+    signature_storage.write_all(buffer)?;
+    let buffer_file_to_sign_path = signature_storage.into_temp_path();
+
+    let gpg_program = SignatureFormat::Ssh.program(config);
+    let mut signing_cmd = prepare_with_shell_on_windows(gpg_program.into_os_string())
+        .args(["-Y", "sign", "-n", "git", "-f"]);
+
+    // Write the key to a temp file. This is needs to be created in the
+    // same scope where its used; IE: in the command, otherwise the
+    // tmpfile will get removed too early.
+    let _key_storage;
+    signing_cmd = match signing_key {
+        SshSigningKey::Literal(signing_key) => {
             let mut keyfile = tempfile::NamedTempFile::new()?;
             keyfile.write_all(signing_key.as_bytes())?;
 
@@ -226,82 +359,363 @@ pub fn sign_buffer(repo: &gix::Repository, buffer: &[u8]) -> anyhow::Result<BStr
 
             let keyfile_path = keyfile.path().to_owned();
             _key_storage = keyfile.into_temp_path();
+            // The private key of literal keys is expected to be in the ssh-agent.
             signing_cmd
                 .arg(keyfile_path)
                 .arg("-U")
                 .arg(buffer_file_to_sign_path.to_path_buf())
-        } else {
-            let signing_key = config
-                .trusted_path("user.signingkey")
-                .transpose()?
-                .with_context(|| format!("Didn't trust 'ssh.signingKey': {signing_key}"))?;
-            signing_cmd
-                .arg(signing_key.into_owned())
-                .arg(buffer_file_to_sign_path.to_path_buf())
-        };
-        let output = into_command(signing_cmd)
-            .stderr(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stdin(Stdio::null())
-            .output()?;
-
-        if output.status.success() {
-            // read signed_storage path plus .sig
-            let signature_path = buffer_file_to_sign_path.with_extension("sig");
-            let sig_data = std::fs::read(signature_path)?;
-            let signature = BString::new(sig_data);
-            Ok(signature)
-        } else {
-            let stderr = BString::new(output.stderr);
-            let stdout = BString::new(output.stdout);
-            let std_both = format!("{stdout} {stderr}");
-            bail!("Failed to sign SSH: {}", std_both);
         }
+        SshSigningKey::Path(signing_key) => signing_cmd
+            .arg(signing_key)
+            .arg(buffer_file_to_sign_path.to_path_buf()),
+    };
+    let output = into_command(signing_cmd)
+        .stderr(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stdin(Stdio::null())
+        .output()?;
+
+    if output.status.success() {
+        // read signed_storage path plus .sig
+        let signature_path = buffer_file_to_sign_path.with_extension("sig");
+        let sig_data = std::fs::read(signature_path)?;
+        let signature = BString::new(sig_data);
+        Ok(signature)
     } else {
-        let gpg_program = config
-            .trusted_program("gpg.program")
-            .filter(|program| !program.is_empty())
-            .map_or_else(
-                || Path::new("gpg").into(),
-                |program| Cow::Owned(program.into_owned().into()),
-            );
-
-        let mut cmd = into_command(
-            prepare_with_shell_on_windows(gpg_program.as_ref())
-                .args(["--status-fd=2", "-bsau"])
-                .arg(gix::path::from_bstring(signing_key))
-                .arg("-"),
+        let stderr = BString::new(output.stderr);
+        let stdout = BString::new(output.stdout);
+        let std_both = format!("{stdout} {stderr}");
+        bail!("Failed to sign SSH: {}", std_both);
+    }
+}
+
+/// An SSH key to sign with.
+enum SshSigningKey {
+    /// The public key itself, with the private key held by the ssh-agent.
+    Literal(BString),
+    /// The path to a file with the private key, or with a public key whose private key is held by the ssh-agent.
+    Path(PathBuf),
+}
+
+/// Determine the SSH key to sign with just like Git, from `user.signingKey` or the output of `gpg.ssh.defaultKeyCommand`.
+fn ssh_signing_key(config: &gix::config::Snapshot<'_>) -> anyhow::Result<SshSigningKey> {
+    if let Some(signing_key) = config.string("user.signingkey") {
+        if let Some(literal_key) = as_literal_key(signing_key.as_ref()) {
+            return Ok(SshSigningKey::Literal(literal_key.to_owned()));
+        }
+        let path = config
+            .trusted_path("user.signingkey")
+            .transpose()?
+            .with_context(|| format!("Didn't trust 'user.signingKey': {signing_key}"))?;
+        return Ok(SshSigningKey::Path(path.into_owned()));
+    }
+
+    let Some(key_command) = config.trusted_program("gpg.ssh.defaultKeyCommand") else {
+        bail!(
+            "Either user.signingKey or gpg.ssh.defaultKeyCommand needs to be configured to sign with SSH"
         );
-        cmd.stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .stdin(Stdio::piped());
-
-        let mut child = match cmd.spawn() {
-            Ok(child) => child,
-            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
-                bail!(
-                    "Could not find '{}'. Please make sure it is in your `PATH` or configure the full path using `gpg.program` in the Git configuration",
-                    gpg_program.display()
-                )
-            }
+    };
+    let output = into_command(
+        gix::command::prepare(key_command.as_ref())
+            .with_shell()
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped()),
+    )
+    .output()
+    .with_context(|| {
+        format!(
+            "Could not run gpg.ssh.defaultKeyCommand '{}'",
+            key_command.to_string_lossy()
+        )
+    })?;
+    if !output.status.success() {
+        bail!(
+            "gpg.ssh.defaultKeyCommand failed: {}",
+            output.stderr.as_bstr()
+        );
+    }
+    output
+        .stdout
+        .lines()
+        .find_map(|line| as_literal_key(line.as_bstr()))
+        .map(|key| SshSigningKey::Literal(key.to_owned()))
+        .with_context(|| {
+            format!(
+                "gpg.ssh.defaultKeyCommand didn't print a usable key: {}",
+                output.stderr.as_bstr()
+            )
+        })
+}
+
+/// The outcome of verifying the signature of a commit with [`verify_commit()`].
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "subject")]
+pub enum SignatureStatus {
+    /// The commit isn't signed.
+    Unsigned,
+    /// The signature matches the commit and was made by a trusted key.
+    Valid {
+        /// The one who signed the commit, if it could be determined.
+        signer: Option<String>,
+    },
+    /// The signature doesn't match the commit, or was made by an expired or revoked key.
+    Invalid {
+        /// What's wrong with the signature.
+        reason: String,
+    },
+    /// The signature couldn't be checked, for instance because the key isn't known.
+    Unverified {
+        /// Why the signature couldn't be checked.
+        reason: String,
+    },
+}
+
+/// Verify the signature of the commit with `commit_id` using the configuration of `repo`, just like Git would.
+///
+/// SSH signatures are checked against the keys in `gpg.ssh.allowedSignersFile`.
+pub fn verify_commit(
+    repo: &gix::Repository,
+    commit_id: gix::ObjectId,
+) -> anyhow::Result<SignatureStatus> {
+    let mut commit = repo.find_commit(commit_id)?.decode()?.to_owned();
+    let Some(pos) = commit
+        .extra_headers()
+        .find_pos(gix::objs::commit::SIGNATURE_FIELD_NAME)
+    else {
+        return Ok(SignatureStatus::Unsigned);
+    };
+    let (_, signature) = commit.extra_headers.remove(pos);
+    // The signature was made over the commit as it was before it was added.
+    let mut payload = Vec::new();
+    commit.write_to(&mut payload)?;
+    verify_buffer(repo, &payload, signature.as_slice())
+}
+
+/// Verify that `signature` was made for `buffer` using the configuration of `repo`.
+pub fn verify_buffer(
+    repo: &gix::Repository,
+    buffer: &[u8],
+    signature: &[u8],
+) -> anyhow::Result<SignatureStatus> {
+    let Some(format) = SignatureFormat::of_signature(signature) else {
+        return Ok(SignatureStatus::Unverified {
+            reason: "The signature has an unknown format".into(),
+        });
+    };
+    let config = repo.config_snapshot();
+    let program = format.program(&config);
+    let mut signature_storage = tempfile::NamedTempFile::new()?;
+    signature_storage.write_all(signature)?;
+    let signature_path = signature_storage.into_temp_path();
+
+    if format != SignatureFormat::Ssh {
+        let cmd = prepare_with_shell_on_windows(program.as_os_str())
+            .args(["--status-fd=1", "--verify"])
+            .arg(signature_path.to_path_buf())
+            .arg("-");
+        let output = match output_with_stdin(into_command(cmd), buffer) {
+            Ok(output) => output,
             Err(err) => {
-                return Err(err).context(format!("Could not execute GPG program using {cmd:?}"));
+                return Ok(SignatureStatus::Unverified {
+                    reason: format!("Could not run '{}': {err}", program.display()),
+                });
             }
         };
-        child.stdin.take().expect("configured").write_all(buffer)?;
+        return Ok(gpg_status(&output, TrustLevel::from_config(&config)?));
+    }
 
-        let output = child.wait_with_output()?;
+    let Some(allowed_signers) = config
+        .trusted_path("gpg.ssh.allowedSignersFile")
+        .transpose()?
+    else {
+        return Ok(
+            match check_ssh_signature(&program, &signature_path, buffer) {
+                Ok(()) => SignatureStatus::Unverified {
+                    reason: "gpg.ssh.allowedSignersFile needs to be configured to know who signed"
+                        .into(),
+                },
+                Err(reason) => SignatureStatus::Invalid { reason },
+            },
+        );
+    };
+    let output = into_command(
+        prepare_with_shell_on_windows(program.as_os_str())
+            .args(["-Y", "find-principals", "-f"])
+            .arg(allowed_signers.as_os_str())
+            .arg("-s")
+            .arg(signature_path.to_path_buf()),
+    )
+    .stdin(Stdio::null())
+    .output()?;
+    let principals = output.stdout.lines().filter(|line| !line.is_empty());
+    let mut last_error = None;
+    for principal in principals {
+        let principal = principal.to_str_lossy().into_owned();
+        let cmd = prepare_with_shell_on_windows(program.as_os_str())
+            .args(["-Y", "verify", "-n", "git", "-f"])
+            .arg(allowed_signers.as_os_str())
+            .arg("-I")
+            .arg(&principal)
+            .arg("-s")
+            .arg(signature_path.to_path_buf());
+        let output = output_with_stdin(into_command(cmd), buffer)?;
         if output.status.success() {
-            // read stdout
-            let signature = BString::new(output.stdout);
-            Ok(signature)
-        } else {
-            let stderr = BString::new(output.stderr);
-            let stdout = BString::new(output.stdout);
-            let std_both = format!("{stdout} {stderr}");
-            bail!("Failed to sign GPG: {}", std_both);
+            return Ok(SignatureStatus::Valid {
+                signer: Some(principal),
+            });
         }
+        last_error = Some(output.stderr.to_str_lossy().trim().to_owned());
+    }
+    if let Some(reason) = last_error {
+        return Ok(SignatureStatus::Invalid { reason });
     }
+    Ok(
+        match check_ssh_signature(&program, &signature_path, buffer) {
+            Ok(()) => SignatureStatus::Unverified {
+                reason: "The key isn't listed in gpg.ssh.allowedSignersFile".into(),
+            },
+            Err(reason) => SignatureStatus::Invalid { reason },
+        },
+    )
+}
+
+/// Check that the SSH signature at `signature_path` matches `buffer` without knowing who made it,
+/// or return why it doesn't.
+fn check_ssh_signature(program: &Path, signature_path: &Path, buffer: &[u8]) -> Result<(), String> {
+    let cmd = prepare_with_shell_on_windows(program.as_os_str())
+        .args(["-Y", "check-novalidate", "-n", "git", "-s"])
+        .arg(signature_path);
+    match output_with_stdin(into_command(cmd), buffer) {
+        Ok(output) if output.status.success() => Ok(()),
+        Ok(output) => Err(output.stderr.to_str_lossy().trim().to_owned()),
+        Err(err) => Err(format!("Could not run '{}': {err}", program.display())),
+    }
+}
+
+/// How much a key is trusted to belong to the one it claims to belong to, as reported by GPG or GPGSM,
+/// in ascending order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum TrustLevel {
+    Undefined,
+    Never,
+    Marginal,
+    Fully,
+    Ultimate,
+}
+
+impl TrustLevel {
+    /// Parse the name of a trust level as used in `gpg.minTrustLevel`, or in `TRUST_*` status lines in upper case.
+    fn from_name(name: &[u8]) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_slice() {
+            b"undefined" => TrustLevel::Undefined,
+            b"never" => TrustLevel::Never,
+            b"marginal" => TrustLevel::Marginal,
+            b"fully" => TrustLevel::Fully,
+            b"ultimate" => TrustLevel::Ultimate,
+            _ => return None,
+        })
+    }
+
+    /// Return the name of the trust level as used in `gpg.minTrustLevel`.
+    fn name(self) -> &'static str {
+        match self {
+            TrustLevel::Undefined => "undefined",
+            TrustLevel::Never => "never",
+            TrustLevel::Marginal => "marginal",
+            TrustLevel::Fully => "fully",
+            TrustLevel::Ultimate => "ultimate",
+        }
+    }
+
+    /// Return the trust level a key needs for its signatures to be valid, as configured in `gpg.minTrustLevel`.
+    /// It defaults to `marginal`, which is when Git considers a good signature to be valid as well.
+    fn from_config(config: &gix::config::Snapshot<'_>) -> anyhow::Result<Self> {
+        let Some(level) = config.string("gpg.minTrustLevel") else {
+            return Ok(TrustLevel::Marginal);
+        };
+        TrustLevel::from_name(level.as_ref())
+            .with_context(|| format!("Unsupported trust level in gpg.minTrustLevel: '{level}'"))
+    }
+}
+
+/// Interpret the status lines that GPG or GPGSM printed in `output` while verifying a signature.
+/// Good signatures are only valid if the key is trusted at least as much as `min_trust`.
+fn gpg_status(output: &std::process::Output, min_trust: TrustLevel) -> SignatureStatus {
+    let mut good_signature = None;
+    let mut trust = None;
+    for line in output.stdout.lines() {
+        let Some(status) = line.strip_prefix(b"[GNUPG:] ") else {
+            continue;
+        };
+        let (keyword, rest) = status
+            .split_once_str(" ")
+            .unwrap_or((status, b"".as_slice()));
+        match keyword {
+            b"GOODSIG" => {
+                // The rest is the id of the key, followed by the user id.
+                let user_id = rest
+                    .split_once_str(" ")
+                    .map(|(_key_id, user_id)| user_id.to_str_lossy().into_owned());
+                good_signature = Some(user_id);
+            }
+            b"BADSIG" => {
+                return SignatureStatus::Invalid {
+                    reason: "The signature doesn't match the commit".into(),
+                };
+            }
+            b"EXPSIG" | b"EXPKEYSIG" => {
+                return SignatureStatus::Invalid {
+                    reason: "The signature or its key expired".into(),
+                };
+            }
+            b"REVKEYSIG" => {
+                return SignatureStatus::Invalid {
+                    reason: "The key was revoked".into(),
+                };
+            }
+            b"ERRSIG" | b"NO_PUBKEY" => {
+                return SignatureStatus::Unverified {
+                    reason: "The key of the signature isn't known".into(),
+                };
+            }
+            _ => {
+                if let Some(level) = keyword.strip_prefix(b"TRUST_") {
+                    trust = TrustLevel::from_name(level);
+                }
+            }
+        }
+    }
+    match (good_signature, trust) {
+        (Some(signer), Some(trust)) if trust >= min_trust => SignatureStatus::Valid { signer },
+        (Some(_), Some(trust)) => SignatureStatus::Unverified {
+            reason: format!(
+                "The signature is good, but the key is trusted '{}' while '{}' is required",
+                trust.name(),
+                min_trust.name()
+            ),
+        },
+        (Some(_), None) => SignatureStatus::Unverified {
+            reason: "The signature is good, but it's unknown if the key can be trusted".into(),
+        },
+        (None, _) => SignatureStatus::Unverified {
+            reason: output.stderr.to_str_lossy().trim().to_owned(),
+        },
+    }
+}
+
+/// Run `cmd`, pass `stdin` to it and collect all of its output.
+fn output_with_stdin(
+    mut cmd: std::process::Command,
+    stdin: &[u8],
+) -> std::io::Result<std::process::Output> {
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child.stdin.take().expect("configured").write_all(stdin)?;
+    child.wait_with_output()
 }
 
 fn into_command(prepare: gix::command::Prepare) -> std::process::Command {
@@ -5,7 +5,7 @@
 
 use crate::commit::DateMode;
 use anyhow::{Context, Ok, Result, anyhow, bail};
-use bstr::BString;
+use bstr::{BString, ByteSlice};
 use gix::objs::Exists;
 use gix::prelude::ObjectIdExt;
 use tracing::instrument;
@@ -13,12 +13,16 @@ use tracing::instrument;
 /// Types for use with cherry-picking
 pub mod cherry_pick;
 use crate::cherry_pick::{EmptyCommit, PickMode};
-pub use cherry_pick::function::cherry_pick_one;
+pub use cherry_pick::function::{cherry_pick_one, cherry_pick_one_with_resolutions};
 
 /// Utilities to create commits (and deal with signing)
 pub mod commit;
 /// Utilities around merging
 pub mod merge;
+/// Record how conflicts were resolved, and reuse these resolutions when the same conflicts happen again
+pub mod rerere;
+/// Export and import rebase steps as todo list in the format of `git-rebase-todo`
+pub mod todo;
 
 /// An instruction for [`RebaseBuilder::rebase()`].
 #[derive(Debug, Clone)]
@@ -50,6 +54,32 @@ pub enum RebaseStep {
     /// If this is the first step in the list, the reference will be to the `base` commit.
     /// If the step before this one is another `Reference` step, this reference will point to the same commit.
     Reference(but_core::Reference),
+    /// Drop an existing commit so it won't be part of the rewritten history.
+    /// The commits of the steps after it will be placed on top of the commit that preceded it.
+    Drop {
+        /// Id of an already existing commit
+        commit_id: gix::ObjectId,
+    },
+    /// Squashes an existing commit into the one that precedes it, just like [`SquashIntoPreceding`](Self::SquashIntoPreceding),
+    /// but keeps the message of the commit it is squashed into.
+    Fixup {
+        /// Id of an already existing commit
+        commit_id: gix::ObjectId,
+    },
+    /// Run `command` with a shell in a temporary checkout of the tree of the commit that precedes this step,
+    /// or the `base` commit if it is the first step.
+    ///
+    /// The rebase is aborted with an error if the command fails, which is useful to test each commit of a series.
+    /// Git commands run by it see the checkout as worktree, along with an index that matches it, and leave the
+    /// worktree and index of the repository alone.
+    Exec {
+        /// The command to run, interpreted by the shell.
+        command: String,
+    },
+    /// Stop the rebase at the commit that precedes this step so it can be edited, for instance in edit-mode.
+    ///
+    /// The remaining steps are returned as part of [`RebaseOutput::stopped_at`] so the rebase can be continued later.
+    Break,
 }
 
 impl RebaseStep {
@@ -57,8 +87,10 @@ impl RebaseStep {
     pub fn commit_id(&self) -> Option<&gix::oid> {
         match self {
             RebaseStep::Pick { commit_id, .. }
-            | RebaseStep::SquashIntoPreceding { commit_id, .. } => Some(commit_id),
-            RebaseStep::Reference { .. } => None,
+            | RebaseStep::SquashIntoPreceding { commit_id, .. }
+            | RebaseStep::Drop { commit_id }
+            | RebaseStep::Fixup { commit_id } => Some(commit_id),
+            RebaseStep::Reference { .. } | RebaseStep::Exec { .. } | RebaseStep::Break => None,
         }
     }
 }
@@ -71,6 +103,9 @@ pub struct Rebase<'repo> {
     base_substitute: Option<gix::ObjectId>,
     steps: Vec<RebaseStep>,
     rebase_noops: bool,
+    allow_merge_conflicts: bool,
+    resolutions: Option<rerere::Resolutions>,
+    stack_tips: Vec<(gix::ObjectId, but_core::ref_metadata::StackId)>,
 }
 
 impl<'repo> Rebase<'repo> {
@@ -99,6 +134,9 @@ impl<'repo> Rebase<'repo> {
             base_substitute,
             steps: Vec::new(),
             rebase_noops: true, // default to always rebasing
+            allow_merge_conflicts: false,
+            resolutions: None,
+            stack_tips: Vec::new(),
         })
     }
 
@@ -121,6 +159,36 @@ impl<'repo> Rebase<'repo> {
         self
     }
 
+    /// Configures whether picked merge commits that can't be re-merged cleanly should be recorded as conflicted
+    /// merge commits, instead of failing the rebase.
+    /// The conflicts are listed in [`RebaseOutput::merge_conflicts`].
+    /// Default is `false`
+    pub fn allow_merge_conflicts(&mut self, value: bool) -> &mut Self {
+        self.allow_merge_conflicts = value;
+        self
+    }
+
+    /// Configures recorded `resolutions` to resolve conflicting hunks with when picking commits, instead of
+    /// creating conflicted commits.
+    /// Commits that were resolved that way are marked with [`HEADERS_AUTO_RESOLVED_FIELD`](but_core::commit::HEADERS_AUTO_RESOLVED_FIELD).
+    /// Default is to not use any resolution
+    pub fn resolutions(&mut self, resolutions: rerere::Resolutions) -> &mut Self {
+        self.resolutions = Some(resolutions);
+        self
+    }
+
+    /// Configures the tip commits of stacks, along with the id of their stack, to know which stacks collide
+    /// when picked merge commits that merge them are conflicted.
+    /// These are listed in [`MergeConflict::stacks`](merge::MergeConflict::stacks).
+    /// Default is to not know any stack.
+    pub fn stack_tips(
+        &mut self,
+        tips: impl IntoIterator<Item = (gix::ObjectId, but_core::ref_metadata::StackId)>,
+    ) -> &mut Self {
+        self.stack_tips = tips.into_iter().collect();
+        self
+    }
+
     /// Performs a rebase on top of a given base, according to the provided steps, or fails if no step was provided.
     /// It does not actually create new git references nor does it update existing ones, it only deals with
     /// altering commits and providing the information needed to update refs.
@@ -143,13 +211,33 @@ impl<'repo> Rebase<'repo> {
         } else {
             PickMode::SkipIfNoop
         };
-        rebase(
+        let mut output = rebase(
             self.repo,
             self.base,
             self.base_substitute,
             std::mem::take(&mut self.steps),
             pick_mode,
-        )
+            self.allow_merge_conflicts,
+            self.resolutions.as_ref(),
+        )?;
+        for (_merge_id, conflict) in &mut output.merge_conflicts {
+            conflict.stacks = conflict
+                .parents
+                .iter()
+                .filter_map(|parent| {
+                    // Rewritten parents are known to the caller by their original commit.
+                    let original = output
+                        .commit_mapping
+                        .iter()
+                        .find_map(|(_base, old, new)| (new == parent).then_some(old))
+                        .unwrap_or(parent);
+                    self.stack_tips
+                        .iter()
+                        .find_map(|(tip, stack_id)| (tip == original).then_some(*stack_id))
+                })
+                .collect();
+        }
+        Ok(output)
     }
 }
 
@@ -165,8 +253,14 @@ impl Rebase<'_> {
     /// - Must not be a reference step immediately before it
     /// - Must not be the first operation
     ///
+    /// Drop operations:
+    /// - The commit must exist, must not be the base commit and must not be used in another step
+    ///
     /// Reference operations:
     /// - The refname must be a valid reference name
+    ///
+    /// Exec operations:
+    /// - The command must not be empty
     fn validate_step(&self, step: &RebaseStep) -> Result<()> {
         match step {
             RebaseStep::Pick { commit_id, .. } => {
@@ -175,7 +269,8 @@ impl Rebase<'_> {
             RebaseStep::SquashIntoPreceding {
                 commit_id,
                 new_message: _,
-            } => {
+            }
+            | RebaseStep::Fixup { commit_id } => {
                 self.assure_unique_step_and_existing_non_base(commit_id, "Fixup")?;
                 if matches!(self.steps.last(), Some(RebaseStep::Reference { .. })) {
                     bail!("Fixup commit must not come after a reference step");
@@ -191,6 +286,15 @@ impl Rebase<'_> {
                     ));
                 }
             }
+            RebaseStep::Drop { commit_id } => {
+                self.assure_unique_step_and_existing_non_base(commit_id, "Dropped")?;
+            }
+            RebaseStep::Exec { command } => {
+                if command.trim().is_empty() {
+                    bail!("Exec step must have a non-empty command");
+                }
+            }
+            RebaseStep::Break => {}
         }
         Ok(())
     }
@@ -218,15 +322,23 @@ fn rebase(
     base_substitute: Option<gix::ObjectId>,
     steps: Vec<RebaseStep>,
     pick_mode: PickMode,
+    allow_merge_conflicts: bool,
+    resolutions: Option<&rerere::Resolutions>,
 ) -> Result<RebaseOutput> {
     let (mut references, mut commit_mapping) = (
         vec![],
         Vec::<(Option<gix::ObjectId>, gix::ObjectId, gix::ObjectId)>::new(),
     );
+    let (mut dropped_commits, mut executed_commands, mut merge_conflicts) =
+        (vec![], vec![], vec![]);
     let (mut cursor, mut last_seen_commit) = (base, base);
     let cache = repo.commit_graph_if_enabled()?;
     let mut graph = repo.revision_graph(cache.as_ref());
-    for step in steps {
+    let branches = branch_of_each_step(&steps);
+    let mut steps = steps.into_iter().zip(branches);
+    let mut stopped_at = None;
+    while let Some((step, branch)) = steps.next() {
+        let branch = branch.as_deref();
         match step {
             RebaseStep::Pick {
                 commit_id,
@@ -239,7 +351,7 @@ fn rebase(
                 if commit.parents.len() > 1 {
                     let mut merge_commit = commit;
                     if let Some(new_message) = new_message {
-                        merge_commit.message = new_message;
+                        set_message(repo, &mut merge_commit, new_message, branch)?;
                     }
                     // Find any parent that we have seen during picking.
                     let parent_to_replace = match merge_commit.parents.iter_mut().find(|id| {
@@ -256,30 +368,40 @@ fn rebase(
                         Some(parent) => parent,
                     };
                     *parent_to_replace = cursor.context("Expecting a base for any merge")?;
-                    cursor = merge::octopus(repo, merge_commit, &mut graph)
-                        .context(
+                    cursor = if allow_merge_conflicts {
+                        let (merge_id, conflict) =
+                            merge::octopus_allow_conflicts(repo, merge_commit, &mut graph)?;
+                        if let Some(conflict) = conflict {
+                            merge_conflicts.push((merge_id, conflict));
+                        }
+                        merge_id
+                    } else {
+                        merge::octopus(repo, merge_commit, &mut graph).context(
                             "The rebase failed as a merge could not be repeated without conflicts",
                         )?
-                        .into();
+                    }
+                    .into();
                 } else {
                     match &mut cursor {
                         Some(cursor) => {
-                            let mut new_commit = cherry_pick_one(
+                            let mut new_commit = cherry_pick_one_with_resolutions(
                                 repo,
                                 *cursor,
                                 commit_id,
                                 pick_mode,
                                 EmptyCommit::Keep,
+                                resolutions,
                             )?;
                             if let Some(new_message) = new_message {
-                                new_commit = reword_commit(repo, new_commit, new_message.clone())?;
+                                new_commit =
+                                    reword_commit(repo, new_commit, new_message.clone(), branch)?;
                             }
                             *cursor = new_commit;
                         }
                         None if commit.parents.is_empty() => {
                             let mut new_commit = commit;
                             if let Some(new_message) = new_message {
-                                new_commit.message = new_message;
+                                set_message(repo, &mut new_commit, new_message, branch)?;
                             }
                             cursor = Some(commit::create(
                                 repo,
@@ -304,22 +426,51 @@ fn rebase(
                     bail!("Can't squash if previous commit is missing");
                 };
                 last_seen_commit = Some(commit_id);
-                let base_commit = repo.find_commit(*cursor)?;
-                let new_commit = cherry_pick_one(
+                *cursor = squash_into(
                     repo,
                     *cursor,
                     commit_id,
-                    PickMode::Unconditionally,
-                    EmptyCommit::Keep,
+                    new_message.map(Squash::Reword),
+                    branch,
+                    resolutions,
                 )?;
-
-                // Now, lets pretend the base didn't exist by swapping parent with the parent of the base
-                let mut new_commit = repo.find_commit(new_commit)?.decode()?.to_owned();
-                new_commit.parents = base_commit.parent_ids().map(|id| id.detach()).collect();
-                if let Some(new_message) = new_message {
-                    new_commit.message = new_message;
-                }
-                *cursor = commit::create(repo, new_commit, DateMode::CommitterUpdateAuthorKeep)?;
+            }
+            RebaseStep::Fixup { commit_id } => {
+                let Some(cursor) = &mut cursor else {
+                    bail!("Can't fixup if previous commit is missing");
+                };
+                last_seen_commit = Some(commit_id);
+                *cursor = squash_into(
+                    repo,
+                    *cursor,
+                    commit_id,
+                    Some(Squash::KeepTarget),
+                    branch,
+                    resolutions,
+                )?;
+            }
+            RebaseStep::Drop { commit_id } => {
+                // References after this step previously pointed to the dropped commit, and now point to its predecessor.
+                last_seen_commit = Some(commit_id);
+                dropped_commits.push(commit_id);
+                continue;
+            }
+            RebaseStep::Exec { command } => {
+                let commit_id =
+                    cursor.context("Can't run a command without a commit to run it on")?;
+                exec(repo, commit_id, &command).with_context(|| {
+                    format!("The rebase was aborted as '{command}' failed on {commit_id}")
+                })?;
+                executed_commands.push((command, commit_id));
+                continue;
+            }
+            RebaseStep::Break => {
+                let commit_id = cursor.context("Can't stop without a commit to stop at")?;
+                stopped_at = Some(RebaseStop {
+                    commit_id,
+                    remaining_steps: steps.map(|(step, _branch)| step).collect(),
+                });
+                break;
             }
             RebaseStep::Reference(reference) => {
                 references.push(ReferenceSpec {
@@ -337,12 +488,112 @@ fn rebase(
     }
 
     Ok(RebaseOutput {
-        top_commit: cursor.expect("validation assures we have at least one commit to process"),
+        top_commit: cursor.context("Cannot drop all commits if there is no base commit")?,
         references,
         commit_mapping,
+        dropped_commits,
+        executed_commands,
+        stopped_at,
+        merge_conflicts,
     })
 }
 
+/// What to do with the message of a commit that is squashed into another commit.
+enum Squash {
+    /// Use the given message, and apply the trailer policy to it.
+    Reword(BString),
+    /// Keep the message of the commit that is squashed into.
+    KeepTarget,
+}
+
+/// Squash `commit_id` into `target`, and return the newly created commit which has the parents of `target`.
+/// If `message` is `None`, the message of `commit_id` is used.
+/// `branch` is the short name of the branch the squashed commit is on, if known.
+fn squash_into(
+    repo: &gix::Repository,
+    target: gix::ObjectId,
+    commit_id: gix::ObjectId,
+    message: Option<Squash>,
+    branch: Option<&str>,
+    resolutions: Option<&rerere::Resolutions>,
+) -> Result<gix::ObjectId> {
+    let base_commit = repo.find_commit(target)?;
+    let new_commit = cherry_pick_one_with_resolutions(
+        repo,
+        target,
+        commit_id,
+        PickMode::Unconditionally,
+        EmptyCommit::Keep,
+        resolutions,
+    )?;
+
+    // Now, lets pretend the base didn't exist by swapping parent with the parent of the base
+    let mut new_commit = repo.find_commit(new_commit)?.decode()?.to_owned();
+    new_commit.parents = base_commit.parent_ids().map(|id| id.detach()).collect();
+    match message {
+        Some(Squash::Reword(new_message)) => {
+            let squashed_message = std::mem::replace(&mut new_commit.message, new_message);
+            commit::apply_trailer_policy(
+                repo,
+                &mut new_commit,
+                &[base_commit.message_raw()?, squashed_message.as_bstr()],
+                branch,
+            )?;
+        }
+        Some(Squash::KeepTarget) => {
+            new_commit.message = base_commit.message_raw()?.to_owned();
+        }
+        None => {}
+    }
+    Ok(commit::create(
+        repo,
+        new_commit,
+        DateMode::CommitterUpdateAuthorKeep,
+    )?)
+}
+
+/// Run `command` with a shell in a temporary checkout of the tree of `commit_id`, and fail if it doesn't succeed.
+/// The worktree and the index of `repo` aren't touched, as Git commands run by `command` use an index of their own
+/// that matches the checkout.
+fn exec(repo: &gix::Repository, commit_id: gix::ObjectId, command: &str) -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let (checkout_dir, index_path) = (tmp.path().join("worktree"), tmp.path().join("index"));
+    std::fs::create_dir(&checkout_dir)?;
+    let tree_id = repo.find_commit(commit_id)?.tree_id()?;
+    let mut index = repo.index_from_tree(&tree_id)?;
+    let mut opts =
+        repo.checkout_options(gix::worktree::stack::state::attributes::Source::IdMapping)?;
+    opts.destination_is_initially_empty = true;
+    gix::worktree::state::checkout(
+        &mut index,
+        &checkout_dir,
+        repo.clone().objects.into_arc()?,
+        &gix::progress::Discard,
+        &gix::progress::Discard,
+        &gix::interrupt::IS_INTERRUPTED,
+        opts,
+    )?;
+    let (state, _path) = index.into_parts();
+    gix::index::File::from_state(state, &index_path).write(Default::default())?;
+
+    let output = std::process::Command::from(gix::command::prepare(command).with_shell())
+        .current_dir(&checkout_dir)
+        .env("GIT_DIR", repo.git_dir())
+        .env("GIT_WORK_TREE", &checkout_dir)
+        .env("GIT_INDEX_FILE", &index_path)
+        .env("BUT_REBASE_COMMIT", commit_id.to_string())
+        .stdin(std::process::Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        bail!(
+            "{status}: {stderr}",
+            status = output.status,
+            stderr = output.stderr.to_str_lossy().trim()
+        );
+    }
+    Ok(())
+}
+
 fn to_commit(repo: &gix::Repository, commit_id: gix::ObjectId) -> Result<gix::objs::Commit> {
     Ok(commit_id
         .attach(repo)
@@ -356,9 +607,10 @@ fn reword_commit(
     repo: &gix::Repository,
     oid: gix::ObjectId,
     new_message: BString,
+    branch: Option<&str>,
 ) -> Result<gix::ObjectId> {
     let mut new_commit = repo.find_commit(oid)?.decode()?.to_owned();
-    new_commit.message = new_message;
+    set_message(repo, &mut new_commit, new_message, branch)?;
     Ok(commit::create(
         repo,
         new_commit,
@@ -366,6 +618,39 @@ fn reword_commit(
     )?)
 }
 
+/// Set `new_message` as message of `commit` and apply the trailer policy to it, as it replaces the current message.
+/// `branch` is the short name of the branch `commit` is on, if known.
+fn set_message(
+    repo: &gix::Repository,
+    commit: &mut gix::objs::Commit,
+    new_message: BString,
+    branch: Option<&str>,
+) -> Result<()> {
+    let previous_message = std::mem::replace(&mut commit.message, new_message);
+    commit::apply_trailer_policy(repo, commit, &[previous_message.as_bstr()], branch)
+}
+
+/// Return the short name of the branch that the commit of each of `steps` ends up on, which is the one
+/// of the first reference step after it.
+fn branch_of_each_step(steps: &[RebaseStep]) -> Vec<Option<String>> {
+    let mut branch = None;
+    let mut branches: Vec<_> = steps
+        .iter()
+        .rev()
+        .map(|step| {
+            if let RebaseStep::Reference(reference) = step {
+                branch = match reference {
+                    but_core::Reference::Git(name) => name.shorten().to_str().ok().map(Into::into),
+                    but_core::Reference::Virtual(name) => Some(name.clone()),
+                };
+            }
+            branch.clone()
+        })
+        .collect();
+    branches.reverse();
+    branches
+}
+
 /// Replaces the tree of a commit for use in the rebase engine.
 pub fn replace_commit_tree(
     repo: &gix::Repository,
@@ -394,6 +679,15 @@ pub struct ReferenceSpec {
     pub previous_commit_id: gix::ObjectId,
 }
 
+/// The place at which a rebase was stopped by a [`RebaseStep::Break`].
+#[derive(Debug, Clone)]
+pub struct RebaseStop {
+    /// The rewritten commit that the rebase stopped at, which is also the [top commit](RebaseOutput::top_commit).
+    pub commit_id: gix::ObjectId,
+    /// The steps that weren't processed yet, which can be used to continue the rebase with `commit_id` as base.
+    pub remaining_steps: Vec<RebaseStep>,
+}
+
 /// The output of the [rebase](RebaseBuilder::rebase()) operation.
 #[derive(Debug, Clone)]
 pub struct RebaseOutput {
@@ -406,4 +700,14 @@ pub struct RebaseOutput {
     ///
     /// That way programmatic users may perform their own remapping without having to deal with [references](RebaseStep::Reference).
     pub commit_mapping: Vec<(Option<gix::ObjectId>, gix::ObjectId, gix::ObjectId)>,
+    /// The commits that were removed by [`RebaseStep::Drop`], in order of steps.
+    pub dropped_commits: Vec<gix::ObjectId>,
+    /// The commands of [`RebaseStep::Exec`] that ran successfully, along with the rewritten commit they ran on.
+    pub executed_commands: Vec<(String, gix::ObjectId)>,
+    /// Set if a [`RebaseStep::Break`] stopped the rebase before all steps were processed.
+    pub stopped_at: Option<RebaseStop>,
+    /// The merge commits that were re-merged with conflicts, along with information about the conflict, in order of steps.
+    ///
+    /// This is only set if [merge conflicts are allowed](Rebase::allow_merge_conflicts()).
+    pub merge_conflicts: Vec<(gix::ObjectId, merge::MergeConflict)>,
 }
@@ -1,8 +1,11 @@
+use crate::cherry_pick::function::{CONFLICTED_COMMIT_README, ConflictEntries};
 use crate::commit::DateMode;
 use anyhow::{Result, anyhow, bail};
 use bstr::{BString, ByteSlice};
 use but_core::commit::TreeKind;
+use but_core::ref_metadata::StackId;
 use gitbutler_oxidize::GixRepositoryExt;
+use gix::object::tree::EntryKind;
 use gix::prelude::ObjectIdExt;
 
 /// Perform a three-base merge for each of the parents in `target_merge_commit` which serves as template for the merge.
@@ -26,13 +29,63 @@ use gix::prelude::ObjectIdExt;
 /// Thanks to this logic, the caller shouldn't have to steer signing.
 pub fn octopus(
     repo: &gix::Repository,
-    mut target_merge_commit: gix::objs::Commit,
+    target_merge_commit: gix::objs::Commit,
     graph: &mut gix::revwalk::Graph<
         '_,
         '_,
         gix::revwalk::graph::Commit<gix::revision::plumbing::merge_base::Flags>,
     >,
 ) -> Result<gix::ObjectId> {
+    octopus_inner(repo, target_merge_commit, graph, false).map(|(id, _conflict)| id)
+}
+
+/// Like [`octopus()`], but instead of failing on conflicts, auto-resolve them in favor of *ours* and record them
+/// in a conflicted merge commit, just like cherry-picks do.
+///
+/// The tree of such a commit records the tree of the first parent as [ours](TreeKind::Ours), and the one of the
+/// second parent as [theirs](TreeKind::Theirs), along with the [merge base](TreeKind::Base) and the
+/// [auto-resolution](TreeKind::AutoResolution).
+/// With more than two parents, *theirs* is the merge of all parents after the first one, with conflicts among them
+/// resolved in favor of the earlier parent, so the conflict can be resolved like any other.
+///
+/// Returns the id of the new merge commit, along with information about the conflict if there was one.
+pub fn octopus_allow_conflicts(
+    repo: &gix::Repository,
+    target_merge_commit: gix::objs::Commit,
+    graph: &mut gix::revwalk::Graph<
+        '_,
+        '_,
+        gix::revwalk::graph::Commit<gix::revision::plumbing::merge_base::Flags>,
+    >,
+) -> Result<(gix::ObjectId, Option<MergeConflict>)> {
+    octopus_inner(repo, target_merge_commit, graph, true)
+}
+
+/// Information about a conflicted merge commit as created by [`octopus_allow_conflicts()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeConflict {
+    /// The parents of the merge commit whose changes collide, in the order of parents.
+    ///
+    /// When rebasing, these are the rewritten stack tips that are merged, and can be mapped back to the original
+    /// commits with [`RebaseOutput::commit_mapping`](crate::RebaseOutput::commit_mapping).
+    pub parents: Vec<gix::ObjectId>,
+    /// The stacks of those of `parents` that are the tips of [stacks known to the rebase](crate::Rebase::stack_tips()),
+    /// in the order of `parents`.
+    pub stacks: Vec<StackId>,
+    /// All the paths that were conflicted, at the location of *our* side.
+    pub paths: Vec<BString>,
+}
+
+fn octopus_inner(
+    repo: &gix::Repository,
+    mut target_merge_commit: gix::objs::Commit,
+    graph: &mut gix::revwalk::Graph<
+        '_,
+        '_,
+        gix::revwalk::graph::Commit<gix::revision::plumbing::merge_base::Flags>,
+    >,
+    allow_conflicts: bool,
+) -> Result<(gix::ObjectId, Option<MergeConflict>)> {
     if target_merge_commit.parents.len() < 2 {
         bail!("An octopus merge commits must have at least two parents");
     }
@@ -42,7 +95,7 @@ pub fn octopus(
     )?
     .tree_id_or_kind(TreeKind::Base)?
     .detach();
-    let mut trees_to_merge = parents_to_merge
+    let parent_trees = parents_to_merge
         .clone()
         .map(|commit_id| -> Result<_> {
             // TODO: as long as only cherry-picking is creating these trees, THEIRS
@@ -52,12 +105,20 @@ pub fn octopus(
                 .tree_id_or_kind(TreeKind::Theirs)?
                 .detach())
         })
-        .collect::<Result<Vec<_>, _>>()?
-        .into_iter();
-    let mut ours = trees_to_merge.next().expect("two or more trees");
-    let (merge_options, unresolved) = repo.merge_options_fail_fast()?;
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut trees_to_merge = parents_to_merge.clone().zip(parent_trees.iter().copied());
+    let (_, mut ours) = trees_to_merge.next().expect("two or more trees");
+    let (merge_options, unresolved) = if allow_conflicts {
+        (
+            repo.merge_options_force_ours()?,
+            gix::merge::tree::TreatAsUnresolved::forced_resolution(),
+        )
+    } else {
+        repo.merge_options_fail_fast()?
+    };
     let mut successfully_merged = vec![ours];
-    for tree_to_merge in trees_to_merge {
+    let mut conflict: Option<MergeConflict> = None;
+    for (parent_idx, (parent_id, tree_to_merge)) in trees_to_merge.enumerate() {
         let mut merge = repo.merge_trees(
             merge_base,
             ours,
@@ -66,37 +127,92 @@ pub fn octopus(
             merge_options.clone(),
         )?;
         if merge.has_unresolved_conflicts(unresolved) {
-            return Err(anyhow!(
-                "Encountered conflict when merging tree {tree_to_merge}{details}",
-                details = if successfully_merged.len() > 1 {
-                    format!(
-                        " after the trees {} were merged successfully",
-                        successfully_merged
-                            .iter()
-                            .map(|id| id.to_string())
-                            .collect::<Vec<_>>()
-                            .join(", ")
-                    )
-                } else {
-                    format!(" and tree {tree_to_merge}")
+            if !allow_conflicts {
+                return Err(anyhow!(
+                    "Encountered conflict when merging tree {tree_to_merge}{details}",
+                    details = if successfully_merged.len() > 1 {
+                        format!(
+                            " after the trees {} were merged successfully",
+                            successfully_merged
+                                .iter()
+                                .map(|id| id.to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )
+                    } else {
+                        format!(" and tree {tree_to_merge}")
+                    }
+                )
+                .context(ConflictErrorContext {
+                    paths: merge
+                        .conflicts
+                        .iter()
+                        .map(|c| c.ours.location().to_owned())
+                        .collect(),
+                }));
+            }
+            let paths: Vec<BString> = merge
+                .conflicts
+                .iter()
+                .filter(|c| c.is_unresolved(unresolved))
+                .map(|c| c.ours.location().to_owned())
+                .collect();
+            let conflict = conflict.get_or_insert_with(|| MergeConflict {
+                parents: Vec::new(),
+                stacks: Vec::new(),
+                paths: Vec::new(),
+            });
+            // The first parent of the iteration was merged already.
+            let merged_parents = &target_merge_commit.parents[..parent_idx + 1];
+            let merged_trees = &parent_trees[..parent_idx + 1];
+            for colliding_parent in
+                colliding_parents(repo, merge_base, merged_parents, merged_trees, &paths)?
+                    .into_iter()
+                    .chain(Some(parent_id))
+            {
+                if !conflict.parents.contains(&colliding_parent) {
+                    conflict.parents.push(colliding_parent);
+                }
+            }
+            for path in paths {
+                if !conflict.paths.contains(&path) {
+                    conflict.paths.push(path);
                 }
-            )
-            .context(ConflictErrorContext {
-                paths: merge
-                    .conflicts
-                    .iter()
-                    .map(|c| c.ours.location().to_owned())
-                    .collect(),
-            }));
+            }
         }
         successfully_merged.push(tree_to_merge);
         ours = merge.tree.write()?.detach();
     }
-    target_merge_commit.tree = ours;
-    if but_core::commit::HeadersV2::try_from_commit(&target_merge_commit).is_none() {
-        but_core::commit::HeadersV2::default().set_in_commit(&mut target_merge_commit);
+
+    if let Some(conflict) = &mut conflict {
+        // Keep the parents in the order of the merge commit.
+        conflict.parents.sort_by_key(|id| {
+            target_merge_commit
+                .parents
+                .iter()
+                .position(|parent| parent == id)
+        });
+        target_merge_commit.tree =
+            write_conflicted_tree(repo, merge_base, &parent_trees, ours, &conflict.paths)?;
+    } else {
+        target_merge_commit.tree = ours;
+    }
+    let conflicted = conflict
+        .as_ref()
+        .map(|conflict| conflict.paths.len().max(1) as u64);
+    match but_core::commit::HeadersV2::try_from_commit(&target_merge_commit) {
+        None => but_core::commit::HeadersV2 {
+            conflicted,
+            ..Default::default()
+        }
+        .set_in_commit(&mut target_merge_commit),
+        Some(mut headers) if headers.conflicted != conflicted => {
+            headers.conflicted = conflicted;
+            headers.set_in_commit(&mut target_merge_commit);
+        }
+        Some(_) => {}
     }
-    if target_merge_commit
+    let id = if target_merge_commit
         .extra_headers()
         .pgp_signature()
         .is_some()
@@ -105,11 +221,105 @@ pub fn octopus(
             repo,
             target_merge_commit,
             DateMode::CommitterUpdateAuthorKeep,
-        )
+        )?
     } else {
         crate::commit::update_committer(repo, &mut target_merge_commit)?;
-        Ok(repo.write_object(target_merge_commit)?.detach())
+        repo.write_object(target_merge_commit)?.detach()
+    };
+    Ok((id, conflict))
+}
+
+/// Return those of `parents` with their respective `trees` that changed any of `paths` compared to `merge_base`.
+/// If none of them can be found to have changed any path, all of them are returned as the collision must be
+/// with the merge of all of them.
+fn colliding_parents(
+    repo: &gix::Repository,
+    merge_base: gix::ObjectId,
+    parents: &[gix::ObjectId],
+    trees: &[gix::ObjectId],
+    paths: &[BString],
+) -> Result<Vec<gix::ObjectId>> {
+    let entry_at = |tree: gix::ObjectId, path: &BString| -> Result<_> {
+        Ok(repo
+            .find_tree(tree)?
+            .lookup_entry_by_path(gix::path::from_bstr(path.as_bstr()))?
+            .map(|entry| (entry.object_id(), entry.mode())))
+    };
+    let mut out = Vec::new();
+    for (parent, tree) in parents.iter().zip(trees) {
+        for path in paths {
+            if entry_at(*tree, path)? != entry_at(merge_base, path)? {
+                out.push(*parent);
+                break;
+            }
+        }
+    }
+    if out.is_empty() {
+        out.extend_from_slice(parents);
+    }
+    Ok(out)
+}
+
+/// Write the special tree of a conflicted merge commit, with the tree of the first parent as *ours*, and the merge
+/// of all other parents as *theirs*.
+fn write_conflicted_tree(
+    repo: &gix::Repository,
+    merge_base: gix::ObjectId,
+    parent_trees: &[gix::ObjectId],
+    auto_resolution: gix::ObjectId,
+    paths: &[BString],
+) -> Result<gix::ObjectId> {
+    let ours = parent_trees[0];
+    let mut theirs = parent_trees[1];
+    for tree in &parent_trees[2..] {
+        theirs = repo
+            .merge_trees(
+                merge_base,
+                theirs,
+                *tree,
+                repo.default_merge_labels(),
+                repo.merge_options_force_ours()?,
+            )?
+            .tree
+            .write()?
+            .detach();
+    }
+
+    let entries_in = |tree: gix::ObjectId| -> Result<Vec<_>> {
+        let tree = repo.find_tree(tree)?;
+        let mut entries = Vec::new();
+        for path in paths {
+            let path = gix::path::from_bstr(path.as_bstr());
+            if tree.lookup_entry_by_path(&path)?.is_some() {
+                entries.push(path.into_owned());
+            }
+        }
+        Ok(entries)
+    };
+    let conflicted_files = ConflictEntries {
+        ancestor_entries: entries_in(merge_base)?,
+        our_entries: entries_in(ours)?,
+        their_entries: entries_in(theirs)?,
+    };
+    let conflicted_files_blob = repo.write_blob(toml::to_string(&conflicted_files)?.as_bytes())?;
+    let readme_blob = repo.write_blob(CONFLICTED_COMMIT_README)?;
+
+    let mut tree = repo.empty_tree().edit()?;
+    for (kind, id) in [
+        (TreeKind::Ours, ours),
+        (TreeKind::Theirs, theirs),
+        (TreeKind::Base, merge_base),
+        (TreeKind::AutoResolution, auto_resolution),
+    ] {
+        tree.upsert(kind.as_tree_entry_name(), EntryKind::Tree, id)?;
     }
+    tree.upsert(
+        TreeKind::ConflictFiles.as_tree_entry_name(),
+        EntryKind::Blob,
+        conflicted_files_blob,
+    )?;
+    tree.upsert("README.txt", EntryKind::Blob, readme_blob)?;
+    Ok(tree.write()?.detach())
 }
 
 /// A type that can be retrieved as an `anyhow` context to see if the rebase failed due to merge conflicts.
@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use anyhow::Result;
+use bstr::{BStr, BString, ByteSlice};
+use gitbutler_oxidize::GixRepositoryExt;
+
+/// A hunk with conflict markers, with all ranges being line indices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictHunk {
+    /// All lines of the hunk, from the `<<<<<<<` line to the `>>>>>>>` line.
+    pub markers: Range<usize>,
+    /// The lines of our side.
+    pub ours: Range<usize>,
+    /// The lines of the common ancestor, only set for diff3-style conflict markers.
+    pub base: Option<Range<usize>>,
+    /// The lines of their side.
+    pub theirs: Range<usize>,
+}
+
+/// Find all hunks with conflict markers in `content`, ignoring incomplete ones.
+pub fn conflict_hunks(content: &[u8]) -> Vec<ConflictHunk> {
+    let mut out = Vec::new();
+    let (mut start, mut base_start, mut theirs_start) = (None, None, None);
+    for (idx, line) in content.lines().enumerate() {
+        if is_marker(line, b'<') {
+            (start, base_start, theirs_start) = (Some(idx), None, None);
+        } else if is_marker(line, b'|') && start.is_some() && theirs_start.is_none() {
+            base_start = Some(idx);
+        } else if is_marker(line, b'=') && start.is_some() {
+            theirs_start = Some(idx);
+        } else if is_marker(line, b'>') {
+            if let Some((start, theirs_start)) = start.zip(theirs_start) {
+                out.push(ConflictHunk {
+                    markers: start..idx + 1,
+                    ours: start + 1..base_start.unwrap_or(theirs_start),
+                    base: base_start.map(|base_start| base_start + 1..theirs_start),
+                    theirs: theirs_start + 1..idx,
+                });
+            }
+            (start, base_start, theirs_start) = (None, None, None);
+        }
+    }
+    out
+}
+
+/// Return `true` if `line` is a conflict marker made of seven `marker` characters, optionally followed by a label.
+fn is_marker(line: &[u8], marker: u8) -> bool {
+    line.len() >= 7
+        && line[..7].iter().all(|b| *b == marker)
+        && line.get(7).is_none_or(|b| b.is_ascii_whitespace())
+}
+
+/// Compute the id under which the resolution of a conflicting hunk with `ours` and `theirs` is recorded.
+///
+/// The sides are ordered before hashing, so the same conflict is recognized no matter which side it is rebased onto.
+pub fn preimage_id(
+    object_hash: gix::hash::Kind,
+    ours: &[u8],
+    theirs: &[u8],
+) -> Result<gix::ObjectId> {
+    let (first, second) = if ours <= theirs {
+        (ours, theirs)
+    } else {
+        (theirs, ours)
+    };
+    let mut buf = Vec::with_capacity(first.len() + second.len() + 1);
+    buf.extend_from_slice(first);
+    buf.push(0);
+    buf.extend_from_slice(second);
+    Ok(gix::objs::compute_hash(
+        object_hash,
+        gix::object::Kind::Blob,
+        &buf,
+    )?)
+}
+
+/// Merge the trees of a cherry-pick like [`cherry_pick_one()`](crate::cherry_pick_one()) does, but write conflict
+/// markers into the conflicting files instead of resolving them in favor of *ours*, and return the merged tree.
+pub fn merge_with_conflict_markers(
+    repo: &gix::Repository,
+    base: gix::ObjectId,
+    ours: gix::ObjectId,
+    theirs: gix::ObjectId,
+) -> Result<gix::ObjectId> {
+    let mut outcome = repo.merge_trees(
+        base,
+        ours,
+        theirs,
+        repo.default_merge_labels(),
+        repo.tree_merge_options()?,
+    )?;
+    Ok(outcome.tree.write()?.detach())
+}
+
+/// Extract how each of the conflicting hunks in `preimage`, a file with conflict markers, was resolved in `postimage`,
+/// and return the [preimage id](preimage_id()) of each hunk along with its resolution.
+///
+/// The resolutions are found by looking for the lines around the conflicting hunks in `postimage`, so nothing is
+/// returned if these were changed as well, or if `postimage` still has conflict markers.
+pub fn extract_resolutions(
+    object_hash: gix::hash::Kind,
+    preimage: &[u8],
+    postimage: &[u8],
+) -> Result<Vec<(gix::ObjectId, BString)>> {
+    let hunks = conflict_hunks(preimage);
+    if hunks.is_empty() || !conflict_hunks(postimage).is_empty() {
+        return Ok(Vec::new());
+    }
+    let lines: Vec<_> = preimage.lines_with_terminator().collect();
+    let join = |range: Range<usize>| -> BString { lines[range].concat().into() };
+
+    let mut contexts = Vec::with_capacity(hunks.len() + 1);
+    let mut context_start = 0;
+    for hunk in &hunks {
+        contexts.push(join(context_start..hunk.markers.start));
+        context_start = hunk.markers.end;
+    }
+    contexts.push(join(context_start..lines.len()));
+
+    let (leading, trailing) = (&contexts[0], &contexts[contexts.len() - 1]);
+    if !postimage.starts_with(leading)
+        || !postimage.ends_with(trailing)
+        || postimage.len() < leading.len() + trailing.len()
+    {
+        return Ok(Vec::new());
+    }
+    let postimage = &postimage[..postimage.len() - trailing.len()];
+    let mut pos = leading.len();
+    let mut resolved = Vec::with_capacity(hunks.len());
+    for context in &contexts[1..contexts.len() - 1] {
+        // Empty contexts between hunks would make it impossible to know where one resolution ends.
+        let Some(offset) = (!context.is_empty())
+            .then(|| postimage[pos..].find(context))
+            .flatten()
+        else {
+            return Ok(Vec::new());
+        };
+        resolved.push(BString::from(&postimage[pos..pos + offset]));
+        pos += offset + context.len();
+    }
+    resolved.push(postimage[pos..].into());
+
+    hunks
+        .iter()
+        .zip(resolved)
+        .map(|(hunk, resolution)| {
+            let id = preimage_id(
+                object_hash,
+                &join(hunk.ours.clone()),
+                &join(hunk.theirs.clone()),
+            )?;
+            Ok((id, resolution))
+        })
+        .collect()
+}
+
+/// Resolutions of conflicting hunks that were recorded when the user resolved them, keyed by [preimage id](preimage_id()),
+/// to reuse them when the same conflict happens again, like `git rerere` does.
+#[derive(Debug, Default, Clone)]
+pub struct Resolutions {
+    by_preimage: HashMap<gix::ObjectId, BString>,
+}
+
+impl Resolutions {
+    /// Load all resolutions that were recorded in `db`.
+    pub fn from_db(db: &mut but_db::DbHandle) -> Result<Self> {
+        let by_preimage = db
+            .conflict_resolutions()
+            .list()?
+            .into_iter()
+            .filter_map(|resolution| {
+                let id = gix::ObjectId::from_hex(resolution.preimage_id.as_bytes()).ok()?;
+                Some((id, resolution.resolution.into()))
+            })
+            .collect();
+        Ok(Resolutions { by_preimage })
+    }
+
+    /// Record that the conflicting hunk with `preimage_id` is resolved with `resolution`.
+    pub fn insert(&mut self, preimage_id: gix::ObjectId, resolution: BString) {
+        self.by_preimage.insert(preimage_id, resolution);
+    }
+
+    /// Return the recorded resolution of the conflicting hunk with `preimage_id`.
+    pub fn get(&self, preimage_id: &gix::oid) -> Option<&BStr> {
+        self.by_preimage
+            .get(preimage_id)
+            .map(|resolution| resolution.as_bstr())
+    }
+
+    /// Return `true` if no resolution was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.by_preimage.is_empty()
+    }
+
+    /// Replace all conflicting hunks in `content`, a file with conflict markers, with their recorded resolutions,
+    /// and return the resolved content along with the amount of resolved hunks.
+    ///
+    /// Return `None` if there are no conflict markers, or if any of the hunks has no recorded resolution.
+    pub fn resolve(
+        &self,
+        object_hash: gix::hash::Kind,
+        content: &[u8],
+    ) -> Result<Option<(BString, usize)>> {
+        let hunks = conflict_hunks(content);
+        if hunks.is_empty() {
+            return Ok(None);
+        }
+        let lines: Vec<_> = content.lines_with_terminator().collect();
+        let mut out = BString::default();
+        let mut context_start = 0;
+        for hunk in &hunks {
+            let id = preimage_id(
+                object_hash,
+                &lines[hunk.ours.clone()].concat(),
+                &lines[hunk.theirs.clone()].concat(),
+            )?;
+            let Some(resolution) = self.get(&id) else {
+                return Ok(None);
+            };
+            out.extend(lines[context_start..hunk.markers.start].concat());
+            out.extend_from_slice(resolution);
+            context_start = hunk.markers.end;
+        }
+        out.extend(lines[context_start..].concat());
+        Ok(Some((out, hunks.len())))
+    }
+}
+
+/// Mark the conflicts of `commit_id` that were [resolved automatically](crate::cherry_pick_one_with_resolutions())
+/// as reviewed by the user, and return the id of the rewritten commit without the
+/// [auto-resolved header](but_core::commit::HEADERS_AUTO_RESOLVED_FIELD).
+///
+/// `commit_id` is returned unchanged if it wasn't auto-resolved.
+/// Note that commits on top of the rewritten commit have to be rebased onto it.
+pub fn acknowledge_auto_resolution(
+    repo: &gix::Repository,
+    commit_id: gix::ObjectId,
+) -> Result<gix::ObjectId> {
+    let mut commit = repo.find_commit(commit_id)?.decode()?.to_owned();
+    let Some(pos) = commit
+        .extra_headers()
+        .find_pos(but_core::commit::HEADERS_AUTO_RESOLVED_FIELD)
+    else {
+        return Ok(commit_id);
+    };
+    commit.extra_headers.remove(pos);
+    crate::commit::create(
+        repo,
+        commit,
+        crate::commit::DateMode::CommitterKeepAuthorKeep,
+    )
+}
+
+/// Resolve the conflicts at `paths` of a cherry-pick of `theirs` onto `ours` with `base` as merge-base with recorded
+/// `resolutions`, and return the `auto_resolved_tree` with the resolved files along with the amount of resolved hunks.
+///
+/// Return `None` if any of the conflicts can't be resolved that way, for instance because it isn't a conflict in
+/// the content of a file, or because one of its hunks was never resolved before.
+pub(crate) fn resolve_conflicts(
+    repo: &gix::Repository,
+    (base, ours, theirs): (gix::ObjectId, gix::ObjectId, gix::ObjectId),
+    auto_resolved_tree: gix::ObjectId,
+    paths: &[BString],
+    resolutions: &Resolutions,
+) -> Result<Option<(gix::ObjectId, usize)>> {
+    let marker_tree = merge_with_conflict_markers(repo, base, ours, theirs)?;
+    let marker_tree = repo.find_tree(marker_tree)?;
+    let mut editor = repo.find_tree(auto_resolved_tree)?.edit()?;
+    let mut resolved_hunks = 0;
+    for path in paths {
+        let Some(entry) = marker_tree.lookup_entry_by_path(gix::path::from_bstr(path.as_bstr()))?
+        else {
+            return Ok(None);
+        };
+        if !entry.mode().is_blob() {
+            return Ok(None);
+        }
+        let blob = entry.object()?;
+        let Some((resolved, hunks)) = resolutions.resolve(repo.object_hash(), &blob.data)? else {
+            return Ok(None);
+        };
+        editor.upsert(
+            path.as_bstr(),
+            entry.mode().kind(),
+            repo.write_blob(resolved)?,
+        )?;
+        resolved_hunks += hunks;
+    }
+    Ok(Some((editor.write()?.detach(), resolved_hunks)))
+}
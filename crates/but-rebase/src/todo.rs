@@ -0,0 +1,218 @@
+use crate::RebaseStep;
+use anyhow::{Context, Result, bail};
+use bstr::{BStr, BString, ByteSlice};
+use gix::prelude::ObjectIdExt;
+use std::fmt::Write;
+
+const HELP: &str = r#"
+# Commands:
+# p, pick <commit> = use commit
+# r, reword <commit> = use commit, but edit the commit message
+# s, squash <commit> = use commit, but meld into previous commit
+# f, fixup <commit> = like "squash", but keep only the previous commit's message
+# x, exec <command> = run command (the rest of the line) using shell on the commit of the line above
+# b, break = stop at the commit of the line above to edit it
+# d, drop <commit> = remove commit
+# u, update-ref <ref> = point <ref> to the commit of the line above
+#
+# Lines are applied from top to bottom, starting with the oldest commit.
+# Removing the line of a commit drops it.
+"#;
+
+/// Write `steps` as todo list in the format of `git-rebase-todo`, with one line per step and the oldest
+/// commit first, followed by comments that explain all commands.
+///
+/// Picks with a new message are written as `reword`, and squashes as `squash`, without their messages.
+pub fn serialize(repo: &gix::Repository, steps: &[RebaseStep]) -> Result<String> {
+    let mut out = String::new();
+    for step in steps {
+        match step {
+            RebaseStep::Pick {
+                commit_id,
+                new_message,
+            } => {
+                let command = if new_message.is_some() {
+                    "reword"
+                } else {
+                    "pick"
+                };
+                writeln!(out, "{command} {}", commit_line(repo, commit_id)?)?;
+            }
+            RebaseStep::SquashIntoPreceding { commit_id, .. } => {
+                writeln!(out, "squash {}", commit_line(repo, commit_id)?)?;
+            }
+            RebaseStep::Fixup { commit_id } => {
+                writeln!(out, "fixup {}", commit_line(repo, commit_id)?)?;
+            }
+            RebaseStep::Drop { commit_id } => {
+                writeln!(out, "drop {}", commit_line(repo, commit_id)?)?;
+            }
+            RebaseStep::Exec { command } => {
+                writeln!(out, "exec {command}")?;
+            }
+            RebaseStep::Break => {
+                writeln!(out, "break")?;
+            }
+            RebaseStep::Reference(reference) => {
+                let name = match reference {
+                    but_core::Reference::Git(name) => name.as_bstr().to_str_lossy(),
+                    but_core::Reference::Virtual(name) => name.into(),
+                };
+                writeln!(out, "update-ref {name}")?;
+            }
+        }
+    }
+    out.push_str(HELP);
+    Ok(out)
+}
+
+/// Parse `todo` in the format of `git-rebase-todo`, typically created by [`serialize()`] and edited by the user,
+/// into rebase steps. Empty lines and comments are ignored.
+///
+/// `reword` is called with the id and the current message of each commit to reword, and returns its new message.
+/// Squashed commits get the messages of both commits, while fixups keep the message of the commit they are squashed into.
+/// Commits that aren't mentioned are dropped, just like the ones that are dropped explicitly.
+///
+/// Errors mention the number and content of the offending line.
+pub fn parse(
+    repo: &gix::Repository,
+    todo: &str,
+    mut reword: impl FnMut(gix::ObjectId, &BStr) -> Result<BString>,
+) -> Result<Vec<RebaseStep>> {
+    let mut steps = Vec::new();
+    // The message of the commit that a squash or fixup would go into.
+    let mut message = None;
+    for (idx, line) in todo.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let step = parse_line(repo, line, &steps, &mut message, &mut reword)
+            .with_context(|| format!("line {}: {line}", idx + 1))?;
+        steps.push(step);
+    }
+    if !steps.iter().any(|step| {
+        matches!(
+            step,
+            RebaseStep::Pick { .. }
+                | RebaseStep::SquashIntoPreceding { .. }
+                | RebaseStep::Fixup { .. }
+        )
+    }) {
+        bail!("Nothing to do as no commit is picked");
+    }
+    Ok(steps)
+}
+
+fn parse_line(
+    repo: &gix::Repository,
+    line: &str,
+    previous_steps: &[RebaseStep],
+    message: &mut Option<BString>,
+    reword: &mut dyn FnMut(gix::ObjectId, &BStr) -> Result<BString>,
+) -> Result<RebaseStep> {
+    let (command, args) = line
+        .split_once(char::is_whitespace)
+        .map_or((line, ""), |(command, args)| (command, args.trim()));
+    let step = match command {
+        "p" | "pick" | "r" | "reword" | "s" | "squash" | "f" | "fixup" | "d" | "drop" => {
+            let commit_id = parse_commit(repo, args)?;
+            if previous_steps
+                .iter()
+                .any(|step| step.commit_id() == Some(&*commit_id))
+            {
+                bail!("Commit {commit_id} is used more than once");
+            }
+            let commit_message = repo.find_commit(commit_id)?.message_raw()?.to_owned();
+            match command {
+                "p" | "pick" => {
+                    *message = Some(commit_message);
+                    RebaseStep::Pick {
+                        commit_id,
+                        new_message: None,
+                    }
+                }
+                "r" | "reword" => {
+                    let new_message = reword(commit_id, commit_message.as_bstr())?;
+                    *message = Some(new_message.clone());
+                    RebaseStep::Pick {
+                        commit_id,
+                        new_message: Some(new_message),
+                    }
+                }
+                "d" | "drop" => RebaseStep::Drop { commit_id },
+                _ => {
+                    let Some(target_message) = message.as_ref() else {
+                        bail!("There is no previous commit to squash into");
+                    };
+                    if matches!(previous_steps.last(), Some(RebaseStep::Reference(_))) {
+                        bail!("Can't squash into a commit that a reference was placed on");
+                    }
+                    if matches!(command, "f" | "fixup") {
+                        RebaseStep::Fixup { commit_id }
+                    } else {
+                        let new_message = but_core::commit::trailer::squash_messages([
+                            target_message,
+                            &commit_message,
+                        ]);
+                        *message = Some(new_message.clone());
+                        RebaseStep::SquashIntoPreceding {
+                            commit_id,
+                            new_message: Some(new_message),
+                        }
+                    }
+                }
+            }
+        }
+        "x" | "exec" => {
+            if args.is_empty() {
+                bail!("Expected a command to run");
+            }
+            RebaseStep::Exec {
+                command: args.to_owned(),
+            }
+        }
+        "b" | "break" => {
+            if !args.is_empty() {
+                bail!("'break' doesn't take arguments");
+            }
+            RebaseStep::Break
+        }
+        "u" | "update-ref" => {
+            if args.is_empty() || args.contains(char::is_whitespace) {
+                bail!("Expected a single reference name");
+            }
+            let reference = if args.starts_with("refs/") {
+                but_core::Reference::Git(args.try_into()?)
+            } else {
+                but_core::Reference::Virtual(args.to_owned())
+            };
+            RebaseStep::Reference(reference)
+        }
+        _ => bail!("Unknown command '{command}'"),
+    };
+    Ok(step)
+}
+
+/// Parse the commit at the beginning of `args`, ignoring the commit title that typically follows it.
+fn parse_commit(repo: &gix::Repository, args: &str) -> Result<gix::ObjectId> {
+    let spec = args
+        .split_whitespace()
+        .next()
+        .context("Expected a commit after the command")?;
+    let commit = repo
+        .rev_parse_single(spec)
+        .ok()
+        .and_then(|id| id.object().ok()?.try_into_commit().ok())
+        .with_context(|| format!("'{spec}' is not a commit"))?;
+    Ok(commit.id)
+}
+
+fn commit_line(repo: &gix::Repository, commit_id: &gix::oid) -> Result<String> {
+    let commit = repo.find_commit(commit_id)?;
+    let title = commit.message()?.summary();
+    Ok(format!(
+        "{} {title}",
+        commit_id.to_owned().attach(repo).shorten_or_id()
+    ))
+}
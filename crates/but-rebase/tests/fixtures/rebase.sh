@@ -47,3 +47,45 @@ git init merge-in-the-middle
   git checkout with-inner-merge && git merge --no-ff B
   echo seq 10 >'added-after-with-inner-merge' && git add . && git commit -m "on top of inner merge"
 )
+
+git init merge-that-conflicts-when-rebased
+(cd merge-that-conflicts-when-rebased
+  seq 10 >file && git add . && git commit -m "base" && git tag base
+  git branch B
+  git branch A-conflicting
+
+  git checkout -b A
+  echo a >a && git add . && git commit -m "A: add a"
+
+  git checkout B
+  { seq 5; echo B; seq 7 10; } >file && git add . && git commit -m "B: change line 6"
+
+  git checkout A-conflicting
+  { seq 5; echo A; seq 7 10; } >file && git add . && git commit -m "A: change line 6 as well"
+
+  git checkout main
+  git merge A B
+)
+
+git init octopus-that-conflicts-when-rebased
+(cd octopus-that-conflicts-when-rebased
+  seq 10 >file && git add . && git commit -m "base" && git tag base
+  git branch B
+  git branch C
+  git branch A-conflicting
+
+  git checkout -b A
+  echo a >a && git add . && git commit -m "A: add a"
+
+  git checkout B
+  { seq 5; echo B; seq 7 10; } >file && git add . && git commit -m "B: change line 6"
+
+  git checkout C
+  echo c >c && git add . && git commit -m "C: add c"
+
+  git checkout A-conflicting
+  git rm file && git commit -m "A: remove file"
+
+  git checkout main
+  git merge A B C
+)
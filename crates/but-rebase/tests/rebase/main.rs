@@ -3,11 +3,15 @@ use crate::utils::{
 };
 use anyhow::Result;
 use bstr::ByteSlice;
+use but_core::ref_metadata::StackId;
+use but_rebase::merge::MergeConflict;
 use but_rebase::{Rebase, RebaseStep};
 use but_testsupport::{assure_stable_env, visualize_commit_graph};
 use gix::prelude::ObjectIdExt;
 
 mod error_handling;
+mod rerere;
+mod todo;
 
 mod commit {
     mod store_author_globally_if_unset {
@@ -64,6 +68,422 @@ mod commit {
             Ok(())
         }
     }
+
+    mod signing {
+        use std::io::Write as _;
+        use std::path::{Path, PathBuf};
+        use std::process::{Child, Command, Stdio};
+
+        use anyhow::Context as _;
+        use bstr::ByteSlice as _;
+
+        use crate::utils::{fixture, fixture_writable};
+        use but_rebase::commit::{self, DateMode, SignatureFormat, SignatureStatus};
+
+        #[test]
+        fn format_defaults_to_openpgp_and_rejects_unknown_values() -> anyhow::Result<()> {
+            let mut repo = fixture("four-commits")?;
+            assert_eq!(
+                SignatureFormat::from_config(&repo.config_snapshot())?,
+                SignatureFormat::OpenPgp
+            );
+
+            repo.config_snapshot_mut()
+                .set_raw_value(&"gpg.format", "x509")?;
+            assert_eq!(
+                SignatureFormat::from_config(&repo.config_snapshot())?,
+                SignatureFormat::X509
+            );
+
+            repo.config_snapshot_mut()
+                .set_raw_value(&"gpg.format", "unknown")?;
+            let err = SignatureFormat::from_config(&repo.config_snapshot()).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Unsupported signature format in gpg.format: 'unknown'"
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn unsigned_commits() -> anyhow::Result<()> {
+            let repo = fixture("four-commits")?;
+            let head = repo.head_id()?.detach();
+            assert_eq!(
+                commit::verify_commit(&repo, head)?,
+                SignatureStatus::Unsigned
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn ssh_signatures_are_verified_with_allowed_signers() -> anyhow::Result<()> {
+            let (mut repo, tmp) = fixture_writable("four-commits")?;
+            let key_path = tmp.path().join("signing-key");
+            let status = std::process::Command::new("ssh-keygen")
+                .args(["-q", "-t", "ed25519", "-N", "", "-C", "signer", "-f"])
+                .arg(&key_path)
+                .status()?;
+            assert!(status.success(), "the key can be generated");
+            let public_key = std::fs::read_to_string(key_path.with_extension("pub"))?;
+            let allowed_signers = tmp.path().join("allowed-signers");
+            std::fs::write(&allowed_signers, format!("signer@example.com {public_key}"))?;
+            {
+                let mut config = repo.config_snapshot_mut();
+                config.set_raw_value(&"gitbutler.signCommits", "true")?;
+                config.set_raw_value(&"gpg.format", "ssh")?;
+                config.set_raw_value(&"user.signingKey", key_path.to_str().expect("UTF-8"))?;
+                config.set_raw_value(
+                    &"gpg.ssh.allowedSignersFile",
+                    allowed_signers.to_str().expect("UTF-8"),
+                )?;
+            }
+
+            let head = repo.head_commit()?.decode()?.to_owned();
+            let signed = commit::create(&repo, head, DateMode::CommitterKeepAuthorKeep)?;
+            assert_eq!(
+                commit::verify_commit(&repo, signed)?,
+                SignatureStatus::Valid {
+                    signer: Some("signer@example.com".into())
+                }
+            );
+
+            let mut tampered = repo.find_commit(signed)?.decode()?.to_owned();
+            tampered.message = "a message that wasn't signed".into();
+            let tampered = repo.write_object(&tampered)?.detach();
+            assert!(
+                matches!(
+                    commit::verify_commit(&repo, tampered)?,
+                    SignatureStatus::Invalid { .. }
+                ),
+                "the signature is kept as is, but doesn't match anymore"
+            );
+            Ok(())
+        }
+
+        #[test]
+        #[cfg(unix)]
+        fn ssh_literal_keys_are_used_through_the_agent() -> anyhow::Result<()> {
+            let (mut repo, tmp) = fixture_writable("four-commits")?;
+            let agent = SshAgent::with_new_key(tmp.path())?;
+            configure_ssh_signing(&mut repo, tmp.path(), &agent)?;
+            repo.config_snapshot_mut().set_raw_value(
+                &"user.signingKey",
+                format!("key::{}", agent.public_key).as_str(),
+            )?;
+
+            let head = repo.head_commit()?.decode()?.to_owned();
+            let signed = commit::create(&repo, head, DateMode::CommitterKeepAuthorKeep)?;
+            assert_eq!(
+                commit::verify_commit(&repo, signed)?,
+                SignatureStatus::Valid {
+                    signer: Some("signer@example.com".into())
+                },
+                "only the agent has the private key"
+            );
+            Ok(())
+        }
+
+        #[test]
+        #[cfg(unix)]
+        fn ssh_default_key_command_provides_the_first_usable_key() -> anyhow::Result<()> {
+            let (mut repo, tmp) = fixture_writable("four-commits")?;
+            let agent = SshAgent::with_new_key(tmp.path())?;
+            configure_ssh_signing(&mut repo, tmp.path(), &agent)?;
+            repo.config_snapshot_mut().set_raw_value(
+                &"gpg.ssh.defaultKeyCommand",
+                format!("printf 'not a key\\nkey::{}\\n'", agent.public_key).as_str(),
+            )?;
+
+            let head = repo.head_commit()?.decode()?.to_owned();
+            let signed = commit::create(&repo, head, DateMode::CommitterKeepAuthorKeep)?;
+            assert_eq!(
+                commit::verify_commit(&repo, signed)?,
+                SignatureStatus::Valid {
+                    signer: Some("signer@example.com".into())
+                }
+            );
+
+            repo.config_snapshot_mut()
+                .set_raw_value(&"gpg.ssh.defaultKeyCommand", "echo nothing-usable")?;
+            let head = repo.head_commit()?.decode()?.to_owned();
+            let err = commit::create(&repo, head, DateMode::CommitterKeepAuthorKeep).unwrap_err();
+            assert!(
+                err.to_string()
+                    .starts_with("gpg.ssh.defaultKeyCommand didn't print a usable key"),
+                "{err}"
+            );
+            Ok(())
+        }
+
+        #[test]
+        #[cfg(unix)]
+        fn x509_signatures_are_only_valid_with_trusted_certificates() -> anyhow::Result<()> {
+            if Command::new("gpgsm").arg("--version").output().is_err() {
+                eprintln!("Skipping as gpgsm isn't installed");
+                return Ok(());
+            }
+            let (mut repo, tmp) = fixture_writable("four-commits")?;
+            let gpgsm = GpgsmHome::with_new_certificate(&tmp.path().join("gnupg"))?;
+            let program = program_with_env(tmp.path(), "gpgsm", &[("GNUPGHOME", &gpgsm.home)])?;
+            {
+                let mut config = repo.config_snapshot_mut();
+                config.set_raw_value(&"gitbutler.signCommits", "true")?;
+                config.set_raw_value(&"gpg.format", "x509")?;
+                config.set_raw_value(&"gpg.x509.program", program.to_str().expect("UTF-8"))?;
+                config.set_raw_value(&"user.signingKey", "signer@example.com")?;
+            }
+
+            let head = repo.head_commit()?.decode()?.to_owned();
+            let signed = commit::create(&repo, head, DateMode::CommitterKeepAuthorKeep)?;
+            let status = commit::verify_commit(&repo, signed)?;
+            assert!(
+                matches!(&status, SignatureStatus::Valid { signer: Some(signer) } if signer.contains("signer@example.com")),
+                "{status:?}"
+            );
+
+            gpgsm.distrust()?;
+            assert_eq!(
+                commit::verify_commit(&repo, signed)?,
+                SignatureStatus::Unverified {
+                    reason: "The signature is good, but the key is trusted 'undefined' while 'marginal' is required".into()
+                },
+                "good signatures of untrusted certificates aren't valid"
+            );
+
+            repo.config_snapshot_mut()
+                .set_raw_value(&"gpg.minTrustLevel", "undefined")?;
+            assert!(
+                matches!(
+                    commit::verify_commit(&repo, signed)?,
+                    SignatureStatus::Valid { .. }
+                ),
+                "the required trust can be configured"
+            );
+            Ok(())
+        }
+
+        /// Run `cmd` and return its output, failing if it fails.
+        fn run(cmd: &mut Command) -> anyhow::Result<Vec<u8>> {
+            let output = cmd.stdin(Stdio::null()).output()?;
+            anyhow::ensure!(
+                output.status.success(),
+                "{cmd:?} failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+            Ok(output.stdout)
+        }
+
+        /// Write an executable script into `dir` that runs `program` with `vars` in its environment,
+        /// to not have to change the environment of the test itself.
+        #[cfg(unix)]
+        fn program_with_env(
+            dir: &Path,
+            program: &str,
+            vars: &[(&str, &Path)],
+        ) -> anyhow::Result<PathBuf> {
+            use std::os::unix::fs::PermissionsExt;
+            let mut script = String::from("#!/bin/sh\n");
+            for (name, value) in vars {
+                script.push_str(&format!("export {name}='{}'\n", value.display()));
+            }
+            script.push_str(&format!("exec {program} \"$@\"\n"));
+            let path = dir.join(format!("{program}-with-env"));
+            std::fs::write(&path, script)?;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+            Ok(path)
+        }
+
+        /// Sign commits with SSH, using the key held by `agent`, and accept its signatures as made by `signer@example.com`.
+        #[cfg(unix)]
+        fn configure_ssh_signing(
+            repo: &mut gix::Repository,
+            dir: &Path,
+            agent: &SshAgent,
+        ) -> anyhow::Result<()> {
+            let program = program_with_env(dir, "ssh-keygen", &[("SSH_AUTH_SOCK", &agent.socket)])?;
+            let allowed_signers = dir.join("allowed-signers");
+            std::fs::write(
+                &allowed_signers,
+                format!("signer@example.com {}\n", agent.public_key),
+            )?;
+            let mut config = repo.config_snapshot_mut();
+            config.set_raw_value(&"gitbutler.signCommits", "true")?;
+            config.set_raw_value(&"gpg.format", "ssh")?;
+            config.set_raw_value(&"gpg.ssh.program", program.to_str().expect("UTF-8"))?;
+            config.set_raw_value(
+                &"gpg.ssh.allowedSignersFile",
+                allowed_signers.to_str().expect("UTF-8"),
+            )?;
+            Ok(())
+        }
+
+        /// An `ssh-agent` that is the only one holding the private key of a new key pair, stopped when dropped.
+        struct SshAgent {
+            child: Child,
+            socket: PathBuf,
+            /// The public key, as used in `authorized_keys` files.
+            public_key: String,
+        }
+
+        impl SshAgent {
+            fn with_new_key(dir: &Path) -> anyhow::Result<Self> {
+                let key_path = dir.join("agent-key");
+                run(Command::new("ssh-keygen")
+                    .args(["-q", "-t", "ed25519", "-N", "", "-C", "signer", "-f"])
+                    .arg(&key_path))?;
+                let public_key = std::fs::read_to_string(key_path.with_extension("pub"))?
+                    .trim()
+                    .to_owned();
+                let socket = dir.join("agent.sock");
+                let child = Command::new("ssh-agent")
+                    .arg("-D")
+                    .arg("-a")
+                    .arg(&socket)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .spawn()?;
+                let agent = SshAgent {
+                    child,
+                    socket,
+                    public_key,
+                };
+                for _ in 0..100 {
+                    if agent.socket.exists() {
+                        break;
+                    }
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+                }
+                run(Command::new("ssh-add")
+                    .arg(&key_path)
+                    .env("SSH_AUTH_SOCK", &agent.socket))?;
+                std::fs::remove_file(&key_path)?;
+                Ok(agent)
+            }
+        }
+
+        impl Drop for SshAgent {
+            fn drop(&mut self) {
+                self.child.kill().ok();
+                self.child.wait().ok();
+            }
+        }
+
+        /// A `GNUPGHOME` for `gpgsm` with a new self-signed certificate for `signer@example.com`,
+        /// whose agent is stopped when dropped.
+        struct GpgsmHome {
+            home: PathBuf,
+        }
+
+        impl GpgsmHome {
+            fn with_new_certificate(home: &Path) -> anyhow::Result<Self> {
+                use std::os::unix::fs::DirBuilderExt;
+                std::fs::DirBuilder::new().mode(0o700).create(home)?;
+                let gpgsm = GpgsmHome {
+                    home: home.to_owned(),
+                };
+                std::fs::write(home.join("gpgsm.conf"), "disable-crl-checks\n")?;
+                std::fs::write(
+                    home.join("gpg-agent.conf"),
+                    "allow-loopback-pinentry\nallow-preset-passphrase\n",
+                )?;
+
+                let (key, cert, bundle) = (
+                    home.join("key.pem"),
+                    home.join("cert.pem"),
+                    home.join("bundle.p12"),
+                );
+                run(Command::new("openssl")
+                    .args([
+                        "req", "-x509", "-newkey", "rsa:2048", "-nodes", "-days", "1",
+                    ])
+                    .args(["-subj", "/CN=Signer/emailAddress=signer@example.com"])
+                    .args(["-addext", "keyUsage=digitalSignature,keyCertSign"])
+                    .arg("-keyout")
+                    .arg(&key)
+                    .arg("-out")
+                    .arg(&cert))?;
+                // `gpgsm` can only read bundles encrypted the old way.
+                run(Command::new("openssl")
+                    .args(["pkcs12", "-export", "-passout", "pass:secret"])
+                    .args(["-keypbe", "PBE-SHA1-3DES", "-certpbe", "PBE-SHA1-3DES"])
+                    .args(["-macalg", "sha1", "-inkey"])
+                    .arg(&key)
+                    .arg("-in")
+                    .arg(&cert)
+                    .arg("-out")
+                    .arg(&bundle))?;
+                let mut import = gpgsm
+                    .command("gpgsm")
+                    .args([
+                        "--batch",
+                        "--pinentry-mode",
+                        "loopback",
+                        "--passphrase-fd",
+                        "0",
+                    ])
+                    .arg("--import")
+                    .arg(&bundle)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn()?;
+                import
+                    .stdin
+                    .take()
+                    .expect("piped")
+                    .write_all(b"secret\n\n\n")?;
+                anyhow::ensure!(import.wait()?.success(), "the certificate can be imported");
+
+                let listing = run(gpgsm.command("gpgsm").args([
+                    "--with-colons",
+                    "--with-keygrip",
+                    "--list-secret-keys",
+                ]))?;
+                let field = |record: &str| {
+                    listing
+                        .lines()
+                        .find_map(|line| {
+                            let line = line.to_str().ok()?;
+                            line.starts_with(record)
+                                .then(|| line.split(':').nth(9).map(ToOwned::to_owned))?
+                        })
+                        .with_context(|| format!("no {record} record"))
+                };
+                let (fingerprint, keygrip) = (field("fpr:")?, field("grp:")?);
+                let libexec_dir =
+                    run(gpgsm.command("gpgconf").args(["--list-dirs", "libexecdir"]))?;
+                run(gpgsm
+                    .command(Path::new(libexec_dir.trim().to_str()?).join("gpg-preset-passphrase"))
+                    .args(["--preset", "-P", "secret"])
+                    .arg(keygrip))?;
+                std::fs::write(
+                    home.join("trustlist.txt"),
+                    format!("{fingerprint} S relax\n"),
+                )?;
+                Ok(gpgsm)
+            }
+
+            /// Stop trusting the certificate.
+            fn distrust(&self) -> anyhow::Result<()> {
+                std::fs::remove_file(self.home.join("trustlist.txt"))?;
+                run(self.command("gpgconf").args(["--reload", "gpg-agent"]))?;
+                Ok(())
+            }
+
+            fn command(&self, program: impl AsRef<std::ffi::OsStr>) -> Command {
+                let mut cmd = Command::new(program);
+                cmd.env("GNUPGHOME", &self.home);
+                cmd
+            }
+        }
+
+        impl Drop for GpgsmHome {
+            fn drop(&mut self) {
+                run(self.command("gpgconf").args(["--kill", "all"])).ok();
+            }
+        }
+    }
 }
 
 #[test]
@@ -133,6 +553,10 @@ fn single_stack_journey() -> Result<()> {
                 Sha1(b036cfe2b7250396114e433883a48271a90ebe4d),
             ),
         ],
+        dropped_commits: [],
+        executed_commands: [],
+        stopped_at: None,
+        merge_conflicts: [],
     }
     "#);
     assure_nonconflicting(&repo, &out)?;
@@ -213,6 +637,10 @@ fn amended_commit() -> Result<()> {
                 Sha1(cc1d6d57b45f1967b8ee333941a6d6d6d512da13),
             ),
         ],
+        dropped_commits: [],
+        executed_commands: [],
+        stopped_at: None,
+        merge_conflicts: [],
     }
     ");
     assure_nonconflicting(&repo, &out)?;
@@ -292,6 +720,10 @@ fn reorder_merge_in_reverse() -> Result<()> {
                 Sha1(5949b5b6f3de27a6f8db16c3ae2bdda155220e6a),
             ),
         ],
+        dropped_commits: [],
+        executed_commands: [],
+        stopped_at: None,
+        merge_conflicts: [],
     }
     ");
     assure_nonconflicting(&repo, &out)?;
@@ -373,6 +805,10 @@ fn reorder_with_conflict_and_remerge_and_pick_from_conflicts() -> Result<()> {
                 Sha1(555c076b0434c148991fc6be55cafbcdde37f8eb),
             ),
         ],
+        dropped_commits: [],
+        executed_commands: [],
+        stopped_at: None,
+        merge_conflicts: [],
     }
     ");
     insta::assert_snapshot!(visualize_commit_graph(&repo, out.top_commit)?, @r"
@@ -500,6 +936,156 @@ fn reorder_with_conflict_and_remerge_and_pick_from_conflicts() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn remerge_with_conflicts_creates_conflicted_merge_commit() -> Result<()> {
+    let (repo, _tmp) = fixture_writable("merge-that-conflicts-when-rebased")?;
+    let steps = [
+        RebaseStep::Pick {
+            commit_id: repo.rev_parse_single("A-conflicting")?.into(),
+            new_message: None,
+        },
+        RebaseStep::Pick {
+            commit_id: repo.rev_parse_single("main")?.into(),
+            new_message: None,
+        },
+    ];
+    let base = repo.rev_parse_single("base")?.detach();
+    let err = Rebase::new(&repo, base, None)?
+        .steps(steps.clone())?
+        .rebase()
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "The rebase failed as a merge could not be repeated without conflicts",
+        "by default, conflicting merges fail"
+    );
+
+    let (stack_a, stack_b) = (StackId::generate(), StackId::generate());
+    let out = Rebase::new(&repo, base, None)?
+        .allow_merge_conflicts(true)
+        .stack_tips([
+            (repo.rev_parse_single("A-conflicting")?.detach(), stack_a),
+            (repo.rev_parse_single("B")?.detach(), stack_b),
+        ])
+        .steps(steps)?
+        .rebase()?;
+    let rebased_a = out.commit_mapping[0].2;
+    assert_eq!(
+        out.merge_conflicts,
+        [(
+            out.top_commit,
+            MergeConflict {
+                parents: vec![rebased_a, repo.rev_parse_single("B")?.detach()],
+                stacks: vec![stack_a, stack_b],
+                paths: vec!["file".into()],
+            }
+        )],
+        "both parents changed the same line, and the rewritten parent is known by its original stack tip"
+    );
+    assert_eq!(conflicted(&repo, &out), [false, true]);
+
+    let tree = out.top_commit.attach(&repo).object()?.peel_to_tree()?;
+    let entries: Vec<_> = tree
+        .iter()
+        .map(|entry| entry.map(|entry| entry.filename().to_owned()))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(
+        entries,
+        [
+            ".auto-resolution",
+            ".conflict-base-0",
+            ".conflict-files",
+            ".conflict-side-0",
+            ".conflict-side-1",
+            "README.txt"
+        ],
+        "the tree of each parent is recorded"
+    );
+    let auto_resolved = repo
+        .rev_parse_single(format!("{}:.auto-resolution/file", out.top_commit).as_str())?
+        .object()?;
+    assert_eq!(
+        auto_resolved.data.as_bstr(),
+        "1\n2\n3\n4\n5\nA\n7\n8\n9\n10\n",
+        "conflicts are resolved in favor of ours"
+    );
+    assert!(
+        repo.rev_parse_single(format!("{}:.auto-resolution/a", out.top_commit).as_str())
+            .is_ok(),
+        "non-conflicting changes of A are merged as well"
+    );
+    Ok(())
+}
+
+#[test]
+fn remerge_of_octopus_with_conflicts_records_two_sides() -> Result<()> {
+    let (repo, _tmp) = fixture_writable("octopus-that-conflicts-when-rebased")?;
+    let out = Rebase::new(&repo, repo.rev_parse_single("base")?.detach(), None)?
+        .allow_merge_conflicts(true)
+        .steps([
+            RebaseStep::Pick {
+                commit_id: repo.rev_parse_single("A-conflicting")?.into(),
+                new_message: None,
+            },
+            RebaseStep::Pick {
+                commit_id: repo.rev_parse_single("main")?.into(),
+                new_message: None,
+            },
+        ])?
+        .rebase()?;
+    assert_eq!(conflicted(&repo, &out), [false, true]);
+    let conflict = &out.merge_conflicts[0].1;
+    assert_eq!(
+        conflict.parents.len(),
+        2,
+        "only B collides with the rewritten A"
+    );
+    assert!(conflict.stacks.is_empty(), "no stack tips were configured");
+
+    let tree = out.top_commit.attach(&repo).object()?.peel_to_tree()?;
+    let entries: Vec<_> = tree
+        .iter()
+        .map(|entry| entry.map(|entry| entry.filename().to_owned()))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(
+        entries,
+        [
+            ".auto-resolution",
+            ".conflict-base-0",
+            ".conflict-files",
+            ".conflict-side-0",
+            ".conflict-side-1",
+            "README.txt"
+        ],
+        "there are only two sides, even with three parents"
+    );
+    for (path, expected) in [("file", "1\n2\n3\n4\n5\nB\n7\n8\n9\n10\n"), ("c", "c\n")] {
+        let blob = repo
+            .rev_parse_single(format!("{}:.conflict-side-1/{path}", out.top_commit).as_str())?
+            .object()?;
+        assert_eq!(
+            blob.data.as_bstr(),
+            expected,
+            "theirs is the merge of all parents but the first"
+        );
+    }
+
+    let conflict_files = repo
+        .rev_parse_single(format!("{}:.conflict-files", out.top_commit).as_str())?
+        .object()?;
+    let entries: but_core::commit::ConflictEntries = toml::from_str(conflict_files.data.to_str()?)?;
+    assert_eq!(
+        entries,
+        but_core::commit::ConflictEntries {
+            ancestor_entries: vec!["file".into()],
+            our_entries: vec![],
+            their_entries: vec!["file".into()],
+        },
+        "ours removed the file that theirs changed"
+    );
+    Ok(())
+}
+
 #[test]
 fn reversible_conflicts() -> anyhow::Result<()> {
     assure_stable_env();
@@ -674,12 +1260,203 @@ fn pick_the_first_commit_with_no_parents_for_squashing() -> Result<()> {
                 Sha1(9c68471968e68ffe5df832a4cb850e8c3e7b7cd0),
             ),
         ],
+        dropped_commits: [],
+        executed_commands: [],
+        stopped_at: None,
+        merge_conflicts: [],
     }
     ");
     assure_nonconflicting(&repo, &out)?;
     Ok(())
 }
 
+#[test]
+fn drop_fixup_and_exec() -> Result<()> {
+    assure_stable_env();
+    let (repo, commits, _tmp) = four_commits_writable()?;
+    let out = Rebase::new(&repo, commits.base, None)?
+        .steps([
+            RebaseStep::Pick {
+                commit_id: commits.a,
+                new_message: None,
+            },
+            RebaseStep::Drop {
+                commit_id: commits.b,
+            },
+            RebaseStep::Fixup {
+                commit_id: commits.c,
+            },
+            RebaseStep::Exec {
+                command: "test -f a && test -f c && test ! -f b".into(),
+            },
+            RebaseStep::Reference(but_core::Reference::Virtual("anchor".into())),
+        ])?
+        .rebase()?;
+
+    let top = repo.find_commit(out.top_commit)?;
+    assert_eq!(
+        top.message_raw()?,
+        "a\n",
+        "fixups keep the message of their target"
+    );
+    assert_eq!(
+        top.parent_ids().map(|id| id.detach()).collect::<Vec<_>>(),
+        [commits.base]
+    );
+    assert_eq!(out.dropped_commits, [commits.b]);
+    assert_eq!(
+        out.executed_commands,
+        [(
+            "test -f a && test -f c && test ! -f b".to_string(),
+            out.top_commit
+        )],
+        "the command ran on the tree of the squashed commit"
+    );
+    assert!(out.stopped_at.is_none());
+    assert_eq!(
+        out.references[0].previous_commit_id, commits.c,
+        "the reference was placed on the commit that was fixed up"
+    );
+    assure_nonconflicting(&repo, &out)?;
+    Ok(())
+}
+
+#[test]
+fn reworded_commits_get_the_ticket_of_their_branch() -> Result<()> {
+    assure_stable_env();
+    let (mut repo, commits, _tmp) = four_commits_writable()?;
+    repo.config_snapshot_mut()
+        .set_raw_value(&"gitbutler.ticketPattern", "([A-Z]+-[0-9]+)")?;
+    let out = Rebase::new(&repo, commits.base, None)?
+        .steps([
+            RebaseStep::Pick {
+                commit_id: commits.a,
+                new_message: Some("a reworded".into()),
+            },
+            RebaseStep::Reference(but_core::Reference::Git(
+                "refs/heads/ABC-1-first".try_into()?,
+            )),
+            RebaseStep::Pick {
+                commit_id: commits.b,
+                new_message: None,
+            },
+            RebaseStep::SquashIntoPreceding {
+                commit_id: commits.c,
+                new_message: Some("b and c".into()),
+            },
+            RebaseStep::Reference(but_core::Reference::Virtual("XYZ-2-second".into())),
+        ])?
+        .rebase()?;
+
+    let top = repo.find_commit(out.top_commit)?;
+    assert_eq!(
+        top.message_raw()?,
+        "b and c\n\nRefs: XYZ-2\n",
+        "the ticket is taken from the branch the commit is on"
+    );
+    let first = repo.find_commit(out.references[0].commit_id)?;
+    assert_eq!(first.message_raw()?, "a reworded\n\nRefs: ABC-1\n");
+    assure_nonconflicting(&repo, &out)?;
+    Ok(())
+}
+
+#[test]
+fn failing_exec_aborts_the_rebase() -> Result<()> {
+    let (repo, commits, _tmp) = four_commits_writable()?;
+    let err = Rebase::new(&repo, commits.base, None)?
+        .steps([
+            RebaseStep::Pick {
+                commit_id: commits.a,
+                new_message: None,
+            },
+            RebaseStep::Exec {
+                command: "echo failed >&2; exit 42".into(),
+            },
+            RebaseStep::Pick {
+                commit_id: commits.b,
+                new_message: None,
+            },
+        ])?
+        .rebase()
+        .unwrap_err();
+    let err = format!("{err:#}");
+    assert!(
+        err.starts_with("The rebase was aborted as 'echo failed >&2; exit 42' failed on "),
+        "{err}"
+    );
+    assert!(err.ends_with(": failed"), "stderr is included: {err}");
+    Ok(())
+}
+
+#[test]
+fn exec_uses_an_index_of_its_own() -> Result<()> {
+    let (repo, commits, _tmp) = four_commits_writable()?;
+    let index_before = std::fs::read(repo.index_path()).ok();
+    let command = "test -z \"$(git status --porcelain)\" && echo new >new && git add new";
+    let out = Rebase::new(&repo, commits.base, None)?
+        .steps([
+            RebaseStep::Pick {
+                commit_id: commits.a,
+                new_message: None,
+            },
+            RebaseStep::Exec {
+                command: command.into(),
+            },
+        ])?
+        .rebase()?;
+    assert_eq!(
+        out.executed_commands.len(),
+        1,
+        "the checkout looked clean to Git as its index matched"
+    );
+    assert_eq!(
+        std::fs::read(repo.index_path()).ok(),
+        index_before,
+        "the index of the repository wasn't touched"
+    );
+    Ok(())
+}
+
+#[test]
+fn break_stops_and_can_be_continued() -> Result<()> {
+    assure_stable_env();
+    let (repo, commits, _tmp) = four_commits_writable()?;
+    let out = Rebase::new(&repo, commits.base, None)?
+        .steps([
+            RebaseStep::Pick {
+                commit_id: commits.a,
+                new_message: None,
+            },
+            RebaseStep::Break,
+            RebaseStep::Pick {
+                commit_id: commits.b,
+                new_message: None,
+            },
+            RebaseStep::Reference(but_core::Reference::Virtual("anchor".into())),
+        ])?
+        .rebase()?;
+    let stop = out.stopped_at.expect("the break stopped the rebase");
+    assert_eq!(stop.commit_id, out.top_commit);
+    assert_eq!(out.commit_mapping.len(), 1, "only a was picked");
+    assert!(
+        out.references.is_empty(),
+        "references after the break are not processed"
+    );
+    assert_eq!(stop.remaining_steps.len(), 2);
+
+    let out = Rebase::new(&repo, stop.commit_id, None)?
+        .steps(stop.remaining_steps)?
+        .rebase()?;
+    let top = repo.find_commit(out.top_commit)?;
+    assert_eq!(top.message_raw()?, "b\n");
+    assert_eq!(
+        top.parent_ids().map(|id| id.detach()).collect::<Vec<_>>(),
+        [stop.commit_id]
+    );
+    assert_eq!(out.references.len(), 1);
+    Ok(())
+}
+
 pub mod utils {
     use anyhow::Result;
     use but_rebase::RebaseOutput;
@@ -0,0 +1,174 @@
+use crate::utils::fixture_writable;
+use bstr::{BString, ByteSlice};
+use but_core::commit::TreeKind;
+use but_rebase::cherry_pick::{EmptyCommit, PickMode};
+use but_rebase::rerere::{self, ConflictHunk, Resolutions};
+use but_testsupport::assure_stable_env;
+use gix::prelude::ObjectIdExt;
+
+#[test]
+fn conflict_hunks_with_and_without_base() {
+    let content = b"a\n<<<<<<< ours\nleft\n||||||| base\nbase\n=======\nright\n>>>>>>> theirs\nb\n<<<<<<< ours\nl1\nl2\n=======\n>>>>>>> theirs\n";
+    assert_eq!(
+        rerere::conflict_hunks(content),
+        [
+            ConflictHunk {
+                markers: 1..8,
+                ours: 2..3,
+                base: Some(4..5),
+                theirs: 6..7,
+            },
+            ConflictHunk {
+                markers: 9..14,
+                ours: 10..12,
+                base: None,
+                theirs: 13..13,
+            }
+        ]
+    );
+    assert!(
+        rerere::conflict_hunks(b"<<<<<<<< not a marker\n=======\n>>>>>>> x\n").is_empty(),
+        "markers must have exactly seven characters, and incomplete hunks are ignored"
+    );
+}
+
+#[test]
+fn resolutions_are_extracted_per_hunk_and_reapplied() -> anyhow::Result<()> {
+    let hash = gix::hash::Kind::Sha1;
+    let preimage = b"a\n<<<<<<< ours\nleft\n=======\nright\n>>>>>>> theirs\nb\n<<<<<<< ours\nl1\n=======\nr1\n>>>>>>> theirs\nc\n";
+    let recorded = rerere::extract_resolutions(hash, preimage, b"a\nboth\nb\nr1\nl1\nc\n")?;
+    assert_eq!(
+        recorded
+            .iter()
+            .map(|(_, r)| r.as_bstr())
+            .collect::<Vec<_>>(),
+        ["both\n", "r1\nl1\n"]
+    );
+
+    let mut resolutions = Resolutions::default();
+    for (id, resolution) in recorded {
+        resolutions.insert(id, resolution);
+    }
+    let swapped = b"x\n<<<<<<< ours\nright\n=======\nleft\n>>>>>>> theirs\ny\n";
+    assert_eq!(
+        resolutions.resolve(hash, swapped)?,
+        Some((BString::from("x\nboth\ny\n"), 1)),
+        "the hunk is recognized even if the sides are swapped and the surrounding lines changed"
+    );
+    assert_eq!(
+        resolutions.resolve(
+            hash,
+            b"<<<<<<< ours\nleft\n=======\nother\n>>>>>>> theirs\n"
+        )?,
+        None,
+        "unknown hunks can't be resolved"
+    );
+
+    assert!(
+        rerere::extract_resolutions(hash, preimage, b"changed\nboth\nb\nr1\nc\n")?.is_empty(),
+        "nothing is recorded if the lines around the hunks changed"
+    );
+    Ok(())
+}
+
+#[test]
+fn cherry_pick_reuses_recorded_resolutions() -> anyhow::Result<()> {
+    assure_stable_env();
+    let (repo, _tmp) = fixture_writable("three-branches-merged")?;
+    let onto = repo.rev_parse_single("C~2")?.detach();
+    let to_pick = repo.rev_parse_single("C")?.detach();
+
+    let conflicted = but_rebase::cherry_pick_one(
+        &repo,
+        onto,
+        to_pick,
+        PickMode::Unconditionally,
+        EmptyCommit::Keep,
+    )?;
+    let conflicted = but_core::Commit::from_id(conflicted.attach(&repo))?;
+    assert!(conflicted.is_conflicted());
+
+    // Resolve all hunks by taking their side, just like a user would in edit mode.
+    let marker_tree = rerere::merge_with_conflict_markers(
+        &repo,
+        conflicted.tree_id_or_kind(TreeKind::Base)?.detach(),
+        conflicted.tree_id_or_kind(TreeKind::Ours)?.detach(),
+        conflicted.tree_id_or_kind(TreeKind::Theirs)?.detach(),
+    )?;
+    let preimage = repo
+        .find_tree(marker_tree)?
+        .lookup_entry_by_path("new-file")?
+        .expect("conflicting file")
+        .object()?
+        .detach()
+        .data;
+    let lines: Vec<_> = preimage.lines_with_terminator().collect();
+    let hunks = rerere::conflict_hunks(&preimage);
+    assert!(!hunks.is_empty());
+    let mut postimage = Vec::new();
+    let mut context_start = 0;
+    for hunk in &hunks {
+        postimage.extend(lines[context_start..hunk.markers.start].concat());
+        postimage.extend(lines[hunk.theirs.clone()].concat());
+        context_start = hunk.markers.end;
+    }
+    postimage.extend(lines[context_start..].concat());
+
+    let mut resolutions = Resolutions::default();
+    for (id, resolution) in rerere::extract_resolutions(repo.object_hash(), &preimage, &postimage)?
+    {
+        resolutions.insert(id, resolution);
+    }
+
+    let resolved = but_rebase::cherry_pick_one_with_resolutions(
+        &repo,
+        onto,
+        to_pick,
+        PickMode::Unconditionally,
+        EmptyCommit::Keep,
+        Some(&resolutions),
+    )?;
+    let resolved = but_core::Commit::from_id(resolved.attach(&repo))?;
+    assert!(
+        !resolved.is_conflicted(),
+        "the recorded resolution was used"
+    );
+    assert_eq!(resolved.auto_resolved_hunks(), Some(hunks.len() as u64));
+    let content = repo
+        .find_tree(resolved.tree)?
+        .lookup_entry_by_path("new-file")?
+        .expect("resolved file")
+        .object()?
+        .detach()
+        .data;
+    assert_eq!(content.as_bstr(), postimage.as_bstr());
+
+    let repicked = but_rebase::cherry_pick_one(
+        &repo,
+        onto,
+        resolved.id.detach(),
+        PickMode::Unconditionally,
+        EmptyCommit::Keep,
+    )?;
+    let repicked = but_core::Commit::from_id(repicked.attach(&repo))?;
+    assert_eq!(
+        repicked.auto_resolved_hunks(),
+        Some(hunks.len() as u64),
+        "the resolution remains to be reviewed after a clean pick"
+    );
+
+    let acknowledged = rerere::acknowledge_auto_resolution(&repo, repicked.id.detach())?;
+    let acknowledged = but_core::Commit::from_id(acknowledged.attach(&repo))?;
+    assert_eq!(acknowledged.auto_resolved_hunks(), None);
+    assert_eq!(
+        acknowledged.tree, repicked.tree,
+        "only the header is removed"
+    );
+    assert_eq!(acknowledged.parents, repicked.parents);
+    assert_eq!(
+        rerere::acknowledge_auto_resolution(&repo, acknowledged.id.detach())?,
+        acknowledged.id,
+        "commits without auto-resolution are kept"
+    );
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use crate::utils::{four_commits, four_commits_writable};
+use but_rebase::{Rebase, RebaseStep, todo};
+
+#[test]
+fn serialize_and_parse_roundtrip() -> anyhow::Result<()> {
+    let (repo, commits) = four_commits()?;
+    let steps = [
+        RebaseStep::Pick {
+            commit_id: commits.a,
+            new_message: None,
+        },
+        RebaseStep::Reference(but_core::Reference::Virtual("anchor".into())),
+        RebaseStep::Pick {
+            commit_id: commits.b,
+            new_message: None,
+        },
+        RebaseStep::Pick {
+            commit_id: commits.c,
+            new_message: None,
+        },
+        RebaseStep::Reference(but_core::Reference::Git("refs/heads/main".try_into()?)),
+    ];
+    let out = todo::serialize(&repo, &steps)?;
+    insta::assert_snapshot!(out, @r#"
+    pick d591dfe a
+    update-ref anchor
+    pick a96434e b
+    pick 120e3a9 c
+    update-ref refs/heads/main
+
+    # Commands:
+    # p, pick <commit> = use commit
+    # r, reword <commit> = use commit, but edit the commit message
+    # s, squash <commit> = use commit, but meld into previous commit
+    # f, fixup <commit> = like "squash", but keep only the previous commit's message
+    # x, exec <command> = run command (the rest of the line) using shell on the commit of the line above
+    # b, break = stop at the commit of the line above to edit it
+    # d, drop <commit> = remove commit
+    # u, update-ref <ref> = point <ref> to the commit of the line above
+    #
+    # Lines are applied from top to bottom, starting with the oldest commit.
+    # Removing the line of a commit drops it.
+    "#);
+
+    let parsed = todo::parse(&repo, &out, |_, _| unreachable!("nothing to reword"))?;
+    assert_eq!(format!("{parsed:?}"), format!("{steps:?}"));
+    Ok(())
+}
+
+#[test]
+fn edited_todo_list_is_applied() -> anyhow::Result<()> {
+    let (repo, commits, _tmp) = four_commits_writable()?;
+    let steps = todo::parse(
+        &repo,
+        "# a comment
+        reword a96434e
+        fixup 120e3a9 the title is ignored
+        drop d591dfe
+
+        update-ref anchor",
+        |id, message| {
+            assert_eq!(id, commits.b);
+            assert_eq!(message, "b\n");
+            Ok("b reworded\n".into())
+        },
+    )?;
+    let out = Rebase::new(&repo, commits.base, None)?
+        .steps(steps)?
+        .rebase()?;
+    let top = repo.find_commit(out.top_commit)?;
+    assert_eq!(
+        top.message_raw()?,
+        "b reworded\n",
+        "the fixup keeps the message"
+    );
+    assert_eq!(
+        top.parent_ids().map(|id| id.detach()).collect::<Vec<_>>(),
+        [commits.base],
+        "b and c were squashed, and a was dropped"
+    );
+    let tree = top.tree()?;
+    assert!(tree.find_entry("a").is_none());
+    assert!(tree.find_entry("b").is_some() && tree.find_entry("c").is_some());
+    assert_eq!(out.references.len(), 1);
+    Ok(())
+}
+
+#[test]
+fn errors_point_at_the_offending_line() -> anyhow::Result<()> {
+    let (repo, _commits) = four_commits()?;
+    let err = |todo: &str| {
+        format!(
+            "{:#}",
+            todo::parse(&repo, todo, |_, message| Ok(message.to_owned())).unwrap_err()
+        )
+    };
+    assert_eq!(
+        err("pick d591dfe\nedit a96434e"),
+        "line 2: edit a96434e: Unknown command 'edit'"
+    );
+    assert_eq!(
+        err("squash d591dfe"),
+        "line 1: squash d591dfe: There is no previous commit to squash into"
+    );
+    assert_eq!(
+        err("pick d591dfe\nupdate-ref anchor\nfixup a96434e"),
+        "line 3: fixup a96434e: Can't squash into a commit that a reference was placed on"
+    );
+    assert_eq!(
+        err("pick d591dfe\n\npick d591dfe"),
+        "line 3: pick d591dfe: Commit d591dfed1777b8f00f5b7b6f427537eeb5878178 is used more than once"
+    );
+    assert_eq!(
+        err("pick eeeeeee"),
+        "line 1: pick eeeeeee: 'eeeeeee' is not a commit"
+    );
+    assert_eq!(
+        err("# everything was removed\n"),
+        "Nothing to do as no commit is picked"
+    );
+    Ok(())
+}
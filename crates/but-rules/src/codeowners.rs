@@ -0,0 +1,115 @@
+use std::path::Path;
+
+/// The places a `CODEOWNERS` file is looked up in, relative to the worktree root and in order of precedence.
+const LOCATIONS: &[&str] = &[
+    ".github/CODEOWNERS",
+    "CODEOWNERS",
+    "docs/CODEOWNERS",
+    ".gitlab/CODEOWNERS",
+];
+
+/// The parsed rules of a `CODEOWNERS` file, mapping paths to the people or teams owning them.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct CodeOwners {
+    /// Patterns along with their owners, in the order they appear in the file.
+    rules: Vec<(String, Vec<String>)>,
+}
+
+impl CodeOwners {
+    /// Read the first `CODEOWNERS` file found in its usual locations in `worktree_dir`, or return no rules if there is none.
+    pub(crate) fn discover(worktree_dir: &Path) -> anyhow::Result<Self> {
+        for location in LOCATIONS {
+            match std::fs::read_to_string(worktree_dir.join(location)) {
+                Ok(content) => return Ok(Self::parse(&content)),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(Self::default())
+    }
+
+    /// Parse the `content` of a `CODEOWNERS` file. Sections in GitLab style, like `[Docs]`, are ignored.
+    pub(crate) fn parse(content: &str) -> Self {
+        let rules = content
+            .lines()
+            .map(|line| line.split_once(" #").map_or(line, |(rule, _comment)| rule))
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with('['))
+            .filter_map(|line| {
+                let mut tokens = line.split_whitespace();
+                let pattern = tokens.next()?.to_owned();
+                Some((pattern, tokens.map(ToOwned::to_owned).collect()))
+            })
+            .collect();
+        CodeOwners { rules }
+    }
+
+    /// Return the owners of the repository-relative `path`, as given by the last matching rule.
+    pub(crate) fn owners_of(&self, path: &str) -> &[String] {
+        self.rules
+            .iter()
+            .rev()
+            .find(|(pattern, _)| crate::glob::matches(pattern, path))
+            .map(|(_, owners)| owners.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Return `true` if `owner` is among the owners of `path`, ignoring case as do the forges.
+    pub(crate) fn is_owned_by(&self, path: &str, owner: &str) -> bool {
+        self.owners_of(path)
+            .iter()
+            .any(|candidate| candidate.eq_ignore_ascii_case(owner))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CodeOwners;
+
+    #[test]
+    fn the_last_matching_rule_determines_the_owners() {
+        let codeowners = CodeOwners::parse(
+            "# Everything belongs to the core team, except for the docs.\n\
+             *        @org/core\n\
+             \n\
+             /docs/   @org/docs @writer # the writer helps out\n\
+             *.rs     @org/rust\n",
+        );
+        assert_eq!(codeowners.owners_of("README.md"), ["@org/core"]);
+        assert_eq!(
+            codeowners.owners_of("docs/intro.md"),
+            ["@org/docs", "@writer"]
+        );
+        assert_eq!(codeowners.owners_of("docs/example.rs"), ["@org/rust"]);
+        assert_eq!(
+            codeowners.owners_of("crates/but/src/main.rs"),
+            ["@org/rust"]
+        );
+    }
+
+    #[test]
+    fn sections_and_comments_are_ignored() {
+        let codeowners = CodeOwners::parse(
+            "[Documentation]\n\
+             # docs/ @nobody\n\
+             docs/ @org/docs\n",
+        );
+        assert_eq!(codeowners.owners_of("docs/intro.md"), ["@org/docs"]);
+        assert!(codeowners.owners_of("src/lib.rs").is_empty());
+    }
+
+    #[test]
+    fn owners_are_compared_ignoring_case() {
+        let codeowners = CodeOwners::parse("*.rs @Org/Rust\n");
+        assert!(codeowners.is_owned_by("src/lib.rs", "@org/rust"));
+        assert!(!codeowners.is_owned_by("src/lib.rs", "@org/docs"));
+        assert!(!codeowners.is_owned_by("README.md", "@org/rust"));
+    }
+
+    #[test]
+    fn rules_without_owners_remove_ownership() {
+        let codeowners = CodeOwners::parse("* @org/core\n/generated/\n");
+        assert!(codeowners.owners_of("generated/schema.rs").is_empty());
+        assert!(codeowners.is_owned_by("src/lib.rs", "@org/core"));
+    }
+}
@@ -0,0 +1,81 @@
+use gix::bstr::ByteSlice;
+use gix::glob::wildmatch;
+
+/// Return `true` if the repository-relative `path` is matched by `pattern`, which follows the rules of `.gitignore`:
+///
+/// * A pattern without a slash, other than a trailing one, matches at any depth, like `*.md`.
+/// * Any other pattern is relative to the repository root, like `/build` or `docs/*.md`.
+/// * A pattern matching a directory matches everything inside it, and a trailing slash only matches directories.
+/// * `**` matches any amount of directories, like in `docs/**/*.md`.
+pub(crate) fn matches(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim();
+    let (pattern, directory_only) = match pattern.strip_suffix('/') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+    if pattern.is_empty() {
+        return false;
+    }
+    let pattern = match pattern.strip_prefix('/') {
+        Some(anchored) => anchored.to_owned(),
+        None if pattern.contains('/') => pattern.to_owned(),
+        None => format!("**/{pattern}"),
+    };
+    let wildmatch = |pattern: &str| {
+        wildmatch(
+            pattern.as_bytes().as_bstr(),
+            path.as_bytes().as_bstr(),
+            wildmatch::Mode::NO_MATCH_SLASH_LITERAL,
+        )
+    };
+    (!directory_only && wildmatch(&pattern)) || wildmatch(&format!("{pattern}/**"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::matches;
+
+    #[test]
+    fn patterns_without_slash_match_at_any_depth() {
+        assert!(matches("*.md", "README.md"));
+        assert!(matches("*.md", "docs/guide/intro.md"));
+        assert!(!matches("*.md", "README.mdx"));
+        assert!(
+            matches("src", "crates/but/src/main.rs"),
+            "directories match their content"
+        );
+    }
+
+    #[test]
+    fn patterns_with_slash_are_relative_to_the_root() {
+        assert!(matches("/build", "build/out.txt"));
+        assert!(!matches("/build", "crates/build/out.txt"));
+        assert!(matches("docs/*.md", "docs/intro.md"));
+        assert!(
+            !matches("docs/*.md", "docs/guide/intro.md"),
+            "`*` doesn't match slashes"
+        );
+        assert!(!matches("docs/*.md", "crates/docs/intro.md"));
+    }
+
+    #[test]
+    fn double_asterisks_match_any_amount_of_directories() {
+        assert!(matches("docs/**/*.md", "docs/intro.md"));
+        assert!(matches("docs/**/*.md", "docs/guide/advanced/intro.md"));
+        assert!(!matches("docs/**/*.md", "src/intro.md"));
+    }
+
+    #[test]
+    fn trailing_slashes_only_match_directories() {
+        assert!(matches("target/", "target/debug/but"));
+        assert!(matches("target/", "crates/but/target/debug/but"));
+        assert!(!matches("target/", "target"), "a file can't be matched");
+        assert!(matches("target", "target"));
+    }
+
+    #[test]
+    fn empty_patterns_match_nothing() {
+        assert!(!matches("", "README.md"));
+        assert!(!matches(" / ", "README.md"));
+    }
+}
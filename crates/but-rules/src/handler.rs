@@ -1,34 +1,107 @@
+use but_core::commit::trailer;
+use but_core::unified_diff::semantic::{self, HunkKind};
 use but_graph::VirtualBranchesTomlMetadata;
 use but_hunk_assignment::{HunkAssignment, assign, assignments_to_requests};
 use but_hunk_dependency::ui::HunkDependencies;
+use but_tools::emit::Emitter;
 use but_workspace::{DiffSpec, StackId, StacksFilter, commit_engine, ui::StackEntry};
 use gitbutler_command_context::CommandContext;
+use gitbutler_oplog::{
+    OplogExt as _, SnapshotQuery,
+    entry::{OperationKind, Snapshot, SnapshotDetails, Trailer},
+};
+use gitbutler_oxidize::ObjectIdExt as _;
 use itertools::Itertools;
-use std::str::FromStr;
+use std::{str::FromStr, sync::Arc};
 
-use crate::{Filter, StackTarget};
+use crate::{Event, Filter, SemanticType, StackTarget, WorkspaceRule, codeowners::CodeOwners};
+
+/// The key of the trailer on snapshots of rule actions that holds the id of the rule.
+pub const RULE_TRAILER: &str = "rule";
+/// The key of the trailer on snapshots of prompt rules that identifies the changes the prompt was run with.
+const CHANGES_TRAILER: &str = "changes";
+/// The time in seconds after a prompt rule ran before it can run again.
+const LLM_PROMPT_DEBOUNCE_SECONDS: i64 = 60;
+/// How far back in seconds the oplog is searched for the last run of a prompt rule.
+const LLM_PROMPT_LOOKBACK_SECONDS: i64 = 24 * 60 * 60;
 
 pub fn process_workspace_rules(
     ctx: &mut CommandContext,
     assignments: &[HunkAssignment],
     dependencies: &Option<HunkDependencies>,
 ) -> anyhow::Result<usize> {
-    let mut updates = 0;
     if assignments.is_empty() {
         // Dont create stacks if there are no changes to assign anywhere
-        return Ok(updates);
+        return Ok(0);
     }
     let rules = super::list_rules(ctx)?
         .into_iter()
         .filter(|r| r.enabled)
         .filter(|r| matches!(r.trigger, super::Trigger::FileSytemChange))
+        .collect_vec();
+    apply_change_rules(ctx, rules, assignments, dependencies)
+}
+
+/// Perform the actions of `rules`, which were triggered by `event`.
+pub(crate) fn process_event_rules(
+    ctx: &mut CommandContext,
+    event: &Event,
+    rules: Vec<WorkspaceRule>,
+) -> anyhow::Result<usize> {
+    let (commit_rules, change_rules): (Vec<_>, Vec<_>) = rules.into_iter().partition(|r| {
+        matches!(
+            r.action,
+            super::Action::Explicit(super::Operation::AddTrailer { .. })
+        )
+    });
+
+    let mut updates = 0;
+    if let Event::CommitCreated {
+        stack_id,
+        commit_id,
+        ..
+    } = event
+    {
+        // Each rewrite yields a new commit, which the next rule has to operate on.
+        let mut commit_id = commit_id.to_git2();
+        for rule in commit_rules {
+            if let super::Action::Explicit(super::Operation::AddTrailer { key, value }) =
+                &rule.action
+            {
+                if let Some(new_commit_id) =
+                    handle_add_trailer(ctx, *stack_id, commit_id, key, value)?
+                {
+                    commit_id = new_commit_id;
+                    updates += 1;
+                }
+            }
+        }
+    }
+
+    if change_rules.is_empty() {
+        return Ok(updates);
+    }
+    let (assignments, dependencies) = super::worktree_assignments(ctx)?;
+    if assignments.is_empty() {
+        return Ok(updates);
+    }
+    Ok(updates + apply_change_rules(ctx, change_rules, &assignments, &Some(dependencies))?)
+}
+
+/// Apply the actions of `rules` that operate on changes to the matching `assignments`.
+fn apply_change_rules(
+    ctx: &mut CommandContext,
+    rules: Vec<WorkspaceRule>,
+    assignments: &[HunkAssignment],
+    dependencies: &Option<HunkDependencies>,
+) -> anyhow::Result<usize> {
+    let mut updates = 0;
+    let rules = rules
+        .into_iter()
         .filter(|r| {
-            matches!(
-                &r.action,
-                super::Action::Explicit(super::Operation::Assign { .. })
-            ) || matches!(
+            !matches!(
                 &r.action,
-                super::Action::Explicit(super::Operation::Amend { .. })
+                super::Action::Explicit(super::Operation::AddTrailer { .. })
             )
         })
         .collect_vec();
@@ -51,7 +124,7 @@ pub fn process_workspace_rules(
         match rule.action {
             super::Action::Explicit(super::Operation::Assign { target }) => {
                 if let Some(stack_id) = get_or_create_stack_id(ctx, target, &stacks_in_ws) {
-                    let assignments = matching(assignments, rule.filters.clone())
+                    let assignments = matching(ctx, assignments, rule.filters.clone())
                         .into_iter()
                         .filter(|e| e.stack_id != Some(stack_id))
                         .map(|mut e| {
@@ -64,15 +137,76 @@ pub fn process_workspace_rules(
                 }
             }
             super::Action::Explicit(super::Operation::Amend { change_id }) => {
-                let assignments = matching(assignments, rule.filters.clone());
+                let assignments = matching(ctx, assignments, rule.filters.clone());
                 handle_amend(ctx, assignments, change_id).unwrap_or_default();
             }
-            _ => continue,
+            super::Action::Explicit(super::Operation::NewCommit { branch_name }) => {
+                let assignments = matching(ctx, assignments, rule.filters.clone());
+                updates +=
+                    handle_new_commit(ctx, &rule.id, assignments, &branch_name, &stacks_in_ws)
+                        .unwrap_or_default();
+            }
+            super::Action::Implicit(super::ImplicitOperation::AssignToAppropriateBranch) => {
+                let assignments = matching(ctx, assignments, rule.filters.clone())
+                    .into_iter()
+                    .filter_map(|mut e| {
+                        let stack_id = locked_stack_id(&e)?;
+                        (e.stack_id != Some(stack_id)).then(|| {
+                            e.stack_id = Some(stack_id);
+                            e
+                        })
+                    })
+                    .collect_vec();
+                if assignments.is_empty()
+                    || snapshot_rule_action(ctx, &rule.id, OperationKind::MoveHunk).is_err()
+                {
+                    continue;
+                }
+                updates +=
+                    handle_assign(ctx, assignments, dependencies.as_ref()).unwrap_or_default();
+            }
+            super::Action::Implicit(super::ImplicitOperation::AbsorbIntoDependentCommit) => {
+                let assignments = matching(ctx, assignments, rule.filters.clone());
+                updates += handle_absorb(ctx, &rule.id, assignments).unwrap_or_default();
+            }
+            super::Action::Implicit(super::ImplicitOperation::LLMPrompt(prompt)) => {
+                let assignments = matching(ctx, assignments, rule.filters.clone());
+                updates +=
+                    handle_llm_prompt(ctx, &rule.id, assignments, &prompt).unwrap_or_default();
+            }
+            super::Action::Explicit(super::Operation::AddTrailer { .. }) => continue,
         };
     }
     Ok(updates)
 }
 
+/// Add the trailer `key: value` to the message of `commit_id` in `stack_id` and return the id of the rewritten commit,
+/// or `None` if the trailer was already present.
+fn handle_add_trailer(
+    ctx: &CommandContext,
+    stack_id: StackId,
+    commit_id: git2::Oid,
+    key: &str,
+    value: &str,
+) -> anyhow::Result<Option<git2::Oid>> {
+    let commit = ctx.repo().find_commit(commit_id)?;
+    let message = commit.message().unwrap_or_default();
+    let Some(new_message) = with_trailer(message, key, value) else {
+        return Ok(None);
+    };
+    let new_commit_id =
+        gitbutler_branch_actions::update_commit_message(ctx, stack_id, commit_id, &new_message)?;
+    Ok(Some(new_commit_id))
+}
+
+/// Return `message` with the trailer `key: value` appended to its trailers, or `None` if it's already present.
+fn with_trailer(message: &str, key: &str, value: &str) -> Option<String> {
+    let mut message = trailer::Message::parse(message.into());
+    message
+        .push(trailer::Trailer::new(key, value))
+        .then(|| String::from_utf8_lossy(&message.into_message()).into_owned())
+}
+
 fn handle_amend(
     ctx: &mut CommandContext,
     assignments: Vec<HunkAssignment>,
@@ -127,6 +261,223 @@ fn handle_amend(
     Ok(())
 }
 
+/// Commit `assignments` on top of the branch named `branch_name`, returning the amount of committed hunks.
+fn handle_new_commit(
+    ctx: &CommandContext,
+    rule_id: &str,
+    assignments: Vec<HunkAssignment>,
+    branch_name: &str,
+    stacks_in_ws: &[StackEntry],
+) -> anyhow::Result<usize> {
+    if assignments.is_empty() {
+        return Ok(0);
+    }
+    let stack_id = stacks_in_ws
+        .iter()
+        .find(|s| s.heads.iter().any(|h| h.name == branch_name))
+        .and_then(|s| s.id)
+        .ok_or_else(|| anyhow::anyhow!("No branch named '{branch_name}' in the workspace"))?;
+    let paths = assignments
+        .iter()
+        .map(|a| a.path.as_str())
+        .unique()
+        .join(", ");
+    let message = format!("Update {paths}");
+    let len = assignments.len();
+
+    let mut guard = ctx.project().exclusive_worktree_access();
+    ctx.create_snapshot(
+        SnapshotDetails::new(OperationKind::CreateCommit).with_trailers(vec![
+            rule_trailer(rule_id),
+            Trailer {
+                key: "message".to_string(),
+                value: message.clone(),
+            },
+        ]),
+        guard.write_permission(),
+    )?;
+    let outcome = commit_engine::create_commit_simple(
+        ctx,
+        stack_id,
+        None,
+        diff_specs(assignments),
+        message,
+        branch_name.to_owned(),
+        guard.write_permission(),
+    )?;
+    Ok(if outcome.new_commit.is_some() { len } else { 0 })
+}
+
+/// Absorb those of `assignments` that are locked to a single commit into that commit, returning the amount of hunks
+/// that were considered.
+///
+/// This only uses the hunk locks and never involves an LLM, as changes shouldn't be sent anywhere without the user
+/// asking for it with an [`LLMPrompt`](super::ImplicitOperation::LLMPrompt) rule.
+fn handle_absorb(
+    ctx: &mut CommandContext,
+    rule_id: &str,
+    assignments: Vec<HunkAssignment>,
+) -> anyhow::Result<usize> {
+    let changes = tree_changes(ctx, &assignments)?;
+    if changes.is_empty() {
+        return Ok(0);
+    }
+    snapshot_rule_action(ctx, rule_id, OperationKind::AmendCommit)?;
+    but_action::absorb_locked(silent_emitter(), ctx, changes)?;
+    Ok(assignments.len())
+}
+
+/// Let the LLM perform what `prompt` asks for with `assignments` using the workspace tools,
+/// returning the amount of hunks it was given.
+///
+/// As every change in the worktree triggers the rule, the prompt is neither run again within
+/// [`LLM_PROMPT_DEBOUNCE_SECONDS`] nor for the same changes it was last run with.
+fn handle_llm_prompt(
+    ctx: &mut CommandContext,
+    rule_id: &str,
+    assignments: Vec<HunkAssignment>,
+    prompt: &str,
+) -> anyhow::Result<usize> {
+    let changes = tree_changes(ctx, &assignments)?;
+    if changes.is_empty() {
+        return Ok(0);
+    }
+    let fingerprint = changes_fingerprint(&assignments)?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs() as i64;
+    let last_run = ctx
+        .query_snapshots(
+            &SnapshotQuery {
+                since: Some(now - LLM_PROMPT_LOOKBACK_SECONDS),
+                kinds: vec![OperationKind::AutoHandleChangesBefore],
+                trailer: Some((RULE_TRAILER.to_owned(), Some(rule_id.to_owned()))),
+                ..Default::default()
+            },
+            1,
+        )?
+        .into_iter()
+        .next();
+    if !prompt_is_due(last_run.as_ref(), &fingerprint, now) {
+        return Ok(0);
+    }
+    let openai = but_action::OpenAiProvider::with(None).ok_or_else(|| {
+        anyhow::anyhow!("No LLM is configured to run the prompt of rule {rule_id}")
+    })?;
+
+    let mut guard = ctx.project().exclusive_worktree_access();
+    ctx.create_snapshot(
+        SnapshotDetails::new(OperationKind::AutoHandleChangesBefore).with_trailers(vec![
+            rule_trailer(rule_id),
+            Trailer {
+                key: CHANGES_TRAILER.to_owned(),
+                value: fingerprint,
+            },
+        ]),
+        guard.write_permission(),
+    )?;
+    drop(guard);
+    but_action::prompt(silent_emitter(), ctx, &openai, prompt, changes)?;
+    Ok(assignments.len())
+}
+
+/// Return `true` if a prompt rule should run for the changes with `fingerprint` at `now`, in seconds since the
+/// Unix epoch, given the snapshot of its `last_run`.
+fn prompt_is_due(last_run: Option<&Snapshot>, fingerprint: &str, now: i64) -> bool {
+    let Some(last_run) = last_run else {
+        return true;
+    };
+    if now - last_run.created_at.seconds() < LLM_PROMPT_DEBOUNCE_SECONDS {
+        return false;
+    }
+    !last_run.details.as_ref().is_some_and(|details| {
+        details
+            .trailers
+            .iter()
+            .any(|t| t.key == CHANGES_TRAILER && t.value == fingerprint)
+    })
+}
+
+/// Return an identifier for the content of `assignments`, which changes whenever one of the hunks does.
+fn changes_fingerprint(assignments: &[HunkAssignment]) -> anyhow::Result<String> {
+    let mut content = Vec::new();
+    for assignment in assignments {
+        content.extend_from_slice(&assignment.path_bytes);
+        content.push(0);
+        if let Some(diff) = &assignment.diff {
+            content.extend_from_slice(diff);
+        }
+        content.push(0);
+    }
+    Ok(git2::Oid::hash_object(git2::ObjectType::Blob, &content)?.to_string())
+}
+
+/// Return the stack all hunk locks of `assignment` point to, or `None` if it isn't locked or locked to multiple stacks.
+fn locked_stack_id(assignment: &HunkAssignment) -> Option<StackId> {
+    assignment
+        .hunk_locks
+        .as_ref()?
+        .iter()
+        .map(|lock| lock.stack_id)
+        .unique()
+        .exactly_one()
+        .ok()
+}
+
+/// Return the worktree changes of the files `assignments` belong to.
+fn tree_changes(
+    ctx: &CommandContext,
+    assignments: &[HunkAssignment],
+) -> anyhow::Result<Vec<but_core::TreeChange>> {
+    if assignments.is_empty() {
+        return Ok(Vec::new());
+    }
+    let changes = but_core::diff::worktree_changes(&ctx.gix_repo()?)?
+        .changes
+        .into_iter()
+        .filter(|change| assignments.iter().any(|a| a.path_bytes == change.path))
+        .collect();
+    Ok(changes)
+}
+
+/// Turn `assignments` into one diff spec per file, with the hunks of all assignments of that file.
+fn diff_specs(assignments: Vec<HunkAssignment>) -> Vec<DiffSpec> {
+    let mut specs: Vec<DiffSpec> = Vec::new();
+    for spec in assignments.into_iter().map(DiffSpec::from) {
+        match specs.iter_mut().find(|s| s.path == spec.path) {
+            Some(existing) => existing.hunk_headers.extend(spec.hunk_headers),
+            None => specs.push(spec),
+        }
+    }
+    specs
+}
+
+/// Record a snapshot for the action of the rule with `rule_id`, before it's performed, so it can be undone.
+fn snapshot_rule_action(
+    ctx: &CommandContext,
+    rule_id: &str,
+    operation: OperationKind,
+) -> anyhow::Result<()> {
+    let mut guard = ctx.project().exclusive_worktree_access();
+    ctx.create_snapshot(
+        SnapshotDetails::new(operation).with_trailers(vec![rule_trailer(rule_id)]),
+        guard.write_permission(),
+    )?;
+    Ok(())
+}
+
+fn rule_trailer(rule_id: &str) -> Trailer {
+    Trailer {
+        key: RULE_TRAILER.to_string(),
+        value: rule_id.to_string(),
+    }
+}
+
+/// Rules run in the background, so there is nobody to show the progress of the tools to.
+fn silent_emitter() -> Arc<Emitter> {
+    Arc::new(|_: &str, _: serde_json::Value| {})
+}
+
 fn get_or_create_stack_id(
     ctx: &CommandContext,
     target: StackTarget,
@@ -198,36 +549,310 @@ fn handle_assign(
     }
 }
 
-fn matching(wt_assignments: &[HunkAssignment], filters: Vec<Filter>) -> Vec<HunkAssignment> {
-    if filters.is_empty() {
-        return wt_assignments.to_vec();
-    }
-    let mut assignments = Vec::new();
-    for filter in filters {
-        match filter {
-            Filter::PathMatchesRegex(regex) => {
-                for change in wt_assignments.iter() {
-                    if regex.is_match(&change.path) {
-                        assignments.push(change.clone());
-                    }
-                }
-            }
-            Filter::ContentMatchesRegex(regex) => {
-                for change in wt_assignments.iter() {
-                    if let Some(diff) = change.diff.clone() {
-                        let diff = diff.to_string();
-                        let matching_lines: Vec<&str> =
-                            diff.lines().filter(|line| line.starts_with('+')).collect();
-                        if matching_lines.iter().any(|line| regex.is_match(line)) {
-                            assignments.push(change.clone());
-                        }
-                    }
-                }
+/// Return the assignments among `wt_assignments` that match all `filters`, each at most once.
+/// Filters that can't be evaluated for hunks, like the ones matching events, are ignored.
+fn matching(
+    ctx: &CommandContext,
+    wt_assignments: &[HunkAssignment],
+    filters: Vec<Filter>,
+) -> Vec<HunkAssignment> {
+    let codeowners = if filters.iter().any(|f| matches!(f, Filter::CodeOwner(_))) {
+        match CodeOwners::discover(&ctx.project().path) {
+            Ok(codeowners) => Some(codeowners),
+            Err(err) => {
+                tracing::warn!("Failed to read CODEOWNERS, ignoring owner filter: {err}");
+                None
             }
-            Filter::FileChangeType(_) => continue,
-            Filter::SemanticType(_) => continue,
-            Filter::ClaudeCodeSessionId(_) => continue,
         }
+    } else {
+        None
+    };
+    matching_with_owners(wt_assignments, &filters, codeowners.as_ref())
+}
+
+/// Like [`matching()`], but with the already loaded `codeowners` to evaluate owner filters, if available.
+fn matching_with_owners(
+    wt_assignments: &[HunkAssignment],
+    filters: &[Filter],
+    codeowners: Option<&CodeOwners>,
+) -> Vec<HunkAssignment> {
+    wt_assignments
+        .iter()
+        .filter(|change| {
+            filters
+                .iter()
+                .all(|filter| filter_matches(filter, change, codeowners))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Return `true` if `change` is matched by `filter`, or if `filter` can't be evaluated for it.
+/// Owner filters are only evaluated if `codeowners` are available.
+fn filter_matches(
+    filter: &Filter,
+    change: &HunkAssignment,
+    codeowners: Option<&CodeOwners>,
+) -> bool {
+    match filter {
+        Filter::PathMatchesRegex(regex) => regex.is_match(&change.path),
+        Filter::PathMatchesGlob(pattern) => crate::glob::matches(pattern, &change.path),
+        Filter::CodeOwner(owner) => {
+            codeowners.is_none_or(|codeowners| codeowners.is_owned_by(&change.path, owner))
+        }
+        Filter::HunkSizeAtLeast(min) => changed_lines(change).is_some_and(|lines| lines >= *min),
+        Filter::HunkSizeAtMost(max) => changed_lines(change).is_some_and(|lines| lines <= *max),
+        Filter::ContentMatchesRegex(regex) => change.diff.as_ref().is_some_and(|diff| {
+            diff.to_string()
+                .lines()
+                .filter(|line| line.starts_with('+'))
+                .any(|line| regex.is_match(line))
+        }),
+        Filter::SemanticType(semantic_type) => match hunk_kind(semantic_type) {
+            Some(kind) => change.diff.as_ref().is_some_and(|diff| {
+                semantic::classify(change.path_bytes.as_bstr(), diff.as_bstr()) == kind
+            }),
+            None => true,
+        },
+        Filter::FileChangeType(_) | Filter::ClaudeCodeSessionId(_) | Filter::BranchName(_) => true,
+    }
+}
+
+/// Return the kind of hunk that `semantic_type` matches, or `None` if it can't be determined from the hunk alone.
+fn hunk_kind(semantic_type: &SemanticType) -> Option<HunkKind> {
+    Some(match semantic_type {
+        SemanticType::Whitespace => HunkKind::Whitespace,
+        SemanticType::Comment => HunkKind::Comment,
+        SemanticType::ImportReordering => HunkKind::ImportReordering,
+        SemanticType::Code => HunkKind::Code,
+        SemanticType::Refactor
+        | SemanticType::NewFeature
+        | SemanticType::BugFix
+        | SemanticType::Documentation
+        | SemanticType::UserDefined(_) => return None,
+    })
+}
+
+/// Return the amount of added and removed lines of the hunk of `assignment`, or `None` if it has no hunk,
+/// like binary files.
+fn changed_lines(assignment: &HunkAssignment) -> Option<usize> {
+    let added = assignment.line_nums_added.as_ref()?.len();
+    let removed = assignment.line_nums_removed.as_ref()?.len();
+    Some(added + removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use but_hunk_assignment::HunkAssignment;
+    use regex::Regex;
+
+    use gitbutler_oplog::entry::{OperationKind, Snapshot, SnapshotDetails, Trailer};
+
+    use super::{
+        CHANGES_TRAILER, LLM_PROMPT_DEBOUNCE_SECONDS, changes_fingerprint, matching_with_owners,
+        prompt_is_due,
+    };
+    use crate::{Filter, SemanticType, codeowners::CodeOwners};
+
+    fn change(path: &str, diff: &str) -> HunkAssignment {
+        let lines = |prefix| {
+            diff.lines()
+                .filter(|line| line.starts_with(prefix))
+                .enumerate()
+                .map(|(idx, _)| idx + 1)
+                .collect()
+        };
+        HunkAssignment {
+            id: None,
+            hunk_header: None,
+            path: path.into(),
+            path_bytes: path.into(),
+            stack_id: None,
+            hunk_locks: None,
+            line_nums_added: Some(lines('+')),
+            line_nums_removed: Some(lines('-')),
+            diff: Some(diff.into()),
+        }
+    }
+
+    fn paths(assignments: Vec<HunkAssignment>) -> Vec<String> {
+        assignments.into_iter().map(|a| a.path).collect()
+    }
+
+    fn changes() -> Vec<HunkAssignment> {
+        vec![
+            change(
+                "src/lib.rs",
+                "@@ -1 +1 @@\n-fn a() {}\n+fn a() {} // TODO\n",
+            ),
+            change(
+                "src/main.rs",
+                "@@ -1 +1,2 @@\n-fn main() {}\n+fn main() {\n+}\n",
+            ),
+            change("docs/intro.md", "@@ -1 +1 @@\n-Intro\n+TODO: Intro\n"),
+        ]
+    }
+
+    #[test]
+    fn all_filters_of_a_rule_have_to_match() {
+        let filters = [
+            Filter::PathMatchesGlob("src/".into()),
+            Filter::ContentMatchesRegex(Regex::new("TODO").unwrap()),
+        ];
+        assert_eq!(
+            paths(matching_with_owners(&changes(), &filters, None)),
+            ["src/lib.rs"]
+        );
+    }
+
+    #[test]
+    fn changes_matched_by_multiple_filters_are_returned_once() {
+        let filters = [
+            Filter::PathMatchesRegex(Regex::new(r"\.rs$").unwrap()),
+            Filter::PathMatchesGlob("*.rs".into()),
+        ];
+        assert_eq!(
+            paths(matching_with_owners(&changes(), &filters, None)),
+            ["src/lib.rs", "src/main.rs"]
+        );
+    }
+
+    #[test]
+    fn rules_without_filters_match_everything() {
+        assert_eq!(
+            paths(matching_with_owners(&changes(), &[], None)),
+            ["src/lib.rs", "src/main.rs", "docs/intro.md"]
+        );
+    }
+
+    #[test]
+    fn hunk_size_filters_form_a_range() {
+        let filters = [Filter::HunkSizeAtLeast(3), Filter::HunkSizeAtMost(3)];
+        assert_eq!(
+            paths(matching_with_owners(&changes(), &filters, None)),
+            ["src/main.rs"]
+        );
+    }
+
+    #[test]
+    fn owner_filters_use_the_codeowners_if_available() {
+        let filters = [Filter::CodeOwner("@org/docs".into())];
+        let codeowners = CodeOwners::parse("* @org/core\n/docs/ @org/docs\n");
+        assert_eq!(
+            paths(matching_with_owners(
+                &changes(),
+                &filters,
+                Some(&codeowners)
+            )),
+            ["docs/intro.md"]
+        );
+        assert_eq!(
+            matching_with_owners(&changes(), &filters, None).len(),
+            3,
+            "without codeowners, the filter can't be evaluated and is ignored"
+        );
+    }
+
+    #[test]
+    fn filters_that_dont_apply_to_changes_are_ignored() {
+        let filters = [
+            Filter::BranchName("main".into()),
+            Filter::ContentMatchesRegex(Regex::new("TODO").unwrap()),
+        ];
+        assert_eq!(
+            paths(matching_with_owners(&changes(), &filters, None)),
+            ["src/lib.rs", "docs/intro.md"]
+        );
+    }
+
+    #[test]
+    fn semantic_type_filters_classify_hunks() {
+        let changes = [
+            change(
+                "src/lib.rs",
+                "@@ -1 +1 @@\n-fn a() {} // one\n+fn a() {} // two\n",
+            ),
+            change(
+                "src/main.rs",
+                "@@ -1 +1,3 @@\n-fn main() { run() }\n+fn main() {\n+    run()\n+}\n",
+            ),
+            change(
+                "src/util.rs",
+                "@@ -1,2 +1,2 @@\n-use b;\n-use a;\n+use a;\n+use b;\n",
+            ),
+            change("src/run.rs", "@@ -1 +1 @@\n-run(1)\n+run(2)\n"),
+        ];
+        let matching = |semantic_type| {
+            paths(matching_with_owners(
+                &changes,
+                &[Filter::SemanticType(semantic_type)],
+                None,
+            ))
+        };
+        assert_eq!(matching(SemanticType::Comment), ["src/lib.rs"]);
+        assert_eq!(matching(SemanticType::Whitespace), ["src/main.rs"]);
+        assert_eq!(matching(SemanticType::ImportReordering), ["src/util.rs"]);
+        assert_eq!(matching(SemanticType::Code), ["src/run.rs"]);
+        assert_eq!(
+            matching(SemanticType::BugFix).len(),
+            4,
+            "types that can't be told from the hunk alone are ignored"
+        );
+    }
+
+    #[test]
+    fn semantic_type_filters_dont_match_changes_without_hunks() {
+        let mut binary = change("image.png", "");
+        binary.diff = None;
+        assert!(
+            matching_with_owners(&[binary], &[Filter::SemanticType(SemanticType::Code)], None)
+                .is_empty()
+        );
+    }
+
+    fn prompt_run(at: i64, fingerprint: &str) -> Snapshot {
+        Snapshot {
+            commit_id: git2::Oid::zero(),
+            created_at: git2::Time::new(at, 0),
+            details: Some(
+                SnapshotDetails::new(OperationKind::AutoHandleChangesBefore).with_trailers(vec![
+                    Trailer {
+                        key: CHANGES_TRAILER.into(),
+                        value: fingerprint.into(),
+                    },
+                ]),
+            ),
+        }
+    }
+
+    #[test]
+    fn prompts_are_debounced_and_not_repeated_for_the_same_changes() {
+        let now = 1_000_000;
+        let later = now + LLM_PROMPT_DEBOUNCE_SECONDS;
+        assert!(
+            prompt_is_due(None, "a", now),
+            "rules that never ran are due"
+        );
+        assert!(
+            !prompt_is_due(Some(&prompt_run(now - 1, "a")), "b", now),
+            "even new changes have to wait for the debounce"
+        );
+        assert!(prompt_is_due(Some(&prompt_run(now, "a")), "b", later));
+        assert!(
+            !prompt_is_due(Some(&prompt_run(now, "a")), "a", later),
+            "the same changes are only given to the prompt once"
+        );
+    }
+
+    #[test]
+    fn fingerprints_change_with_the_hunks() -> anyhow::Result<()> {
+        let fingerprint = changes_fingerprint(&changes())?;
+        assert_eq!(fingerprint, changes_fingerprint(&changes())?);
+
+        let mut edited = changes();
+        edited[2] = change("docs/intro.md", "@@ -1 +1 @@\n-Intro\n+Introduction\n");
+        assert_ne!(fingerprint, changes_fingerprint(&edited)?);
+        assert_ne!(fingerprint, changes_fingerprint(&changes()[..2])?);
+        Ok(())
     }
-    assignments
 }
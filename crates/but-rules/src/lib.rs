@@ -1,8 +1,12 @@
 use but_hunk_dependency::ui::hunk_dependencies_for_workspace_changes_by_worktree_dir;
 use gitbutler_command_context::CommandContext;
+/// An event in the app, other than changes in the worktree, that causes rules with the matching [`Trigger`] to be evaluated.
+pub use gitbutler_command_context::Event;
 use serde::{Deserialize, Serialize};
 
+mod codeowners;
 pub mod db;
+mod glob;
 pub mod handler;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -75,6 +79,11 @@ pub enum Trigger {
     FileSytemChange,
     /// Whenever a Claude Code hook is invoked.
     ClaudeCodeHook,
+    /// After a commit was created in a stack.
+    CommitCreated,
+    /// After upstream changes were integrated into the workspace, either by updating the workspace base
+    /// or by merging the upstream of a branch.
+    BaseUpdated,
 }
 
 /// A filter is a condition that determines what files or changes the rule applies to.
@@ -85,6 +94,15 @@ pub enum Filter {
     /// Matches the file path (relative to the repository root).
     #[serde(with = "serde_regex")]
     PathMatchesRegex(regex::Regex),
+    /// Matches the file path (relative to the repository root) against a pattern as used in `.gitignore` files,
+    /// like `docs/**/*.md`.
+    PathMatchesGlob(String),
+    /// Matches files owned by the given owner, like `@org/team`, according to the `CODEOWNERS` file of the repository.
+    CodeOwner(String),
+    /// Matches hunks with at least this many added or removed lines.
+    HunkSizeAtLeast(usize),
+    /// Matches hunks with at most this many added or removed lines.
+    HunkSizeAtMost(usize),
     /// Match the file content.
     #[serde(with = "serde_regex")]
     ContentMatchesRegex(regex::Regex),
@@ -94,6 +112,8 @@ pub enum Filter {
     SemanticType(SemanticType),
     /// Matches changes that originated from a specific Claude Code session.
     ClaudeCodeSessionId(String),
+    /// Matches events that happened on the branch with the given short name, like the creation of a commit.
+    BranchName(String),
 }
 
 /// Represents the type of change that occurred in the Git worktree.
@@ -124,6 +144,14 @@ pub enum SemanticType {
     BugFix,
     /// A change that updates or adds documentation, such as code inline docs, comments or README files.
     Documentation,
+    /// A change that only affects whitespace, like indentation, line breaks or empty lines.
+    Whitespace,
+    /// A change that only affects comments.
+    Comment,
+    /// A change that only reorders import statements.
+    ImportReordering,
+    /// A change to the code, meaning it's not only a change to whitespace, comments or the order of imports.
+    Code,
     /// A change that is not recognized or does not fit into the predefined categories.
     UserDefined(String),
 }
@@ -150,6 +178,8 @@ pub enum Operation {
     Amend { change_id: String },
     /// Create a new commit with the matched changes on a specific branch.
     NewCommit { branch_name: String },
+    /// Add a trailer like `key: value` to the message of the commit that triggered the rule, unless it's already present.
+    AddTrailer { key: String, value: String },
 }
 
 /// The target stack for a given operation. It's either specifying a specific stack ID, or alternaitvely the leftmost or rightmost stack in the workspace.
@@ -301,6 +331,62 @@ pub fn list_rules(ctx: &mut CommandContext) -> anyhow::Result<Vec<WorkspaceRule>
 }
 
 pub fn process_rules(ctx: &mut CommandContext) -> anyhow::Result<()> {
+    let (assignments, dependencies) = worktree_assignments(ctx)?;
+    handler::process_workspace_rules(ctx, &assignments, &Some(dependencies))?;
+    Ok(())
+}
+
+/// Evaluate all enabled rules triggered by `event` and perform their actions, returning the amount of rules that were applied.
+///
+/// Actions operating on changes are applied to the changes currently in the worktree.
+pub fn process_rules_for_event(ctx: &mut CommandContext, event: &Event) -> anyhow::Result<usize> {
+    let rules = list_rules(ctx)?
+        .into_iter()
+        .filter(|r| r.enabled && event_matches(r, event))
+        .collect::<Vec<_>>();
+    if rules.is_empty() {
+        return Ok(0);
+    }
+    handler::process_event_rules(ctx, event, rules)
+}
+
+/// Evaluate the rules triggered by the events that were recorded in `ctx` while running a command, if rules are enabled.
+///
+/// Failures are logged as the operations that caused the events already happened.
+/// Events recorded by the actions of these rules, like commits they create, don't trigger rules themselves.
+pub fn process_recorded_events(ctx: &mut CommandContext) {
+    let events = ctx.take_events();
+    if !ctx.app_settings().feature_flags.rules {
+        return;
+    }
+    for event in &events {
+        if let Err(err) = process_rules_for_event(ctx, event) {
+            tracing::warn!(?event, "Failed to process rules: {err:#}");
+        }
+    }
+    ctx.take_events();
+}
+
+/// Return `true` if `rule` is triggered by `event` and its filters that apply to events match it.
+fn event_matches(rule: &WorkspaceRule, event: &Event) -> bool {
+    let (trigger, branch_name) = match event {
+        Event::CommitCreated { branch_name, .. } => (Trigger::CommitCreated, Some(branch_name)),
+        Event::BaseUpdated => (Trigger::BaseUpdated, None),
+    };
+    rule.trigger == trigger
+        && rule.filters.iter().all(|f| match f {
+            Filter::BranchName(name) => branch_name == Some(name),
+            _ => true,
+        })
+}
+
+/// Return the assignments of all changes in the worktree along with their dependencies.
+fn worktree_assignments(
+    ctx: &mut CommandContext,
+) -> anyhow::Result<(
+    Vec<but_hunk_assignment::HunkAssignment>,
+    but_hunk_dependency::ui::HunkDependencies,
+)> {
     let wt_changes = but_core::diff::worktree_changes(&ctx.gix_repo()?)?;
 
     let dependencies = hunk_dependencies_for_workspace_changes_by_worktree_dir(
@@ -308,6 +394,7 @@ pub fn process_rules(ctx: &mut CommandContext) -> anyhow::Result<()> {
         &ctx.project().path,
         &ctx.project().gb_dir(),
         Some(wt_changes.changes.clone()),
+        ctx.app_settings().hunk_dependencies.into(),
     )?;
 
     let (assignments, _) = but_hunk_assignment::assignments_with_fallback(
@@ -317,7 +404,65 @@ pub fn process_rules(ctx: &mut CommandContext) -> anyhow::Result<()> {
         Some(&dependencies),
     )
     .map_err(|e| anyhow::anyhow!("Failed to get assignments: {}", e))?;
+    Ok((assignments, dependencies))
+}
 
-    handler::process_workspace_rules(ctx, &assignments, &Some(dependencies))?;
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(trigger: Trigger, filters: Vec<Filter>) -> WorkspaceRule {
+        WorkspaceRule {
+            id: "rule".into(),
+            created_at: chrono::DateTime::UNIX_EPOCH.naive_utc(),
+            enabled: true,
+            trigger,
+            filters,
+            action: Action::Explicit(Operation::AddTrailer {
+                key: "Reviewed-by".into(),
+                value: "me".into(),
+            }),
+        }
+    }
+
+    fn commit_created(branch_name: &str) -> Event {
+        Event::CommitCreated {
+            stack_id: but_workspace::StackId::generate(),
+            branch_name: branch_name.into(),
+            commit_id: gix::ObjectId::null(gix::hash::Kind::Sha1),
+        }
+    }
+
+    #[test]
+    fn events_only_match_rules_with_their_trigger() {
+        let on_commit = rule(Trigger::CommitCreated, vec![]);
+        let on_base_update = rule(Trigger::BaseUpdated, vec![]);
+        let on_change = rule(Trigger::FileSytemChange, vec![]);
+
+        assert!(event_matches(&on_commit, &commit_created("main")));
+        assert!(!event_matches(&on_base_update, &commit_created("main")));
+        assert!(!event_matches(&on_change, &commit_created("main")));
+
+        assert!(event_matches(&on_base_update, &Event::BaseUpdated));
+        assert!(!event_matches(&on_commit, &Event::BaseUpdated));
+    }
+
+    #[test]
+    fn branch_name_filters_match_the_branch_of_the_event() {
+        let on_feature = rule(
+            Trigger::CommitCreated,
+            vec![Filter::BranchName("feature".into())],
+        );
+        assert!(event_matches(&on_feature, &commit_created("feature")));
+        assert!(!event_matches(&on_feature, &commit_created("main")));
+
+        let on_base_update = rule(
+            Trigger::BaseUpdated,
+            vec![Filter::BranchName("feature".into())],
+        );
+        assert!(
+            !event_matches(&on_base_update, &Event::BaseUpdated),
+            "events without a branch don't match branch filters"
+        );
+    }
 }
@@ -161,6 +161,7 @@ async fn handle_command(
         "commit_details" => diff::commit_details_cmd(request.params),
         "changes_in_branch" => diff::changes_in_branch_cmd(request.params),
         "changes_in_worktree" => diff::changes_in_worktree_cmd(request.params),
+        "explain_hunk_locks" => diff::explain_hunk_locks_cmd(request.params),
         "assign_hunk" => diff::assign_hunk_cmd(request.params),
         // Workspace commands
         "stacks" => workspace::stacks_cmd(request.params),
@@ -231,6 +232,13 @@ async fn handle_command(
             .and_then(|params| {
                 settings::update_reviews(&app_settings_sync, params).map(|r| json!(r))
             }),
+        "update_hunk_dependencies" => {
+            serde_json::from_value(request.params)
+                .to_error()
+                .and_then(|params| {
+                    settings::update_hunk_dependencies(&app_settings_sync, params).map(|r| json!(r))
+                })
+        }
         // Secret management
         "secret_get_global" => secret::secret_get_global_cmd(request.params),
         "secret_set_global" => secret::secret_set_global_cmd(request.params),
@@ -284,10 +292,16 @@ async fn handle_command(
         "move_commit" => virtual_branches::move_commit_cmd(request.params),
         "move_branch" => virtual_branches::move_branch_cmd(request.params),
         "update_commit_message" => virtual_branches::update_commit_message_cmd(request.params),
+        "acknowledge_auto_resolution" => {
+            virtual_branches::acknowledge_auto_resolution_cmd(request.params)
+        }
         "find_commit" => virtual_branches::find_commit_cmd(request.params),
         "upstream_integration_statuses" => {
             virtual_branches::upstream_integration_statuses_cmd(request.params)
         }
+        "upstream_integration_dry_run" => {
+            virtual_branches::upstream_integration_dry_run_cmd(request.params)
+        }
         "integrate_upstream" => virtual_branches::integrate_upstream_cmd(request.params),
         "resolve_upstream_integration" => {
             virtual_branches::resolve_upstream_integration_cmd(request.params)
@@ -303,6 +317,12 @@ async fn handle_command(
         }
         "edit_initial_index_state" => modes::edit_initial_index_state_cmd(request.params),
         "edit_changes_from_initial" => modes::edit_changes_from_initial_cmd(request.params),
+        "edit_conflicted_paths" => modes::edit_conflicted_paths_cmd(request.params),
+        "edit_take_conflict_side" => modes::edit_take_conflict_side_cmd(request.params),
+        "edit_write_conflict_markers" => modes::edit_write_conflict_markers_cmd(request.params),
+        "finish_edit_resolution_and_return_to_workspace" => {
+            modes::finish_edit_resolution_and_return_to_workspace_cmd(request.params)
+        }
         // Repository commands
         "git_get_local_config" => repo::git_get_local_config_cmd(request.params),
         "git_set_local_config" => repo::git_set_local_config_cmd(request.params),
@@ -325,8 +345,12 @@ async fn handle_command(
         "push_stack_to_review" => stack::push_stack_to_review_cmd(request.params),
         // Undo/Snapshot commands
         "list_snapshots" => undo::list_snapshots_cmd(request.params),
+        "query_snapshots" => undo::query_snapshots_cmd(request.params),
         "restore_snapshot" => undo::restore_snapshot_cmd(request.params),
+        "undo_operation" => undo::undo_operation_cmd(request.params),
+        "redo_operation" => undo::redo_operation_cmd(request.params),
         "snapshot_diff" => undo::snapshot_diff_cmd(request.params),
+        "compact_oplog" => undo::compact_oplog_cmd(request.params),
         // "oplog_diff_worktrees" => undo::oplog_diff_worktrees(&ctx, request.params),
         // Config management commands
         "get_gb_config" => config::get_gb_config_cmd(request.params),
@@ -403,6 +427,16 @@ async fn handle_command(
                 Err(e) => Err(e),
             }
         }
+        "get_oplog_bundle_path" => {
+            let params = serde_json::from_value(request.params).to_error();
+            match params {
+                Ok(params) => {
+                    let result = zip::get_oplog_bundle_path(&app, params);
+                    result.map(|r| json!(r))
+                }
+                Err(e) => Err(e),
+            }
+        }
         "get_logs_archive_path" => {
             let params = serde_json::from_value(request.params).to_error();
             match params {
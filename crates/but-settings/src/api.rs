@@ -1,4 +1,5 @@
 use crate::AppSettingsWithDiskSync;
+use crate::app_settings::HunkDependencyAlgorithm;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
@@ -42,6 +43,14 @@ pub struct ReviewsUpdate {
     pub auto_fill_pr_description_from_commit: Option<bool>,
 }
 
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+/// Update request for [`crate::app_settings::HunkDependencies`].
+pub struct HunkDependenciesUpdate {
+    pub algorithm: Option<HunkDependencyAlgorithm>,
+    pub compare: Option<bool>,
+}
+
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 /// Update request for [`crate::app_settings::Fetch`].
@@ -151,4 +160,15 @@ impl AppSettingsWithDiskSync {
         }
         settings.save()
     }
+
+    pub fn update_hunk_dependencies(&self, update: HunkDependenciesUpdate) -> Result<()> {
+        let mut settings = self.get_mut_enforce_save()?;
+        if let Some(algorithm) = update.algorithm {
+            settings.hunk_dependencies.algorithm = algorithm;
+        }
+        if let Some(compare) = update.compare {
+            settings.hunk_dependencies.compare = compare;
+        }
+        settings.save()
+    }
 }
@@ -83,6 +83,24 @@ pub struct Claude {
     pub use_configured_model: bool,
 }
 
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct HunkDependencies {
+    /// The algorithm to calculate which commits the uncommitted changes depend on.
+    pub algorithm: HunkDependencyAlgorithm,
+    /// Whether to also calculate the dependencies with the other algorithm, and log where they disagree.
+    pub compare: bool,
+}
+
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum HunkDependencyAlgorithm {
+    /// Track the ranges of committed hunks, which makes changes within the context lines of a hunk depend on it.
+    Ranges,
+    /// Track the commit that introduced each line, so only changes to committed lines depend on them.
+    Lines,
+}
+
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct Reviews {
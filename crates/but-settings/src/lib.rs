@@ -22,6 +22,8 @@ pub struct AppSettings {
     pub claude: app_settings::Claude,
     /// Settings related to code reviews and pull requests.
     pub reviews: app_settings::Reviews,
+    /// Settings related to the calculation of hunk dependencies.
+    pub hunk_dependencies: app_settings::HunkDependencies,
 }
 
 impl Default for AppSettings {
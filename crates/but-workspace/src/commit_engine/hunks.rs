@@ -91,6 +91,55 @@ impl HunkHeader {
     pub fn contains(self, other: HunkHeader) -> bool {
         self.old_range().contains(other.old_range()) && self.new_range().contains(other.new_range())
     }
+
+    /// Return `true` if this header selects lines of only one side of a hunk, with the other side being a null-range,
+    /// like `-2,1 +0,0` to select the removed line 2, or `-0,0 +4,2` to select the added lines 4 and 5.
+    ///
+    /// Note that hunks of newly added or deleted files also look like this.
+    pub fn is_line_selection(&self) -> bool {
+        self.old_range().is_null() != self.new_range().is_null()
+    }
+
+    /// Turn the 1-based `removed_lines` of the old image and `added_lines` of the new image into
+    /// [line selections](Self::is_line_selection()) as understood by [`create_commit()`](super::create_commit()),
+    /// with consecutive lines merged into one selection.
+    ///
+    /// Selections of removed lines are returned before the ones of added lines, which is the order in which they appear
+    /// in a hunk, so all lines should be of the same hunk.
+    pub fn line_selections(removed_lines: &[usize], added_lines: &[usize]) -> Vec<HunkHeader> {
+        let removed = line_runs(removed_lines).map(|range| HunkHeader {
+            old_start: range.start,
+            old_lines: range.lines,
+            new_start: 0,
+            new_lines: 0,
+        });
+        let added = line_runs(added_lines).map(|range| HunkHeader {
+            old_start: 0,
+            old_lines: 0,
+            new_start: range.start,
+            new_lines: range.lines,
+        });
+        removed.chain(added).collect()
+    }
+}
+
+/// Return the ranges of consecutive line numbers in `lines`, in ascending order.
+fn line_runs(lines: &[usize]) -> impl Iterator<Item = HunkRange> {
+    let mut lines = lines.to_vec();
+    lines.sort_unstable();
+    lines.dedup();
+    let mut runs: Vec<HunkRange> = Vec::new();
+    for line in lines {
+        let line = line as u32;
+        match runs.last_mut() {
+            Some(run) if run.end() == line => run.lines += 1,
+            _ => runs.push(HunkRange {
+                start: line,
+                lines: 1,
+            }),
+        }
+    }
+    runs.into_iter()
 }
 
 impl std::fmt::Debug for HunkHeader {
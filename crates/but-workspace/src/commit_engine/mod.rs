@@ -2,12 +2,12 @@
 
 use crate::{DiffSpec, commit_engine::reference_frame::InferenceMode};
 use anyhow::{Context, bail};
-use bstr::BString;
+use bstr::{BString, ByteSlice};
 use but_core::RepositoryExt;
 use but_rebase::RebaseOutput;
 use but_rebase::commit::DateMode;
 use but_rebase::merge::ConflictErrorContext;
-use gitbutler_command_context::CommandContext;
+use gitbutler_command_context::{CommandContext, Event};
 use gitbutler_project::access::WorktreeWritePermission;
 use gitbutler_stack::{StackId, VirtualBranchesHandle, VirtualBranchesState};
 use gix::prelude::ObjectIdExt as _;
@@ -208,6 +208,19 @@ pub fn create_commit(
     move_source: Option<MoveSourceCommit>,
     changes: Vec<DiffSpec>,
     context_lines: u32,
+) -> anyhow::Result<CreateCommitOutcome> {
+    create_commit_on_branch(repo, destination, None, move_source, changes, context_lines)
+}
+
+/// Like [`create_commit()`], but with `amended_branch` being the branch that the commit to amend is on, if known,
+/// to apply the trailer policy with when its message changes.
+fn create_commit_on_branch(
+    repo: &gix::Repository,
+    destination: Destination,
+    amended_branch: Option<&gix::refs::FullNameRef>,
+    move_source: Option<MoveSourceCommit>,
+    changes: Vec<DiffSpec>,
+    context_lines: u32,
 ) -> anyhow::Result<CreateCommitOutcome> {
     let parents = match &destination {
         Destination::NewCommit {
@@ -258,11 +271,18 @@ pub fn create_commit(
             Destination::NewCommit {
                 message,
                 parent_commit_id: _,
-                stack_segment: _,
+                stack_segment,
             } => {
                 let (author, committer) = repo.commit_signatures()?;
                 let new_commit = create_possibly_signed_commit(
-                    repo, author, committer, &message, new_tree, parents, None,
+                    repo,
+                    author,
+                    committer,
+                    &message,
+                    new_tree,
+                    parents,
+                    None,
+                    stack_segment.as_ref().map(|s| s.segment_ref.as_ref()),
                 )?;
                 Some(new_commit)
             }
@@ -273,7 +293,13 @@ pub fn create_commit(
                 let mut commit = but_core::Commit::from_id(commit_id.attach(repo))?;
                 commit.tree = new_tree;
                 if let Some(message) = new_message {
-                    commit.message = message.into();
+                    let previous_message = std::mem::replace(&mut commit.message, message.into());
+                    but_rebase::commit::apply_trailer_policy(
+                        repo,
+                        &mut commit.inner,
+                        &[previous_message.as_bstr()],
+                        amended_branch.and_then(short_name),
+                    )?;
                 }
                 Some(but_rebase::commit::create(
                     repo,
@@ -337,9 +363,23 @@ pub fn create_commit_and_update_refs(
     changes: Vec<DiffSpec>,
     context_lines: u32,
 ) -> anyhow::Result<CreateCommitOutcome> {
-    let mut out = create_commit(
+    let amended_branch = match &destination {
+        Destination::AmendCommit {
+            commit_id,
+            new_message: Some(_),
+        } => branch_containing(
+            repo,
+            frame
+                .branch_tip
+                .or_else(|| repo.head_id().ok().map(|id| id.detach())),
+            *commit_id,
+        )?,
+        _ => None,
+    };
+    let mut out = create_commit_on_branch(
         repo,
         destination.clone(),
+        amended_branch.as_ref().map(|name| name.as_ref()),
         move_source,
         changes.clone(),
         context_lines,
@@ -677,7 +717,10 @@ pub fn create_commit_and_update_refs_with_project(
     Ok(out)
 }
 
-/// Create a commit exactly as specified, and sign it depending on Git and GitButler specific Git configuration.
+/// Create a commit as specified with the configured trailers added to its `message`, and sign it depending on Git
+/// and GitButler specific Git configuration.
+/// `branch` is the name of the branch the commit is created on, if known.
+#[allow(clippy::too_many_arguments)]
 fn create_possibly_signed_commit(
     repo: &gix::Repository,
     author: gix::actor::Signature,
@@ -686,8 +729,9 @@ fn create_possibly_signed_commit(
     tree: gix::ObjectId,
     parents: impl IntoIterator<Item = impl Into<gix::ObjectId>>,
     commit_headers: Option<but_core::commit::HeadersV2>,
+    branch: Option<&gix::refs::FullNameRef>,
 ) -> anyhow::Result<gix::ObjectId> {
-    let commit = gix::objs::Commit {
+    let mut commit = gix::objs::Commit {
         message: message.into(),
         tree,
         author,
@@ -696,10 +740,63 @@ fn create_possibly_signed_commit(
         parents: parents.into_iter().map(Into::into).collect(),
         extra_headers: (&commit_headers.unwrap_or_default()).into(),
     };
+    but_rebase::commit::apply_trailer_policy(repo, &mut commit, &[], branch.and_then(short_name))?;
     but_rebase::commit::create(repo, commit, DateMode::CommitterKeepAuthorKeep)
 }
 
+/// Return the short name of the branch `name`, if it is valid UTF-8.
+fn short_name(name: &gix::refs::FullNameRef) -> Option<&str> {
+    name.shorten().to_str().ok()
+}
+
+/// Return the name of the branch that `commit_id` is on, which is the first local branch pointing to it or to one
+/// of its descendants along the first parents of `branch_tip`, or `None` if there is none.
+fn branch_containing(
+    repo: &gix::Repository,
+    branch_tip: Option<gix::ObjectId>,
+    commit_id: gix::ObjectId,
+) -> anyhow::Result<Option<gix::refs::FullName>> {
+    let Some(branch_tip) = branch_tip else {
+        return Ok(None);
+    };
+    let mut commits = Vec::new();
+    for info in branch_tip
+        .attach(repo)
+        .ancestors()
+        .first_parent_only()
+        .all()?
+    {
+        let id = info?.id;
+        commits.push(id);
+        if id == commit_id {
+            break;
+        }
+    }
+    if commits.last() != Some(&commit_id) {
+        return Ok(None);
+    }
+
+    let mut branches_by_id = gix::hashtable::HashMap::<_, gix::refs::FullName>::default();
+    for reference in repo
+        .references()?
+        .prefixed("refs/heads/")?
+        .filter_map(Result::ok)
+    {
+        if let Some(id) = reference.try_id() {
+            branches_by_id
+                .entry(id.detach())
+                .or_insert(reference.inner.name);
+        }
+    }
+    Ok(commits
+        .iter()
+        .rev()
+        .find_map(|id| branches_by_id.remove(id)))
+}
+
 /// Less pure but a simpler version of [`create_commit_and_update_refs_with_project`]
+///
+/// If a commit was created, [`Event::CommitCreated`] is recorded in `ctx`.
 pub fn create_commit_simple(
     ctx: &CommandContext,
     stack_id: StackId,
@@ -755,5 +852,12 @@ pub fn create_commit_simple(
     if !outcome.rejected_specs.is_empty() {
         tracing::warn!(?outcome.rejected_specs, "Failed to commit at least one hunk");
     }
+    if let Some(commit_id) = outcome.new_commit {
+        ctx.record_event(Event::CommitCreated {
+            stack_id,
+            branch_name: stack_branch_name,
+            commit_id,
+        });
+    }
     Ok(outcome)
 }
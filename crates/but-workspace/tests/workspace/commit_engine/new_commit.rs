@@ -5,7 +5,7 @@ use crate::utils::{
     write_sequence,
 };
 use but_testsupport::assure_stable_env;
-use but_workspace::{DiffSpec, commit_engine};
+use but_workspace::{DiffSpec, HunkHeader, commit_engine};
 use commit_engine::Destination;
 use gix::prelude::ObjectIdExt;
 
@@ -762,6 +762,47 @@ fn modification_with_complex_selection() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn modification_with_line_selections() -> anyhow::Result<()> {
+    assure_stable_env();
+
+    let repo = read_only_in_memory_scenario("plain-modifications")?;
+    let selections = HunkHeader::line_selections(&[2, 3, 6, 7, 9, 10], &[1, 5, 6, 9, 10]);
+    assert_eq!(
+        selections,
+        [
+            hunk_header("-2,2", "+0,0"),
+            hunk_header("-6,2", "+0,0"),
+            hunk_header("-9,2", "+0,0"),
+            hunk_header("-0,0", "+1,1"),
+            hunk_header("-0,0", "+5,2"),
+            hunk_header("-0,0", "+9,2"),
+        ],
+        "consecutive lines are merged"
+    );
+
+    let outcome = commit_engine::create_commit(
+        &repo,
+        Destination::NewCommit {
+            parent_commit_id: Some(repo.head_id()?.into()),
+            message: "select lines by their numbers".into(),
+            stack_segment: None,
+        },
+        None,
+        vec![diff_spec(None, "all-modified", selections)],
+        UI_CONTEXT_LINES,
+    )?;
+    assert_eq!(outcome.rejected_specs, [], "everything was assigned");
+
+    insta::assert_snapshot!(visualize_tree(&repo, &outcome)?, @r#"
+    4bbd0d5
+    ├── all-added:100644:e69de29 ""
+    ├── all-modified:100644:fcf7eb0 "1\n4\n5\n8\n11\n15\n16\n19\n20\n"
+    └── all-removed:100644:f00c965 "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n"
+    "#);
+    Ok(())
+}
+
 #[test]
 fn submodule_typechanges() -> anyhow::Result<()> {
     assure_stable_env();
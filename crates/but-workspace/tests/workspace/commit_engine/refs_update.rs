@@ -665,6 +665,10 @@ fn insert_commit_into_single_stack_with_signatures() -> anyhow::Result<()> {
                         Sha1(a8fbed8ea304d850e168033468be9d9f128e17c3),
                     ),
                 ],
+                dropped_commits: [],
+                executed_commands: [],
+                stopped_at: None,
+                merge_conflicts: [],
             },
         ),
         index: None,
@@ -1677,6 +1681,45 @@ fn amend_edit_message_only() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn amended_message_gets_the_ticket_of_its_branch() -> anyhow::Result<()> {
+    assure_stable_env();
+
+    let (mut repo, _tmp) = writable_scenario("merge-with-two-branches-line-offset");
+    repo.config_snapshot_mut()
+        .set_raw_value(&"gitbutler.ticketPattern", "^([A-Z])$")?;
+
+    let head_commit_id = repo.head_id()?;
+    let branch_a = repo.rev_parse_single("A")?.detach();
+    let mut vb = VirtualBranchesState::default();
+    let stack = stack_with_branches("s1", branch_a, [("s1-b/top", branch_a)], &repo);
+    vb.branches.insert(stack.id, stack);
+
+    let outcome = but_workspace::commit_engine::create_commit_and_update_refs(
+        &repo,
+        ReferenceFrame {
+            workspace_tip: Some(head_commit_id.detach()),
+            branch_tip: Some(branch_a),
+        },
+        &mut vb,
+        Destination::AmendCommit {
+            commit_id: branch_a,
+            new_message: Some("add 10 to the beginning (amended)".into()),
+        },
+        None,
+        Vec::new(),
+        CONTEXT_LINES,
+    )?;
+
+    let new_commit = repo.find_commit(outcome.new_commit.expect("the message was changed"))?;
+    assert_eq!(
+        new_commit.message_raw()?,
+        "add 10 to the beginning (amended)\n\nRefs: A\n",
+        "the ticket is taken from the branch the amended commit is on"
+    );
+    Ok(())
+}
+
 mod utils {
     use but_testsupport::visualize_commit_graph;
     use gitbutler_stack::VirtualBranchesState;
@@ -17,7 +17,11 @@ pub struct Args {
 #[derive(Debug, clap::Subcommand)]
 pub enum Subcommands {
     /// Show commits on active branches in your workspace.
-    Log,
+    Log {
+        /// Verify the signatures of the commits and show if they are signed, unsigned or invalid.
+        #[clap(long = "show-signature", default_value_t = false)]
+        show_signature: bool,
+    },
     /// Overview of the uncommitted changes in the repository.
     #[clap(alias = "st")]
     Status {
@@ -27,6 +31,9 @@ pub enum Subcommands {
         /// Show verbose output with commit author and timestamp.
         #[clap(short = 'v', long = "verbose", default_value_t = false)]
         verbose: bool,
+        /// Explain why the uncommitted changes to the given file are locked to commits, instead of showing the overview.
+        #[clap(long = "explain", value_name = "FILE")]
+        explain: Option<String>,
     },
     /// Overview of the uncommitted changes in the repository with files shown.
     /// Equivalent to `but status --files`.
@@ -75,6 +82,8 @@ For examples see `but rub --help`."
         /// Deletes a mark
         #[clap(long, short = 'd')]
         delete: bool,
+        #[clap(flatten)]
+        filters: crate::mark::Filters,
     },
     /// Removes all marks from the workspace
     Unmark,
@@ -100,19 +109,85 @@ For examples see `but rub --help`."
         /// Commit ID to edit the message for
         commit: String,
     },
-    /// Show operation history (last 20 entries).
+    /// Rewrite the commits of a stack by editing a todo list in your editor, like `git rebase -i`.
+    Rebase {
+        /// Branch CLI ID or name of the stack to rebase
+        branch: String,
+    },
+    /// Resolve the conflicts of a conflicted commit in edit mode, file by file or hunk by hunk.
+    #[clap(args_conflicts_with_subcommands = true)]
+    Resolve {
+        #[clap(subcommand)]
+        cmd: Option<crate::resolve::Subcommands>,
+        /// Commit ID of the conflicted commit to start resolving. Lists the conflicted files of the commit being resolved if omitted.
+        commit: Option<String>,
+    },
+    /// Show operation history, most recent first.
+    #[clap(args_conflicts_with_subcommands = true)]
     Oplog {
+        #[clap(subcommand)]
+        cmd: Option<crate::oplog::Subcommands>,
         /// Start from this oplog SHA instead of the head
         #[clap(long)]
         since: Option<String>,
+        /// Only show operations after this point in time, either relative like `30m`, `2h`, `3d` or `1w`, or a date like `2025-01-31`.
+        #[clap(long)]
+        after: Option<String>,
+        /// Only show operations before this point in time, in the same format as `--after`.
+        #[clap(long)]
+        before: Option<String>,
+        /// Only show operations of this kind, like `squash-commit` or `move-commit`. May be given multiple times.
+        #[clap(long = "kind")]
+        kinds: Vec<String>,
+        /// Only show operations that changed this file, or any file in this directory.
+        #[clap(long)]
+        path: Option<String>,
+        /// Only show operations with this trailer, given as `key` or `key=value`.
+        #[clap(long)]
+        trailer: Option<String>,
+        /// The maximum number of operations to show.
+        #[clap(short = 'n', long, default_value_t = 20)]
+        limit: usize,
     },
     /// Restore to a specific oplog snapshot.
     Restore {
         /// Oplog SHA to restore to
         oplog_sha: String,
     },
-    /// Undo the last operation by reverting to the previous snapshot.
+    /// Undo the last operation by reverting to the snapshot taken before it. Repeat to step further back.
     Undo,
+    /// Redo the last undone operation.
+    Redo,
+    /// Push all branches of a stack to the remote, or a stack up to and including the given branch.
+    Push {
+        /// Branch CLI ID or name to push, along with all branches below it. All stacks are pushed if omitted.
+        branch: Option<String>,
+        /// Force-push without checking that the remote branch still points to the last known remote head.
+        #[clap(long)]
+        skip_force_push_protection: bool,
+        /// Do not run the `pre-push` hook.
+        #[clap(long, short = 'n')]
+        no_verify: bool,
+    },
+    /// Push a stack and create or update the forge review (pull request) for each of its branches.
+    ///
+    /// The forge is authenticated with the token in `BUT_FORGE_TOKEN`, or the GitHub token of the logged-in user.
+    Pr {
+        /// Branch CLI ID or name of the stack to push and review. All stacks are used if omitted.
+        branch: Option<String>,
+        /// The number of an existing review to update for the given branch.
+        #[clap(long, requires = "branch")]
+        number: Option<usize>,
+        /// Open new reviews as drafts.
+        #[clap(long)]
+        draft: bool,
+        /// Force-push without checking that the remote branch still points to the last known remote head.
+        #[clap(long)]
+        skip_force_push_protection: bool,
+        /// Do not run the `pre-push` hook.
+        #[clap(long, short = 'n')]
+        no_verify: bool,
+    },
     /// Starts up the MCP server.
     Mcp {
         /// Starts the internal MCP server which has more granular tools.
@@ -154,12 +229,29 @@ pub enum CommandName {
     New,
     #[clap(alias = "describe")]
     Describe,
+    #[clap(alias = "rebase")]
+    Rebase,
+    #[clap(alias = "resolve")]
+    Resolve,
+    ResolveTake,
+    ResolveMarkers,
+    ResolveFinish,
+    ResolveAbort,
     #[clap(alias = "oplog")]
     Oplog,
+    OplogGc,
+    OplogExport,
+    OplogImport,
     #[clap(alias = "restore")]
     Restore,
     #[clap(alias = "undo")]
     Undo,
+    #[clap(alias = "redo")]
+    Redo,
+    #[clap(alias = "push")]
+    Push,
+    #[clap(alias = "pr")]
+    Pr,
     BaseCheck,
     BaseUpdate,
     BranchNew,
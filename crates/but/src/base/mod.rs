@@ -1,10 +1,18 @@
+use std::io::{self, IsTerminal, Write};
+
+use anyhow::bail;
+use bstr::ByteSlice;
 use colored::Colorize;
 use gitbutler_branch_actions::upstream_integration::{
+    ApproachDryRun, BaseBranchResolution, BaseBranchResolutionApproach,
     BranchStatus::{Conflicted, Empty, Integrated, SaflyUpdatable},
-    Resolution, ResolutionApproach,
+    Resolution, ResolutionApproach, StackStatus,
     StackStatuses::{UpToDate, UpdatesRequired},
+    TreeStatus, UpstreamIntegrationDryRun,
 };
+use gitbutler_oxidize::ObjectIdExt;
 use gitbutler_project::Project;
+use gitbutler_stack::StackId;
 
 #[derive(Debug, clap::Parser)]
 pub struct Platform {
@@ -16,7 +24,73 @@ pub enum Subcommands {
     /// Fetches remotes from the remote and checks the mergeability of the branches in the workspace.
     Check,
     /// Updates the worspace (with all applied branches) to include the latest changes from the base branch.
-    Update,
+    Update {
+        /// Only show which commits of each branch would conflict with each approach, without changing anything.
+        /// Stacks with a chosen approach only show that approach.
+        #[clap(long)]
+        dry_run: bool,
+        /// How to update a stack, as `<stack>=<approach>`, where `<stack>` is the id or the name of a branch of the stack.
+        /// Can be given multiple times. Stacks that aren't mentioned are rebased, or prompted for in a terminal
+        /// if no stack is given.
+        #[clap(long = "stack", value_name = "STACK=APPROACH", value_parser = parse_stack_approach)]
+        stacks: Vec<(String, Approach)>,
+        /// How to update the base branch if it diverged from its remote.
+        #[clap(long)]
+        base_approach: Option<BaseApproach>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Approach {
+    /// Rebase the commits of the stack onto the new base.
+    Rebase,
+    /// Merge the new base into the stack, only possible for stacks with a single branch.
+    Merge,
+    /// Unapply the stack from the workspace.
+    Unapply,
+    /// Delete the stack, only possible if all of its branches were integrated.
+    Delete,
+}
+
+impl From<Approach> for ResolutionApproach {
+    fn from(approach: Approach) -> Self {
+        match approach {
+            Approach::Rebase => ResolutionApproach::Rebase,
+            Approach::Merge => ResolutionApproach::Merge,
+            Approach::Unapply => ResolutionApproach::Unapply,
+            Approach::Delete => ResolutionApproach::Delete,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum BaseApproach {
+    /// Rebase the local commits of the base branch onto its remote.
+    Rebase,
+    /// Merge the remote into the local base branch.
+    Merge,
+    /// Discard the local commits of the base branch.
+    HardReset,
+}
+
+impl From<BaseApproach> for BaseBranchResolutionApproach {
+    fn from(approach: BaseApproach) -> Self {
+        match approach {
+            BaseApproach::Rebase => BaseBranchResolutionApproach::Rebase,
+            BaseApproach::Merge => BaseBranchResolutionApproach::Merge,
+            BaseApproach::HardReset => BaseBranchResolutionApproach::HardReset,
+        }
+    }
+}
+
+fn parse_stack_approach(value: &str) -> Result<(String, Approach), String> {
+    let (stack, approach) = value
+        .rsplit_once('=')
+        .ok_or_else(|| format!("expected `<stack>=<approach>`, got '{value}'"))?;
+    let approach = <Approach as clap::ValueEnum>::from_str(approach, true).map_err(|_| {
+        format!("unknown approach '{approach}', expected one of rebase, merge, unapply or delete")
+    })?;
+    Ok((stack.to_owned(), approach))
 }
 
 pub fn handle(cmd: &Subcommands, project: &Project, json: bool) -> anyhow::Result<()> {
@@ -104,59 +178,485 @@ pub fn handle(cmd: &Subcommands, project: &Project, json: bool) -> anyhow::Resul
             println!("\nRun `but base update` to update your branches");
             Ok(())
         }
-        Subcommands::Update => {
-            let status =
-                but_api::virtual_branches::upstream_integration_statuses(project.id, None)?;
-            let resolutions = match status {
-                UpToDate => {
-                    println!("✅ Everything is up to date");
-                    None
+        Subcommands::Update {
+            dry_run: true,
+            stacks,
+            base_approach,
+        } => update_dry_run(project, stacks, *base_approach, json),
+        Subcommands::Update {
+            dry_run: false,
+            stacks,
+            base_approach,
+        } => update(project, stacks, *base_approach, json),
+    }
+}
+
+/// Show what would happen when updating, with the approaches chosen in `stacks` and `base_approach`.
+fn update_dry_run(
+    project: &Project,
+    stacks: &[(String, Approach)],
+    base_approach: Option<BaseApproach>,
+    json: bool,
+) -> anyhow::Result<()> {
+    if let UpdatesRequired { statuses, .. } =
+        but_api::virtual_branches::upstream_integration_statuses(project.id, None)?
+    {
+        validate_stack_choices(stacks, &statuses)?;
+    }
+    let base_branch_resolution = resolve_base_branch(project, base_approach, false)?;
+    let dry_run = but_api::virtual_branches::upstream_integration_dry_run(
+        project.id,
+        base_branch_resolution,
+    )?;
+    let dry_run = match dry_run {
+        UpstreamIntegrationDryRun::UpdatesRequired {
+            worktree_conflicts,
+            stacks: mut stack_dry_runs,
+        } => {
+            // Only show the chosen approach of stacks that were mentioned.
+            for stack in &mut stack_dry_runs {
+                let chosen = stack
+                    .stack_id
+                    .and_then(|stack_id| chosen_approach(stacks, stack_id, &stack.status));
+                if let Some((_, approach)) = chosen {
+                    let approach = ResolutionApproach::from(*approach);
+                    stack
+                        .approaches
+                        .retain(|outcome| outcome.approach == approach);
                 }
-                UpdatesRequired {
-                    worktree_conflicts,
-                    statuses,
-                } => {
-                    if !worktree_conflicts.is_empty() {
-                        println!(
-                            "❗️ There are uncommitted changes in the worktree that may conflict with
-                            the updates. Please commit or stash them and try again."
-                        );
-                        None
-                    } else {
-                        println!("🔄 Updating branches...");
-                        let mut resolutions = vec![];
-                        for (maybe_stack_id, status) in statuses {
-                            let Some(stack_id) = maybe_stack_id else {
-                                println!("No stack ID, assuming we're on single-branch mode...",);
-                                continue;
-                            };
-                            let approach = if status
-                                .branch_statuses
-                                .iter()
-                                .all(|s| s.status == gitbutler_branch_actions::upstream_integration::BranchStatus::Integrated)
-                            && status.tree_status != gitbutler_branch_actions::upstream_integration::TreeStatus::Conflicted
-                            {
-                                    ResolutionApproach::Delete
-                                } else {
-                                    ResolutionApproach::Rebase
-                                };
-                            let resolution = Resolution {
-                                stack_id,
-                                approach,
-                                delete_integrated_branches: true,
-                                force_integrated_branches: vec![],
-                            };
-                            resolutions.push(resolution);
-                        }
-                        Some(resolutions)
-                    }
+            }
+            UpstreamIntegrationDryRun::UpdatesRequired {
+                worktree_conflicts,
+                stacks: stack_dry_runs,
+            }
+        }
+        UpstreamIntegrationDryRun::UpToDate => UpstreamIntegrationDryRun::UpToDate,
+    };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&dry_run)?);
+        return Ok(());
+    }
+    match dry_run {
+        UpstreamIntegrationDryRun::UpToDate => println!("✅ Everything is up to date"),
+        UpstreamIntegrationDryRun::UpdatesRequired {
+            worktree_conflicts,
+            stacks,
+        } => {
+            if !worktree_conflicts.is_empty() {
+                println!(
+                    "❗️ There are uncommitted changes in the worktree that may conflict with the updates."
+                );
+            }
+            for stack in stacks {
+                let names = stack
+                    .status
+                    .branch_statuses
+                    .iter()
+                    .map(|bs| bs.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                println!("\n{}", names.bold());
+                for outcome in stack.approaches {
+                    print_approach_dry_run(&outcome);
                 }
-            };
+            }
+        }
+    }
+    Ok(())
+}
 
-            if let Some(resolutions) = resolutions {
-                but_api::virtual_branches::integrate_upstream(project.id, resolutions, None)?;
+/// Update the workspace with the approaches chosen in `stacks` and `base_approach`, prompting for the others
+/// in a terminal unless stacks were chosen explicitly.
+fn update(
+    project: &Project,
+    stacks: &[(String, Approach)],
+    base_approach: Option<BaseApproach>,
+    json: bool,
+) -> anyhow::Result<()> {
+    let interactive = !json && stacks.is_empty() && std::io::stdin().is_terminal();
+    // Fail before the base branch is touched if the chosen approaches can't be used.
+    if let UpdatesRequired { statuses, .. } =
+        but_api::virtual_branches::upstream_integration_statuses(project.id, None)?
+    {
+        validate_stack_choices(stacks, &statuses)?;
+    }
+    let base_branch_resolution = resolve_base_branch(project, base_approach, interactive)?;
+    let target_commit_id = base_branch_resolution
+        .as_ref()
+        .map(|resolution| resolution.target_commit_oid.to_string());
+    let status =
+        but_api::virtual_branches::upstream_integration_statuses(project.id, target_commit_id)?;
+    let resolutions = match status {
+        UpToDate => {
+            println!("✅ Everything is up to date");
+            None
+        }
+        UpdatesRequired {
+            worktree_conflicts,
+            statuses,
+        } => {
+            if !worktree_conflicts.is_empty() {
+                println!(
+                    "❗️ There are uncommitted changes in the worktree that may conflict with
+                    the updates. Please commit or stash them and try again."
+                );
+                None
+            } else {
+                validate_stack_choices(stacks, &statuses)?;
+                let mut resolutions = vec![];
+                for (maybe_stack_id, status) in statuses {
+                    let Some(stack_id) = maybe_stack_id else {
+                        println!("No stack ID, assuming we're on single-branch mode...",);
+                        continue;
+                    };
+                    let approach =
+                        if let Some((_, approach)) = chosen_approach(stacks, stack_id, &status) {
+                            ResolutionApproach::from(*approach)
+                        } else if status
+                            .branch_statuses
+                            .iter()
+                            .all(|s| s.status == Integrated)
+                            && status.tree_status != TreeStatus::Conflicted
+                        {
+                            ResolutionApproach::Delete
+                        } else if interactive {
+                            prompt_for_approach(&status)?
+                        } else {
+                            ResolutionApproach::Rebase
+                        };
+                    let resolution = Resolution {
+                        stack_id,
+                        approach,
+                        delete_integrated_branches: true,
+                        force_integrated_branches: vec![],
+                    };
+                    resolutions.push(resolution);
+                }
+                println!("🔄 Updating branches...");
+                Some(resolutions)
             }
-            Ok(())
         }
+    };
+
+    if let Some(resolutions) = resolutions {
+        but_api::virtual_branches::integrate_upstream(
+            project.id,
+            resolutions,
+            base_branch_resolution,
+        )?;
+    }
+    Ok(())
+}
+
+/// Return the choice of `stacks` for the stack with `stack_id` and `status`.
+fn chosen_approach<'a>(
+    stacks: &'a [(String, Approach)],
+    stack_id: StackId,
+    status: &StackStatus,
+) -> Option<&'a (String, Approach)> {
+    stacks
+        .iter()
+        .find(|(stack, _)| names_stack(stack, stack_id, status))
+}
+
+/// Return `true` if `name` is the id of the stack with `stack_id` and `status`, or the name of one of its branches.
+fn names_stack(name: &str, stack_id: StackId, status: &StackStatus) -> bool {
+    name == stack_id.to_string() || status.branch_statuses.iter().any(|bs| bs.name == name)
+}
+
+/// Fail if any of the `stacks` doesn't name exactly one of the stacks with `statuses`, if a stack is chosen more
+/// than once, or if its approach can't be used.
+fn validate_stack_choices(
+    stacks: &[(String, Approach)],
+    statuses: &[(Option<StackId>, StackStatus)],
+) -> anyhow::Result<()> {
+    let mut chosen_stacks = Vec::new();
+    for (stack, approach) in stacks {
+        let mut matches = statuses.iter().filter_map(|(stack_id, status)| {
+            let stack_id = (*stack_id)?;
+            names_stack(stack, stack_id, status).then_some((stack_id, status))
+        });
+        let Some((stack_id, status)) = matches.next() else {
+            bail!("No stack with id or branch '{stack}' needs to be updated");
+        };
+        if matches.next().is_some() {
+            bail!("'{stack}' names more than one stack, use the id of the stack instead");
+        }
+        if chosen_stacks.contains(&stack_id) {
+            bail!("The stack of '{stack}' was chosen more than once");
+        }
+        chosen_stacks.push(stack_id);
+        let approach = ResolutionApproach::from(*approach);
+        if !status.resolution_acceptable(&approach) {
+            bail!(
+                "Stack '{stack}' can't be updated with {}",
+                approach_name(approach)
+            );
+        }
+    }
+    Ok(())
+}
+
+/// If the base branch diverged from its remote, integrate the remote with `approach`, or with the approach chosen
+/// by the user if `interactive`, and return the resolution to use when updating the stacks.
+fn resolve_base_branch(
+    project: &Project,
+    approach: Option<BaseApproach>,
+    interactive: bool,
+) -> anyhow::Result<Option<BaseBranchResolution>> {
+    let diverged = but_api::virtual_branches::get_base_branch_data(project.id)?
+        .is_some_and(|base_branch| base_branch.diverged);
+    if !diverged {
+        return Ok(None);
+    }
+    let approach = match approach {
+        Some(approach) => approach,
+        None if interactive => prompt_for_base_approach()?,
+        None => bail!(
+            "The base branch diverged from its remote, choose how to update it with --base-approach"
+        ),
+    };
+    let target_commit_id =
+        but_api::virtual_branches::resolve_upstream_integration(project.id, approach.into())?;
+    Ok(Some(BaseBranchResolution {
+        target_commit_oid: gix::ObjectId::from_hex(target_commit_id.as_bytes())?.to_git2(),
+        approach: approach.into(),
+    }))
+}
+
+fn prompt_for_base_approach() -> anyhow::Result<BaseApproach> {
+    println!("The base branch diverged from its remote.");
+    print!("How should it be updated? [rebase/merge/hard-reset] (rebase): ");
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(BaseApproach::Rebase);
+    }
+    <BaseApproach as clap::ValueEnum>::from_str(input, true)
+        .map_err(|_| anyhow::anyhow!("Unknown approach '{input}'"))
+}
+
+fn prompt_for_approach(status: &StackStatus) -> anyhow::Result<ResolutionApproach> {
+    let acceptable: Vec<_> = [
+        ResolutionApproach::Rebase,
+        ResolutionApproach::Merge,
+        ResolutionApproach::Unapply,
+    ]
+    .into_iter()
+    .filter(|approach| status.resolution_acceptable(approach))
+    .collect();
+    let names = status
+        .branch_statuses
+        .iter()
+        .map(|bs| bs.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let choices = acceptable
+        .iter()
+        .map(|approach| approach_name(*approach).to_lowercase())
+        .collect::<Vec<_>>()
+        .join("/");
+    print!(
+        "How should {} be updated? [{choices}] (rebase): ",
+        names.bold()
+    );
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(ResolutionApproach::Rebase);
+    }
+    acceptable
+        .into_iter()
+        .find(|approach| approach_name(*approach).eq_ignore_ascii_case(input))
+        .ok_or_else(|| anyhow::anyhow!("Unknown approach '{input}'"))
+}
+
+fn approach_name(approach: ResolutionApproach) -> &'static str {
+    match approach {
+        ResolutionApproach::Rebase => "Rebase",
+        ResolutionApproach::Merge => "Merge",
+        ResolutionApproach::Unapply => "Unapply",
+        ResolutionApproach::Delete => "Delete",
+    }
+}
+
+fn print_approach_dry_run(outcome: &ApproachDryRun) {
+    let name = approach_name(outcome.approach);
+    let conflicted = outcome
+        .commits
+        .iter()
+        .filter(|commit| !commit.conflicted_paths.is_empty())
+        .count();
+    let summary = if conflicted == 0 {
+        "no conflicts".green()
+    } else {
+        format!("{conflicted} conflicted commit(s)").red()
+    };
+    let unavailable = if outcome.acceptable {
+        String::new()
+    } else {
+        format!(" {}", "(not available)".dimmed())
+    };
+    println!(
+        "  {name}: {} commit(s) afterwards, {}{unavailable}",
+        outcome.commit_count, summary
+    );
+    if outcome.integrated_commit_count > 0 {
+        println!(
+            "    {} integrated commit(s) would be dropped",
+            outcome.integrated_commit_count
+        );
+    }
+    for commit in &outcome.commits {
+        let id = commit.id.map_or_else(
+            || "merge commit".to_string(),
+            |id| id.to_hex_with_len(7).to_string(),
+        );
+        if !commit.conflicted_paths.is_empty() {
+            let paths = commit
+                .conflicted_paths
+                .iter()
+                .map(|path| path.to_str_lossy())
+                .collect::<Vec<_>>()
+                .join(", ");
+            println!("    {} {}: {paths}", "❗️".red(), id);
+        } else if commit.auto_resolved {
+            println!(
+                "    {} {}: resolved with recorded resolutions",
+                "🔁".yellow(),
+                id
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gitbutler_branch_actions::upstream_integration::{BranchStatus, NameAndStatus};
+
+    fn status(names: &[&str], status: impl Fn() -> BranchStatus) -> StackStatus {
+        StackStatus {
+            tree_status: TreeStatus::Empty,
+            branch_statuses: names
+                .iter()
+                .map(|name| NameAndStatus {
+                    name: name.to_string(),
+                    status: status(),
+                })
+                .collect(),
+        }
+    }
+
+    fn choice(stack: &str, approach: Approach) -> (String, Approach) {
+        (stack.to_owned(), approach)
+    }
+
+    #[test]
+    fn stack_approaches_are_parsed() {
+        assert_eq!(
+            parse_stack_approach("feature=merge"),
+            Ok(choice("feature", Approach::Merge))
+        );
+        assert_eq!(
+            parse_stack_approach("a=b=Unapply"),
+            Ok(choice("a=b", Approach::Unapply)),
+            "the approach follows the last '=', and its case is ignored"
+        );
+        assert_eq!(
+            parse_stack_approach("feature"),
+            Err("expected `<stack>=<approach>`, got 'feature'".to_owned())
+        );
+        assert_eq!(
+            parse_stack_approach("feature=squash"),
+            Err(
+                "unknown approach 'squash', expected one of rebase, merge, unapply or delete"
+                    .to_owned()
+            )
+        );
+    }
+
+    #[test]
+    fn stack_choices_must_name_a_stack_once_with_an_acceptable_approach() {
+        let (single, stacked) = (StackId::generate(), StackId::generate());
+        let statuses = vec![
+            (
+                Some(single),
+                status(&["single"], || BranchStatus::SaflyUpdatable),
+            ),
+            (
+                Some(stacked),
+                status(&["bottom", "top"], || BranchStatus::SaflyUpdatable),
+            ),
+        ];
+
+        validate_stack_choices(
+            &[
+                choice("single", Approach::Merge),
+                choice(&stacked.to_string(), Approach::Unapply),
+            ],
+            &statuses,
+        )
+        .expect("stacks are named by branch or id");
+
+        let err = |stacks: &[(String, Approach)]| {
+            validate_stack_choices(stacks, &statuses)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            err(&[choice("unknown", Approach::Rebase)]),
+            "No stack with id or branch 'unknown' needs to be updated"
+        );
+        assert_eq!(
+            err(&[choice("top", Approach::Merge)]),
+            "Stack 'top' can't be updated with Merge",
+            "only single-branch stacks can be merged"
+        );
+        assert_eq!(
+            err(&[
+                choice("bottom", Approach::Rebase),
+                choice("top", Approach::Unapply)
+            ]),
+            "The stack of 'top' was chosen more than once"
+        );
+    }
+
+    #[test]
+    fn integrated_stacks_can_only_be_unapplied_or_deleted() {
+        let stack_id = StackId::generate();
+        let statuses = vec![(
+            Some(stack_id),
+            status(&["done"], || BranchStatus::Integrated),
+        )];
+        validate_stack_choices(&[choice("done", Approach::Delete)], &statuses)
+            .expect("integrated stacks can be deleted");
+        assert_eq!(
+            validate_stack_choices(&[choice("done", Approach::Rebase)], &statuses)
+                .unwrap_err()
+                .to_string(),
+            "Stack 'done' can't be updated with Rebase"
+        );
+    }
+
+    #[test]
+    fn chosen_approach_is_found_by_id_or_branch_name() {
+        let stack_id = StackId::generate();
+        let status = status(&["bottom", "top"], || BranchStatus::SaflyUpdatable);
+        let stacks = [choice("top", Approach::Unapply)];
+        assert_eq!(
+            chosen_approach(&stacks, stack_id, &status),
+            Some(&stacks[0])
+        );
+        let stacks = [choice(&stack_id.to_string(), Approach::Rebase)];
+        assert_eq!(
+            chosen_approach(&stacks, stack_id, &status),
+            Some(&stacks[0])
+        );
+        assert_eq!(chosen_approach(&stacks, StackId::generate(), &status), None);
     }
 }
@@ -421,7 +421,7 @@ fn get_commit_message_from_editor(
     Ok(message)
 }
 
-fn get_editor_command() -> anyhow::Result<String> {
+pub(crate) fn get_editor_command() -> anyhow::Result<String> {
     // Try $EDITOR first
     if let Ok(editor) = std::env::var("EDITOR") {
         return Ok(editor);
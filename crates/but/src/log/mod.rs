@@ -1,16 +1,25 @@
+use std::collections::BTreeMap;
+
+use but_core::RepositoryExt;
 use but_graph::VirtualBranchesTomlMetadata;
+use but_rebase::commit::SignatureStatus;
 use but_settings::AppSettings;
 use but_workspace::{
     StackId, StacksFilter,
     ui::{StackDetails, StackEntry},
 };
-use colored::Colorize;
+use colored::{ColoredString, Colorize};
 use gitbutler_command_context::CommandContext;
 use gitbutler_project::Project;
+use serde::Serialize;
 
 use crate::id::CliId;
 
-pub(crate) fn commit_graph(project: &Project, json: bool) -> anyhow::Result<()> {
+pub(crate) fn commit_graph(
+    project: &Project,
+    json: bool,
+    show_signature: bool,
+) -> anyhow::Result<()> {
     let ctx = &mut CommandContext::open(project, AppSettings::load_from_default_path_creating()?)?;
     but_rules::process_rules(ctx).ok(); // TODO: this is doing double work (dependencies can be reused)
     let stacks = stacks(ctx)?
@@ -19,8 +28,26 @@ pub(crate) fn commit_graph(project: &Project, json: bool) -> anyhow::Result<()>
         .filter_map(Result::ok)
         .collect::<Vec<_>>();
 
+    let repo = ctx.gix_repo()?;
     if json {
-        return output_json(stacks.into_iter().map(|(_, stack)| stack).collect());
+        let mut output = Vec::with_capacity(stacks.len());
+        for (_, stack) in stacks {
+            let mut gerrit_reviews = BTreeMap::new();
+            for commit in stack
+                .branch_details
+                .iter()
+                .flat_map(|branch| &branch.commits)
+            {
+                if let Some(review) = gerrit_review(ctx, &repo, commit.id)? {
+                    gerrit_reviews.insert(commit.id.to_string(), review);
+                }
+            }
+            output.push(StackWithReviews {
+                stack,
+                gerrit_reviews,
+            });
+        }
+        return output_json(output);
     }
 
     let mut nesting = 0;
@@ -131,6 +158,21 @@ pub(crate) fn commit_graph(project: &Project, json: bool) -> anyhow::Result<()>
                     "│ ".repeat(nesting),
                     commit.message.to_string().lines().next().unwrap_or("")
                 );
+                if show_signature {
+                    println!(
+                        "{}│ {}",
+                        "│ ".repeat(nesting),
+                        signature_status(&repo, commit.id)
+                    );
+                }
+                if let Some(review) = gerrit_review(ctx, &repo, commit.id)? {
+                    println!(
+                        "{}│ {} {}",
+                        "│ ".repeat(nesting),
+                        format!("#{}", review.change_number).cyan(),
+                        review.review_url.underline().dimmed()
+                    );
+                }
                 if i == stacks.len() - 1 {
                     if nesting == 0 {
                         println!("│");
@@ -165,6 +207,32 @@ pub(crate) fn commit_graph(project: &Project, json: bool) -> anyhow::Result<()>
     Ok(())
 }
 
+/// Return a description of the signature of `commit_id`, colored by how trustworthy it is.
+fn signature_status(repo: &gix::Repository, commit_id: gix::ObjectId) -> ColoredString {
+    match but_rebase::commit::verify_commit(repo, commit_id) {
+        Ok(SignatureStatus::Unsigned) => "{unsigned}".dimmed(),
+        Ok(SignatureStatus::Valid {
+            signer: Some(signer),
+        }) => format!("{{signed}} by {signer}").green(),
+        Ok(SignatureStatus::Valid { signer: None }) => "{signed}".green(),
+        Ok(SignatureStatus::Invalid { reason }) => format!("{{invalid}} {reason}").red(),
+        Ok(SignatureStatus::Unverified { reason }) => format!("{{unverified}} {reason}").yellow(),
+        Err(err) => format!("{{unverified}} {err}").yellow(),
+    }
+}
+
+/// Return what Gerrit reported for `commit_id` when it was last pushed for review, if the repository is in Gerrit mode.
+pub(crate) fn gerrit_review(
+    ctx: &mut CommandContext,
+    repo: &gix::Repository,
+    commit_id: gix::ObjectId,
+) -> anyhow::Result<Option<but_db::GerritMetadata>> {
+    if !repo.git_settings()?.gitbutler_gerrit_mode.unwrap_or(false) {
+        return Ok(None);
+    }
+    but_gerrit::metadata_for_commit(ctx.db()?, repo, commit_id)
+}
+
 pub(crate) fn all_commits(ctx: &CommandContext) -> anyhow::Result<Vec<CliId>> {
     let stacks = stacks(ctx)?
         .iter()
@@ -212,7 +280,18 @@ pub(crate) fn stack_details(
     }
 }
 
-fn output_json(stacks: Vec<but_workspace::ui::StackDetails>) -> anyhow::Result<()> {
+/// The details of a stack as shown by `but log --json`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StackWithReviews {
+    #[serde(flatten)]
+    stack: StackDetails,
+    /// What Gerrit reported for the commits of the stack by commit id, if the repository is in Gerrit mode.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    gerrit_reviews: BTreeMap<String, but_db::GerritMetadata>,
+}
+
+fn output_json(stacks: Vec<StackWithReviews>) -> anyhow::Result<()> {
     let json_output = serde_json::to_string_pretty(&stacks)?;
     println!("{json_output}");
     Ok(())
@@ -20,6 +20,10 @@ mod mcp;
 mod mcp_internal;
 mod metrics;
 mod oplog;
+mod pr;
+mod push;
+mod rebase;
+mod resolve;
 mod rub;
 mod status;
 
@@ -122,7 +126,7 @@ async fn main() -> Result<()> {
                 app_settings,
                 match cmd {
                     base::Subcommands::Check => CommandName::BaseCheck,
-                    base::Subcommands::Update => CommandName::BaseUpdate,
+                    base::Subcommands::Update { .. } => CommandName::BaseUpdate,
                 },
                 props(start, &result),
             )
@@ -135,20 +139,27 @@ async fn main() -> Result<()> {
             metrics_if_configured(app_settings, CommandName::BranchNew, props(start, &result)).ok();
             Ok(())
         }
-        Subcommands::Log => {
+        Subcommands::Log { show_signature } => {
             let project = get_or_init_project(&args.current_dir)?;
-            let result = log::commit_graph(&project, args.json);
+            let result = log::commit_graph(&project, args.json, *show_signature);
             metrics_if_configured(app_settings, CommandName::Log, props(start, &result)).ok();
             Ok(())
         }
         Subcommands::Status {
             show_files,
             verbose,
+            explain,
         } => {
             let project = get_or_init_project(&args.current_dir)?;
-            let result = status::worktree(&project, args.json, *show_files, *verbose);
+            let result = match explain {
+                Some(path) => {
+                    status::explain::hunk_locks(&project, &args.current_dir, path, args.json)
+                }
+                None => status::worktree(&project, args.json, *show_files, *verbose),
+            };
             metrics_if_configured(app_settings, CommandName::Status, props(start, &result)).ok();
-            Ok(())
+            // Only explaining hunk locks fails the command, plain status keeps its exit code.
+            if explain.is_some() { result } else { Ok(()) }
         }
         Subcommands::Stf { verbose } => {
             let project = get_or_init_project(&args.current_dir)?;
@@ -166,9 +177,13 @@ async fn main() -> Result<()> {
             metrics_if_configured(app_settings, CommandName::Rub, props(start, &result)).ok();
             Ok(())
         }
-        Subcommands::Mark { target, delete } => {
+        Subcommands::Mark {
+            target,
+            delete,
+            filters,
+        } => {
             let project = get_or_init_project(&args.current_dir)?;
-            let result = mark::handle(&project, args.json, target, *delete)
+            let result = mark::handle(&project, args.json, target, *delete, filters)
                 .context("Can't mark this. Taaaa-na-na-na. Can't mark this.");
             if let Err(e) = &result {
                 eprintln!("{} {}", e, e.root_cause());
@@ -214,9 +229,132 @@ async fn main() -> Result<()> {
             metrics_if_configured(app_settings, CommandName::Describe, props(start, &result)).ok();
             result
         }
-        Subcommands::Oplog { since } => {
+        Subcommands::Rebase { branch } => {
+            let project = get_or_init_project(&args.current_dir)?;
+            let result = rebase::interactive(&project, args.json, branch);
+            metrics_if_configured(app_settings, CommandName::Rebase, props(start, &result)).ok();
+            result
+        }
+        Subcommands::Resolve {
+            cmd: Some(resolve::Subcommands::Take { side, path, hunk }),
+            ..
+        } => {
+            let project = get_or_init_project(&args.current_dir)?;
+            let result = resolve::take(&project, args.json, *side, path, *hunk);
+            metrics_if_configured(
+                app_settings,
+                CommandName::ResolveTake,
+                props(start, &result),
+            )
+            .ok();
+            result
+        }
+        Subcommands::Resolve {
+            cmd: Some(resolve::Subcommands::Markers { path }),
+            ..
+        } => {
+            let project = get_or_init_project(&args.current_dir)?;
+            let result = resolve::markers(&project, args.json, path);
+            metrics_if_configured(
+                app_settings,
+                CommandName::ResolveMarkers,
+                props(start, &result),
+            )
+            .ok();
+            result
+        }
+        Subcommands::Resolve {
+            cmd: Some(resolve::Subcommands::Finish { force }),
+            ..
+        } => {
+            let project = get_or_init_project(&args.current_dir)?;
+            let result = resolve::finish(&project, args.json, *force);
+            metrics_if_configured(
+                app_settings,
+                CommandName::ResolveFinish,
+                props(start, &result),
+            )
+            .ok();
+            result
+        }
+        Subcommands::Resolve {
+            cmd: Some(resolve::Subcommands::Abort),
+            ..
+        } => {
+            let project = get_or_init_project(&args.current_dir)?;
+            let result = resolve::abort(&project, args.json);
+            metrics_if_configured(
+                app_settings,
+                CommandName::ResolveAbort,
+                props(start, &result),
+            )
+            .ok();
+            result
+        }
+        Subcommands::Resolve { cmd: None, commit } => {
             let project = get_or_init_project(&args.current_dir)?;
-            let result = oplog::show_oplog(&project, args.json, since.as_deref());
+            let result = match commit {
+                Some(commit) => resolve::start(&project, args.json, commit),
+                None => resolve::list(&project, args.json),
+            };
+            metrics_if_configured(app_settings, CommandName::Resolve, props(start, &result)).ok();
+            result
+        }
+        Subcommands::Oplog {
+            cmd: Some(oplog::Subcommands::Gc),
+            ..
+        } => {
+            let project = get_or_init_project(&args.current_dir)?;
+            let result = oplog::gc(&project, args.json);
+            metrics_if_configured(app_settings, CommandName::OplogGc, props(start, &result)).ok();
+            result
+        }
+        Subcommands::Oplog {
+            cmd: Some(oplog::Subcommands::Export { output }),
+            ..
+        } => {
+            let project = get_or_init_project(&args.current_dir)?;
+            let result = oplog::export(&project, args.json, output);
+            metrics_if_configured(
+                app_settings,
+                CommandName::OplogExport,
+                props(start, &result),
+            )
+            .ok();
+            result
+        }
+        Subcommands::Oplog {
+            cmd: Some(oplog::Subcommands::Import { bundle, directory }),
+            ..
+        } => {
+            let result = oplog::import(bundle, directory, args.json);
+            metrics_if_configured(
+                app_settings,
+                CommandName::OplogImport,
+                props(start, &result),
+            )
+            .ok();
+            result
+        }
+        Subcommands::Oplog {
+            cmd: None,
+            since,
+            after,
+            before,
+            kinds,
+            path,
+            trailer,
+            limit,
+        } => {
+            let project = get_or_init_project(&args.current_dir)?;
+            let filter = oplog::Filter {
+                after: after.as_deref(),
+                before: before.as_deref(),
+                kinds,
+                path: path.as_deref(),
+                trailer: trailer.as_deref(),
+            };
+            let result = oplog::show_oplog(&project, args.json, since.as_deref(), &filter, *limit);
             metrics_if_configured(app_settings, CommandName::Oplog, props(start, &result)).ok();
             result
         }
@@ -232,6 +370,49 @@ async fn main() -> Result<()> {
             metrics_if_configured(app_settings, CommandName::Undo, props(start, &result)).ok();
             result
         }
+        Subcommands::Redo => {
+            let project = get_or_init_project(&args.current_dir)?;
+            let result = oplog::redo_last_undo(&project, args.json);
+            metrics_if_configured(app_settings, CommandName::Redo, props(start, &result)).ok();
+            result
+        }
+        Subcommands::Push {
+            branch,
+            skip_force_push_protection,
+            no_verify,
+        } => {
+            let project = get_or_init_project(&args.current_dir)?;
+            let result = push::push(
+                &project,
+                args.json,
+                branch.as_deref(),
+                *skip_force_push_protection,
+                *no_verify,
+            );
+            metrics_if_configured(app_settings, CommandName::Push, props(start, &result)).ok();
+            result
+        }
+        Subcommands::Pr {
+            branch,
+            number,
+            draft,
+            skip_force_push_protection,
+            no_verify,
+        } => {
+            let project = get_or_init_project(&args.current_dir)?;
+            let result = pr::handle(
+                &project,
+                args.json,
+                branch.as_deref(),
+                *number,
+                *draft,
+                *skip_force_push_protection,
+                *no_verify,
+            )
+            .await;
+            metrics_if_configured(app_settings, CommandName::Pr, props(start, &result)).ok();
+            result
+        }
         Subcommands::Init { repo } => init::repo(&args.current_dir, args.json, *repo)
             .context("Failed to initialize GitButler project."),
     }
@@ -281,11 +462,14 @@ fn print_grouped_help() {
         ("Inspection".yellow(), vec!["log", "status"]),
         (
             "Stack Operation".yellow(),
-            vec!["commit", "rub", "new", "describe", "branch"],
+            vec![
+                "commit", "rub", "new", "describe", "branch", "rebase", "resolve",
+            ],
         ),
+        ("Remote".yellow(), vec!["push", "pr"]),
         (
             "Operation History".yellow(),
-            vec!["oplog", "undo", "restore"],
+            vec!["oplog", "undo", "redo", "restore"],
         ),
     ];
 
@@ -8,11 +8,55 @@ use but_workspace::StackId;
 use gitbutler_command_context::CommandContext;
 use gitbutler_commit::commit_ext::CommitExt;
 use gitbutler_project::Project;
+
+/// Limits to the changes a mark applies to. Changes matching any of them are marked, and all changes are marked
+/// if none is given.
+#[derive(Debug, Default, clap::Args)]
+pub struct Filters {
+    /// Only mark changes to paths matching this pattern, as used in `.gitignore` files, like `docs/**/*.md`.
+    #[clap(long)]
+    glob: Option<String>,
+    /// Only mark changes to files owned by this owner according to `CODEOWNERS`, like `@org/team`.
+    #[clap(long)]
+    owner: Option<String>,
+    /// Only mark hunks with at least this many added or removed lines.
+    #[clap(long)]
+    min_lines: Option<usize>,
+    /// Only mark hunks with at most this many added or removed lines.
+    #[clap(long)]
+    max_lines: Option<usize>,
+}
+
+impl Filters {
+    fn to_rule_filters(&self) -> anyhow::Result<Vec<but_rules::Filter>> {
+        let mut filters = Vec::new();
+        if let Some(glob) = &self.glob {
+            filters.push(but_rules::Filter::PathMatchesGlob(glob.clone()));
+        }
+        if let Some(owner) = &self.owner {
+            filters.push(but_rules::Filter::CodeOwner(owner.clone()));
+        }
+        if let Some(min) = self.min_lines {
+            filters.push(but_rules::Filter::HunkSizeAtLeast(min));
+        }
+        if let Some(max) = self.max_lines {
+            filters.push(but_rules::Filter::HunkSizeAtMost(max));
+        }
+        if filters.is_empty() {
+            filters.push(but_rules::Filter::PathMatchesRegex(regex::Regex::new(
+                ".*",
+            )?));
+        }
+        Ok(filters)
+    }
+}
+
 pub(crate) fn handle(
     project: &Project,
     _json: bool,
     target_str: &str,
     delete: bool,
+    filters: &Filters,
 ) -> anyhow::Result<()> {
     let ctx = &mut CommandContext::open(project, AppSettings::load_from_default_path_creating()?)?;
     let target_result = crate::id::CliId::from_str(ctx, target_str)?;
@@ -28,13 +72,18 @@ pub(crate) fn handle(
         but_rules::delete_rule(ctx, &rule.id())?;
     }
     match target_result[0].clone() {
-        crate::id::CliId::Branch { name } => mark_branch(ctx, name, delete),
-        crate::id::CliId::Commit { oid } => mark_commit(ctx, oid, delete),
+        crate::id::CliId::Branch { name } => mark_branch(ctx, name, delete, filters),
+        crate::id::CliId::Commit { oid } => mark_commit(ctx, oid, delete, filters),
         _ => bail!("Nope"),
     }
 }
 
-fn mark_commit(ctx: &mut CommandContext, oid: gix::ObjectId, delete: bool) -> anyhow::Result<()> {
+fn mark_commit(
+    ctx: &mut CommandContext,
+    oid: gix::ObjectId,
+    delete: bool,
+    filters: &Filters,
+) -> anyhow::Result<()> {
     if delete {
         let rules = but_rules::list_rules(ctx)?;
         for rule in rules {
@@ -53,9 +102,7 @@ fn mark_commit(ctx: &mut CommandContext, oid: gix::ObjectId, delete: bool) -> an
     let action = but_rules::Action::Explicit(Operation::Amend { change_id });
     let req = but_rules::CreateRuleRequest {
         trigger: but_rules::Trigger::FileSytemChange,
-        filters: vec![but_rules::Filter::PathMatchesRegex(regex::Regex::new(
-            ".*",
-        )?)],
+        filters: filters.to_rule_filters()?,
         action,
     };
     but_rules::create_rule(ctx, req)?;
@@ -63,7 +110,12 @@ fn mark_commit(ctx: &mut CommandContext, oid: gix::ObjectId, delete: bool) -> an
     Ok(())
 }
 
-fn mark_branch(ctx: &mut CommandContext, branch_name: String, delete: bool) -> anyhow::Result<()> {
+fn mark_branch(
+    ctx: &mut CommandContext,
+    branch_name: String,
+    delete: bool,
+    filters: &Filters,
+) -> anyhow::Result<()> {
     let stack_id = branch_name_to_stack_id(ctx, Some(&branch_name))?;
     if delete {
         let rules = but_rules::list_rules(ctx)?;
@@ -82,9 +134,7 @@ fn mark_branch(ctx: &mut CommandContext, branch_name: String, delete: bool) -> a
     });
     let req = but_rules::CreateRuleRequest {
         trigger: but_rules::Trigger::FileSytemChange,
-        filters: vec![but_rules::Filter::PathMatchesRegex(regex::Regex::new(
-            ".*",
-        )?)],
+        filters: filters.to_rule_filters()?,
         action,
     };
     but_rules::create_rule(ctx, req)?;
@@ -1,43 +1,91 @@
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use but_settings::AppSettings;
 use colored::Colorize;
-use gitbutler_oplog::entry::OperationKind;
+use gitbutler_command_context::CommandContext;
+use gitbutler_oplog::{
+    SnapshotQuery,
+    entry::{OperationKind, Snapshot},
+};
 use gitbutler_project::Project;
 
-pub(crate) fn show_oplog(project: &Project, json: bool, since: Option<&str>) -> anyhow::Result<()> {
-    let snapshots = if let Some(since_sha) = since {
+#[derive(Debug, clap::Subcommand)]
+pub enum Subcommands {
+    /// Remove old snapshots according to the retention policy of the project, and delete the objects only they used.
+    Gc,
+    /// Write all snapshots along with the GitButler state into a git bundle, to reproduce the workspace elsewhere.
+    Export {
+        /// The file to write the bundle to.
+        output: PathBuf,
+    },
+    /// Set up a new repository from a bundle created with `but oplog export`, to restore its snapshots there.
+    Import {
+        /// The bundle to import.
+        bundle: PathBuf,
+        /// The directory to create the repository in, which must not exist or be empty.
+        directory: PathBuf,
+    },
+}
+
+/// The criteria to select operations by, as given on the command-line.
+pub(crate) struct Filter<'a> {
+    pub after: Option<&'a str>,
+    pub before: Option<&'a str>,
+    pub kinds: &'a [String],
+    pub path: Option<&'a str>,
+    pub trailer: Option<&'a str>,
+}
+
+impl Filter<'_> {
+    fn to_query(&self) -> anyhow::Result<SnapshotQuery> {
+        Ok(SnapshotQuery {
+            since: self.after.map(parse_time).transpose()?,
+            until: self.before.map(parse_time).transpose()?,
+            kinds: self
+                .kinds
+                .iter()
+                .map(|kind| parse_kind(kind))
+                .collect::<anyhow::Result<_>>()?,
+            path: self.path.map(ToOwned::to_owned),
+            trailer: self.trailer.map(|trailer| match trailer.split_once('=') {
+                Some((key, value)) => (key.trim().to_owned(), Some(value.trim().to_owned())),
+                None => (trailer.trim().to_owned(), None),
+            }),
+        })
+    }
+}
+
+pub(crate) fn show_oplog(
+    project: &Project,
+    json: bool,
+    since: Option<&str>,
+    filter: &Filter<'_>,
+    limit: usize,
+) -> anyhow::Result<()> {
+    let mut query = filter.to_query()?;
+    if let Some(from_sha) = since {
         // Get all snapshots first to find the starting point
         let all_snapshots = but_api::undo::list_snapshots(project.id, 1000, None, None)?; // Get a large number to find the SHA
-        let mut found_index = None;
-
-        // Find the snapshot that matches the since SHA (partial match supported)
-        for (index, snapshot) in all_snapshots.iter().enumerate() {
-            let snapshot_sha = snapshot.commit_id.to_string();
-            if snapshot_sha.starts_with(since_sha) {
-                found_index = Some(index);
-                break;
-            }
-        }
 
-        match found_index {
-            Some(index) => {
-                // Take 20 entries starting from the found index
-                all_snapshots.into_iter().skip(index).take(20).collect()
-            }
-            None => {
-                return Err(anyhow::anyhow!(
-                    "No oplog entry found matching SHA: {}",
-                    since_sha
-                ));
-            }
-        }
-    } else {
-        but_api::undo::list_snapshots(project.id, 20, None, None)?
-    };
+        // Find the snapshot that matches the from SHA (partial match supported)
+        let from_snapshot = all_snapshots
+            .iter()
+            .find(|snapshot| snapshot.commit_id.to_string().starts_with(from_sha))
+            .ok_or_else(|| anyhow::anyhow!("No oplog entry found matching SHA: {}", from_sha))?;
+        // Nothing after the starting point is shown.
+        let from_time = from_snapshot.created_at.seconds();
+        query.until = Some(query.until.map_or(from_time, |until| until.min(from_time)));
+    }
+    let snapshots = but_api::undo::query_snapshots(project.id, query, limit)?;
 
     if snapshots.is_empty() {
         if json {
             println!("[]");
         } else {
-            println!("No operations found in history.");
+            println!("No matching operations found in history.");
         }
         return Ok(());
     }
@@ -113,6 +161,73 @@ pub(crate) fn show_oplog(project: &Project, json: bool, since: Option<&str>) ->
     Ok(())
 }
 
+pub(crate) fn gc(project: &Project, json: bool) -> anyhow::Result<()> {
+    let outcome = but_api::undo::compact_oplog(project.id)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&outcome)?);
+        return Ok(());
+    }
+    println!(
+        "Removed {} of {} snapshots and {} of their objects.",
+        outcome.snapshots_removed.to_string().green().bold(),
+        outcome.snapshots_before,
+        human_bytes(outcome.bytes_removed).green().bold()
+    );
+    Ok(())
+}
+
+pub(crate) fn export(project: &Project, json: bool, output: &Path) -> anyhow::Result<()> {
+    let mut ctx = CommandContext::open(project, AppSettings::load_from_default_path_creating()?)?;
+    let guard = project.shared_worktree_access();
+    gitbutler_oplog::export_bundle(&mut ctx, output, guard.read_permission())?;
+    if json {
+        println!("{}", serde_json::json!({ "bundle": output }));
+        return Ok(());
+    }
+    println!(
+        "{} Exported the oplog to {}",
+        "✓".green().bold(),
+        output.display().to_string().cyan()
+    );
+    Ok(())
+}
+
+pub(crate) fn import(bundle: &Path, directory: &Path, json: bool) -> anyhow::Result<()> {
+    let oplog_head = gitbutler_oplog::import_bundle(bundle, directory)?;
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({ "directory": directory, "oplogHead": oplog_head.to_string() })
+        );
+        return Ok(());
+    }
+    println!(
+        "{} Imported the oplog into {}",
+        "✓".green().bold(),
+        directory.display().to_string().cyan()
+    );
+    println!(
+        "  Run {} there to list its snapshots, and {} to restore one.",
+        "but oplog".bold(),
+        "but restore <sha>".bold()
+    );
+    Ok(())
+}
+
+fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
 pub(crate) fn restore_to_oplog(
     project: &Project,
     _json: bool,
@@ -193,16 +308,24 @@ pub(crate) fn restore_to_oplog(
 }
 
 pub(crate) fn undo_last_operation(project: &Project, _json: bool) -> anyhow::Result<()> {
-    // Get the last two snapshots - restore to the second one back
-    let snapshots = but_api::undo::list_snapshots(project.id, 2, None, None)?;
-
-    if snapshots.len() < 2 {
+    println!("{}", "Undoing operation...".blue().bold());
+    let Some(target_snapshot) = but_api::undo::undo_operation(project.id)? else {
         println!("{}", "No previous operations to undo.".yellow());
         return Ok(());
-    }
+    };
+    print_restored(&target_snapshot, "Undo")
+}
 
-    let target_snapshot = &snapshots[1];
+pub(crate) fn redo_last_undo(project: &Project, _json: bool) -> anyhow::Result<()> {
+    println!("{}", "Redoing operation...".blue().bold());
+    let Some(target_snapshot) = but_api::undo::redo_operation(project.id)? else {
+        println!("{}", "No undone operations to redo.".yellow());
+        return Ok(());
+    };
+    print_restored(&target_snapshot, "Redo")
+}
 
+fn print_restored(target_snapshot: &Snapshot, action: &str) -> anyhow::Result<()> {
     let target_operation = target_snapshot
         .details
         .as_ref()
@@ -214,16 +337,12 @@ pub(crate) fn undo_last_operation(project: &Project, _json: bool) -> anyhow::Res
         .format("%Y-%m-%d %H:%M:%S")
         .to_string();
 
-    println!("{}", "Undoing operation...".blue().bold());
     println!(
-        "  Reverting to: {} ({})",
+        "  Reverted to: {} ({})",
         target_operation.green(),
         target_time.dimmed()
     );
 
-    // Restore to the previous snapshot using the but_api
-    but_api::undo::restore_snapshot(project.id, target_snapshot.commit_id.to_string())?;
-
     let restore_commit_short = format!(
         "{}{}",
         &target_snapshot.commit_id.to_string()[..7]
@@ -233,10 +352,55 @@ pub(crate) fn undo_last_operation(project: &Project, _json: bool) -> anyhow::Res
     );
 
     println!(
-        "{} Undo completed successfully! Restored to snapshot: {}",
+        "{} {action} completed successfully! Restored to snapshot: {}",
         "✓".green().bold(),
         restore_commit_short
     );
 
     Ok(())
 }
+
+/// Parse `kind` as operation kind, either in kebab-case like `squash-commit` or as named in the oplog, like `SquashCommit`.
+fn parse_kind(kind: &str) -> anyhow::Result<OperationKind> {
+    let name: String = kind
+        .split(['-', '_'])
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect();
+    OperationKind::from_str(&name).map_err(|_| anyhow::anyhow!("Unknown operation kind: {kind}"))
+}
+
+/// Parse `value` as point in time, either relative to now like `2h`, or as date like `2025-01-31`,
+/// and return it as seconds since the Unix epoch.
+fn parse_time(value: &str) -> anyhow::Result<i64> {
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return date
+            .and_hms_opt(0, 0, 0)
+            .and_then(|midnight| midnight.and_local_timezone(chrono::Local).earliest())
+            .map(|time| time.timestamp())
+            .ok_or_else(|| anyhow::anyhow!("Could not determine the start of {value}"));
+    }
+    let unit_start = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(unit_start);
+    let amount: i64 = amount.parse().map_err(|_| {
+        anyhow::anyhow!("Invalid time '{value}', expected something like '2h' or '2025-01-31'")
+    })?;
+    let unit_seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => anyhow::bail!(
+            "Invalid time unit in '{value}', expected one of 's', 'm', 'h', 'd' or 'w'"
+        ),
+    };
+    Ok(chrono::Local::now().timestamp() - amount * unit_seconds)
+}
@@ -0,0 +1,503 @@
+use std::str::FromStr;
+
+use anyhow::{Context, Result, bail};
+use bstr::{BStr, ByteSlice};
+use but_settings::AppSettings;
+use but_workspace::StackId;
+use colored::Colorize;
+use gitbutler_command_context::CommandContext;
+use gitbutler_forge::forge::{
+    AnyForge, CreateReviewRequest, Forge, ForgeName, Review, ReviewStatus, forge_for_name,
+};
+use gitbutler_project::Project;
+use serde::Serialize;
+
+/// The environment variable holding the token to authenticate with at the forge.
+const TOKEN_VAR: &str = "BUT_FORGE_TOKEN";
+
+/// What happened to the review of a branch on the forge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum ReviewChange {
+    /// A new review was opened.
+    Created,
+    /// The description and base of an existing review were updated.
+    Updated,
+}
+
+/// The review state of a single branch after its stack was pushed.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BranchReview {
+    pub stack_id: StackId,
+    /// The name of the branch the review is for.
+    pub branch: String,
+    /// The name of the branch the review should be merged into, which is the branch below it in the stack
+    /// or the target branch for the bottom-most branch.
+    pub base: String,
+    /// The number of the review as known to the forge, if there is one.
+    pub pr_number: Option<usize>,
+    /// A link to the review, or `None` if the forge of the push remote isn't supported.
+    pub url: Option<String>,
+    /// What was done to the review on the forge, or `None` if the forge of the push remote isn't supported.
+    pub change: Option<ReviewChange>,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn handle(
+    project: &Project,
+    json: bool,
+    branch: Option<&str>,
+    number: Option<usize>,
+    draft: bool,
+    skip_force_push_protection: bool,
+    no_verify: bool,
+) -> Result<()> {
+    let ctx = &mut CommandContext::open(project, AppSettings::load_from_default_path_creating()?)?;
+    let targets = crate::push::stacks_to_push(ctx, branch)?;
+
+    if let Some(number) = number {
+        let [(stack_id, branch_name)] = targets.as_slice() else {
+            bail!("A single branch must be given to use an existing review for");
+        };
+        // The review is updated on the forge below, which fails if there is no review with this number.
+        but_api::stack::update_branch_pr_number(
+            project.id,
+            *stack_id,
+            branch_name.clone(),
+            Some(number),
+        )?;
+    }
+
+    let mut reviews = Vec::new();
+    for (stack_id, branch_limit) in targets {
+        if !json {
+            println!("⏫ Pushing {}...", branch_limit.green().bold());
+        }
+        let outcome = crate::push::push_stack(
+            project,
+            stack_id,
+            branch_limit.clone(),
+            skip_force_push_protection,
+            no_verify,
+        )?;
+        let forge = forge(ctx, &outcome.remote)?;
+        reviews.extend(
+            sync_branch_reviews(project, ctx, stack_id, &branch_limit, forge.as_ref(), draft)
+                .await?,
+        );
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reviews)?);
+        return Ok(());
+    }
+
+    for review in &reviews {
+        let state = match (review.pr_number, review.change) {
+            (Some(number), Some(ReviewChange::Created)) => format!("#{number} created").green(),
+            (Some(number), _) => format!("#{number}").cyan(),
+            (None, _) => "unknown forge".yellow(),
+        };
+        println!(
+            "{} {} → {}",
+            state,
+            review.branch.green().bold(),
+            review.base.dimmed()
+        );
+        if let Some(url) = &review.url {
+            println!("   {}", url.underline());
+        }
+    }
+    Ok(())
+}
+
+/// Create or update the review of each branch of `stack_id` from the bottom of the stack up to and including
+/// `branch_limit` on `forge`, and record the numbers of newly created reviews.
+async fn sync_branch_reviews(
+    project: &Project,
+    ctx: &CommandContext,
+    stack_id: StackId,
+    branch_limit: &str,
+    forge: Option<&AnyForge>,
+    draft: bool,
+) -> Result<Vec<BranchReview>> {
+    let target =
+        gitbutler_stack::VirtualBranchesHandle::new(ctx.project().gb_dir()).get_default_target()?;
+    let details = crate::log::stack_details(ctx, stack_id)?;
+
+    let mut base = target.branch.branch().to_owned();
+    let mut reviews = Vec::new();
+    // Branch details are ordered from the top of the stack to the bottom.
+    for branch in details.branch_details.iter().rev() {
+        let name = branch.name.to_string();
+        let mut review = BranchReview {
+            stack_id,
+            branch: name.clone(),
+            base: std::mem::replace(&mut base, name.clone()),
+            pr_number: branch.pr_number,
+            url: None,
+            change: None,
+        };
+        if let Some(forge) = forge {
+            // Commits are ordered from the top of the branch to the bottom.
+            let messages: Vec<&BStr> = branch
+                .commits
+                .iter()
+                .rev()
+                .map(|commit| commit.message.as_bstr())
+                .collect();
+            let (title, body) = title_and_body(&name, branch.description.as_deref(), &messages);
+            let (pr, change) = create_or_update_review(
+                forge,
+                &CreateReviewRequest {
+                    title,
+                    body,
+                    head: name.clone(),
+                    base: review.base.clone(),
+                    draft,
+                },
+                branch.pr_number.map(|number| number as u64),
+            )
+            .await
+            .with_context(|| format!("Could not create or update the review of '{name}'"))?;
+            let number = pr.number as usize;
+            if branch.pr_number != Some(number) {
+                but_api::stack::update_branch_pr_number(
+                    project.id,
+                    stack_id,
+                    name.clone(),
+                    Some(number),
+                )?;
+            }
+            review.pr_number = Some(number);
+            review.url = Some(pr.url);
+            review.change = Some(change);
+        }
+        reviews.push(review);
+        if name == branch_limit {
+            break;
+        }
+    }
+    Ok(reviews)
+}
+
+/// Update the description and base of the review for `request.head` to `request.body` and `request.base`,
+/// or open a new review as described by `request` if there is no open one.
+///
+/// The review is the one with `number` if it is known and still open, or any open review for `request.head`
+/// on `forge` otherwise.
+async fn create_or_update_review(
+    forge: &impl Forge,
+    request: &CreateReviewRequest,
+    number: Option<u64>,
+) -> Result<(Review, ReviewChange)> {
+    let is_open = |status: ReviewStatus| matches!(status, ReviewStatus::Open | ReviewStatus::Draft);
+    let number = match number {
+        Some(number) if is_open(forge.review_status(number).await?) => Some(number),
+        // Closed and merged reviews are left alone, and the branch gets a new one.
+        _ => forge
+            .list_reviews_for_branch(&request.head)
+            .await?
+            .into_iter()
+            .find(|review| is_open(review.status))
+            .map(|review| review.number),
+    };
+    Ok(match number {
+        Some(number) => (
+            forge
+                .update_review(number, &request.body, &request.base)
+                .await?,
+            ReviewChange::Updated,
+        ),
+        None => (forge.create_review(request).await?, ReviewChange::Created),
+    })
+}
+
+/// Return the title and description of the review for the branch `name` with `description`, and the
+/// commit `messages` ordered from the bottom of the branch to the top.
+///
+/// A branch with a single commit uses its message, like forges do. Otherwise the title is the branch name,
+/// and the description lists the commit titles unless the branch has a description of its own.
+fn title_and_body(name: &str, description: Option<&str>, messages: &[&BStr]) -> (String, String) {
+    if let [message] = messages {
+        let message = message.to_str_lossy();
+        let (title, body) = message.split_once('\n').unwrap_or((message.as_ref(), ""));
+        let body = description.unwrap_or(body.trim());
+        return (title.trim().to_owned(), body.to_owned());
+    }
+    let body = match description {
+        Some(description) => description.to_owned(),
+        None => messages
+            .iter()
+            .map(|message| {
+                let title = message.lines().next().unwrap_or_default();
+                format!("- {}", title.to_str_lossy())
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    };
+    (name.to_owned(), body)
+}
+
+/// Return the forge hosting the repository behind `remote_name`, if it's supported, as configured by the
+/// forge override of the project or determined by the host name.
+///
+/// The token is read from the `BUT_FORGE_TOKEN` environment variable, and falls back to the GitHub token
+/// of the logged-in user for repositories on GitHub.
+fn forge(ctx: &CommandContext, remote_name: &str) -> Result<Option<AnyForge>> {
+    let repo = ctx.repo();
+    let remote = repo.find_remote(remote_name)?;
+    let url = remote
+        .url()
+        .with_context(|| format!("Remote named {remote_name} didn't have a URL"))?;
+    let url = gitbutler_url::Url::from_str(url)?;
+    let Some(name) = ctx
+        .project()
+        .forge_override
+        .as_deref()
+        .and_then(ForgeName::from_override)
+        .or_else(|| ForgeName::from_remote_url(&url))
+    else {
+        return Ok(None);
+    };
+    let token = match std::env::var(TOKEN_VAR).ok() {
+        Some(token) => Some(token),
+        None if name == ForgeName::GitHub => {
+            but_api::users::get_user()?.and_then(|user| user.github_access_token)
+        }
+        None => None,
+    };
+    // Remotes we can't make sense of merely go without reviews.
+    Ok(forge_for_name(name, &url, token).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    /// A forge that keeps its reviews in memory.
+    #[derive(Default)]
+    struct InMemoryForge {
+        reviews: RefCell<Vec<Review>>,
+    }
+
+    impl InMemoryForge {
+        fn with_review(number: u64, head: &str, status: ReviewStatus) -> Self {
+            let forge = InMemoryForge::default();
+            forge.reviews.borrow_mut().push(Review {
+                number,
+                title: "existing".into(),
+                body: None,
+                head: head.into(),
+                base: "main".into(),
+                url: forge.review_url(number),
+                status,
+            });
+            forge
+        }
+    }
+
+    impl Forge for InMemoryForge {
+        fn name(&self) -> ForgeName {
+            ForgeName::GitHub
+        }
+
+        fn review_url(&self, number: u64) -> String {
+            format!("https://forge/pull/{number}")
+        }
+
+        fn create_review_url(&self, head: &str, base: &str) -> String {
+            format!("https://forge/compare/{base}...{head}")
+        }
+
+        async fn create_review(&self, request: &CreateReviewRequest) -> Result<Review> {
+            let number = self.reviews.borrow().len() as u64 + 1;
+            let review = Review {
+                number,
+                title: request.title.clone(),
+                body: Some(request.body.clone()),
+                head: request.head.clone(),
+                base: request.base.clone(),
+                url: self.review_url(number),
+                status: if request.draft {
+                    ReviewStatus::Draft
+                } else {
+                    ReviewStatus::Open
+                },
+            };
+            self.reviews.borrow_mut().push(review.clone());
+            Ok(review)
+        }
+
+        async fn update_review(&self, number: u64, body: &str, base: &str) -> Result<Review> {
+            let mut reviews = self.reviews.borrow_mut();
+            let review = reviews
+                .iter_mut()
+                .find(|review| review.number == number)
+                .context("no such review")?;
+            review.body = Some(body.to_owned());
+            review.base = base.to_owned();
+            Ok(review.clone())
+        }
+
+        async fn list_reviews_for_branch(&self, head: &str) -> Result<Vec<Review>> {
+            Ok(self
+                .reviews
+                .borrow()
+                .iter()
+                .filter(|review| review.head == head)
+                .cloned()
+                .collect())
+        }
+
+        async fn review_status(&self, number: u64) -> Result<ReviewStatus> {
+            self.reviews
+                .borrow()
+                .iter()
+                .find(|review| review.number == number)
+                .map(|review| review.status)
+                .context("no such review")
+        }
+    }
+
+    fn request(head: &str) -> CreateReviewRequest {
+        CreateReviewRequest {
+            title: "title".into(),
+            body: "body".into(),
+            head: head.into(),
+            base: "main".into(),
+            draft: false,
+        }
+    }
+
+    fn request_with_base(head: &str, base: &str) -> CreateReviewRequest {
+        CreateReviewRequest {
+            base: base.into(),
+            ..request(head)
+        }
+    }
+
+    fn block_on<T>(future: impl Future<Output = T>) -> T {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .expect("runtime can be created")
+            .block_on(future)
+    }
+
+    #[test]
+    fn review_is_created_if_there_is_none() -> Result<()> {
+        let forge = InMemoryForge::with_review(1, "other", ReviewStatus::Open);
+        let (review, change) =
+            block_on(create_or_update_review(&forge, &request("feature"), None))?;
+        assert_eq!(change, ReviewChange::Created);
+        assert_eq!(review.number, 2);
+        assert_eq!(review.head, "feature");
+        assert_eq!(forge.reviews.borrow().len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn open_review_of_the_branch_is_updated() -> Result<()> {
+        let forge = InMemoryForge::with_review(1, "feature", ReviewStatus::Draft);
+        let (review, change) =
+            block_on(create_or_update_review(&forge, &request("feature"), None))?;
+        assert_eq!(change, ReviewChange::Updated);
+        assert_eq!(review.number, 1);
+        assert_eq!(review.body.as_deref(), Some("body"));
+        assert_eq!(forge.reviews.borrow().len(), 1, "no new review was opened");
+        Ok(())
+    }
+
+    #[test]
+    fn closed_reviews_of_the_branch_are_not_reused() -> Result<()> {
+        let forge = InMemoryForge::with_review(1, "feature", ReviewStatus::Merged);
+        let (review, change) =
+            block_on(create_or_update_review(&forge, &request("feature"), None))?;
+        assert_eq!(change, ReviewChange::Created);
+        assert_eq!(review.number, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn known_review_number_is_updated() -> Result<()> {
+        let forge = InMemoryForge::with_review(7, "renamed", ReviewStatus::Open);
+        let (review, change) = block_on(create_or_update_review(
+            &forge,
+            &request("feature"),
+            Some(7),
+        ))?;
+        assert_eq!(change, ReviewChange::Updated);
+        assert_eq!(review.number, 7);
+
+        let err = block_on(create_or_update_review(
+            &forge,
+            &request("feature"),
+            Some(8),
+        ))
+        .unwrap_err();
+        assert_eq!(err.to_string(), "no such review");
+        Ok(())
+    }
+
+    #[test]
+    fn known_review_number_of_a_closed_review_is_replaced() -> Result<()> {
+        for status in [ReviewStatus::Closed, ReviewStatus::Merged] {
+            let forge = InMemoryForge::with_review(7, "feature", status);
+            let (review, change) = block_on(create_or_update_review(
+                &forge,
+                &request("feature"),
+                Some(7),
+            ))?;
+            assert_eq!(change, ReviewChange::Created, "{status:?}");
+            assert_eq!(review.number, 2);
+            assert_eq!(
+                forge.reviews.borrow()[0].body,
+                None,
+                "the {status:?} review is left alone"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn updated_reviews_get_the_new_base() -> Result<()> {
+        let forge = InMemoryForge::with_review(7, "feature", ReviewStatus::Open);
+        let (review, change) = block_on(create_or_update_review(
+            &forge,
+            &request_with_base("feature", "develop"),
+            Some(7),
+        ))?;
+        assert_eq!(change, ReviewChange::Updated);
+        assert_eq!(review.base, "develop", "the target branch changed");
+        Ok(())
+    }
+
+    #[test]
+    fn title_and_body_of_single_commit_branch_come_from_the_message() {
+        let messages = [BStr::new("Add feature\n\nThe details.\n")];
+        assert_eq!(
+            title_and_body("feature", None, &messages),
+            ("Add feature".to_owned(), "The details.".to_owned())
+        );
+        assert_eq!(
+            title_and_body("feature", Some("Described"), &messages),
+            ("Add feature".to_owned(), "Described".to_owned())
+        );
+    }
+
+    #[test]
+    fn title_and_body_of_multi_commit_branch_list_the_commits() {
+        let messages = [BStr::new("first\n\nbody"), BStr::new("second")];
+        assert_eq!(
+            title_and_body("feature", None, &messages),
+            ("feature".to_owned(), "- first\n- second".to_owned())
+        );
+        assert_eq!(
+            title_and_body("feature", Some("Described"), &messages),
+            ("feature".to_owned(), "Described".to_owned())
+        );
+    }
+}
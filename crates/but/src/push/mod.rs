@@ -0,0 +1,142 @@
+use anyhow::{Result, bail};
+use but_settings::AppSettings;
+use but_workspace::StackId;
+use colored::Colorize;
+use gitbutler_command_context::CommandContext;
+use gitbutler_project::Project;
+use serde::Serialize;
+
+use crate::id::CliId;
+
+/// The outcome of pushing a single stack.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct StackPushOutcome {
+    /// The stack that was pushed.
+    pub stack_id: StackId,
+    /// The name of the remote all branches were pushed to.
+    pub remote: String,
+    /// The pushed branches along with the remote reference they were pushed to, ordered from the bottom to the top of the stack.
+    pub branches: Vec<PushedBranch>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PushedBranch {
+    pub name: String,
+    pub remote_ref: String,
+}
+
+pub(crate) fn push(
+    project: &Project,
+    json: bool,
+    branch: Option<&str>,
+    skip_force_push_protection: bool,
+    no_verify: bool,
+) -> Result<()> {
+    let ctx = &mut CommandContext::open(project, AppSettings::load_from_default_path_creating()?)?;
+    let targets = stacks_to_push(ctx, branch)?;
+    if targets.is_empty() {
+        if json {
+            println!("[]");
+        } else {
+            println!("Nothing to push, there are no stacks in the workspace.");
+        }
+        return Ok(());
+    }
+
+    let mut outcomes = Vec::new();
+    for (stack_id, branch_limit) in targets {
+        if !json {
+            println!("⏫ Pushing {}...", branch_limit.green().bold());
+        }
+        let outcome = push_stack(
+            project,
+            stack_id,
+            branch_limit,
+            skip_force_push_protection,
+            no_verify,
+        )?;
+        if !json {
+            if outcome.branches.is_empty() {
+                println!("   Nothing to push");
+            }
+            for branch in &outcome.branches {
+                println!("   {} → {}", branch.name.green(), branch.remote_ref.blue());
+            }
+        }
+        outcomes.push(outcome);
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&outcomes)?);
+    }
+    Ok(())
+}
+
+/// Push all branches of `stack_id` from the bottom of the stack up to and including `branch_limit`.
+///
+/// Pushes are always forced, but unless `skip_force_push_protection` is set, they are protected
+/// by `--force-with-lease` against the last known remote head of each branch if the project enables
+/// force push protection.
+pub(crate) fn push_stack(
+    project: &Project,
+    stack_id: StackId,
+    branch_limit: String,
+    skip_force_push_protection: bool,
+    no_verify: bool,
+) -> Result<StackPushOutcome> {
+    let result = but_api::stack::push_stack(
+        project.id,
+        stack_id,
+        true, /* with_force */
+        skip_force_push_protection,
+        branch_limit,
+        !no_verify,
+    )?;
+    Ok(StackPushOutcome {
+        stack_id,
+        remote: result.remote,
+        branches: result
+            .branch_to_remote
+            .into_iter()
+            .map(|(name, remote_ref)| PushedBranch {
+                name,
+                remote_ref: remote_ref.to_string(),
+            })
+            .collect(),
+    })
+}
+
+/// Resolve `branch` to the stack containing it and the name of the branch up to which the stack should be pushed.
+/// If `branch` is `None`, all stacks in the workspace are returned along with the name of their top-most branch.
+pub(crate) fn stacks_to_push(
+    ctx: &mut CommandContext,
+    branch: Option<&str>,
+) -> Result<Vec<(StackId, String)>> {
+    let stacks = crate::log::stacks(ctx)?;
+    let Some(branch) = branch else {
+        return Ok(stacks
+            .iter()
+            .filter_map(|s| Some((s.id?, s.name()?.to_string())))
+            .collect());
+    };
+
+    let cli_ids = CliId::from_str(ctx, branch)?;
+    let branch_name = match cli_ids.as_slice() {
+        [] => bail!("Branch '{}' not found", branch),
+        [CliId::Branch { name }] => name,
+        [other] => bail!("Target must be a branch, not {}", other.kind()),
+        _ => bail!(
+            "Branch '{}' is ambiguous. Found {} matches",
+            branch,
+            cli_ids.len()
+        ),
+    };
+    let stack_id = stacks
+        .iter()
+        .find(|s| s.heads.iter().any(|h| h.name == branch_name.as_str()))
+        .and_then(|s| s.id)
+        .ok_or_else(|| anyhow::anyhow!("Branch '{}' is not part of any stack", branch_name))?;
+    Ok(vec![(stack_id, branch_name.clone())])
+}
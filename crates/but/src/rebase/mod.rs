@@ -0,0 +1,107 @@
+use anyhow::{Result, bail};
+use but_settings::AppSettings;
+use but_workspace::{StackId, stack_ext::StackExt};
+use gitbutler_command_context::CommandContext;
+use gitbutler_project::Project;
+use gitbutler_stack::VirtualBranchesHandle;
+use serde::Serialize;
+use std::io::Write;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RebaseOutcome {
+    stack_id: StackId,
+    /// The commit at the top of the rebased stack.
+    top_commit: String,
+    /// The commit that is checked out in edit mode, as the todo list stopped at it with `break`.
+    stopped_at: Option<String>,
+}
+
+/// Let the user edit the commits of the stack containing `branch` as todo list in the format of `git rebase -i`,
+/// and rewrite the stack accordingly.
+pub(crate) fn interactive(project: &Project, json: bool, branch: &str) -> Result<()> {
+    let ctx = &mut CommandContext::open(project, AppSettings::load_from_default_path_creating()?)?;
+    let [(stack_id, _)] = crate::push::stacks_to_push(ctx, Some(branch))?[..] else {
+        bail!("Branch '{branch}' must belong to exactly one stack");
+    };
+    let repo = ctx.gix_repo()?;
+    let stack = VirtualBranchesHandle::new(project.gb_dir()).get_stack_in_workspace(stack_id)?;
+    let steps = stack.as_rebase_steps(ctx, &repo)?;
+    if steps.iter().all(|step| step.commit_id().is_none()) {
+        bail!("There are no commits to rebase in the stack of '{branch}'");
+    }
+
+    let todo = but_rebase::todo::serialize(&repo, &steps)?;
+    let edited = edit_in_editor("git-rebase-todo", &todo)?;
+    if edited == todo {
+        if !json {
+            println!("Nothing to do.");
+        }
+        return Ok(());
+    }
+
+    let steps = but_rebase::todo::parse(&repo, &edited, |commit_id, message| {
+        let template = format!(
+            "{message}\n# Please enter the new commit message for {commit_id}. Lines starting\n# with '#' will be ignored, and an empty message aborts the rebase.\n"
+        );
+        let message = strip_comments(&edit_in_editor("COMMIT_EDITMSG", &template)?);
+        if message.is_empty() {
+            bail!("Aborting the rebase as the new message of {commit_id} is empty");
+        }
+        Ok(format!("{message}\n").into())
+    })?;
+    let stopped_at = gitbutler_branch_actions::rebase_stack(ctx, stack_id, steps)?;
+
+    let stack = VirtualBranchesHandle::new(project.gb_dir()).get_stack_in_workspace(stack_id)?;
+    let top_commit = stack.head_oid(&repo)?.to_string();
+    if let Some(commit_id) = stopped_at {
+        but_api::modes::enter_edit_mode(project.id, commit_id.to_string(), stack_id)?;
+    }
+    if json {
+        let outcome = RebaseOutcome {
+            stack_id,
+            top_commit,
+            stopped_at: stopped_at.map(|id| id.to_string()),
+        };
+        println!("{}", serde_json::to_string_pretty(&outcome)?);
+    } else {
+        println!("Rebased the stack of '{branch}' (now {})", &top_commit[..7]);
+        if let Some(commit_id) = stopped_at {
+            println!(
+                "Stopped at {} to edit it. Change the files, then run `but resolve finish` to rewrite it and the commits above it, or `but resolve abort`.",
+                &commit_id.to_string()[..7]
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Open `content` in the editor in a temporary file named after `name`, and return the edited content.
+fn edit_in_editor(name: &str, content: &str) -> Result<String> {
+    let editor = crate::commit::get_editor_command()?;
+    let mut temp_file = tempfile::Builder::new()
+        .prefix(&format!("but_{name}_"))
+        .tempfile()?;
+    temp_file.write_all(content.as_bytes())?;
+    temp_file.flush()?;
+
+    let status = std::process::Command::new(&editor)
+        .arg(temp_file.path())
+        .status()?;
+    if !status.success() {
+        bail!("Editor exited with non-zero status");
+    }
+
+    // Editors may replace the file, so read it by path.
+    Ok(std::fs::read_to_string(temp_file.path())?)
+}
+
+fn strip_comments(content: &str) -> String {
+    content
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
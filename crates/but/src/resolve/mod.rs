@@ -0,0 +1,178 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+use but_settings::AppSettings;
+use but_workspace::StackId;
+use colored::Colorize;
+use gitbutler_command_context::CommandContext;
+use gitbutler_edit_mode::resolve::{ConflictSide, ConflictedPath};
+use gitbutler_project::Project;
+use gix::prelude::ObjectIdExt;
+
+use crate::id::CliId;
+
+#[derive(Debug, clap::Subcommand)]
+pub enum Subcommands {
+    /// Resolve a conflicted file by taking one side of the conflict.
+    Take {
+        /// The side to take.
+        side: Side,
+        /// The conflicted file, relative to the root of the worktree.
+        path: PathBuf,
+        /// Only resolve the conflicting hunk with this number, counted from 1 in order of appearance.
+        #[clap(long)]
+        hunk: Option<usize>,
+    },
+    /// Write the conflict of a file with conflict markers again, to start resolving it over.
+    Markers {
+        /// The conflicted file, relative to the root of the worktree.
+        path: PathBuf,
+    },
+    /// Rewrite the commit in edit mode with the resolved or changed files, rebase all commits on top of it and return to the workspace.
+    Finish {
+        /// Finish even if there are conflict markers left.
+        #[clap(long)]
+        force: bool,
+    },
+    /// Discard all resolutions or changes and return to the workspace.
+    Abort,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Side {
+    /// The side the commit was rebased onto.
+    Ours,
+    /// The side of the commit that was rebased.
+    Theirs,
+    /// The common ancestor of both sides.
+    Base,
+}
+
+impl From<Side> for ConflictSide {
+    fn from(side: Side) -> Self {
+        match side {
+            Side::Ours => ConflictSide::Ours,
+            Side::Theirs => ConflictSide::Theirs,
+            Side::Base => ConflictSide::Base,
+        }
+    }
+}
+
+/// Check out the conflicted `commit` in edit mode to resolve its conflicts, and list them.
+pub(crate) fn start(project: &Project, json: bool, commit: &str) -> Result<()> {
+    let mut ctx = CommandContext::open(project, AppSettings::load_from_default_path_creating()?)?;
+    let cli_ids = CliId::from_str(&mut ctx, commit)?;
+    let commit_id = match cli_ids.as_slice() {
+        [] => bail!("Commit '{}' not found", commit),
+        [CliId::Commit { oid }] => *oid,
+        [other] => bail!("Target must be a commit ID, not {}", other.kind()),
+        _ => bail!(
+            "Commit '{}' is ambiguous. Found {} matches",
+            commit,
+            cli_ids.len()
+        ),
+    };
+    let repo = ctx.gix_repo()?;
+    if !but_core::Commit::from_id(commit_id.attach(&repo))?.is_conflicted() {
+        bail!("Commit {} isn't conflicted", commit_id.to_hex_with_len(7));
+    }
+    let stack_id = stack_of_commit(project, commit_id)?;
+    but_api::modes::enter_edit_mode(project.id, commit_id.to_string(), stack_id)?;
+    list(project, json)
+}
+
+/// List the conflicted files of the commit that is being resolved.
+pub(crate) fn list(project: &Project, json: bool) -> Result<()> {
+    let paths = but_api::modes::edit_conflicted_paths(project.id)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&paths)?);
+        return Ok(());
+    }
+    if paths.is_empty() {
+        println!("There are no conflicted files.");
+        return Ok(());
+    }
+    for path in &paths {
+        println!("{}", format_path(path));
+    }
+    if paths.iter().all(ConflictedPath::is_resolved) {
+        println!("\nAll conflicts are resolved, run `but resolve finish` to rewrite the commit.");
+    } else {
+        println!(
+            "\nEdit the files, or use `but resolve take <ours|theirs|base> <path> [--hunk <n>]`, then run `but resolve finish`."
+        );
+    }
+    Ok(())
+}
+
+pub(crate) fn take(
+    project: &Project,
+    json: bool,
+    side: Side,
+    path: &Path,
+    hunk: Option<usize>,
+) -> Result<()> {
+    let hunk = match hunk {
+        Some(0) => bail!("Hunks are counted from 1"),
+        Some(hunk) => Some(hunk - 1),
+        None => None,
+    };
+    but_api::modes::edit_take_conflict_side(project.id, path.to_owned(), side.into(), hunk)?;
+    list(project, json)
+}
+
+pub(crate) fn markers(project: &Project, json: bool, path: &Path) -> Result<()> {
+    but_api::modes::edit_write_conflict_markers(project.id, path.to_owned())?;
+    list(project, json)
+}
+
+pub(crate) fn finish(project: &Project, json: bool, force: bool) -> Result<()> {
+    but_api::modes::finish_edit_resolution_and_return_to_workspace(project.id, force)?;
+    if !json {
+        println!("Rewrote the resolved commit and returned to the workspace.");
+    }
+    Ok(())
+}
+
+pub(crate) fn abort(project: &Project, json: bool) -> Result<()> {
+    but_api::modes::abort_edit_and_return_to_workspace(project.id)?;
+    if !json {
+        println!("Discarded all resolutions and returned to the workspace.");
+    }
+    Ok(())
+}
+
+fn format_path(path: &ConflictedPath) -> String {
+    let sides: Vec<_> = [
+        (path.presence.ours, "ours"),
+        (path.presence.theirs, "theirs"),
+        (path.presence.ancestor, "base"),
+    ]
+    .into_iter()
+    .filter_map(|(present, side)| present.then_some(side))
+    .collect();
+    let state = if path.is_resolved() {
+        "resolved".green()
+    } else {
+        format!("{} conflicting hunk(s)", path.unresolved_hunks).red()
+    };
+    format!("{} ({}): {state}", path.path, sides.join(", "))
+}
+
+fn stack_of_commit(project: &Project, commit_id: gix::ObjectId) -> Result<StackId> {
+    for stack in but_api::workspace::stacks(project.id, None)? {
+        let Some(stack_id) = stack.id else {
+            continue;
+        };
+        let details = but_api::workspace::stack_details(project.id, Some(stack_id))?;
+        if details
+            .branch_details
+            .iter()
+            .flat_map(|branch| &branch.commits)
+            .any(|commit| commit.id == commit_id)
+        {
+            return Ok(stack_id);
+        }
+    }
+    bail!("Commit {commit_id} not found in any stack")
+}
@@ -0,0 +1,177 @@
+use bstr::ByteSlice;
+use but_hunk_dependency::ui::{HunkLock, HunkLockExplanation, LineRange};
+use colored::Colorize;
+use gitbutler_project::Project;
+use std::path::{Component, Path, PathBuf};
+
+/// Explain why the hunks of the uncommitted changes to the file at `path`, relative to `current_dir`, are locked.
+pub(crate) fn hunk_locks(
+    project: &Project,
+    current_dir: &Path,
+    path: &str,
+    json: bool,
+) -> anyhow::Result<()> {
+    let path = path_in_worktree(&project.path, current_dir, path)?;
+    let explanations = but_api::diff::explain_hunk_locks(project.id, path.clone())?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&explanations)?);
+        return Ok(());
+    }
+    if explanations.is_empty() {
+        println!("{} has no uncommitted changes with hunks", path.yellow());
+        return Ok(());
+    }
+
+    let stacks = but_api::workspace::stacks(project.id, None)?;
+    let stack_name = |lock: &HunkLock| {
+        stacks
+            .iter()
+            .find(|stack| stack.id == Some(lock.stack_id))
+            .and_then(|stack| stack.name())
+            .map(|name| name.to_str_lossy().into_owned())
+            .unwrap_or_else(|| lock.stack_id.to_string())
+    };
+    for HunkLockExplanation {
+        path,
+        hunk,
+        commits,
+        amendable_into,
+    } in explanations
+    {
+        let header = format!(
+            "@@ -{},{} +{},{} @@",
+            hunk.old_start, hunk.old_lines, hunk.new_start, hunk.new_lines
+        );
+        if commits.is_empty() {
+            println!("{} {}: not locked", path.bold(), header.cyan());
+            continue;
+        }
+        println!(
+            "{} {}: 🔒 by {} commit{}",
+            path.bold(),
+            header.cyan(),
+            commits.len(),
+            if commits.len() == 1 { "" } else { "s" }
+        );
+        for commit in &commits {
+            let changes = commit
+                .renamed_file
+                .then(|| "renamed the file".to_owned())
+                .into_iter()
+                .chain(
+                    commit
+                        .ranges
+                        .iter()
+                        .map(|range| format!("changed {}", format_range(range))),
+                )
+                .collect::<Vec<_>>()
+                .join(", ");
+            println!(
+                "  {} [{}] {changes}",
+                short_id(&commit.lock),
+                stack_name(&commit.lock).green()
+            );
+        }
+        match amendable_into {
+            Some(lock) => println!(
+                "  → can be amended into {} [{}]",
+                short_id(&lock),
+                stack_name(&lock).green()
+            ),
+            None => println!(
+                "  → {}",
+                "locked to multiple stacks, split the hunk to commit it".yellow()
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Return `path`, relative to `current_dir`, as slash-separated path relative to `worktree_dir`.
+fn path_in_worktree(worktree_dir: &Path, current_dir: &Path, path: &str) -> anyhow::Result<String> {
+    let worktree_dir = worktree_dir.canonicalize()?;
+    let mut absolute_path = PathBuf::new();
+    for component in current_dir.canonicalize()?.join(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                absolute_path.pop();
+            }
+            component => absolute_path.push(component),
+        }
+    }
+    let relative_path = absolute_path.strip_prefix(&worktree_dir).map_err(|_| {
+        anyhow::anyhow!(
+            "{path} is outside of the worktree at {}",
+            worktree_dir.display()
+        )
+    })?;
+    if relative_path.as_os_str().is_empty() {
+        anyhow::bail!("{path} is the worktree itself, not a file in it");
+    }
+    Ok(relative_path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+fn short_id(lock: &HunkLock) -> String {
+    let commit_id = lock.commit_id.to_string();
+    format!(
+        "{}{}",
+        commit_id[..2].blue().underline(),
+        commit_id[2..7].blue()
+    )
+}
+
+fn format_range(range: &LineRange) -> String {
+    match range.lines {
+        0 => format!("deleted lines at line {}", range.start),
+        1 => format!("line {}", range.start),
+        lines => format!("lines {}-{}", range.start, range.start + lines - 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::path_in_worktree;
+
+    #[test]
+    fn paths_are_relative_to_the_worktree() -> anyhow::Result<()> {
+        let worktree = tempfile::tempdir()?;
+        let subdir = worktree.path().join("sub").join("dir");
+        std::fs::create_dir_all(&subdir)?;
+
+        let root = worktree.path();
+        assert_eq!(path_in_worktree(root, root, "file")?, "file");
+        assert_eq!(path_in_worktree(root, root, "./file")?, "file");
+        assert_eq!(
+            path_in_worktree(root, root, "sub/dir/file")?,
+            "sub/dir/file"
+        );
+        assert_eq!(path_in_worktree(root, &subdir, "file")?, "sub/dir/file");
+        assert_eq!(path_in_worktree(root, &subdir, "./file")?, "sub/dir/file");
+        assert_eq!(path_in_worktree(root, &subdir, "../file")?, "sub/file");
+        assert_eq!(
+            path_in_worktree(root, &subdir, "../../file")?,
+            "file",
+            "paths may leave the current directory as long as they stay in the worktree"
+        );
+        assert_eq!(
+            path_in_worktree(root, &subdir, &root.join("file").to_string_lossy())?,
+            "file",
+            "absolute paths are made relative to the worktree"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn paths_outside_of_the_worktree_are_rejected() -> anyhow::Result<()> {
+        let worktree = tempfile::tempdir()?;
+        let root = worktree.path();
+        assert!(path_in_worktree(root, root, "../file").is_err());
+        assert!(path_in_worktree(root, root, ".").is_err());
+        Ok(())
+    }
+}
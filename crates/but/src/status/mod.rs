@@ -13,6 +13,7 @@ use gitbutler_project::Project;
 use serde::Serialize;
 use std::collections::BTreeMap;
 pub(crate) mod assignment;
+pub(crate) mod explain;
 
 use crate::id::CliId;
 
@@ -189,6 +190,7 @@ pub fn print_group(
     first: bool,
 ) -> anyhow::Result<()> {
     if let Some(group) = &group {
+        let repo = ctx.gix_repo()?;
         let mut first = true;
         for branch in &group.branch_details {
             let id = CliId::branch(branch.name.to_str()?)
@@ -234,6 +236,7 @@ pub fn print_group(
                     show_files,
                     verbose,
                     false,
+                    None,
                 )?;
             }
             for commit in &branch.commits {
@@ -250,6 +253,7 @@ pub fn print_group(
                     }
                     but_workspace::ui::CommitState::Integrated => "●".purple(),
                 };
+                let review = crate::log::gerrit_review(ctx, &repo, commit.id)?;
                 print_commit(
                     commit.id,
                     commit.created_at as i64,
@@ -261,6 +265,7 @@ pub fn print_group(
                     show_files,
                     verbose,
                     commit.has_conflicts,
+                    review,
                 )?;
             }
         }
@@ -365,6 +370,7 @@ fn print_commit(
     show_files: bool,
     verbose: bool,
     has_conflicts: bool,
+    review: Option<but_db::GerritMetadata>,
 ) -> anyhow::Result<()> {
     let mark = if marked {
         Some("◀ Marked ▶".red().bold())
@@ -411,6 +417,13 @@ fn print_commit(
             mark.unwrap_or_default()
         );
         println!("┊│     {message}");
+        if let Some(review) = &review {
+            println!(
+                "┊│     {} {}",
+                format!("#{}", review.change_number).cyan(),
+                review.review_url.underline().dimmed()
+            );
+        }
     } else {
         // Original format: everything on one line
         println!(
@@ -422,6 +435,13 @@ fn print_commit(
             conflicted_str,
             mark.unwrap_or_default()
         );
+        if let Some(review) = &review {
+            println!(
+                "┊│     {} {}",
+                format!("#{}", review.change_number).cyan(),
+                review.review_url.underline().dimmed()
+            );
+        }
     }
     if show_files {
         for change in &commit_details.changes.changes {
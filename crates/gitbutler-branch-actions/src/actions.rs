@@ -6,7 +6,7 @@ use crate::move_commits::{self, MoveCommitIllegalAction};
 use crate::reorder::{self, StackOrder};
 use crate::upstream_integration::{
     self, BaseBranchResolution, BaseBranchResolutionApproach, IntegrationOutcome, Resolution,
-    StackStatuses, UpstreamIntegrationContext,
+    StackStatuses, UpstreamIntegrationContext, UpstreamIntegrationDryRun,
 };
 use crate::{
     base,
@@ -377,6 +377,36 @@ pub fn reorder_stack(
     Ok(())
 }
 
+/// Rewrite the stack with `stack_id` according to `steps`, as created and edited in an interactive rebase.
+///
+/// A `break` step doesn't stop the rebase, but the rewritten commit it would stop at is returned so it can be
+/// edited in edit mode, which rebases all commits above it once it's done.
+pub fn rebase_stack(
+    ctx: &CommandContext,
+    stack_id: StackId,
+    mut steps: Vec<but_rebase::RebaseStep>,
+) -> Result<Option<git2::Oid>> {
+    let mut guard = ctx.project().exclusive_worktree_access();
+    ctx.verify(guard.write_permission())?;
+    ensure_open_workspace_mode(ctx).context("Rebasing a stack requires open workspace mode")?;
+    let stop_at = reorder::take_break(&mut steps)?;
+    let _ = ctx.create_snapshot(
+        SnapshotDetails::new(OperationKind::ReorderCommit),
+        guard.write_permission(),
+    );
+    let output = reorder::rebase_stack(ctx, stack_id, steps, guard.write_permission())?;
+    stop_at
+        .map(|commit_id| {
+            output
+                .commit_mapping
+                .iter()
+                .rev()
+                .find_map(|(_base, old, new)| (*old == commit_id).then_some(new.to_git2()))
+                .with_context(|| format!("Couldn't find the rewritten commit of {commit_id}"))
+        })
+        .transpose()
+}
+
 pub fn find_git_branches(ctx: &CommandContext, branch_name: &str) -> Result<Vec<RemoteBranchData>> {
     remote::find_git_branches(ctx, branch_name)
 }
@@ -416,6 +446,24 @@ pub fn update_commit_message(
     vbranch::update_commit_message(ctx, stack_id, commit_oid, message)
 }
 
+/// Mark the conflicts of `commit_oid` that were resolved automatically with recorded resolutions as reviewed,
+/// and return the id of the rewritten commit.
+pub fn acknowledge_auto_resolution(
+    ctx: &CommandContext,
+    stack_id: StackId,
+    commit_oid: git2::Oid,
+) -> Result<git2::Oid> {
+    let mut guard = ctx.project().exclusive_worktree_access();
+    ctx.verify(guard.write_permission())?;
+    ensure_open_workspace_mode(ctx)
+        .context("Acknowledging an automatic conflict resolution requires open workspace mode")?;
+    let _ = ctx.create_snapshot(
+        SnapshotDetails::new(OperationKind::GenericBranchUpdate),
+        guard.write_permission(),
+    );
+    vbranch::acknowledge_auto_resolution(ctx, stack_id, commit_oid)
+}
+
 pub fn find_commit(ctx: &CommandContext, commit_oid: git2::Oid) -> Result<Option<RemoteCommit>> {
     remote::get_commit_data(ctx, commit_oid)
 }
@@ -519,24 +567,51 @@ pub fn get_uncommited_files(ctx: &CommandContext) -> Result<Vec<RemoteBranchFile
 }
 
 pub fn upstream_integration_statuses(
-    ctx: &CommandContext,
+    ctx: &mut CommandContext,
     target_commit_oid: Option<git2::Oid>,
 ) -> Result<StackStatuses> {
     let mut guard = ctx.project().exclusive_worktree_access();
 
     let gix_repo = ctx.gix_repo()?;
+    let recorded_resolutions = upstream_integration::recorded_resolutions(ctx)?;
     let context = UpstreamIntegrationContext::open(
         ctx,
         target_commit_oid,
         guard.write_permission(),
         &gix_repo,
+        recorded_resolutions,
     )?;
 
     upstream_integration::upstream_integration_statuses(&context)
 }
 
+/// Like [`upstream_integration_statuses()`], but also integrates the upstream changes into each stack with each
+/// approach in memory, to report the outcome per commit without changing anything.
+/// `base_branch_resolution` is the same as would be passed to [`integrate_upstream()`].
+pub fn upstream_integration_dry_run(
+    ctx: &mut CommandContext,
+    base_branch_resolution: Option<BaseBranchResolution>,
+) -> Result<UpstreamIntegrationDryRun> {
+    let mut guard = ctx.project().exclusive_worktree_access();
+
+    let (target_commit_oid, base_branch_resolution_approach) = base_branch_resolution
+        .map(|r| (Some(r.target_commit_oid), Some(r.approach)))
+        .unwrap_or((None, None));
+    let gix_repo = ctx.gix_repo()?;
+    let recorded_resolutions = upstream_integration::recorded_resolutions(ctx)?;
+    let context = UpstreamIntegrationContext::open(
+        ctx,
+        target_commit_oid,
+        guard.write_permission(),
+        &gix_repo,
+        recorded_resolutions,
+    )?;
+
+    upstream_integration::upstream_integration_dry_run(&context, base_branch_resolution_approach)
+}
+
 pub fn integrate_upstream(
-    ctx: &CommandContext,
+    ctx: &mut CommandContext,
     resolutions: &[Resolution],
     base_branch_resolution: Option<BaseBranchResolution>,
 ) -> Result<IntegrationOutcome> {
@@ -556,7 +631,7 @@ pub fn integrate_upstream(
 }
 
 pub fn resolve_upstream_integration(
-    ctx: &CommandContext,
+    ctx: &mut CommandContext,
     resolution_approach: BaseBranchResolutionApproach,
 ) -> Result<git2::Oid> {
     let mut guard = ctx.project().exclusive_worktree_access();
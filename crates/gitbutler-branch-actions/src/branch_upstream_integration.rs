@@ -2,7 +2,7 @@ use anyhow::{bail, Result};
 use but_graph::VirtualBranchesTomlMetadata;
 use but_rebase::{Rebase, RebaseStep};
 use but_workspace::{stack_ext::StackExt, ui::CommitState};
-use gitbutler_command_context::CommandContext;
+use gitbutler_command_context::{CommandContext, Event};
 use gitbutler_oxidize::ObjectIdExt;
 use gitbutler_project::access::WorktreeWritePermission;
 use gitbutler_stack::{StackId, VirtualBranchesHandle};
@@ -151,7 +151,11 @@ pub fn integrate_branch_with_steps(
             RebaseStep::Pick { .. } => {
                 continue;
             }
-            RebaseStep::SquashIntoPreceding { .. } => {
+            RebaseStep::SquashIntoPreceding { .. }
+            | RebaseStep::Fixup { .. }
+            | RebaseStep::Drop { .. }
+            | RebaseStep::Exec { .. }
+            | RebaseStep::Break => {
                 continue;
             }
             RebaseStep::Reference(_) => {
@@ -253,9 +257,11 @@ pub fn integrate_upstream_commits_for_series(
                 Some(stack_id),
                 series_name.to_owned(),
             )?;
-            integrate_branch_with_steps(ctx, stack_id, series_name, steps, perm)
+            integrate_branch_with_steps(ctx, stack_id, series_name, steps, perm)?;
         }
     }
+    ctx.record_event(Event::BaseUpdated);
+    Ok(())
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
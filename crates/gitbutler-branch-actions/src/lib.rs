@@ -2,14 +2,15 @@
 mod actions;
 // This is our API
 pub use actions::{
-    amend, can_apply_remote_branch, create_commit, create_virtual_branch,
-    create_virtual_branch_from_branch, delete_local_branch, fetch_from_remotes, find_commit,
-    find_git_branches, get_initial_integration_steps_for_branch, get_uncommited_files,
-    insert_blank_commit, integrate_branch_with_steps, integrate_upstream,
+    acknowledge_auto_resolution, amend, can_apply_remote_branch, create_commit,
+    create_virtual_branch, create_virtual_branch_from_branch, delete_local_branch,
+    fetch_from_remotes, find_commit, find_git_branches, get_initial_integration_steps_for_branch,
+    get_uncommited_files, insert_blank_commit, integrate_branch_with_steps, integrate_upstream,
     integrate_upstream_commits, list_commit_files, move_branch, move_commit, push_base_branch,
-    reorder_stack, resolve_upstream_integration, set_base_branch, set_target_push_remote,
-    squash_commits, unapply_stack, undo_commit, update_commit_message, update_stack_order,
-    update_virtual_branch, upstream_integration_statuses,
+    rebase_stack, reorder_stack, resolve_upstream_integration, set_base_branch,
+    set_target_push_remote, squash_commits, unapply_stack, undo_commit, update_commit_message,
+    update_stack_order, update_virtual_branch, upstream_integration_dry_run,
+    upstream_integration_statuses,
 };
 mod squash;
 
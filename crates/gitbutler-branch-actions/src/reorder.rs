@@ -29,23 +29,11 @@ pub fn reorder_stack(
     new_order: StackOrder,
     perm: &mut WorktreeWritePermission,
 ) -> Result<RebaseOutput> {
-    let old_workspace = WorkspaceState::create(ctx, perm.read_permission())?;
     let state = ctx.project().virtual_branches();
-    let repo = ctx.repo();
-    let mut stack = state.get_stack(stack_id)?;
+    let stack = state.get_stack(stack_id)?;
     let current_order = commits_order(ctx, &stack)?;
     new_order.validate(current_order.clone())?;
 
-    let gix_repo = ctx.gix_repo()?;
-    let default_target = state.get_default_target()?;
-    let default_target_commit = repo
-        .find_reference(&default_target.branch.to_string())?
-        .peel_to_commit()?;
-    let merge_base = repo.merge_base(
-        default_target_commit.id(),
-        stack.head_oid(&gix_repo)?.to_git2(),
-    )?;
-
     let mut steps: Vec<RebaseStep> = Vec::new();
     for series in new_order.series.iter().rev() {
         for oid in series.commit_ids.iter().rev() {
@@ -58,10 +46,49 @@ pub fn reorder_stack(
             series.name.clone(),
         )));
     }
+    rebase_stack(ctx, stack_id, steps, perm)
+}
+
+/// Rebase the stack with `stack_id` onto the merge-base of its head and the target branch, following `steps`,
+/// and update the stack, its heads and the workspace to match the result.
+///
+/// `steps` may rewrite the stack at will, as long as all of its references are placed.
+///
+/// # Errors
+/// Errors out without changing anything if `steps` don't place each branch of the stack exactly once,
+/// or place references that aren't branches of the stack.
+pub(crate) fn rebase_stack(
+    ctx: &CommandContext,
+    stack_id: StackId,
+    steps: Vec<RebaseStep>,
+    perm: &mut WorktreeWritePermission,
+) -> Result<RebaseOutput> {
+    let old_workspace = WorkspaceState::create(ctx, perm.read_permission())?;
+    let state = ctx.project().virtual_branches();
+    let repo = ctx.repo();
+    let mut stack = state.get_stack(stack_id)?;
+    validate_references(&stack, &steps)?;
+
+    let gix_repo = ctx.gix_repo()?;
+    let default_target = state.get_default_target()?;
+    let default_target_commit = repo
+        .find_reference(&default_target.branch.to_string())?
+        .peel_to_commit()?;
+    let merge_base = repo.merge_base(
+        default_target_commit.id(),
+        stack.head_oid(&gix_repo)?.to_git2(),
+    )?;
+
     let mut builder = but_rebase::Rebase::new(&gix_repo, merge_base.to_gix(), None)?;
     let builder = builder.steps(steps)?;
     builder.rebase_noops(false);
     let output = builder.rebase()?;
+    if let Some(stop) = &output.stopped_at {
+        bail!(
+            "Stopping at commit {} isn't supported when rebasing a stack",
+            stop.commit_id
+        );
+    }
 
     let new_head = output.top_commit.to_git2();
 
@@ -79,6 +106,68 @@ pub fn reorder_stack(
     Ok(output)
 }
 
+/// Remove the `break` step from `steps`, and return the commit it stops at, which is the one of the last step before it
+/// that produces a commit, or `None` if there is no `break`.
+///
+/// # Errors
+/// Errors out if there is more than one `break`, or no commit to stop at.
+pub(crate) fn take_break(steps: &mut Vec<RebaseStep>) -> Result<Option<gix::ObjectId>> {
+    let mut breaks = steps
+        .iter()
+        .positions(|step| matches!(step, RebaseStep::Break));
+    let Some(pos) = breaks.next() else {
+        return Ok(None);
+    };
+    if breaks.next().is_some() {
+        bail!("Only a single 'break' is supported when rebasing a stack");
+    }
+    let commit_id = steps[..pos]
+        .iter()
+        .rev()
+        .find_map(|step| match step {
+            RebaseStep::Pick { commit_id, .. }
+            | RebaseStep::SquashIntoPreceding { commit_id, .. }
+            | RebaseStep::Fixup { commit_id } => Some(*commit_id),
+            RebaseStep::Drop { .. }
+            | RebaseStep::Exec { .. }
+            | RebaseStep::Reference(_)
+            | RebaseStep::Break => None,
+        })
+        .context("There is no commit to stop at before 'break'")?;
+    steps.remove(pos);
+    Ok(Some(commit_id))
+}
+
+/// Assure that the reference steps in `steps` place each branch of `stack` exactly once, and nothing else.
+fn validate_references(stack: &Stack, steps: &[RebaseStep]) -> Result<()> {
+    let references = steps
+        .iter()
+        .filter_map(|step| match step {
+            RebaseStep::Reference(reference) => Some(reference.to_string()),
+            _ => None,
+        })
+        .collect_vec();
+    let branches = stack
+        .heads
+        .iter()
+        .filter(|head| !head.archived)
+        .map(|head| head.name())
+        .collect_vec();
+    if let Some(missing) = branches.iter().find(|branch| !references.contains(branch)) {
+        bail!("The branch '{missing}' must be kept, but there is no reference step for it");
+    }
+    if let Some(unknown) = references
+        .iter()
+        .find(|reference| !branches.contains(reference))
+    {
+        bail!("The reference '{unknown}' isn't a branch of the stack");
+    }
+    if let Some(duplicate) = references.iter().duplicates().next() {
+        bail!("The branch '{duplicate}' can only be placed once");
+    }
+    Ok(())
+}
+
 /// Represents the order of series (branches) and changes (commits) in a stack.
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
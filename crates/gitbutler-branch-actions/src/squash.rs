@@ -193,16 +193,17 @@ fn do_squash_commits(
         .iter()
         .filter(|&commit| commit.id() != destination_commit.id());
 
-    // Squash commit messages string separating with newlines
-    let new_message = Some(destination_commit)
+    // Squash commit messages string separating with newlines, keeping all trailers at the end
+    let messages = Some(destination_commit)
         .into_iter()
         .chain(source_commits_without_destination)
         .filter_map(|c| {
             let msg = c.message().unwrap_or_default();
             (!msg.trim().is_empty()).then_some(msg)
         })
-        .collect::<Vec<_>>()
-        .join("\n");
+        .collect::<Vec<_>>();
+    let new_message = but_core::commit::trailer::squash_messages(&messages);
+    let new_message = String::from_utf8_lossy(&new_message);
     let parents: Vec<_> = parent_most_source_commit.parents().collect();
 
     // Create a new commit with the final tree
@@ -157,7 +157,7 @@ pub fn update_branch_pr_number(
 /// Pushes all series in the stack to the remote.
 /// This operation will error out if the target has no push remote configured.
 pub fn push_stack(
-    ctx: &CommandContext,
+    ctx: &mut CommandContext,
     stack_id: StackId,
     with_force: bool,
     skip_force_push_protection: bool,
@@ -171,10 +171,10 @@ pub fn push_stack(
 
     let repo = ctx.repo();
     let default_target = state.get_default_target()?;
-    let merge_base = repo.find_commit(repo.merge_base(
+    let merge_base = repo.merge_base(
         stack.head_oid(&repo.to_gix()?)?.to_git2(),
         default_target.sha,
-    )?)?;
+    )?;
 
     // First fetch, because we dont want to push integrated series
     ctx.fetch(
@@ -202,11 +202,11 @@ pub fn push_stack(
             // Nothing to push for this one
             continue;
         }
-        if branch.head_oid(&gix_repo)? == merge_base.id().to_gix() {
+        if branch.head_oid(&gix_repo)? == merge_base.to_gix() {
             // Nothing to push for this one
             continue;
         }
-        if branch_integrated(&mut check_commit, &branch, repo, &gix_repo)? {
+        if branch_integrated(&mut check_commit, &branch, ctx.repo(), &gix_repo)? {
             // Already integrated, nothing to push
             continue;
         }
@@ -236,16 +236,16 @@ pub fn push_stack(
         }
 
         let refspec = if gerrit_mode {
-            Some(format!(
-                "{}:refs/for/{}",
+            Some(but_gerrit::push::refspec(
                 push_details.head,
-                default_target.branch.branch()
+                default_target.branch.branch(),
+                Some(branch.name().as_str()),
             ))
         } else {
             None
         };
 
-        ctx.push(
+        let push_output = ctx.push(
             push_details.head,
             &push_details.remote_refname,
             with_force,
@@ -254,6 +254,18 @@ pub fn push_stack(
             Some(Some(stack.id)),
         )?;
 
+        if gerrit_mode {
+            // The push succeeded, so failing to record what Gerrit said about it doesn't fail the push.
+            if let Err(err) =
+                record_gerrit_push(ctx, &gix_repo, push_details.head, merge_base, &push_output)
+            {
+                tracing::warn!(
+                    "Failed to record the Gerrit changes of branch {}: {err:#}",
+                    branch.name()
+                );
+            }
+        }
+
         result.branch_to_remote.push((
             branch.name().to_owned(),
             push_details.remote_refname.to_owned().into(),
@@ -267,6 +279,28 @@ pub fn push_stack(
     Ok(result)
 }
 
+/// Record what Gerrit said in `push_output` about the commits from `merge_base` to `head` that were just pushed.
+fn record_gerrit_push(
+    ctx: &mut CommandContext,
+    gix_repo: &gix::Repository,
+    head: git2::Oid,
+    merge_base: git2::Oid,
+    push_output: &str,
+) -> Result<()> {
+    let commits: Vec<_> = gix_repo
+        .find_object(head.to_gix())?
+        .id()
+        .ancestors()
+        .first_parent_only()
+        .with_hidden(Some(merge_base.to_gix()))
+        .all()?
+        .map(|info| info.map(|info| info.id))
+        .collect::<Result<_, _>>()?;
+    let commits: Vec<_> = commits.into_iter().rev().collect();
+    but_gerrit::record_push_output(ctx.db()?, gix_repo, &commits, push_output)?;
+    Ok(())
+}
+
 pub(crate) fn branch_integrated(
     check_commit: &mut IsCommitIntegrated,
     branch: &StackBranch,
@@ -8,7 +8,7 @@ use but_graph::VirtualBranchesTomlMetadata;
 use but_rebase::{RebaseOutput, RebaseStep};
 use but_workspace::ref_info::Options;
 use but_workspace::stack_ext::StackDetailsExt;
-use gitbutler_command_context::CommandContext;
+use gitbutler_command_context::{CommandContext, Event};
 use gitbutler_commit::commit_ext::CommitExt as _;
 use gitbutler_oxidize::{
     git2_to_gix_object_id, gix_to_git2_oid, GixRepositoryExt, ObjectIdExt, OidExt,
@@ -22,6 +22,7 @@ use gitbutler_serde::BStringForFrontend;
 use gitbutler_stack::{StackId, Target, VirtualBranchesHandle};
 use gitbutler_workspace::branch_trees::{update_uncommited_changes, WorkspaceState};
 use gix::merge::tree::TreatAsUnresolved;
+use gix::prelude::ObjectIdExt as _;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, PartialEq, Debug)]
@@ -89,9 +90,10 @@ pub enum ResolutionApproach {
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct BaseBranchResolution {
+    /// The commit to use as new target, as returned by [`resolve_upstream_integration()`](crate::resolve_upstream_integration()).
     #[serde(with = "gitbutler_serde::oid")]
-    target_commit_oid: git2::Oid,
-    approach: BaseBranchResolutionApproach,
+    pub target_commit_oid: git2::Oid,
+    pub approach: BaseBranchResolutionApproach,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
@@ -103,6 +105,53 @@ pub struct IntegrationOutcome {
     review_ids_to_close: Vec<String>,
 }
 
+/// What would happen if the upstream changes were integrated, computed without changing anything.
+#[derive(Serialize, PartialEq, Debug)]
+#[serde(tag = "type", content = "subject", rename_all = "camelCase")]
+pub enum UpstreamIntegrationDryRun {
+    UpToDate,
+    UpdatesRequired {
+        #[serde(rename = "worktreeConflicts")]
+        worktree_conflicts: Vec<BStringForFrontend>,
+        stacks: Vec<StackDryRun>,
+    },
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct StackDryRun {
+    pub stack_id: Option<StackId>,
+    pub status: StackStatus,
+    /// What would happen with each of the approaches to integrate the upstream changes into the stack.
+    pub approaches: Vec<ApproachDryRun>,
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ApproachDryRun {
+    pub approach: ResolutionApproach,
+    /// If the approach can be chosen for the stack, given its status.
+    pub acceptable: bool,
+    /// The commits that would be rewritten or created, oldest first.
+    pub commits: Vec<CommitDryRun>,
+    /// The amount of commits the stack would have afterwards.
+    pub commit_count: usize,
+    /// The amount of commits that would be dropped as they are integrated upstream.
+    pub integrated_commit_count: usize,
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitDryRun {
+    /// The commit as it is now, or `None` for a merge commit that would be created.
+    #[serde(with = "gitbutler_serde::object_id_opt")]
+    pub id: Option<gix::ObjectId>,
+    /// The paths the commit would be conflicted in, empty if it wouldn't be conflicted.
+    pub conflicted_paths: Vec<BStringForFrontend>,
+    /// If the commit would conflict, but all of its conflicts would be resolved with recorded resolutions.
+    pub auto_resolved: bool,
+}
+
 impl StackStatus {
     fn create(tree_status: TreeStatus, branch_statuses: Vec<NameAndStatus>) -> Result<Self> {
         if branch_statuses.is_empty() {
@@ -115,7 +164,8 @@ impl StackStatus {
         })
     }
 
-    fn resolution_acceptable(&self, approach: &ResolutionApproach) -> bool {
+    /// Return `true` if the stack can be updated with `approach`.
+    pub fn resolution_acceptable(&self, approach: &ResolutionApproach) -> bool {
         if self.tree_status == TreeStatus::Empty
             && self
                 .branch_statuses
@@ -179,6 +229,7 @@ pub struct UpstreamIntegrationContext<'a> {
     target: Target,
     ctx: &'a CommandContext,
     gix_repo: &'a gix::Repository,
+    recorded_resolutions: but_rebase::rerere::Resolutions,
 }
 
 impl<'a> UpstreamIntegrationContext<'a> {
@@ -187,6 +238,7 @@ impl<'a> UpstreamIntegrationContext<'a> {
         target_commit_oid: Option<git2::Oid>,
         permission: &'a mut WorktreeWritePermission,
         gix_repo: &'a gix::Repository,
+        recorded_resolutions: but_rebase::rerere::Resolutions,
     ) -> Result<Self> {
         let meta = ctx.meta(permission.read_permission())?;
         let repo = ctx.gix_repo()?;
@@ -221,6 +273,7 @@ impl<'a> UpstreamIntegrationContext<'a> {
             stacks_in_workspace,
             ctx,
             gix_repo,
+            recorded_resolutions,
         })
     }
 }
@@ -270,6 +323,7 @@ fn get_stack_status(
     new_target_commit_id: gix::ObjectId,
     stack_id: Option<StackId>,
     ctx: &CommandContext,
+    recorded_resolutions: &but_rebase::rerere::Resolutions,
 ) -> Result<StackStatus> {
     let mut unintegrated_branch_found = false;
 
@@ -332,17 +386,20 @@ fn get_stack_status(
             .collect();
         let mut rebase = but_rebase::Rebase::new(gix_repo, Some(rebase_base.to_gix()), None)?;
         rebase.rebase_noops(false);
+        rebase.allow_merge_conflicts(true);
+        rebase.resolutions(recorded_resolutions.clone());
         rebase.steps(steps)?;
         let output = rebase.rebase()?;
         let new_head_oid = output.top_commit.to_git2();
 
-        let any_conflicted = output.commit_mapping.iter().any(|(_base, _old, new)| {
-            if let Ok(commit) = gix_repo.find_commit(*new) {
-                commit.is_conflicted()
-            } else {
-                false
-            }
-        });
+        let any_conflicted = !output.merge_conflicts.is_empty()
+            || output.commit_mapping.iter().any(|(_base, _old, new)| {
+                if let Ok(commit) = gix_repo.find_commit(*new) {
+                    commit.is_conflicted()
+                } else {
+                    false
+                }
+            });
 
         last_head = new_head_oid;
 
@@ -446,6 +503,7 @@ pub fn upstream_integration_statuses(
                     git2_to_gix_object_id(new_target.id()),
                     stack.id,
                     context.ctx,
+                    &context.recorded_resolutions,
                 )?,
             ))
         })
@@ -458,7 +516,7 @@ pub fn upstream_integration_statuses(
 }
 
 pub(crate) fn integrate_upstream(
-    ctx: &CommandContext,
+    ctx: &mut CommandContext,
     resolutions: &[Resolution],
     base_branch_resolution: Option<BaseBranchResolution>,
     permission: &mut WorktreeWritePermission,
@@ -470,7 +528,14 @@ pub(crate) fn integrate_upstream(
         .unwrap_or((None, None));
 
     let gix_repo = ctx.gix_repo()?;
-    let context = UpstreamIntegrationContext::open(ctx, target_commit_oid, permission, &gix_repo)?;
+    let recorded_resolutions = recorded_resolutions(ctx)?;
+    let context = UpstreamIntegrationContext::open(
+        ctx,
+        target_commit_oid,
+        permission,
+        &gix_repo,
+        recorded_resolutions,
+    )?;
     let virtual_branches_state = VirtualBranchesHandle::new(ctx.project().gb_dir());
     let default_target = virtual_branches_state.get_default_target()?;
 
@@ -631,6 +696,7 @@ pub(crate) fn integrate_upstream(
         crate::integration::update_workspace_commit(&virtual_branches_state, ctx)?;
     }
 
+    ctx.record_event(Event::BaseUpdated);
     Ok(IntegrationOutcome {
         archived_branches: newly_archived_branches,
         review_ids_to_close: to_be_closed_review_ids,
@@ -638,12 +704,14 @@ pub(crate) fn integrate_upstream(
 }
 
 pub(crate) fn resolve_upstream_integration(
-    ctx: &CommandContext,
+    ctx: &mut CommandContext,
     resolution_approach: BaseBranchResolutionApproach,
     permission: &mut WorktreeWritePermission,
 ) -> Result<git2::Oid> {
     let gix_repo = ctx.gix_repo()?;
-    let context = UpstreamIntegrationContext::open(ctx, None, permission, &gix_repo)?;
+    let recorded_resolutions = recorded_resolutions(ctx)?;
+    let context =
+        UpstreamIntegrationContext::open(ctx, None, permission, &gix_repo, recorded_resolutions)?;
     let repo = ctx.repo();
     let new_target_id = context.new_target.id();
     let old_target_id = context.target.sha;
@@ -677,6 +745,7 @@ pub(crate) fn resolve_upstream_integration(
                 but_rebase::Rebase::new(&gix_repo, Some(new_target_id.to_gix()), None)?;
             rebase.steps(steps)?;
             rebase.rebase_noops(false);
+            rebase.resolutions(context.recorded_resolutions.clone());
             let outcome = rebase.rebase()?;
             let new_head = outcome.top_commit.to_git2();
 
@@ -685,6 +754,13 @@ pub(crate) fn resolve_upstream_integration(
     }
 }
 
+/// Load the conflict resolutions that were recorded in edit mode, to resolve conflicts the same way when rebasing.
+pub(crate) fn recorded_resolutions(
+    ctx: &mut CommandContext,
+) -> Result<but_rebase::rerere::Resolutions> {
+    but_rebase::rerere::Resolutions::from_db(ctx.db()?)
+}
+
 fn compute_resolutions(
     context: &UpstreamIntegrationContext,
     resolutions: &[Resolution],
@@ -697,6 +773,7 @@ fn compute_resolutions(
         stacks_in_workspace,
         ..
     } = context;
+    let recorded_resolutions = &context.recorded_resolutions;
 
     let results = resolutions
         .iter()
@@ -744,75 +821,21 @@ fn compute_resolutions(
                     // Rebase the commits, then try rebasing the tree. If
                     // the tree ends up conflicted, commit the tree.
 
-                    // If the base branch needs to resolve its divergence
-                    // pick only the commits that are ahead of the old target head
-                    let lower_bound = if base_branch_resolution_approach.is_some() {
-                        target.sha
-                    } else {
-                        new_target.id()
-                    };
-
-                    let details = stack_details(context.ctx, stack.id)?;
-                    let mut commit_map = HashMap::new();
-                    for branch in &details.branch_details {
-                        for commit in &branch.commits {
-                            commit_map.insert(commit.id, commit.clone());
-                        }
-                    }
-
-                    let all_steps = details.as_rebase_steps(context.gix_repo)?;
-                    let branches_before = as_buckets(all_steps.clone());
-                    // Filter out any integrated commits
-                    let steps = all_steps
-                        .into_iter()
-                        .filter_map(|s| match s {
-                            RebaseStep::Pick {
-                                commit_id,
-                                new_message: _,
-                            } => {
-                                let commit = repo.find_commit(commit_id.to_git2()).ok()?;
-                                let is_integrated = commit_map.get(&commit_id).is_some_and(|c| {
-                                    matches!(c.state, but_workspace::ui::CommitState::Integrated)
-                                });
-                                let forced = forced_integrated(
-                                    &resolution.force_integrated_branches,
-                                    &branches_before,
-                                    &commit.id().to_gix(),
-                                );
-                                if is_integrated || forced {
-                                    None
-                                } else {
-                                    Some(s)
-                                }
-                            }
-                            _ => Some(s),
-                        })
-                        .collect::<Vec<_>>();
-
-                    let branches_after = as_buckets(steps.clone());
-
-                    // Branches that used to have commits but now don't are marked for archival
-                    let mut for_archival = vec![];
-                    for (ref_before, steps_before) in branches_before {
-                        if let Some((_, steps_after)) = branches_after
-                            .iter()
-                            .find(|(ref_after, _)| ref_after == &ref_before)
-                        {
-                            // if there were steps before and now there are none, this should be marked for archival
-                            if !steps_before.is_empty() && steps_after.is_empty() {
-                                for_archival.push(ref_before);
-                            }
-                        }
-                    }
+                    let lower_bound =
+                        rebase_lower_bound(context, base_branch_resolution_approach.as_ref());
 
-                    let mut rebase = but_rebase::Rebase::new(
+                    let StackRebase {
+                        output,
+                        for_archival,
+                        ..
+                    } = rebase_stack(
+                        context,
                         context.gix_repo,
-                        Some(lower_bound.to_gix()),
-                        None,
+                        stack,
+                        &resolution.force_integrated_branches,
+                        lower_bound,
+                        recorded_resolutions,
                     )?;
-                    rebase.rebase_noops(false);
-                    rebase.steps(steps)?;
-                    let output = rebase.rebase()?;
                     let new_head = output.top_commit.to_git2();
 
                     Ok((
@@ -832,6 +855,277 @@ fn compute_resolutions(
     Ok(results)
 }
 
+/// Return the commit to rebase the commits of stacks onto.
+fn rebase_lower_bound(
+    context: &UpstreamIntegrationContext,
+    base_branch_resolution_approach: Option<&BaseBranchResolutionApproach>,
+) -> git2::Oid {
+    // If the base branch needs to resolve its divergence
+    // pick only the commits that are ahead of the old target head
+    if base_branch_resolution_approach.is_some() {
+        context.target.sha
+    } else {
+        context.new_target.id()
+    }
+}
+
+/// The result of rebasing the commits of a stack onto the new target.
+struct StackRebase {
+    output: RebaseOutput,
+    /// The references of branches that have no commits left, as all of them are integrated.
+    for_archival: Vec<Reference>,
+    /// The amount of commits that weren't picked as they are integrated.
+    integrated_commit_count: usize,
+}
+
+/// Rebase all commits of `stack` that aren't integrated onto `lower_bound` in `gix_repo`,
+/// considering all commits of `force_integrated_branches` integrated.
+fn rebase_stack(
+    context: &UpstreamIntegrationContext,
+    gix_repo: &gix::Repository,
+    stack: &but_workspace::ui::StackEntry,
+    force_integrated_branches: &[String],
+    lower_bound: git2::Oid,
+    recorded_resolutions: &but_rebase::rerere::Resolutions,
+) -> Result<StackRebase> {
+    let details = stack_details(context.ctx, stack.id)?;
+    let mut commit_map = HashMap::new();
+    for branch in &details.branch_details {
+        for commit in &branch.commits {
+            commit_map.insert(commit.id, commit.clone());
+        }
+    }
+
+    let all_steps = details.as_rebase_steps(gix_repo)?;
+    let branches_before = as_buckets(all_steps.clone());
+    let picks_before = all_steps
+        .iter()
+        .filter(|s| matches!(s, RebaseStep::Pick { .. }))
+        .count();
+    // Filter out any integrated commits
+    let steps = all_steps
+        .into_iter()
+        .filter_map(|s| match s {
+            RebaseStep::Pick {
+                commit_id,
+                new_message: _,
+            } => {
+                let commit = context.repo.find_commit(commit_id.to_git2()).ok()?;
+                let is_integrated = commit_map
+                    .get(&commit_id)
+                    .is_some_and(|c| matches!(c.state, but_workspace::ui::CommitState::Integrated));
+                let forced = forced_integrated(
+                    force_integrated_branches,
+                    &branches_before,
+                    &commit.id().to_gix(),
+                );
+                if is_integrated || forced {
+                    None
+                } else {
+                    Some(s)
+                }
+            }
+            _ => Some(s),
+        })
+        .collect::<Vec<_>>();
+    let integrated_commit_count = picks_before
+        - steps
+            .iter()
+            .filter(|s| matches!(s, RebaseStep::Pick { .. }))
+            .count();
+
+    let branches_after = as_buckets(steps.clone());
+
+    // Branches that used to have commits but now don't are marked for archival
+    let mut for_archival = vec![];
+    for (ref_before, steps_before) in branches_before {
+        if let Some((_, steps_after)) = branches_after
+            .iter()
+            .find(|(ref_after, _)| ref_after == &ref_before)
+        {
+            // if there were steps before and now there are none, this should be marked for archival
+            if !steps_before.is_empty() && steps_after.is_empty() {
+                for_archival.push(ref_before);
+            }
+        }
+    }
+
+    let mut rebase = but_rebase::Rebase::new(gix_repo, Some(lower_bound.to_gix()), None)?;
+    rebase.rebase_noops(false);
+    // Merges that conflict after rebasing are recorded as conflicted commits,
+    // so the user can resolve them later instead of being blocked.
+    rebase.allow_merge_conflicts(true);
+    // Conflicts that were resolved before are resolved the same way again.
+    rebase.resolutions(recorded_resolutions.clone());
+    rebase.steps(steps)?;
+    let output = rebase.rebase()?;
+
+    Ok(StackRebase {
+        output,
+        for_archival,
+        integrated_commit_count,
+    })
+}
+
+/// Integrate the upstream changes into each stack with each of the approaches that can be chosen, but only in memory,
+/// to report which commits would be conflicted and how many commits each stack would have afterwards.
+///
+/// `base_branch_resolution_approach` is the approach that was used to resolve a diverged base branch, if any,
+/// just like in [`integrate_upstream()`].
+pub fn upstream_integration_dry_run(
+    context: &UpstreamIntegrationContext,
+    base_branch_resolution_approach: Option<BaseBranchResolutionApproach>,
+) -> Result<UpstreamIntegrationDryRun> {
+    let StackStatuses::UpdatesRequired {
+        worktree_conflicts,
+        statuses,
+    } = upstream_integration_statuses(context)?
+    else {
+        return Ok(UpstreamIntegrationDryRun::UpToDate);
+    };
+
+    // Nothing that is written here ends up in the object database.
+    let gix_repo =
+        gitbutler_command_context::gix_repo_for_merging(context.repo.path())?.with_object_memory();
+    let recorded_resolutions = &context.recorded_resolutions;
+    let new_target_id = context.new_target.id().to_gix();
+    let lower_bound = rebase_lower_bound(context, base_branch_resolution_approach.as_ref());
+
+    let stacks = statuses
+        .into_iter()
+        .map(|(stack_id, status)| {
+            let Some(stack) = context
+                .stacks_in_workspace
+                .iter()
+                .find(|stack| stack.id == stack_id)
+            else {
+                bail!("Failed to find virtual branch");
+            };
+            let commit_count = stack_details(context.ctx, stack.id)?
+                .branch_details
+                .iter()
+                .map(|branch| branch.commits.len())
+                .sum::<usize>();
+
+            let approaches = [
+                ResolutionApproach::Rebase,
+                ResolutionApproach::Merge,
+                ResolutionApproach::Unapply,
+            ]
+            .into_iter()
+            .map(|approach| {
+                let acceptable = status.resolution_acceptable(&approach);
+                Ok(match approach {
+                    ResolutionApproach::Rebase => {
+                        let StackRebase {
+                            output,
+                            integrated_commit_count,
+                            ..
+                        } = rebase_stack(
+                            context,
+                            &gix_repo,
+                            stack,
+                            &[],
+                            lower_bound,
+                            recorded_resolutions,
+                        )?;
+                        let mut commits = Vec::<CommitDryRun>::new();
+                        for (_base, old, new) in &output.commit_mapping {
+                            // References are mapped too, but the commits they point to were seen before.
+                            if commits.iter().any(|commit| commit.id == Some(*old)) {
+                                continue;
+                            }
+                            let old = but_core::Commit::from_id(old.attach(&gix_repo))?;
+                            let new = but_core::Commit::from_id(new.attach(&gix_repo))?;
+                            commits.push(CommitDryRun {
+                                id: Some(old.id.detach()),
+                                conflicted_paths: conflicted_paths(&new)?,
+                                auto_resolved: new.auto_resolved_hunks().is_some()
+                                    && old.auto_resolved_hunks().is_none(),
+                            });
+                        }
+                        ApproachDryRun {
+                            approach,
+                            acceptable,
+                            commit_count: commits.len(),
+                            commits,
+                            integrated_commit_count,
+                        }
+                    }
+                    ResolutionApproach::Merge => {
+                        // The merge commit is created like `compute_resolutions()` does.
+                        let (_merged_tree, conflict_entries) =
+                            gitbutler_repo::rebase::merge_commit_trees(
+                                &gix_repo,
+                                stack.tip,
+                                new_target_id,
+                            )?;
+                        ApproachDryRun {
+                            approach,
+                            acceptable,
+                            commits: vec![CommitDryRun {
+                                id: None,
+                                conflicted_paths: conflict_entries
+                                    .as_ref()
+                                    .map(entry_paths)
+                                    .unwrap_or_default(),
+                                auto_resolved: false,
+                            }],
+                            commit_count: commit_count + 1,
+                            integrated_commit_count: 0,
+                        }
+                    }
+                    ResolutionApproach::Unapply | ResolutionApproach::Delete => ApproachDryRun {
+                        approach,
+                        acceptable,
+                        commits: vec![],
+                        commit_count,
+                        integrated_commit_count: 0,
+                    },
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+            Ok(StackDryRun {
+                stack_id,
+                status,
+                approaches,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(UpstreamIntegrationDryRun::UpdatesRequired {
+        worktree_conflicts,
+        stacks,
+    })
+}
+
+/// Return all paths that `commit` is conflicted in.
+fn conflicted_paths(commit: &but_core::Commit<'_>) -> Result<Vec<BStringForFrontend>> {
+    Ok(commit
+        .conflict_entries()?
+        .as_ref()
+        .map(entry_paths)
+        .unwrap_or_default())
+}
+
+/// Return all paths of `entries`, without duplicates.
+fn entry_paths(entries: &but_core::commit::ConflictEntries) -> Vec<BStringForFrontend> {
+    let mut paths = Vec::<BStringForFrontend>::new();
+    for path in entries
+        .ancestor_entries
+        .iter()
+        .chain(&entries.our_entries)
+        .chain(&entries.their_entries)
+    {
+        let path = gix::path::into_bstr(path.as_path()).into_owned().into();
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
 // If the commit is in a bucket (branches_before) where the reference matches any of the
 // resolution.force_integrated_branches then we consider it integrated.
 fn forced_integrated(
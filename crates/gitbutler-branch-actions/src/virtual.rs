@@ -292,7 +292,7 @@ pub(crate) struct IsCommitIntegrated<'repo, 'cache, 'graph> {
 
 impl<'repo, 'cache, 'graph> IsCommitIntegrated<'repo, 'cache, 'graph> {
     pub(crate) fn new(
-        ctx: &'repo CommandContext,
+        ctx: &CommandContext,
         target: &Target,
         gix_repo: &'repo gix::Repository,
         graph: &'graph mut MergeBaseCommitGraph<'repo, 'cache>,
@@ -522,6 +522,52 @@ pub(crate) fn insert_blank_commit(
     Ok((blank_commit_id, commit_map))
 }
 
+// marks the automatic conflict resolution of commit_oid as reviewed, rebases everything above it, updates branch head if successful
+pub(crate) fn acknowledge_auto_resolution(
+    ctx: &CommandContext,
+    stack_id: StackId,
+    commit_id: git2::Oid,
+) -> Result<git2::Oid> {
+    let vb_state = ctx.project().virtual_branches();
+    let gix_repo = ctx.gix_repo()?;
+    let mut stack = vb_state.get_stack_in_workspace(stack_id)?;
+
+    let acknowledged =
+        but_rebase::rerere::acknowledge_auto_resolution(&gix_repo, commit_id.to_gix())?;
+    let mut steps = stack.as_rebase_steps(ctx, &gix_repo)?;
+    let mut found = false;
+    for step in steps.iter_mut() {
+        if let RebaseStep::Pick { commit_id: id, .. } = step {
+            if *id == commit_id.to_gix() {
+                *id = acknowledged;
+                found = true;
+            }
+        }
+    }
+    if !found {
+        bail!("commit {commit_id} not in the branch");
+    }
+    let merge_base = stack.merge_base(ctx)?;
+    let mut rebase = but_rebase::Rebase::new(&gix_repo, Some(merge_base), None)?;
+    rebase.steps(steps)?;
+    let output = rebase.rebase()?;
+
+    let new_head = output.top_commit.to_git2();
+    stack.set_stack_head(&vb_state, &gix_repo, new_head, None)?;
+    stack.set_heads_from_rebase_output(ctx, output.references)?;
+
+    crate::integration::update_workspace_commit(&vb_state, ctx)
+        .context("failed to update gitbutler workspace")?;
+
+    output
+        .commit_mapping
+        .iter()
+        .find_map(|(_base, old, new)| (*old == acknowledged).then_some(new.to_git2()))
+        .ok_or(anyhow!(
+            "Failed to find the acknowledged commit id after rebasing"
+        ))
+}
+
 // changes a commit message for commit_oid, rebases everything above it, updates branch head if successful
 pub(crate) fn update_commit_message(
     ctx: &CommandContext,
@@ -1,10 +1,11 @@
 use std::collections::HashMap;
 
 use anyhow::Result;
+use but_rebase::RebaseStep;
 use git2::Oid;
-use gitbutler_branch_actions::{reorder_stack, SeriesOrder, StackOrder};
+use gitbutler_branch_actions::{rebase_stack, reorder_stack, SeriesOrder, StackOrder};
 use gitbutler_command_context::CommandContext;
-use gitbutler_oxidize::{ObjectIdExt, RepoExt};
+use gitbutler_oxidize::{ObjectIdExt, OidExt, RepoExt};
 use gitbutler_stack::VirtualBranchesHandle;
 use gitbutler_testsupport::testing_repository::assert_commit_tree_matches;
 use itertools::Itertools;
@@ -396,6 +397,99 @@ fn conflicting_reorder_stack() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn rebase_stack_keeps_all_branches() -> Result<()> {
+    let (ctx, _temp_dir) = command_ctx("multiple-commits")?;
+    let test_ctx = test_ctx(&ctx)?;
+    let before = vb_commits(&ctx);
+
+    let pick = |commit_id: Oid| RebaseStep::Pick {
+        commit_id: commit_id.to_gix(),
+        new_message: None,
+    };
+    let branch = |name: &str| RebaseStep::Reference(but_core::Reference::Virtual(name.into()));
+    let steps = vec![
+        pick(test_ctx.bottom_commits["commit 1"]),
+        pick(test_ctx.bottom_commits["commit 2"]),
+        pick(test_ctx.bottom_commits["commit 3"]),
+        pick(test_ctx.top_commits["commit 4"]),
+        pick(test_ctx.top_commits["commit 5"]),
+        pick(test_ctx.top_commits["commit 6"]),
+        branch("top-series"),
+    ];
+    let err = rebase_stack(&ctx, test_ctx.stack.id, steps.clone()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "The branch 'my_stack' must be kept, but there is no reference step for it"
+    );
+
+    let mut with_unknown_branch = steps.clone();
+    with_unknown_branch.insert(3, branch("my_stack"));
+    with_unknown_branch.insert(4, branch("unknown"));
+    let err = rebase_stack(&ctx, test_ctx.stack.id, with_unknown_branch).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "The reference 'unknown' isn't a branch of the stack"
+    );
+    assert_eq!(vb_commits(&ctx), before, "nothing was changed");
+
+    let mut all_branches = steps;
+    all_branches.insert(3, branch("my_stack"));
+    rebase_stack(&ctx, test_ctx.stack.id, all_branches)?;
+    let commits = vb_commits(&ctx);
+    assert_eq!(commits[0].msgs(), vec!["commit 6", "commit 5", "commit 4"]);
+    assert_eq!(commits[1].msgs(), vec!["commit 3", "commit 2", "commit 1"]);
+    Ok(())
+}
+
+#[test]
+fn rebase_stack_returns_the_commit_to_stop_at() -> Result<()> {
+    let (ctx, _temp_dir) = command_ctx("multiple-commits")?;
+    let test_ctx = test_ctx(&ctx)?;
+
+    let pick = |commit_id: Oid| RebaseStep::Pick {
+        commit_id: commit_id.to_gix(),
+        new_message: None,
+    };
+    let branch = |name: &str| RebaseStep::Reference(but_core::Reference::Virtual(name.into()));
+    let mut steps = vec![
+        pick(test_ctx.bottom_commits["commit 1"]),
+        pick(test_ctx.bottom_commits["commit 3"]),
+        RebaseStep::Drop {
+            commit_id: test_ctx.bottom_commits["commit 2"].to_gix(),
+        },
+        RebaseStep::Break,
+        branch("my_stack"),
+        pick(test_ctx.top_commits["commit 4"]),
+        pick(test_ctx.top_commits["commit 5"]),
+        pick(test_ctx.top_commits["commit 6"]),
+        branch("top-series"),
+    ];
+    let stopped_at = rebase_stack(&ctx, test_ctx.stack.id, steps.clone())?
+        .expect("the break stops at the commit before it");
+    assert_eq!(
+        ctx.repo().find_commit(stopped_at)?.message(),
+        Some("commit 3"),
+        "dropped commits are skipped"
+    );
+    let commits = vb_commits(&ctx);
+    assert_eq!(
+        commits[0].msgs(),
+        vec!["commit 6", "commit 5", "commit 4"],
+        "all steps are applied, edit mode rebases the commits above the edited one"
+    );
+    assert_eq!(commits[1].msgs(), vec!["commit 3", "commit 1"]);
+    assert_eq!(commits[1].ids()[0], stopped_at);
+
+    steps.insert(0, RebaseStep::Break);
+    let err = rebase_stack(&ctx, test_ctx.stack.id, steps).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Only a single 'break' is supported when rebasing a stack"
+    );
+    Ok(())
+}
+
 fn order(series: Vec<Vec<Oid>>) -> StackOrder {
     StackOrder {
         series: vec![
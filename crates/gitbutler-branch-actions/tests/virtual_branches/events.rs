@@ -0,0 +1,80 @@
+use but_workspace::DiffSpec;
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_command_context::Event;
+
+use super::*;
+
+#[test]
+fn commits_created_by_the_commit_engine_are_recorded() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &Test::default();
+
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse()?,
+        false,
+        ctx.project().exclusive_worktree_access().write_permission(),
+    )?;
+    let stack_entry = gitbutler_branch_actions::create_virtual_branch(
+        ctx,
+        &BranchCreateRequest::default(),
+        ctx.project().exclusive_worktree_access().write_permission(),
+    )?;
+    let branch_name = stack_entry.name().map(|n| n.to_string()).unwrap();
+    fs::write(repo.path().join("file.txt"), "content")?;
+    ctx.take_events();
+
+    let outcome = but_workspace::commit_engine::create_commit_simple(
+        ctx,
+        stack_entry.id,
+        None,
+        vec![DiffSpec {
+            previous_path: None,
+            path: "file.txt".into(),
+            hunk_headers: vec![],
+        }],
+        "commit".into(),
+        branch_name.clone(),
+        ctx.project().exclusive_worktree_access().write_permission(),
+    )?;
+    assert_eq!(
+        ctx.take_events(),
+        [Event::CommitCreated {
+            stack_id: stack_entry.id,
+            branch_name,
+            commit_id: outcome.new_commit.expect("the change was committed"),
+        }]
+    );
+    assert!(ctx.take_events().is_empty(), "events are only taken once");
+    Ok(())
+}
+
+#[test]
+fn integrating_upstream_records_that_the_base_was_updated() -> anyhow::Result<()> {
+    let Test { repo, ctx, .. } = &mut Test::default();
+
+    {
+        // make sure there is an upstream commit to integrate
+        fs::write(repo.path().join("file.txt"), "one")?;
+        let first_commit_oid = repo.commit_all("first");
+        fs::write(repo.path().join("file.txt"), "upstream")?;
+        repo.commit_all("second");
+        repo.push();
+        repo.reset_hard(Some(first_commit_oid));
+    }
+
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse()?,
+        false,
+        ctx.project().exclusive_worktree_access().write_permission(),
+    )?;
+    ctx.take_events();
+
+    gitbutler_branch_actions::integrate_upstream(ctx, &[], None)?;
+    assert_eq!(ctx.take_events(), [Event::BaseUpdated]);
+    assert_eq!(
+        fs::read_to_string(repo.path().join("file.txt"))?,
+        "upstream"
+    );
+    Ok(())
+}
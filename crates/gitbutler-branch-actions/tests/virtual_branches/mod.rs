@@ -66,6 +66,7 @@ impl Test {
 mod amend;
 mod apply_virtual_branch;
 mod create_virtual_branch_from_branch;
+mod events;
 mod init;
 mod insert_blank_commit;
 mod list;
@@ -77,4 +78,5 @@ mod set_base_branch;
 mod unapply_without_saving_virtual_branch;
 mod undo_commit;
 mod update_commit_message;
+mod upstream_integration;
 mod workspace_migration;
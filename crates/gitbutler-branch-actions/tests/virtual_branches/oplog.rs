@@ -1,10 +1,16 @@
-use std::{io::Write, path::Path, time::Duration};
+use std::{
+    collections::{BTreeMap, HashSet},
+    io::Write,
+    path::Path,
+    time::{Duration, SystemTime},
+};
 
 use gitbutler_branch::BranchCreateRequest;
 use gitbutler_branch_actions::list_commit_files;
-use gitbutler_oplog::OplogExt;
+use gitbutler_oplog::{entry::OperationKind, OplogExt, SnapshotQuery};
 use gitbutler_oxidize::ObjectIdExt;
-use gitbutler_stack::VirtualBranchesHandle;
+use gitbutler_project::OplogRetention;
+use gitbutler_stack::{StackId, VirtualBranchesHandle};
 use gitbutler_testsupport::stack_details;
 use itertools::Itertools;
 
@@ -456,3 +462,476 @@ fn first_snapshot_diff_works() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn query_snapshots() -> anyhow::Result<()> {
+    let test = Test::default();
+    let Test { ctx, .. } = &test;
+    branch_with_commits(
+        &test,
+        &[("file.txt", "commit one"), ("dir/file.txt", "commit two")],
+    )?;
+
+    let all = ctx.list_snapshots(10, None, Vec::new())?;
+    assert_eq!(
+        ctx.query_snapshots(&SnapshotQuery::default(), 10)?,
+        all,
+        "the default query matches everything"
+    );
+    assert_eq!(
+        ctx.query_snapshots(&SnapshotQuery::default(), 1)?,
+        all[..1],
+        "the limit is respected"
+    );
+
+    let commit_message = |query: SnapshotQuery| -> anyhow::Result<Vec<String>> {
+        Ok(ctx
+            .query_snapshots(&query, 10)?
+            .into_iter()
+            .filter_map(|s| {
+                s.details?
+                    .trailers
+                    .into_iter()
+                    .find(|t| t.key == "message")
+                    .map(|t| t.value)
+            })
+            .collect())
+    };
+    let by_kind = ctx.query_snapshots(
+        &SnapshotQuery {
+            kinds: vec![OperationKind::CreateCommit],
+            ..Default::default()
+        },
+        10,
+    )?;
+    assert_eq!(by_kind.len(), 2, "only the commits are selected");
+
+    assert_eq!(
+        commit_message(SnapshotQuery {
+            trailer: Some(("message".into(), Some("commit one".into()))),
+            ..Default::default()
+        })?,
+        ["commit one"]
+    );
+    assert_eq!(
+        commit_message(SnapshotQuery {
+            path: Some("dir/".into()),
+            ..Default::default()
+        })?,
+        ["commit two"],
+        "directories match everything inside of them"
+    );
+    assert_eq!(
+        commit_message(SnapshotQuery {
+            path: Some("file.txt".into()),
+            ..Default::default()
+        })?,
+        ["commit one"],
+        "files match exactly, and not by name alone"
+    );
+    assert!(ctx
+        .query_snapshots(
+            &SnapshotQuery {
+                path: Some("di".into()),
+                ..Default::default()
+            },
+            10
+        )?
+        .is_empty());
+
+    let newest = all[0].created_at.seconds();
+    assert!(ctx
+        .query_snapshots(
+            &SnapshotQuery {
+                since: Some(newest + 1),
+                ..Default::default()
+            },
+            10
+        )?
+        .is_empty());
+    assert_eq!(
+        ctx.query_snapshots(
+            &SnapshotQuery {
+                until: Some(newest),
+                ..Default::default()
+            },
+            10
+        )?,
+        all
+    );
+    Ok(())
+}
+
+#[test]
+fn compact_keeps_retained_snapshots_with_their_details() -> anyhow::Result<()> {
+    let test = Test::default();
+    let Test { project, ctx, .. } = &test;
+    let before = branch_with_three_commits(&test)?;
+    assert_eq!(before.len(), 4, "one vbranch, three commits");
+
+    let mut guard = project.exclusive_worktree_access();
+    let outcome = ctx.compact(&keep_latest(2), guard.write_permission())?;
+    drop(guard);
+
+    let after = ctx.list_snapshots(100, None, Vec::new())?;
+    assert_eq!(outcome.snapshots_before, 4);
+    assert_eq!(
+        outcome.snapshots_removed,
+        before.len() - after.len(),
+        "the outcome matches what's visible"
+    );
+    assert_eq!(
+        after.len(),
+        2,
+        "only the latest ones are kept as all others are from the same day"
+    );
+    assert_eq!(
+        after
+            .iter()
+            .map(|s| (s.details.clone(), s.created_at))
+            .collect::<Vec<_>>(),
+        before[..2]
+            .iter()
+            .map(|s| (s.details.clone(), s.created_at))
+            .collect::<Vec<_>>(),
+        "details and times are preserved"
+    );
+    assert_ne!(
+        after[1].commit_id, before[1].commit_id,
+        "the chain was rewritten"
+    );
+
+    let mut guard = project.exclusive_worktree_access();
+    ctx.restore_snapshot(after[1].commit_id, guard.write_permission())
+        .expect("rewritten snapshots can still be restored");
+    Ok(())
+}
+
+/// Create a virtual branch with a commit for each path and message in `commits`, and return the id of its stack
+/// along with the ids of the commits.
+fn branch_with_commits(
+    test: &Test,
+    commits: &[(&str, &str)],
+) -> anyhow::Result<(StackId, Vec<git2::Oid>)> {
+    let Test { repo, ctx, .. } = test;
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse()?,
+        false,
+        ctx.project().exclusive_worktree_access().write_permission(),
+    )?;
+    let stack_entry = gitbutler_branch_actions::create_virtual_branch(
+        ctx,
+        &BranchCreateRequest::default(),
+        ctx.project().exclusive_worktree_access().write_permission(),
+    )?;
+    let mut commit_ids = Vec::new();
+    for (path, message) in commits {
+        let path = repo.path().join(path);
+        fs::create_dir_all(path.parent().expect("files are inside the worktree"))?;
+        fs::write(path, "content")?;
+        commit_ids.push(gitbutler_branch_actions::create_commit(
+            ctx,
+            stack_entry.id,
+            message,
+            None,
+        )?);
+    }
+    Ok((stack_entry.id, commit_ids))
+}
+
+/// Create a virtual branch with three commits, and return the snapshots that were created, most recent first.
+fn branch_with_three_commits(test: &Test) -> anyhow::Result<Vec<gitbutler_oplog::entry::Snapshot>> {
+    branch_with_commits(
+        test,
+        &[
+            ("file0.txt", "commit 0"),
+            ("file1.txt", "commit 1"),
+            ("file2.txt", "commit 2"),
+        ],
+    )?;
+    let Test { ctx, .. } = test;
+    // Assure all snapshots are in the past, as those created in the current second count as new.
+    std::thread::sleep(Duration::from_millis(1100));
+    Ok(ctx.list_snapshots(100, None, Vec::new())?)
+}
+
+/// Return the size of each loose object in `objects_dir` by its id.
+fn loose_objects(objects_dir: &Path) -> anyhow::Result<BTreeMap<git2::Oid, u64>> {
+    let mut objects = BTreeMap::new();
+    for fan_out in fs::read_dir(objects_dir)? {
+        let fan_out = fan_out?;
+        let prefix = fan_out.file_name().to_string_lossy().into_owned();
+        if prefix.len() != 2 || !fan_out.file_type()?.is_dir() {
+            continue;
+        }
+        for object in fs::read_dir(fan_out.path())? {
+            let object = object?;
+            let id = format!("{prefix}{}", object.file_name().to_string_lossy());
+            objects.insert(git2::Oid::from_str(&id)?, object.metadata()?.len());
+        }
+    }
+    Ok(objects)
+}
+
+fn keep_latest(keep_latest: usize) -> OplogRetention {
+    OplogRetention {
+        keep_all_days: 0,
+        keep_hourly_days: 0,
+        keep_latest,
+    }
+}
+
+#[test]
+fn compact_only_deletes_objects_of_removed_snapshots() -> anyhow::Result<()> {
+    let test = Test::default();
+    let Test { project, ctx, .. } = &test;
+    let before = branch_with_three_commits(&test)?;
+
+    // An object nobody refers to, old enough for `git prune` to delete it.
+    let unrelated = ctx.repo().blob(b"unreachable, but not ours to delete")?;
+    let objects_dir = ctx.repo().path().join("objects");
+    let hex = unrelated.to_string();
+    fs::File::open(objects_dir.join(&hex[..2]).join(&hex[2..]))?
+        .set_modified(SystemTime::now() - Duration::from_secs(30 * 24 * 60 * 60))?;
+
+    let objects_before = loose_objects(&objects_dir)?;
+    let mut guard = project.exclusive_worktree_access();
+    let outcome = ctx.compact(&keep_latest(2), guard.write_permission())?;
+    drop(guard);
+    let objects_after = loose_objects(&objects_dir)?;
+
+    assert!(
+        ctx.repo().find_blob(unrelated).is_ok(),
+        "unreachable objects that aren't snapshots are left to `git gc`"
+    );
+    let deleted: Vec<_> = objects_before
+        .keys()
+        .filter(|id| !objects_after.contains_key(id))
+        .collect();
+    assert_eq!(
+        outcome.bytes_removed,
+        deleted.iter().map(|id| objects_before[id]).sum::<u64>(),
+        "exactly the size of the deleted objects is reported"
+    );
+    let snapshot_objects: HashSet<_> = before
+        .iter()
+        .flat_map(|snapshot| {
+            let commit = ctx.repo().find_commit(snapshot.commit_id).unwrap();
+            [commit.id(), commit.tree_id()]
+        })
+        .collect();
+    assert_eq!(
+        deleted.len(),
+        before.len() - 2 /* removed snapshot commits */
+            + 2 /* replaced commits of the kept snapshots */
+            + 2, /* trees of the removed snapshots */
+        "the trees of the kept snapshots are still used"
+    );
+    assert!(
+        deleted.iter().all(|id| snapshot_objects.contains(*id)),
+        "only objects of the previous snapshot chain are deleted"
+    );
+    Ok(())
+}
+
+#[test]
+fn compact_keeps_and_remaps_restored_snapshots() -> anyhow::Result<()> {
+    let test = Test::default();
+    let Test { project, ctx, .. } = &test;
+    let before = branch_with_three_commits(&test)?;
+    let restored = &before[2];
+
+    let mut guard = project.exclusive_worktree_access();
+    ctx.restore_snapshot(restored.commit_id, guard.write_permission())?;
+    drop(guard);
+    std::thread::sleep(Duration::from_millis(1100));
+
+    let mut guard = project.exclusive_worktree_access();
+    let outcome = ctx.compact(&keep_latest(1), guard.write_permission())?;
+    drop(guard);
+
+    let after = ctx.list_snapshots(100, None, Vec::new())?;
+    assert_eq!(
+        outcome.snapshots_removed, 3,
+        "all but the restore and the snapshot it restored are removed"
+    );
+    assert_eq!(after.len(), 2);
+    assert_eq!(after[1].details, restored.details);
+    assert_ne!(
+        after[1].commit_id, restored.commit_id,
+        "the restored snapshot was rewritten as the snapshot below it was removed"
+    );
+    let restored_from = after[0]
+        .details
+        .as_ref()
+        .and_then(|details| {
+            details
+                .trailers
+                .iter()
+                .find(|trailer| trailer.key == "restored_from")
+        })
+        .map(|trailer| trailer.value.clone());
+    assert_eq!(
+        restored_from,
+        Some(after[1].commit_id.to_string()),
+        "the restore refers to the rewritten snapshot"
+    );
+    Ok(())
+}
+
+#[test]
+fn undo_steps_back_and_redo_walks_forward() -> anyhow::Result<()> {
+    let test = Test::default();
+    let Test { project, ctx, .. } = &test;
+    let (stack_id, _) = branch_with_commits(
+        &test,
+        &[("file0.txt", "commit 0"), ("file1.txt", "commit 1")],
+    )?;
+    let commit_count = || {
+        stack_details(ctx)
+            .into_iter()
+            .find(|d| d.0 == stack_id)
+            .map(|(_, details)| details.branch_details[0].commits.len())
+            .unwrap()
+    };
+    assert_eq!(commit_count(), 2);
+
+    let mut guard = project.exclusive_worktree_access();
+    let undone = ctx
+        .undo(guard.write_permission())?
+        .expect("there is something to undo");
+    assert_eq!(
+        undone.details.as_ref().map(|d| d.operation),
+        Some(OperationKind::CreateCommit)
+    );
+    assert_eq!(commit_count(), 1);
+
+    ctx.undo(guard.write_permission())?;
+    assert_eq!(commit_count(), 0, "repeated undos step further back");
+    let last_restore = &ctx.list_snapshots(1, None, Vec::new())?[0];
+    assert_eq!(
+        last_restore.details.as_ref().and_then(|d| {
+            d.trailers
+                .iter()
+                .find(|t| t.key == "restore_action")
+                .map(|t| t.value.as_str())
+        }),
+        Some("undo"),
+        "undos are recorded as such"
+    );
+
+    ctx.redo(guard.write_permission())?
+        .expect("there is something to redo");
+    assert_eq!(commit_count(), 1);
+    ctx.redo(guard.write_permission())?;
+    assert_eq!(commit_count(), 2, "redo walks forward to where we started");
+    assert!(
+        ctx.redo(guard.write_permission())?.is_none(),
+        "everything was redone"
+    );
+
+    ctx.undo(guard.write_permission())?;
+    assert_eq!(commit_count(), 1, "a redo can be undone");
+    Ok(())
+}
+
+#[test]
+fn exported_bundles_can_be_restored_after_import() -> anyhow::Result<()> {
+    let mut test = Test::default();
+    branch_with_commits(&test, &[("file.txt", "commit")])?;
+    let Test { project, ctx, .. } = &mut test;
+    let snapshots = ctx.list_snapshots(100, None, Vec::new())?;
+
+    let tmp = tempfile::tempdir()?;
+    let bundle_path = tmp.path().join("oplog.bundle");
+    let guard = project.shared_worktree_access();
+    gitbutler_oplog::export_bundle(ctx, &bundle_path, guard.read_permission())?;
+    drop(guard);
+    assert!(
+        ctx.repo()
+            .find_reference("refs/gitbutler/export/oplog")
+            .is_err(),
+        "the references needed for the bundle are removed afterwards"
+    );
+
+    let scratch_dir = tmp.path().join("scratch");
+    let oplog_head = gitbutler_oplog::import_bundle(&bundle_path, &scratch_dir)?;
+    assert_eq!(oplog_head, snapshots[0].commit_id);
+
+    let scratch_project = gitbutler_project::Project::from_path(&scratch_dir)?;
+    let scratch_ctx = CommandContext::open(&scratch_project, AppSettings::default())?;
+    assert_eq!(
+        scratch_ctx.list_snapshots(100, None, Vec::new())?,
+        snapshots,
+        "all snapshots are available"
+    );
+    assert_eq!(
+        fs::read_to_string(scratch_dir.join("file.txt"))?,
+        "content",
+        "the workspace is checked out"
+    );
+
+    let mut guard = scratch_project.exclusive_worktree_access();
+    scratch_ctx.restore_snapshot(snapshots[1].commit_id, guard.write_permission())?;
+    assert!(
+        !scratch_dir.join("file.txt").exists(),
+        "the state before the branch was created didn't have the file yet"
+    );
+    Ok(())
+}
+
+#[test]
+fn exported_bundles_contain_unapplied_stacks_and_leave_nothing_behind() -> anyhow::Result<()> {
+    let mut test = Test::default();
+    let (stack_id, commit_ids) = branch_with_commits(&test, &[("file.txt", "commit")])?;
+    let unapplied_commit = commit_ids[0];
+    let Test { project, ctx, .. } = &mut test;
+    let (_, details) = stack_details(ctx)
+        .into_iter()
+        .find(|(id, _)| *id == stack_id)
+        .unwrap();
+    let unapplied_ref = format!("refs/heads/{}", details.derived_name);
+    gitbutler_branch_actions::unapply_stack(ctx, stack_id, Vec::new())?;
+    assert_eq!(
+        ctx.repo().find_reference(&unapplied_ref)?.target(),
+        Some(unapplied_commit),
+        "unapplied stacks keep their branch"
+    );
+
+    let objects_dir = ctx.repo().path().join("objects");
+    let objects_before = loose_objects(&objects_dir)?;
+    let tmp = tempfile::tempdir()?;
+    let bundle_path = tmp.path().join("oplog.bundle");
+    let guard = project.shared_worktree_access();
+    gitbutler_oplog::export_bundle(ctx, &bundle_path, guard.read_permission())?;
+    drop(guard);
+    assert!(
+        ctx.repo()
+            .find_reference("refs/gitbutler/export/state")
+            .is_err(),
+        "the reference to the state is removed"
+    );
+    assert_eq!(
+        loose_objects(&objects_dir)?,
+        objects_before,
+        "the objects written for the state don't remain in the repository"
+    );
+
+    let scratch_dir = tmp.path().join("scratch");
+    gitbutler_oplog::import_bundle(&bundle_path, &scratch_dir)?;
+    let scratch_repo = git2::Repository::open(&scratch_dir)?;
+    assert_eq!(
+        scratch_repo.find_reference(&unapplied_ref)?.target(),
+        Some(unapplied_commit),
+        "the branch of the unapplied stack is part of the bundle"
+    );
+    assert_eq!(
+        scratch_repo
+            .find_commit(unapplied_commit)?
+            .message()
+            .unwrap(),
+        "commit"
+    );
+    Ok(())
+}
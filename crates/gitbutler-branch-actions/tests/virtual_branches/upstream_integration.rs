@@ -0,0 +1,172 @@
+use bstr::ByteSlice;
+use gitbutler_branch::BranchCreateRequest;
+use gitbutler_branch_actions::upstream_integration::{
+    ApproachDryRun, Resolution, ResolutionApproach, UpstreamIntegrationDryRun,
+};
+use gitbutler_stack::StackId;
+use gitbutler_testsupport::stack_details;
+use gix::prelude::ObjectIdExt;
+
+use super::*;
+
+#[test]
+fn dry_run_predicts_the_outcome_of_a_rebase() {
+    let Test { repo, ctx, .. } = &mut Test::default();
+    let stack_id = stack_conflicting_with_upstream(repo, ctx);
+    let commits_before = commits_of(ctx, stack_id);
+
+    let predicted = dry_run(ctx, ResolutionApproach::Rebase);
+    assert_eq!(
+        commits_of(ctx, stack_id),
+        commits_before,
+        "the dry run changes nothing"
+    );
+    assert!(predicted.acceptable);
+    assert_eq!(predicted.commit_count, 1);
+    assert_eq!(predicted.integrated_commit_count, 0);
+    assert_eq!(
+        paths(&predicted),
+        [vec!["file.txt".to_owned()]],
+        "the commit changes the same line as upstream"
+    );
+
+    integrate(ctx, stack_id, ResolutionApproach::Rebase);
+    let commits = commits_of(ctx, stack_id);
+    assert_eq!(commits.len(), predicted.commit_count);
+    assert_eq!(
+        commits
+            .iter()
+            .map(|id| conflicted_paths(ctx, *id))
+            .collect::<Vec<_>>(),
+        paths(&predicted),
+        "the dry run is what actually happens"
+    );
+}
+
+#[test]
+fn dry_run_predicts_the_outcome_of_a_merge() {
+    let Test { repo, ctx, .. } = &mut Test::default();
+    let stack_id = stack_conflicting_with_upstream(repo, ctx);
+
+    let predicted = dry_run(ctx, ResolutionApproach::Merge);
+    assert!(predicted.acceptable);
+    assert_eq!(predicted.commit_count, 2, "a merge commit is added");
+    assert_eq!(predicted.commits.len(), 1);
+    assert_eq!(predicted.commits[0].id, None, "the merge commit is new");
+    assert_eq!(paths(&predicted), [vec!["file.txt".to_owned()]]);
+
+    integrate(ctx, stack_id, ResolutionApproach::Merge);
+    let commits = commits_of(ctx, stack_id);
+    assert_eq!(commits.len(), predicted.commit_count);
+    assert_eq!(
+        conflicted_paths(ctx, commits[0]),
+        paths(&predicted)[0],
+        "the merge commit is conflicted just like predicted"
+    );
+}
+
+/// Create a stack with a commit that changes `file.txt`, which is changed upstream as well.
+fn stack_conflicting_with_upstream(repo: &TestProject, ctx: &CommandContext) -> StackId {
+    fs::write(repo.path().join("file.txt"), "one").unwrap();
+    let first_commit_oid = repo.commit_all("first");
+    fs::write(repo.path().join("file.txt"), "two").unwrap();
+    repo.commit_all("second");
+    repo.push();
+    repo.reset_hard(Some(first_commit_oid));
+
+    gitbutler_branch_actions::set_base_branch(
+        ctx,
+        &"refs/remotes/origin/master".parse().unwrap(),
+        false,
+        ctx.project().exclusive_worktree_access().write_permission(),
+    )
+    .unwrap();
+
+    let stack_entry = gitbutler_branch_actions::create_virtual_branch(
+        ctx,
+        &BranchCreateRequest::default(),
+        ctx.project().exclusive_worktree_access().write_permission(),
+    )
+    .unwrap();
+    fs::write(repo.path().join("file.txt"), "virtual").unwrap();
+    gitbutler_branch_actions::create_commit(ctx, stack_entry.id, "virtual commit", None).unwrap();
+    stack_entry.id
+}
+
+fn dry_run(ctx: &mut CommandContext, approach: ResolutionApproach) -> ApproachDryRun {
+    let UpstreamIntegrationDryRun::UpdatesRequired { mut stacks, .. } =
+        gitbutler_branch_actions::upstream_integration_dry_run(ctx, None).unwrap()
+    else {
+        panic!("upstream changes are to be integrated");
+    };
+    assert_eq!(stacks.len(), 1);
+    let stack = stacks.remove(0);
+    stack
+        .approaches
+        .into_iter()
+        .find(|candidate| candidate.approach == approach)
+        .expect("every approach is tried")
+}
+
+fn integrate(ctx: &mut CommandContext, stack_id: StackId, approach: ResolutionApproach) {
+    gitbutler_branch_actions::integrate_upstream(
+        ctx,
+        &[Resolution {
+            stack_id,
+            approach,
+            delete_integrated_branches: false,
+            force_integrated_branches: vec![],
+        }],
+        None,
+    )
+    .unwrap();
+}
+
+/// The commits of the stack with `stack_id`, top-most first.
+fn commits_of(ctx: &CommandContext, stack_id: StackId) -> Vec<gix::ObjectId> {
+    let (_, details) = stack_details(ctx)
+        .into_iter()
+        .find(|(id, _)| *id == stack_id)
+        .unwrap();
+    details
+        .branch_details
+        .iter()
+        .flat_map(|branch| branch.commits.iter().map(|commit| commit.id))
+        .collect()
+}
+
+/// The conflicted paths of each commit of the `approach`, in order.
+fn paths(approach: &ApproachDryRun) -> Vec<Vec<String>> {
+    approach
+        .commits
+        .iter()
+        .map(|commit| {
+            commit
+                .conflicted_paths
+                .iter()
+                .map(|path| path.to_str_lossy().into_owned())
+                .collect()
+        })
+        .collect()
+}
+
+fn conflicted_paths(ctx: &CommandContext, commit_id: gix::ObjectId) -> Vec<String> {
+    let repo = ctx.gix_repo().unwrap();
+    let commit = but_core::Commit::from_id(commit_id.attach(&repo)).unwrap();
+    let Some(entries) = commit.conflict_entries().unwrap() else {
+        return vec![];
+    };
+    let mut paths = Vec::<String>::new();
+    for path in entries
+        .ancestor_entries
+        .iter()
+        .chain(&entries.our_entries)
+        .chain(&entries.their_entries)
+    {
+        let path = path.to_string_lossy().into_owned();
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+}
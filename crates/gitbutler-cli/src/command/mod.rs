@@ -21,7 +21,8 @@ pub mod workspace {
             UpdateMode::Unapply => upstream_integration::ResolutionApproach::Unapply,
             UpdateMode::Delete => upstream_integration::ResolutionApproach::Delete,
         };
-        let ctx = CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
+        let mut ctx =
+            CommandContext::open(&project, AppSettings::load_from_default_path_creating()?)?;
         let resolutions: Vec<_> = super::vbranch::stacks(&ctx)?
             .into_iter()
             .map(|(id, _details)| upstream_integration::Resolution {
@@ -31,7 +32,7 @@ pub mod workspace {
                 force_integrated_branches: vec![],
             })
             .collect();
-        gitbutler_branch_actions::integrate_upstream(&ctx, &resolutions, None)?;
+        gitbutler_branch_actions::integrate_upstream(&mut ctx, &resolutions, None)?;
 
         Ok(())
     }
@@ -4,6 +4,7 @@ use gitbutler_project::access::{WorktreeReadPermission, WorktreeWritePermission}
 use gitbutler_project::Project;
 use std::ops::{Deref, DerefMut};
 use std::path::Path;
+use std::sync::Mutex;
 
 pub struct CommandContext {
     /// The git repository of the `project` itself.
@@ -13,6 +14,25 @@ pub struct CommandContext {
     /// A snapshot of the app settings obtained at the beginnig of each command.
     app_settings: AppSettings,
     db_handle: Option<but_db::DbHandle>,
+    /// Events that happened while running the command, for the caller to react to once it's done.
+    events: Mutex<Vec<Event>>,
+}
+
+/// Something that happened while running a command which may be of interest once the command is done,
+/// like to evaluate workspace rules.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// A commit was created in a stack.
+    CommitCreated {
+        /// The stack the commit was created in.
+        stack_id: but_core::ref_metadata::StackId,
+        /// The short name of the branch the commit was created on.
+        branch_name: String,
+        /// The id of the new commit.
+        commit_id: gix::ObjectId,
+    },
+    /// Upstream changes were integrated into the workspace.
+    BaseUpdated,
 }
 
 /// A [`but_graph::VirtualBranchesTomlMetadata`] instance that is only accessible if it sees a read
@@ -68,6 +88,7 @@ impl CommandContext {
             project: project.clone(),
             app_settings,
             db_handle: None,
+            events: Mutex::new(Vec::new()),
         })
     }
 
@@ -183,6 +204,19 @@ impl CommandContext {
     pub fn app_settings(&self) -> &AppSettings {
         &self.app_settings
     }
+
+    /// Remember that `event` happened, to be obtained with [`Self::take_events()`] once the command is done.
+    pub fn record_event(&self, event: Event) {
+        self.events
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .push(event);
+    }
+
+    /// Return all events recorded with [`Self::record_event()`] so far, in order, and forget them.
+    pub fn take_events(&self) -> Vec<Event> {
+        std::mem::take(&mut *self.events.lock().unwrap_or_else(|err| err.into_inner()))
+    }
 }
 
 /// Keep these private
@@ -1,3 +1,5 @@
+use std::path::Path;
+
 use anyhow::{Context, Result};
 use but_core::ui::TreeChange;
 use but_workspace::StackId;
@@ -8,7 +10,10 @@ use gitbutler_oplog::{
     OplogExt,
 };
 
-use crate::ConflictEntryPresence;
+use crate::{
+    resolve::{ConflictSide, ConflictedPath},
+    ConflictEntryPresence,
+};
 
 pub fn enter_edit_mode(
     ctx: &CommandContext,
@@ -41,7 +46,7 @@ pub fn enter_edit_mode(
     Ok(edit_mode_metadata)
 }
 
-pub fn save_and_return_to_workspace(ctx: &CommandContext) -> Result<()> {
+pub fn save_and_return_to_workspace(ctx: &mut CommandContext) -> Result<()> {
     let mut guard = ctx.project().exclusive_worktree_access();
 
     ensure_edit_mode(ctx).context("Edit mode may only be left while in edit mode")?;
@@ -49,6 +54,22 @@ pub fn save_and_return_to_workspace(ctx: &CommandContext) -> Result<()> {
     crate::save_and_return_to_workspace(ctx, guard.write_permission())
 }
 
+/// Like [`save_and_return_to_workspace()`], but fails if any of the paths that were conflicted in the edited commit
+/// still contain conflict markers, unless `force` is `true`.
+pub fn finish_resolution_and_return_to_workspace(
+    ctx: &mut CommandContext,
+    force: bool,
+) -> Result<()> {
+    let mut guard = ctx.project().exclusive_worktree_access();
+
+    ensure_edit_mode(ctx).context("Conflicts may only be resolved while in edit mode")?;
+
+    if !force {
+        crate::resolve::ensure_resolved(ctx)?;
+    }
+    crate::save_and_return_to_workspace(ctx, guard.write_permission())
+}
+
 pub fn abort_and_return_to_workspace(ctx: &CommandContext) -> Result<()> {
     let mut guard = ctx.project().exclusive_worktree_access();
 
@@ -76,3 +97,32 @@ pub fn changes_from_initial(ctx: &CommandContext) -> Result<Vec<TreeChange>> {
     let state = crate::changes_from_initial(ctx, guard.read_permission())?;
     Ok(state.into_iter().map(|a| a.into()).collect())
 }
+
+pub fn conflicted_paths(ctx: &CommandContext) -> Result<Vec<ConflictedPath>> {
+    let _guard = ctx.project().shared_worktree_access();
+
+    ensure_edit_mode(ctx).context("Conflicts may only be listed while in edit mode")?;
+
+    crate::resolve::conflicted_paths(ctx)
+}
+
+pub fn take_conflict_side(
+    ctx: &CommandContext,
+    path: &Path,
+    side: ConflictSide,
+    hunk: Option<usize>,
+) -> Result<()> {
+    let _guard = ctx.project().exclusive_worktree_access();
+
+    ensure_edit_mode(ctx).context("Conflicts may only be resolved while in edit mode")?;
+
+    crate::resolve::take_side(ctx, path, side, hunk)
+}
+
+pub fn write_conflict_markers(ctx: &CommandContext, path: &Path) -> Result<()> {
+    let _guard = ctx.project().exclusive_worktree_access();
+
+    ensure_edit_mode(ctx).context("Conflicts may only be resolved while in edit mode")?;
+
+    crate::resolve::write_conflict_markers(ctx, path)
+}
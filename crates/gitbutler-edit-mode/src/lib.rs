@@ -27,6 +27,7 @@ use gitbutler_workspace::branch_trees::{update_uncommited_changes_with_tree, Wor
 use serde::Serialize;
 
 pub mod commands;
+pub mod resolve;
 
 const UNCOMMITTED_CHANGES_REF: &str = "refs/gitbutler/edit-uncommitted-changes";
 
@@ -211,10 +212,15 @@ pub(crate) fn abort_and_return_to_workspace(
 }
 
 pub(crate) fn save_and_return_to_workspace(
-    ctx: &CommandContext,
+    ctx: &mut CommandContext,
     perm: &mut WorktreeWritePermission,
 ) -> Result<()> {
     let edit_mode_metadata = read_edit_mode_metadata(ctx).context("Failed to read metadata")?;
+    if let Err(err) = resolve::record_resolutions(ctx, edit_mode_metadata.commit_oid) {
+        tracing::warn!("Failed to record conflict resolutions: {err:#}");
+    }
+    let resolutions = resolve::recorded_resolutions(ctx)?;
+    let ctx = &*ctx;
     let repository = ctx.repo();
     let vb_state = VirtualBranchesHandle::new(ctx.project().gb_dir());
 
@@ -269,6 +275,8 @@ pub(crate) fn save_and_return_to_workspace(
     let merge_base = stack.merge_base(ctx)?;
     let mut rebase = but_rebase::Rebase::new(&gix_repo, Some(merge_base), None)?;
     rebase.rebase_noops(false);
+    // Commits above that conflict the same way are resolved like the edited commit was.
+    rebase.resolutions(resolutions);
     rebase.steps(steps)?;
     let output = rebase.rebase()?;
 
@@ -0,0 +1,300 @@
+//! Resolve the conflicts of a conflicted commit that is checked out in edit mode, file by file or hunk by hunk.
+use std::path::{Component, Path};
+
+use anyhow::{bail, Context, Result};
+use bstr::{BString, ByteSlice};
+use but_core::commit::TreeKind;
+use but_rebase::rerere;
+use git2::build::CheckoutBuilder;
+use gitbutler_cherry_pick::{ConflictedTreeKey, RepositoryExt as _};
+use gitbutler_command_context::CommandContext;
+use gitbutler_commit::commit_ext::CommitExt;
+use gitbutler_operating_modes::read_edit_mode_metadata;
+use gitbutler_oxidize::OidExt;
+use gix::prelude::ObjectIdExt;
+use serde::{Deserialize, Serialize};
+
+use crate::{get_commit_index, ConflictEntryPresence};
+
+/// The side of a conflict to take when resolving it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictSide {
+    /// The side the commit was rebased onto.
+    Ours,
+    /// The side of the commit that was rebased.
+    Theirs,
+    /// The common ancestor of both sides.
+    Base,
+}
+
+impl ConflictSide {
+    fn tree_key(&self) -> ConflictedTreeKey {
+        match self {
+            ConflictSide::Ours => ConflictedTreeKey::Ours,
+            ConflictSide::Theirs => ConflictedTreeKey::Theirs,
+            ConflictSide::Base => ConflictedTreeKey::Base,
+        }
+    }
+}
+
+/// A path that was conflicted in the commit that is edited, along with its state in the worktree.
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictedPath {
+    /// The path relative to the root of the worktree.
+    #[serde(serialize_with = "gitbutler_serde::bstring_lossy::serialize")]
+    pub path: BString,
+    /// The sides that are present for this path.
+    pub presence: ConflictEntryPresence,
+    /// The amount of hunks with conflict markers that remain in the worktree file.
+    pub unresolved_hunks: usize,
+}
+
+impl ConflictedPath {
+    /// Return `true` if there are no conflict markers left in the worktree file.
+    pub fn is_resolved(&self) -> bool {
+        self.unresolved_hunks == 0
+    }
+}
+
+/// List all paths that are conflicted in the commit that is edited, in the order they are recorded in the commit.
+pub(crate) fn conflicted_paths(ctx: &CommandContext) -> Result<Vec<ConflictedPath>> {
+    let metadata = read_edit_mode_metadata(ctx).context("Failed to read metadata")?;
+    let gix_repo = ctx.gix_repo()?;
+    let commit = but_core::Commit::from_id(metadata.commit_oid.to_gix().attach(&gix_repo))?;
+    let Some(entries) = commit.conflict_entries()? else {
+        return Ok(Vec::new());
+    };
+
+    let workdir = workdir(ctx)?;
+    let mut paths = Vec::new();
+    for path in entries
+        .ancestor_entries
+        .iter()
+        .chain(&entries.our_entries)
+        .chain(&entries.their_entries)
+    {
+        let rela_path = gix::path::into_bstr(path).into_owned();
+        if paths.iter().any(|p: &ConflictedPath| p.path == rela_path) {
+            continue;
+        }
+        let unresolved_hunks = match std::fs::read(workdir.join(path)) {
+            Ok(content) => rerere::conflict_hunks(&content).len(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => 0,
+            Err(err) => return Err(err.into()),
+        };
+        paths.push(ConflictedPath {
+            path: rela_path,
+            presence: ConflictEntryPresence {
+                ours: entries.our_entries.contains(path),
+                theirs: entries.their_entries.contains(path),
+                ancestor: entries.ancestor_entries.contains(path),
+            },
+            unresolved_hunks,
+        });
+    }
+    Ok(paths)
+}
+
+/// Resolve the conflict at `path` by writing the version of `side` into the worktree.
+///
+/// If `hunk` is set, only the conflict markers of the hunk with that index are replaced with the content of `side`,
+/// leaving all other hunks untouched.
+/// Otherwise, the whole file is replaced with the version of `side`, or deleted if `side` doesn't have it.
+pub(crate) fn take_side(
+    ctx: &CommandContext,
+    path: &Path,
+    side: ConflictSide,
+    hunk: Option<usize>,
+) -> Result<()> {
+    ensure_conflicted(ctx, path)?;
+    let worktree_path = workdir(ctx)?.join(path);
+    if let Some(hunk_index) = hunk {
+        let content = std::fs::read(&worktree_path)
+            .with_context(|| format!("Failed to read '{}'", path.display()))?;
+        let hunks = rerere::conflict_hunks(&content);
+        let Some(hunk) = hunks.get(hunk_index) else {
+            bail!(
+                "'{}' has {} conflicting hunk(s), there is no hunk {}",
+                path.display(),
+                hunks.len(),
+                hunk_index + 1
+            );
+        };
+        let lines: Vec<_> = content.lines_with_terminator().collect();
+        let taken = match side {
+            ConflictSide::Ours => hunk.ours.clone(),
+            ConflictSide::Theirs => hunk.theirs.clone(),
+            ConflictSide::Base => hunk
+                .base
+                .clone()
+                .context("The hunk has no base section, write conflict markers to get it")?,
+        };
+        let resolved: Vec<u8> = lines[..hunk.markers.start]
+            .iter()
+            .chain(&lines[taken])
+            .chain(&lines[hunk.markers.end..])
+            .flat_map(|line| line.iter().copied())
+            .collect();
+        std::fs::write(&worktree_path, resolved)?;
+        return Ok(());
+    }
+
+    let metadata = read_edit_mode_metadata(ctx).context("Failed to read metadata")?;
+    let repository = ctx.repo();
+    let commit = repository.find_commit(metadata.commit_oid)?;
+    if !commit.is_conflicted() {
+        bail!("The commit that is edited isn't conflicted");
+    }
+    let tree = repository.find_real_tree(&commit, side.tree_key())?;
+    match tree.get_path(path) {
+        Ok(entry) => {
+            let blob = entry.to_object(repository)?.peel_to_blob()?;
+            if let Some(parent) = worktree_path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&worktree_path, blob.content())?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                let mode = if entry.filemode() == i32::from(git2::FileMode::BlobExecutable) {
+                    0o755
+                } else {
+                    0o644
+                };
+                std::fs::set_permissions(&worktree_path, std::fs::Permissions::from_mode(mode))?;
+            }
+        }
+        Err(err) if err.code() == git2::ErrorCode::NotFound => {
+            if worktree_path.exists() {
+                std::fs::remove_file(&worktree_path)?;
+            }
+        }
+        Err(err) => return Err(err.into()),
+    }
+    Ok(())
+}
+
+/// Write the conflict of `path` into the worktree with diff3-style conflict markers, just like it was
+/// when entering edit mode, to start resolving it over.
+pub(crate) fn write_conflict_markers(ctx: &CommandContext, path: &Path) -> Result<()> {
+    ensure_conflicted(ctx, path)?;
+    let metadata = read_edit_mode_metadata(ctx).context("Failed to read metadata")?;
+    let repository = ctx.repo();
+    let commit = repository.find_commit(metadata.commit_oid)?;
+    if !commit.is_conflicted() {
+        bail!("The commit that is edited isn't conflicted");
+    }
+    let mut index = get_commit_index(repository, &commit)?;
+    repository.checkout_index(
+        Some(&mut index),
+        Some(
+            CheckoutBuilder::new()
+                .force()
+                .path(path)
+                .conflict_style_diff3(true),
+        ),
+    )?;
+    Ok(())
+}
+
+/// Fail unless `path` is relative to the worktree root, stays inside of it, and is conflicted in the commit that
+/// is edited, so only conflicted files are ever written.
+fn ensure_conflicted(ctx: &CommandContext, path: &Path) -> Result<()> {
+    if path
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        bail!(
+            "'{}' must be relative to the root of the worktree, without '.' or '..' components",
+            path.display()
+        );
+    }
+    let rela_path = gix::path::into_bstr(path);
+    if !conflicted_paths(ctx)?
+        .iter()
+        .any(|conflicted| conflicted.path == *rela_path)
+    {
+        bail!(
+            "'{}' isn't conflicted in the commit that is edited",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Fail if any of the conflicted paths still has conflict markers.
+pub(crate) fn ensure_resolved(ctx: &CommandContext) -> Result<()> {
+    let unresolved: Vec<_> = conflicted_paths(ctx)?
+        .into_iter()
+        .filter(|path| !path.is_resolved())
+        .map(|path| path.path.to_string())
+        .collect();
+    if !unresolved.is_empty() {
+        bail!(
+            "There are still conflict markers in {}",
+            unresolved.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Record how the conflicting hunks of the conflicted `commit` were resolved in the worktree,
+/// to resolve them the same way when they conflict again.
+pub(crate) fn record_resolutions(ctx: &mut CommandContext, commit_id: git2::Oid) -> Result<()> {
+    let gix_repo = ctx.gix_repo()?;
+    let commit = but_core::Commit::from_id(commit_id.to_gix().attach(&gix_repo))?;
+    let Some(entries) = commit.conflict_entries()? else {
+        return Ok(());
+    };
+    let marker_tree = rerere::merge_with_conflict_markers(
+        &gix_repo,
+        commit.tree_id_or_kind(TreeKind::Base)?.detach(),
+        commit.tree_id_or_kind(TreeKind::Ours)?.detach(),
+        commit.tree_id_or_kind(TreeKind::Theirs)?.detach(),
+    )?;
+    let marker_tree = gix_repo.find_tree(marker_tree)?;
+
+    let workdir = workdir(ctx)?.to_owned();
+    let db = ctx.db()?;
+    let now = chrono::Local::now().naive_local();
+    for path in entries
+        .our_entries
+        .iter()
+        .filter(|path| entries.their_entries.contains(path))
+    {
+        let Some(entry) = marker_tree.lookup_entry_by_path(path)? else {
+            continue;
+        };
+        let postimage = match std::fs::read(workdir.join(path)) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        };
+        let preimage = entry.object()?.detach().data;
+        for (preimage_id, resolution) in
+            rerere::extract_resolutions(gix_repo.object_hash(), &preimage, &postimage)?
+        {
+            db.conflict_resolutions()
+                .upsert(but_db::ConflictResolution {
+                    preimage_id: preimage_id.to_string(),
+                    resolution: resolution.into(),
+                    created_at: now,
+                    updated_at: now,
+                })?;
+        }
+    }
+    Ok(())
+}
+
+/// Load all resolutions that were recorded with [`record_resolutions()`].
+pub(crate) fn recorded_resolutions(ctx: &mut CommandContext) -> Result<rerere::Resolutions> {
+    rerere::Resolutions::from_db(ctx.db()?)
+}
+
+fn workdir(ctx: &CommandContext) -> Result<&Path> {
+    ctx.repo()
+        .workdir()
+        .context("Conflicts can only be resolved in a repository with a worktree")
+}
@@ -1,7 +1,15 @@
+use std::path::Path;
+
 use anyhow::Result;
+use but_rebase::cherry_pick::{EmptyCommit, PickMode};
 use git2::build::CheckoutBuilder;
 use gitbutler_command_context::CommandContext;
-use gitbutler_edit_mode::commands::{enter_edit_mode, save_and_return_to_workspace};
+use gitbutler_edit_mode::commands::{
+    conflicted_paths, enter_edit_mode, finish_resolution_and_return_to_workspace,
+    save_and_return_to_workspace, take_conflict_side, write_conflict_markers,
+};
+use gitbutler_edit_mode::resolve::ConflictSide;
+use gitbutler_oxidize::ObjectIdExt;
 use gitbutler_stack::VirtualBranchesHandle;
 use tempfile::TempDir;
 
@@ -20,7 +28,7 @@ fn command_ctx(folder: &str) -> Result<(CommandContext, TempDir)> {
 // Where "left" and "right" contain changes which conflict with each other
 #[test]
 fn conficted_entries_get_written_when_leaving_edit_mode() -> Result<()> {
-    let (ctx, _tempdir) = command_ctx("conficted_entries_get_written_when_leaving_edit_mode")?;
+    let (mut ctx, _tempdir) = command_ctx("conficted_entries_get_written_when_leaving_edit_mode")?;
     let repository = ctx.repo();
 
     let foobar = repository.head()?.peel_to_commit()?.parent(0)?;
@@ -57,12 +65,85 @@ fn conficted_entries_get_written_when_leaving_edit_mode() -> Result<()> {
         ),
     )?;
 
-    save_and_return_to_workspace(&ctx)?;
+    let conflict_path = repository.path().parent().unwrap().join("conflict");
+    save_and_return_to_workspace(&mut ctx)?;
 
     assert_eq!(
-        std::fs::read_to_string(repository.path().parent().unwrap().join("conflict"))?,
+        std::fs::read_to_string(conflict_path)?,
         "<<<<<<< ours\nleft\n|||||||\n=======\nright\n>>>>>>> theirs\n".to_string()
     );
 
     Ok(())
 }
+
+#[test]
+fn conflicts_are_resolved_by_file_and_by_hunk() -> Result<()> {
+    let (mut ctx, _tempdir) = command_ctx("conficted_entries_get_written_when_leaving_edit_mode")?;
+    let repository = ctx.repo();
+    let gix_repo = ctx.gix_repo()?;
+    let left = gix_repo.rev_parse_single("left")?.detach();
+    let right = gix_repo.rev_parse_single("right")?.detach();
+    let conflicted = but_rebase::cherry_pick_one(
+        &gix_repo,
+        left,
+        right,
+        PickMode::Unconditionally,
+        EmptyCommit::Keep,
+    )?;
+
+    let vb_state = VirtualBranchesHandle::new(ctx.project().gb_dir());
+    let stack = vb_state.list_stacks_in_workspace()?.remove(0);
+    enter_edit_mode(&ctx, conflicted.to_git2(), stack.id)?;
+
+    let paths = conflicted_paths(&ctx)?;
+    assert_eq!(paths.len(), 1);
+    assert_eq!(paths[0].path, "conflict");
+    assert!(paths[0].presence.ours && paths[0].presence.theirs);
+    assert!(!paths[0].presence.ancestor, "both sides added the file");
+    assert_eq!(paths[0].unresolved_hunks, 1);
+
+    let conflict_path = repository.path().parent().unwrap().join("conflict");
+    take_conflict_side(&ctx, Path::new("conflict"), ConflictSide::Theirs, Some(0))?;
+    assert_eq!(std::fs::read_to_string(&conflict_path)?, "right\n");
+    assert!(conflicted_paths(&ctx)?[0].is_resolved());
+
+    write_conflict_markers(&ctx, Path::new("conflict"))?;
+    assert_eq!(
+        std::fs::read_to_string(&conflict_path)?,
+        "<<<<<<< ours\nleft\n|||||||\n=======\nright\n>>>>>>> theirs\n",
+        "markers can be brought back to start over"
+    );
+    assert!(
+        finish_resolution_and_return_to_workspace(&mut ctx, false).is_err(),
+        "there are conflict markers left"
+    );
+
+    for path in [
+        "not-conflicted",
+        "/conflict",
+        "../conflict",
+        "dir/../conflict",
+    ] {
+        assert!(
+            take_conflict_side(&ctx, Path::new(path), ConflictSide::Ours, None).is_err(),
+            "{path}: only conflicted paths inside the worktree can be resolved"
+        );
+        assert!(
+            write_conflict_markers(&ctx, Path::new(path)).is_err(),
+            "{path}: only conflicted paths inside the worktree get markers"
+        );
+    }
+    assert!(
+        !conflict_path.with_file_name("not-conflicted").exists(),
+        "nothing was written"
+    );
+
+    take_conflict_side(&ctx, Path::new("conflict"), ConflictSide::Ours, None)?;
+    assert_eq!(std::fs::read_to_string(&conflict_path)?, "left\n");
+    take_conflict_side(&ctx, Path::new("conflict"), ConflictSide::Base, None)?;
+    assert!(
+        !conflict_path.exists(),
+        "the file didn't exist in the base, so it's removed"
+    );
+    Ok(())
+}
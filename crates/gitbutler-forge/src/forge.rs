@@ -1,5 +1,19 @@
+use anyhow::{bail, Context, Result};
+use bstr::ByteSlice;
+use gitbutler_url::Url;
 use serde::{Deserialize, Serialize};
 
+mod azure;
+mod bitbucket;
+mod github;
+mod gitlab;
+mod http;
+
+pub use azure::Azure;
+pub use bitbucket::Bitbucket;
+pub use github::GitHub;
+pub use gitlab::GitLab;
+
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 #[serde(tag = "name", rename_all = "lowercase")]
 /// Supported git forge types
@@ -9,3 +23,219 @@ pub enum ForgeName {
     Bitbucket,
     Azure,
 }
+
+impl ForgeName {
+    /// Determine the forge hosting the repository at `url` by its host name, or `None` if it's not known.
+    ///
+    /// Only the hosted forges and their subdomains are recognized. Self-hosted instances have to be configured
+    /// as forge override, see [`ForgeName::from_override()`].
+    pub fn from_remote_url(url: &Url) -> Option<Self> {
+        let host = url.host.as_deref()?.to_ascii_lowercase();
+        let is_on = |domain: &str| {
+            host == domain
+                || host
+                    .strip_suffix(domain)
+                    .is_some_and(|subdomain| subdomain.ends_with('.'))
+        };
+        if is_on("github.com") {
+            Some(ForgeName::GitHub)
+        } else if is_on("gitlab.com") {
+            Some(ForgeName::GitLab)
+        } else if is_on("bitbucket.org") {
+            Some(ForgeName::Bitbucket)
+        } else if is_on("dev.azure.com") || is_on("visualstudio.com") {
+            Some(ForgeName::Azure)
+        } else {
+            None
+        }
+    }
+
+    /// Parse the forge override configured for a project, like `github`, or return `None` if it doesn't
+    /// name a known forge, as is the case for `default`.
+    pub fn from_override(name: &str) -> Option<Self> {
+        match name {
+            "github" => Some(ForgeName::GitHub),
+            "gitlab" => Some(ForgeName::GitLab),
+            "bitbucket" => Some(ForgeName::Bitbucket),
+            "azure" => Some(ForgeName::Azure),
+            _ => None,
+        }
+    }
+}
+
+/// The information needed to open a new review, i.e. a pull request or merge request.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateReviewRequest {
+    pub title: String,
+    pub body: String,
+    /// The name of the branch with the changes to review.
+    pub head: String,
+    /// The name of the branch the changes should be merged into.
+    pub base: String,
+    /// If `true`, the review is opened as draft.
+    pub draft: bool,
+}
+
+/// A review as known to the forge.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Review {
+    /// The number of the review, unique within the repository.
+    pub number: u64,
+    pub title: String,
+    pub body: Option<String>,
+    /// The name of the branch with the changes to review.
+    pub head: String,
+    /// The name of the branch the changes should be merged into.
+    pub base: String,
+    /// The URL at which the review can be seen in the browser.
+    pub url: String,
+    pub status: ReviewStatus,
+}
+
+/// The state of a review, normalized across forges.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum ReviewStatus {
+    Open,
+    Draft,
+    Merged,
+    /// The review was closed without being merged, which some forges call declined or abandoned.
+    Closed,
+}
+
+/// Operations on the reviews of a single repository hosted on a forge.
+#[allow(async_fn_in_trait)]
+pub trait Forge {
+    /// The kind of forge this is.
+    fn name(&self) -> ForgeName;
+    /// Return the URL at which the review with `number` can be seen in the browser.
+    fn review_url(&self, number: u64) -> String;
+    /// Return the URL at which a review for merging `head` into `base` can be created in the browser.
+    fn create_review_url(&self, head: &str, base: &str) -> String;
+    /// Open a new review as described by `request`.
+    async fn create_review(&self, request: &CreateReviewRequest) -> Result<Review>;
+    /// Replace the description of the review with `number` with `body`, and merge it into the branch named `base`.
+    async fn update_review(&self, number: u64, body: &str, base: &str) -> Result<Review>;
+    /// Return all reviews, in any state, whose changes are on the branch named `head`.
+    async fn list_reviews_for_branch(&self, head: &str) -> Result<Vec<Review>>;
+    /// Return the state of the review with `number`.
+    async fn review_status(&self, number: u64) -> Result<ReviewStatus>;
+}
+
+/// Any of the supported forges, as selected by [`forge_for_remote_url()`].
+#[derive(Debug, Clone)]
+pub enum AnyForge {
+    GitHub(GitHub),
+    GitLab(GitLab),
+    Bitbucket(Bitbucket),
+    Azure(Azure),
+}
+
+/// Return the forge client for the repository at the remote `url`, authenticating with `token` if given,
+/// or `None` if the forge can't be determined from the host name.
+pub fn forge_for_remote_url(url: &Url, token: Option<String>) -> Result<Option<AnyForge>> {
+    ForgeName::from_remote_url(url)
+        .map(|name| forge_for_name(name, url, token))
+        .transpose()
+}
+
+/// Return the client of the forge with `name` for the repository at the remote `url`, authenticating
+/// with `token` if given, for when the forge is known regardless of the host name.
+pub fn forge_for_name(name: ForgeName, url: &Url, token: Option<String>) -> Result<AnyForge> {
+    Ok(match name {
+        ForgeName::GitHub => AnyForge::GitHub(GitHub::from_remote_url(url, token)?),
+        ForgeName::GitLab => AnyForge::GitLab(GitLab::from_remote_url(url, token)?),
+        ForgeName::Bitbucket => AnyForge::Bitbucket(Bitbucket::from_remote_url(url, token)?),
+        ForgeName::Azure => AnyForge::Azure(Azure::from_remote_url(url, token)?),
+    })
+}
+
+impl Forge for AnyForge {
+    fn name(&self) -> ForgeName {
+        match self {
+            AnyForge::GitHub(f) => f.name(),
+            AnyForge::GitLab(f) => f.name(),
+            AnyForge::Bitbucket(f) => f.name(),
+            AnyForge::Azure(f) => f.name(),
+        }
+    }
+
+    fn review_url(&self, number: u64) -> String {
+        match self {
+            AnyForge::GitHub(f) => f.review_url(number),
+            AnyForge::GitLab(f) => f.review_url(number),
+            AnyForge::Bitbucket(f) => f.review_url(number),
+            AnyForge::Azure(f) => f.review_url(number),
+        }
+    }
+
+    fn create_review_url(&self, head: &str, base: &str) -> String {
+        match self {
+            AnyForge::GitHub(f) => f.create_review_url(head, base),
+            AnyForge::GitLab(f) => f.create_review_url(head, base),
+            AnyForge::Bitbucket(f) => f.create_review_url(head, base),
+            AnyForge::Azure(f) => f.create_review_url(head, base),
+        }
+    }
+
+    async fn create_review(&self, request: &CreateReviewRequest) -> Result<Review> {
+        match self {
+            AnyForge::GitHub(f) => f.create_review(request).await,
+            AnyForge::GitLab(f) => f.create_review(request).await,
+            AnyForge::Bitbucket(f) => f.create_review(request).await,
+            AnyForge::Azure(f) => f.create_review(request).await,
+        }
+    }
+
+    async fn update_review(&self, number: u64, body: &str, base: &str) -> Result<Review> {
+        match self {
+            AnyForge::GitHub(f) => f.update_review(number, body, base).await,
+            AnyForge::GitLab(f) => f.update_review(number, body, base).await,
+            AnyForge::Bitbucket(f) => f.update_review(number, body, base).await,
+            AnyForge::Azure(f) => f.update_review(number, body, base).await,
+        }
+    }
+
+    async fn list_reviews_for_branch(&self, head: &str) -> Result<Vec<Review>> {
+        match self {
+            AnyForge::GitHub(f) => f.list_reviews_for_branch(head).await,
+            AnyForge::GitLab(f) => f.list_reviews_for_branch(head).await,
+            AnyForge::Bitbucket(f) => f.list_reviews_for_branch(head).await,
+            AnyForge::Azure(f) => f.list_reviews_for_branch(head).await,
+        }
+    }
+
+    async fn review_status(&self, number: u64) -> Result<ReviewStatus> {
+        match self {
+            AnyForge::GitHub(f) => f.review_status(number).await,
+            AnyForge::GitLab(f) => f.review_status(number).await,
+            AnyForge::Bitbucket(f) => f.review_status(number).await,
+            AnyForge::Azure(f) => f.review_status(number).await,
+        }
+    }
+}
+
+/// Return the host of `url` and the components of its path, without a leading slash or trailing `.git`.
+fn host_and_path_components(url: &Url) -> Result<(&str, Vec<&str>)> {
+    let host = url
+        .host
+        .as_deref()
+        .with_context(|| format!("Remote URL '{url}' has no host"))?;
+    let path = url
+        .path
+        .to_str()
+        .with_context(|| format!("Remote URL '{url}' has a path that isn't valid UTF-8"))?;
+    let path = path.trim_matches('/');
+    let components: Vec<_> = path
+        .strip_suffix(".git")
+        .unwrap_or(path)
+        .split('/')
+        .filter(|c| !c.is_empty())
+        .collect();
+    if components.is_empty() {
+        bail!("Remote URL '{url}' doesn't point to a repository");
+    }
+    Ok((host, components))
+}
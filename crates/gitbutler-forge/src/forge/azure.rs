@@ -0,0 +1,194 @@
+use anyhow::{bail, Result};
+use gitbutler_url::Url;
+use reqwest::Method;
+use serde::Deserialize;
+
+use super::{http, CreateReviewRequest, Forge, ForgeName, Review, ReviewStatus};
+
+/// The version of the Azure DevOps REST API to use, which must be passed with every request.
+const API_VERSION: &str = "7.1";
+
+/// Pull requests on Azure DevOps.
+#[derive(Debug, Clone)]
+pub struct Azure {
+    client: reqwest::Client,
+    api_url: String,
+    organization: String,
+    project: String,
+    repo: String,
+    token: Option<String>,
+}
+
+impl Azure {
+    /// Create a client for the repository at `url`, using the personal access `token` for authentication if given.
+    ///
+    /// `url` may be any of the forms Azure DevOps hands out:
+    ///
+    /// * `https://dev.azure.com/<org>/<project>/_git/<repo>`
+    /// * `git@ssh.dev.azure.com:v3/<org>/<project>/<repo>`
+    /// * `https://<org>.visualstudio.com/<project>/_git/<repo>`
+    pub fn from_remote_url(url: &Url, token: Option<String>) -> Result<Self> {
+        let (host, components) = super::host_and_path_components(url)?;
+        let (organization, project, repo) = match components.as_slice() {
+            ["v3", org, project, repo] => (org.to_string(), *project, *repo),
+            [org, project, "_git", repo] if host.ends_with("dev.azure.com") => {
+                (org.to_string(), *project, *repo)
+            }
+            [project, "_git", repo] if host.ends_with(".visualstudio.com") => {
+                let org = host.trim_end_matches(".visualstudio.com");
+                (org.to_owned(), *project, *repo)
+            }
+            _ => bail!("Expected an Azure DevOps repository URL, got '{url}'"),
+        };
+        Ok(Azure {
+            client: reqwest::Client::new(),
+            api_url: "https://dev.azure.com".to_owned(),
+            organization,
+            project: project.to_owned(),
+            repo: repo.to_owned(),
+            token,
+        })
+    }
+
+    /// Send API requests to `api_url` instead of `https://dev.azure.com`.
+    pub fn with_api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.api_url = api_url.into();
+        self
+    }
+
+    fn web_url(&self) -> String {
+        format!(
+            "https://dev.azure.com/{}/{}/_git/{}",
+            self.organization, self.project, self.repo
+        )
+    }
+
+    fn request(&self, method: Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self
+            .client
+            .request(
+                method,
+                format!(
+                    "{}/{}/{}/_apis/git/repositories/{}/pullrequests{path}",
+                    self.api_url, self.organization, self.project, self.repo
+                ),
+            )
+            .query(&[("api-version", API_VERSION)]);
+        match &self.token {
+            // Personal access tokens are passed as password with an empty user name.
+            Some(token) => request.basic_auth("", Some(token)),
+            None => request,
+        }
+    }
+
+    fn to_review(&self, pr: PullRequest) -> Review {
+        let status = pr.status();
+        Review {
+            number: pr.pull_request_id,
+            title: pr.title,
+            body: pr.description,
+            head: branch_name(pr.source_ref_name),
+            base: branch_name(pr.target_ref_name),
+            url: self.review_url(pr.pull_request_id),
+            status,
+        }
+    }
+}
+
+impl Forge for Azure {
+    fn name(&self) -> ForgeName {
+        ForgeName::Azure
+    }
+
+    fn review_url(&self, number: u64) -> String {
+        format!("{}/pullrequest/{number}", self.web_url())
+    }
+
+    fn create_review_url(&self, head: &str, base: &str) -> String {
+        format!(
+            "{}/pullrequestcreate?sourceRef={head}&targetRef={base}",
+            self.web_url()
+        )
+    }
+
+    async fn create_review(&self, request: &CreateReviewRequest) -> Result<Review> {
+        let pr: PullRequest = http::send(self.request(Method::POST, "").json(&serde_json::json!({
+            "title": request.title,
+            "description": request.body,
+            "sourceRefName": format!("refs/heads/{}", request.head),
+            "targetRefName": format!("refs/heads/{}", request.base),
+            "isDraft": request.draft,
+        })))
+        .await?;
+        Ok(self.to_review(pr))
+    }
+
+    async fn update_review(&self, number: u64, body: &str, base: &str) -> Result<Review> {
+        let pr: PullRequest = http::send(self.request(Method::PATCH, &format!("/{number}")).json(
+            &serde_json::json!({
+                "description": body,
+                "targetRefName": format!("refs/heads/{base}"),
+            }),
+        ))
+        .await?;
+        Ok(self.to_review(pr))
+    }
+
+    async fn list_reviews_for_branch(&self, head: &str) -> Result<Vec<Review>> {
+        let list: List = http::send(self.request(Method::GET, "").query(&[
+            (
+                "searchCriteria.sourceRefName",
+                format!("refs/heads/{head}").as_str(),
+            ),
+            ("searchCriteria.status", "all"),
+        ]))
+        .await?;
+        Ok(list
+            .value
+            .into_iter()
+            .map(|pr| self.to_review(pr))
+            .collect())
+    }
+
+    async fn review_status(&self, number: u64) -> Result<ReviewStatus> {
+        let pr: PullRequest = http::send(self.request(Method::GET, &format!("/{number}"))).await?;
+        Ok(pr.status())
+    }
+}
+
+fn branch_name(ref_name: String) -> String {
+    match ref_name.strip_prefix("refs/heads/") {
+        Some(name) => name.to_owned(),
+        None => ref_name,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct List {
+    value: Vec<PullRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PullRequest {
+    pull_request_id: u64,
+    title: String,
+    description: Option<String>,
+    /// One of `active`, `completed` or `abandoned`.
+    status: String,
+    #[serde(default)]
+    is_draft: bool,
+    source_ref_name: String,
+    target_ref_name: String,
+}
+
+impl PullRequest {
+    fn status(&self) -> ReviewStatus {
+        match self.status.as_str() {
+            "completed" => ReviewStatus::Merged,
+            "abandoned" => ReviewStatus::Closed,
+            _ if self.is_draft => ReviewStatus::Draft,
+            _ => ReviewStatus::Open,
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use anyhow::{bail, Result};
+use gitbutler_url::Url;
+use reqwest::Method;
+use serde::Deserialize;
+
+use super::{http, CreateReviewRequest, Forge, ForgeName, Review, ReviewStatus};
+
+/// Pull requests on Bitbucket Cloud.
+#[derive(Debug, Clone)]
+pub struct Bitbucket {
+    client: reqwest::Client,
+    api_url: String,
+    web_url: String,
+    workspace: String,
+    repo: String,
+    token: Option<String>,
+}
+
+impl Bitbucket {
+    /// Create a client for the repository at `url`, using `token` for authentication if given.
+    pub fn from_remote_url(url: &Url, token: Option<String>) -> Result<Self> {
+        let (host, components) = super::host_and_path_components(url)?;
+        let [workspace, repo] = components.as_slice() else {
+            bail!("Expected a Bitbucket repository path like 'workspace/repo', got '{url}'");
+        };
+        Ok(Bitbucket {
+            client: reqwest::Client::new(),
+            api_url: "https://api.bitbucket.org/2.0".to_owned(),
+            web_url: format!("https://{host}/{workspace}/{repo}"),
+            workspace: workspace.to_string(),
+            repo: repo.to_string(),
+            token,
+        })
+    }
+
+    /// Send API requests to `api_url` instead of the one of Bitbucket Cloud.
+    pub fn with_api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.api_url = api_url.into();
+        self
+    }
+
+    fn request(&self, method: Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self.client.request(
+            method,
+            format!(
+                "{}/repositories/{}/{}/pullrequests{path}",
+                self.api_url, self.workspace, self.repo
+            ),
+        );
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+}
+
+impl Forge for Bitbucket {
+    fn name(&self) -> ForgeName {
+        ForgeName::Bitbucket
+    }
+
+    fn review_url(&self, number: u64) -> String {
+        format!("{}/pull-requests/{number}", self.web_url)
+    }
+
+    fn create_review_url(&self, head: &str, base: &str) -> String {
+        format!(
+            "{}/pull-requests/new?source={head}&dest={base}",
+            self.web_url
+        )
+    }
+
+    async fn create_review(&self, request: &CreateReviewRequest) -> Result<Review> {
+        let pr: PullRequest = http::send(self.request(Method::POST, "").json(&serde_json::json!({
+            "title": request.title,
+            "description": request.body,
+            "source": { "branch": { "name": request.head } },
+            "destination": { "branch": { "name": request.base } },
+            "draft": request.draft,
+        })))
+        .await?;
+        Ok(pr.into())
+    }
+
+    async fn update_review(&self, number: u64, body: &str, base: &str) -> Result<Review> {
+        let pr: PullRequest = http::send(self.request(Method::PUT, &format!("/{number}")).json(
+            &serde_json::json!({
+                "description": body,
+                "destination": { "branch": { "name": base } },
+            }),
+        ))
+        .await?;
+        Ok(pr.into())
+    }
+
+    async fn list_reviews_for_branch(&self, head: &str) -> Result<Vec<Review>> {
+        // Without an explicit state, only open pull requests are returned.
+        let page: Page = http::send(self.request(Method::GET, "").query(&[
+            ("q", format!("source.branch.name=\"{head}\"").as_str()),
+            ("state", "OPEN"),
+            ("state", "MERGED"),
+            ("state", "DECLINED"),
+            ("state", "SUPERSEDED"),
+        ]))
+        .await?;
+        Ok(page.values.into_iter().map(Into::into).collect())
+    }
+
+    async fn review_status(&self, number: u64) -> Result<ReviewStatus> {
+        let pr: PullRequest = http::send(self.request(Method::GET, &format!("/{number}"))).await?;
+        Ok(pr.status())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Page {
+    values: Vec<PullRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PullRequest {
+    id: u64,
+    title: String,
+    description: Option<String>,
+    /// One of `OPEN`, `MERGED`, `DECLINED` or `SUPERSEDED`.
+    state: String,
+    #[serde(default)]
+    draft: bool,
+    links: Links,
+    source: Endpoint,
+    destination: Endpoint,
+}
+
+#[derive(Debug, Deserialize)]
+struct Links {
+    html: Link,
+}
+
+#[derive(Debug, Deserialize)]
+struct Link {
+    href: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Endpoint {
+    branch: Branch,
+}
+
+#[derive(Debug, Deserialize)]
+struct Branch {
+    name: String,
+}
+
+impl PullRequest {
+    fn status(&self) -> ReviewStatus {
+        match self.state.as_str() {
+            "MERGED" => ReviewStatus::Merged,
+            "DECLINED" | "SUPERSEDED" => ReviewStatus::Closed,
+            _ if self.draft => ReviewStatus::Draft,
+            _ => ReviewStatus::Open,
+        }
+    }
+}
+
+impl From<PullRequest> for Review {
+    fn from(pr: PullRequest) -> Self {
+        let status = pr.status();
+        Review {
+            number: pr.id,
+            title: pr.title,
+            body: pr.description,
+            head: pr.source.branch.name,
+            base: pr.destination.branch.name,
+            url: pr.links.html.href,
+            status,
+        }
+    }
+}
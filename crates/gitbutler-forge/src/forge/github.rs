@@ -0,0 +1,161 @@
+use anyhow::{bail, Result};
+use gitbutler_url::Url;
+use reqwest::Method;
+use serde::Deserialize;
+
+use super::{http, CreateReviewRequest, Forge, ForgeName, Review, ReviewStatus};
+
+/// Pull requests on GitHub or GitHub Enterprise.
+#[derive(Debug, Clone)]
+pub struct GitHub {
+    client: reqwest::Client,
+    api_url: String,
+    web_url: String,
+    owner: String,
+    repo: String,
+    token: Option<String>,
+}
+
+impl GitHub {
+    /// Create a client for the repository at `url`, using `token` for authentication if given.
+    pub fn from_remote_url(url: &Url, token: Option<String>) -> Result<Self> {
+        let (host, components) = super::host_and_path_components(url)?;
+        let [owner, repo] = components.as_slice() else {
+            bail!("Expected a GitHub repository path like 'owner/repo', got '{url}'");
+        };
+        let api_url = if host == "github.com" {
+            "https://api.github.com".to_owned()
+        } else {
+            format!("https://{host}/api/v3")
+        };
+        Ok(GitHub {
+            client: reqwest::Client::new(),
+            api_url,
+            web_url: format!("https://{host}/{owner}/{repo}"),
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            token,
+        })
+    }
+
+    /// Send API requests to `api_url` instead of the one derived from the remote URL.
+    pub fn with_api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.api_url = api_url.into();
+        self
+    }
+
+    fn request(&self, method: Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self
+            .client
+            .request(
+                method,
+                format!(
+                    "{}/repos/{}/{}/pulls{path}",
+                    self.api_url, self.owner, self.repo
+                ),
+            )
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json");
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+}
+
+impl Forge for GitHub {
+    fn name(&self) -> ForgeName {
+        ForgeName::GitHub
+    }
+
+    fn review_url(&self, number: u64) -> String {
+        format!("{}/pull/{number}", self.web_url)
+    }
+
+    fn create_review_url(&self, head: &str, base: &str) -> String {
+        format!("{}/compare/{base}...{head}?expand=1", self.web_url)
+    }
+
+    async fn create_review(&self, request: &CreateReviewRequest) -> Result<Review> {
+        let pr: PullRequest = http::send(self.request(Method::POST, "").json(&serde_json::json!({
+            "title": request.title,
+            "body": request.body,
+            "head": request.head,
+            "base": request.base,
+            "draft": request.draft,
+        })))
+        .await?;
+        Ok(pr.into())
+    }
+
+    async fn update_review(&self, number: u64, body: &str, base: &str) -> Result<Review> {
+        let pr: PullRequest = http::send(
+            self.request(Method::PATCH, &format!("/{number}"))
+                .json(&serde_json::json!({ "body": body, "base": base })),
+        )
+        .await?;
+        Ok(pr.into())
+    }
+
+    async fn list_reviews_for_branch(&self, head: &str) -> Result<Vec<Review>> {
+        let prs: Vec<PullRequest> = http::send(self.request(Method::GET, "").query(&[
+            ("head", format!("{}:{head}", self.owner).as_str()),
+            ("state", "all"),
+        ]))
+        .await?;
+        Ok(prs.into_iter().map(Into::into).collect())
+    }
+
+    async fn review_status(&self, number: u64) -> Result<ReviewStatus> {
+        let pr: PullRequest = http::send(self.request(Method::GET, &format!("/{number}"))).await?;
+        Ok(pr.status())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PullRequest {
+    number: u64,
+    title: String,
+    body: Option<String>,
+    html_url: String,
+    state: String,
+    #[serde(default)]
+    draft: bool,
+    merged_at: Option<String>,
+    head: BranchRef,
+    base: BranchRef,
+}
+
+#[derive(Debug, Deserialize)]
+struct BranchRef {
+    #[serde(rename = "ref")]
+    name: String,
+}
+
+impl PullRequest {
+    fn status(&self) -> ReviewStatus {
+        if self.merged_at.is_some() {
+            ReviewStatus::Merged
+        } else if self.state == "closed" {
+            ReviewStatus::Closed
+        } else if self.draft {
+            ReviewStatus::Draft
+        } else {
+            ReviewStatus::Open
+        }
+    }
+}
+
+impl From<PullRequest> for Review {
+    fn from(pr: PullRequest) -> Self {
+        let status = pr.status();
+        Review {
+            number: pr.number,
+            title: pr.title,
+            body: pr.body,
+            head: pr.head.name,
+            base: pr.base.name,
+            url: pr.html_url,
+            status,
+        }
+    }
+}
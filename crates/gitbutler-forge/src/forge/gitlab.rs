@@ -0,0 +1,152 @@
+use anyhow::{bail, Result};
+use gitbutler_url::Url;
+use reqwest::Method;
+use serde::Deserialize;
+
+use super::{http, CreateReviewRequest, Forge, ForgeName, Review, ReviewStatus};
+
+/// Merge requests on gitlab.com or a self-hosted GitLab instance.
+#[derive(Debug, Clone)]
+pub struct GitLab {
+    client: reqwest::Client,
+    api_url: String,
+    web_url: String,
+    /// The full path of the project, like `group/subgroup/project`.
+    project: String,
+    token: Option<String>,
+}
+
+impl GitLab {
+    /// Create a client for the repository at `url`, using `token` for authentication if given.
+    pub fn from_remote_url(url: &Url, token: Option<String>) -> Result<Self> {
+        let (host, components) = super::host_and_path_components(url)?;
+        if components.len() < 2 {
+            bail!("Expected a GitLab project path like 'group/project', got '{url}'");
+        }
+        let project = components.join("/");
+        Ok(GitLab {
+            client: reqwest::Client::new(),
+            api_url: format!("https://{host}/api/v4"),
+            web_url: format!("https://{host}/{project}"),
+            project,
+            token,
+        })
+    }
+
+    /// Send API requests to `api_url` instead of the one derived from the remote URL.
+    pub fn with_api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.api_url = api_url.into();
+        self
+    }
+
+    fn request(&self, method: Method, path: &str) -> reqwest::RequestBuilder {
+        // The project may be addressed by its path, as long as it's URL-encoded.
+        let project = self.project.replace('/', "%2F");
+        let request = self.client.request(
+            method,
+            format!("{}/projects/{project}/merge_requests{path}", self.api_url),
+        );
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+}
+
+impl Forge for GitLab {
+    fn name(&self) -> ForgeName {
+        ForgeName::GitLab
+    }
+
+    fn review_url(&self, number: u64) -> String {
+        format!("{}/-/merge_requests/{number}", self.web_url)
+    }
+
+    fn create_review_url(&self, head: &str, base: &str) -> String {
+        format!(
+            "{}/-/merge_requests/new?merge_request[source_branch]={head}&merge_request[target_branch]={base}",
+            self.web_url
+        )
+    }
+
+    async fn create_review(&self, request: &CreateReviewRequest) -> Result<Review> {
+        // GitLab has no draft flag on creation, drafts are recognized by their title.
+        let title = if request.draft {
+            format!("Draft: {}", request.title)
+        } else {
+            request.title.clone()
+        };
+        let mr: MergeRequest =
+            http::send(self.request(Method::POST, "").json(&serde_json::json!({
+                "title": title,
+                "description": request.body,
+                "source_branch": request.head,
+                "target_branch": request.base,
+            })))
+            .await?;
+        Ok(mr.into())
+    }
+
+    async fn update_review(&self, number: u64, body: &str, base: &str) -> Result<Review> {
+        let mr: MergeRequest = http::send(
+            self.request(Method::PUT, &format!("/{number}"))
+                .json(&serde_json::json!({ "description": body, "target_branch": base })),
+        )
+        .await?;
+        Ok(mr.into())
+    }
+
+    async fn list_reviews_for_branch(&self, head: &str) -> Result<Vec<Review>> {
+        let mrs: Vec<MergeRequest> = http::send(
+            self.request(Method::GET, "")
+                .query(&[("source_branch", head)]),
+        )
+        .await?;
+        Ok(mrs.into_iter().map(Into::into).collect())
+    }
+
+    async fn review_status(&self, number: u64) -> Result<ReviewStatus> {
+        let mr: MergeRequest = http::send(self.request(Method::GET, &format!("/{number}"))).await?;
+        Ok(mr.status())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct MergeRequest {
+    iid: u64,
+    title: String,
+    description: Option<String>,
+    web_url: String,
+    /// One of `opened`, `closed`, `locked` or `merged`.
+    state: String,
+    #[serde(default)]
+    draft: bool,
+    source_branch: String,
+    target_branch: String,
+}
+
+impl MergeRequest {
+    fn status(&self) -> ReviewStatus {
+        match self.state.as_str() {
+            "merged" => ReviewStatus::Merged,
+            "closed" | "locked" => ReviewStatus::Closed,
+            _ if self.draft => ReviewStatus::Draft,
+            _ => ReviewStatus::Open,
+        }
+    }
+}
+
+impl From<MergeRequest> for Review {
+    fn from(mr: MergeRequest) -> Self {
+        let status = mr.status();
+        Review {
+            number: mr.iid,
+            title: mr.title,
+            body: mr.description,
+            head: mr.source_branch,
+            base: mr.target_branch,
+            url: mr.web_url,
+            status,
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use anyhow::{bail, Context, Result};
+use serde::de::DeserializeOwned;
+
+/// The user agent to identify with, which some forges require.
+pub(crate) const USER_AGENT: &str = "GitButler";
+
+/// Send `request` and deserialize the JSON response, turning unsuccessful responses into errors
+/// that contain what the forge had to say about it.
+pub(crate) async fn send<T: DeserializeOwned>(request: reqwest::RequestBuilder) -> Result<T> {
+    let response = request
+        .header(reqwest::header::USER_AGENT, USER_AGENT)
+        .send()
+        .await
+        .context("Failed to reach the forge")?;
+    let status = response.status();
+    let url = response.url().clone();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        bail!("Request to {url} failed with status {status}: {body}");
+    }
+    response
+        .json()
+        .await
+        .with_context(|| format!("Could not understand the response of {url}"))
+}
@@ -0,0 +1,533 @@
+use std::str::FromStr;
+
+use gitbutler_forge::forge::{
+    forge_for_name, forge_for_remote_url, AnyForge, CreateReviewRequest, Forge, ForgeName,
+    ReviewStatus,
+};
+use gitbutler_url::Url;
+
+fn url(url: &str) -> Url {
+    Url::from_str(url).expect("valid URL")
+}
+
+fn request(draft: bool) -> CreateReviewRequest {
+    CreateReviewRequest {
+        title: "Add feature".into(),
+        body: "Description".into(),
+        head: "feature".into(),
+        base: "main".into(),
+        draft,
+    }
+}
+
+mod selection {
+    use super::*;
+
+    #[test]
+    fn forge_overrides_name_known_forges() {
+        assert_eq!(ForgeName::from_override("github"), Some(ForgeName::GitHub));
+        assert_eq!(ForgeName::from_override("gitlab"), Some(ForgeName::GitLab));
+        assert_eq!(
+            ForgeName::from_override("bitbucket"),
+            Some(ForgeName::Bitbucket)
+        );
+        assert_eq!(ForgeName::from_override("azure"), Some(ForgeName::Azure));
+        assert_eq!(
+            ForgeName::from_override("default"),
+            None,
+            "the forge is determined by the host"
+        );
+    }
+
+    #[test]
+    fn self_hosted_forges_are_selected_by_name() -> anyhow::Result<()> {
+        let forge = forge_for_name(
+            ForgeName::GitLab,
+            &url("git@git.example.com:group/project.git"),
+            None,
+        )?;
+        assert_eq!(forge.name(), ForgeName::GitLab);
+        assert_eq!(
+            forge.review_url(3),
+            "https://git.example.com/group/project/-/merge_requests/3"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn forge_is_determined_by_host() {
+        for (remote, expected) in [
+            ("https://github.com/owner/repo.git", Some(ForgeName::GitHub)),
+            ("git@github.com:owner/repo.git", Some(ForgeName::GitHub)),
+            (
+                "https://gitlab.com/group/sub/project.git",
+                Some(ForgeName::GitLab),
+            ),
+            ("git@bitbucket.org:ws/repo.git", Some(ForgeName::Bitbucket)),
+            (
+                "https://dev.azure.com/org/project/_git/repo",
+                Some(ForgeName::Azure),
+            ),
+            (
+                "git@ssh.dev.azure.com:v3/org/project/repo",
+                Some(ForgeName::Azure),
+            ),
+            (
+                "https://org.visualstudio.com/project/_git/repo",
+                Some(ForgeName::Azure),
+            ),
+            ("https://example.com/owner/repo.git", None),
+            ("https://github.example.com/owner/repo.git", None),
+            ("https://notgithub.example.com/owner/repo.git", None),
+            ("https://gitlab-mirror.github.io/owner/repo.git", None),
+            ("https://mygithub.com/owner/repo.git", None),
+            (
+                "https://enterprise.github.com/owner/repo.git",
+                Some(ForgeName::GitHub),
+            ),
+        ] {
+            assert_eq!(
+                ForgeName::from_remote_url(&url(remote)),
+                expected,
+                "{remote}"
+            );
+        }
+    }
+
+    #[test]
+    fn web_urls_are_derived_from_the_remote() -> anyhow::Result<()> {
+        let forge = forge_for_remote_url(&url("git@github.com:owner/repo.git"), None)?
+            .expect("known forge");
+        assert_eq!(forge.review_url(3), "https://github.com/owner/repo/pull/3");
+        assert_eq!(
+            forge.create_review_url("feature", "main"),
+            "https://github.com/owner/repo/compare/main...feature?expand=1"
+        );
+
+        let forge = forge_for_remote_url(&url("https://gitlab.com/group/sub/project.git"), None)?
+            .expect("known forge");
+        assert_eq!(
+            forge.review_url(3),
+            "https://gitlab.com/group/sub/project/-/merge_requests/3"
+        );
+
+        let forge = forge_for_remote_url(&url("git@bitbucket.org:ws/repo.git"), None)?
+            .expect("known forge");
+        assert_eq!(
+            forge.review_url(3),
+            "https://bitbucket.org/ws/repo/pull-requests/3"
+        );
+
+        for remote in [
+            "https://dev.azure.com/org/project/_git/repo",
+            "git@ssh.dev.azure.com:v3/org/project/repo",
+            "https://org.visualstudio.com/project/_git/repo",
+        ] {
+            let forge = forge_for_remote_url(&url(remote), None)?.expect("known forge");
+            assert_eq!(
+                forge.review_url(3),
+                "https://dev.azure.com/org/project/_git/repo/pullrequest/3",
+                "{remote}"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_forges_have_no_client() -> anyhow::Result<()> {
+        assert!(forge_for_remote_url(&url("https://example.com/owner/repo.git"), None)?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn repository_paths_of_unexpected_shape_are_rejected() {
+        assert!(forge_for_remote_url(&url("https://github.com/owner.git"), None).is_err());
+        assert!(forge_for_remote_url(&url("https://dev.azure.com/org/repo"), None).is_err());
+    }
+}
+
+mod github {
+    use serde_json::json;
+    use wiremock::{
+        matchers::{bearer_token, body_partial_json, method, path, query_param},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+
+    fn pull_request(number: u64, state: &str, merged: bool) -> serde_json::Value {
+        json!({
+            "number": number,
+            "title": "Add feature",
+            "body": "Description",
+            "html_url": format!("https://github.com/owner/repo/pull/{number}"),
+            "state": state,
+            "draft": false,
+            "merged_at": merged.then_some("2025-01-01T00:00:00Z"),
+            "head": { "ref": "feature" },
+            "base": { "ref": "main" },
+        })
+    }
+
+    fn forge(server: &MockServer) -> AnyForge {
+        let url = url("https://github.com/owner/repo.git");
+        let forge = forge_for_remote_url(&url, Some("secret".into()))
+            .unwrap()
+            .unwrap();
+        match forge {
+            AnyForge::GitHub(github) => AnyForge::GitHub(github.with_api_url(server.uri())),
+            other => panic!("expected GitHub, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn create_update_list_and_status() -> anyhow::Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/repos/owner/repo/pulls"))
+            .and(bearer_token("secret"))
+            .and(body_partial_json(
+                json!({ "head": "feature", "base": "main", "draft": true }),
+            ))
+            .respond_with(ResponseTemplate::new(201).set_body_json(pull_request(7, "open", false)))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/repos/owner/repo/pulls/7"))
+            .and(body_partial_json(
+                json!({ "body": "Updated", "base": "develop" }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(pull_request(7, "open", false)))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/repo/pulls"))
+            .and(query_param("head", "owner:feature"))
+            .and(query_param("state", "all"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!([pull_request(7, "closed", true)])),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/repo/pulls/7"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(pull_request(7, "closed", false)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let forge = forge(&server);
+        let review = forge.create_review(&request(true)).await?;
+        assert_eq!(review.number, 7);
+        assert_eq!(review.url, "https://github.com/owner/repo/pull/7");
+        assert_eq!(
+            (review.head.as_str(), review.base.as_str()),
+            ("feature", "main")
+        );
+        assert_eq!(review.status, ReviewStatus::Open);
+
+        forge.update_review(7, "Updated", "develop").await?;
+        let reviews = forge.list_reviews_for_branch("feature").await?;
+        assert_eq!(reviews.len(), 1);
+        assert_eq!(reviews[0].status, ReviewStatus::Merged);
+        assert_eq!(forge.review_status(7).await?, ReviewStatus::Closed);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn errors_contain_the_response() -> anyhow::Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/repo/pulls/1"))
+            .respond_with(ResponseTemplate::new(404).set_body_string("Not Found"))
+            .mount(&server)
+            .await;
+
+        let err = forge(&server).review_status(1).await.unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("404"), "{message}");
+        assert!(message.contains("Not Found"), "{message}");
+        Ok(())
+    }
+}
+
+mod gitlab {
+    use serde_json::json;
+    use wiremock::{
+        matchers::{bearer_token, body_partial_json, method, path, query_param},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+
+    fn merge_request(iid: u64, state: &str, draft: bool) -> serde_json::Value {
+        json!({
+            "iid": iid,
+            "title": "Add feature",
+            "description": "Description",
+            "web_url": format!("https://gitlab.com/group/project/-/merge_requests/{iid}"),
+            "state": state,
+            "draft": draft,
+            "source_branch": "feature",
+            "target_branch": "main",
+        })
+    }
+
+    #[tokio::test]
+    async fn create_update_list_and_status() -> anyhow::Result<()> {
+        let server = MockServer::start().await;
+        let base = "/projects/group%2Fproject/merge_requests";
+        Mock::given(method("POST"))
+            .and(path(base))
+            .and(bearer_token("secret"))
+            .and(body_partial_json(json!({
+                "title": "Draft: Add feature",
+                "source_branch": "feature",
+                "target_branch": "main",
+            })))
+            .respond_with(
+                ResponseTemplate::new(201).set_body_json(merge_request(3, "opened", true)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path(format!("{base}/3")))
+            .and(body_partial_json(
+                json!({ "description": "Updated", "target_branch": "develop" }),
+            ))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(merge_request(3, "opened", true)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(base))
+            .and(query_param("source_branch", "feature"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!([merge_request(3, "merged", false)])),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("{base}/3")))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(merge_request(3, "locked", false)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let AnyForge::GitLab(forge) = forge_for_remote_url(
+            &url("git@gitlab.com:group/project.git"),
+            Some("secret".into()),
+        )?
+        .expect("known forge") else {
+            panic!("expected GitLab");
+        };
+        let forge = forge.with_api_url(server.uri());
+
+        let review = forge.create_review(&request(true)).await?;
+        assert_eq!(review.number, 3);
+        assert_eq!(review.status, ReviewStatus::Draft);
+        forge.update_review(3, "Updated", "develop").await?;
+        let reviews = forge.list_reviews_for_branch("feature").await?;
+        assert_eq!(reviews[0].status, ReviewStatus::Merged);
+        assert_eq!(forge.review_status(3).await?, ReviewStatus::Closed);
+        Ok(())
+    }
+}
+
+mod bitbucket {
+    use serde_json::json;
+    use wiremock::{
+        matchers::{body_partial_json, method, path, query_param},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+
+    fn pull_request(id: u64, state: &str) -> serde_json::Value {
+        json!({
+            "id": id,
+            "title": "Add feature",
+            "description": "Description",
+            "state": state,
+            "draft": false,
+            "links": { "html": { "href": format!("https://bitbucket.org/ws/repo/pull-requests/{id}") } },
+            "source": { "branch": { "name": "feature" } },
+            "destination": { "branch": { "name": "main" } },
+        })
+    }
+
+    #[tokio::test]
+    async fn create_update_list_and_status() -> anyhow::Result<()> {
+        let server = MockServer::start().await;
+        let base = "/repositories/ws/repo/pullrequests";
+        Mock::given(method("POST"))
+            .and(path(base))
+            .and(body_partial_json(json!({
+                "source": { "branch": { "name": "feature" } },
+                "destination": { "branch": { "name": "main" } },
+            })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(pull_request(5, "OPEN")))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path(format!("{base}/5")))
+            .and(body_partial_json(json!({
+                "description": "Updated",
+                "destination": { "branch": { "name": "develop" } },
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(pull_request(5, "OPEN")))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(base))
+            .and(query_param("q", "source.branch.name=\"feature\""))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "values": [pull_request(5, "MERGED")] })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("{base}/5")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(pull_request(5, "DECLINED")))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let AnyForge::Bitbucket(forge) =
+            forge_for_remote_url(&url("https://bitbucket.org/ws/repo.git"), None)?
+                .expect("known forge")
+        else {
+            panic!("expected Bitbucket");
+        };
+        let forge = forge.with_api_url(server.uri());
+
+        let review = forge.create_review(&request(false)).await?;
+        assert_eq!(review.number, 5);
+        assert_eq!(review.url, "https://bitbucket.org/ws/repo/pull-requests/5");
+        assert_eq!(review.status, ReviewStatus::Open);
+        forge.update_review(5, "Updated", "develop").await?;
+        let reviews = forge.list_reviews_for_branch("feature").await?;
+        assert_eq!(reviews[0].status, ReviewStatus::Merged);
+        assert_eq!(forge.review_status(5).await?, ReviewStatus::Closed);
+        Ok(())
+    }
+}
+
+mod azure {
+    use serde_json::json;
+    use wiremock::{
+        matchers::{basic_auth, body_partial_json, method, path, query_param},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+
+    fn pull_request(id: u64, status: &str, draft: bool) -> serde_json::Value {
+        json!({
+            "pullRequestId": id,
+            "title": "Add feature",
+            "description": "Description",
+            "status": status,
+            "isDraft": draft,
+            "sourceRefName": "refs/heads/feature",
+            "targetRefName": "refs/heads/main",
+        })
+    }
+
+    #[tokio::test]
+    async fn create_update_list_and_status() -> anyhow::Result<()> {
+        let server = MockServer::start().await;
+        let base = "/org/project/_apis/git/repositories/repo/pullrequests";
+        Mock::given(method("POST"))
+            .and(path(base))
+            .and(query_param("api-version", "7.1"))
+            .and(basic_auth("", "secret"))
+            .and(body_partial_json(json!({
+                "sourceRefName": "refs/heads/feature",
+                "targetRefName": "refs/heads/main",
+                "isDraft": true,
+            })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(pull_request(9, "active", true)))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path(format!("{base}/9")))
+            .and(body_partial_json(json!({
+                "description": "Updated",
+                "targetRefName": "refs/heads/develop",
+            })))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(pull_request(9, "active", false)),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(base))
+            .and(query_param(
+                "searchCriteria.sourceRefName",
+                "refs/heads/feature",
+            ))
+            .and(query_param("searchCriteria.status", "all"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                json!({ "value": [pull_request(9, "completed", false)], "count": 1 }),
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path(format!("{base}/9")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(pull_request(
+                9,
+                "abandoned",
+                false,
+            )))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let AnyForge::Azure(forge) = forge_for_remote_url(
+            &url("https://dev.azure.com/org/project/_git/repo"),
+            Some("secret".into()),
+        )?
+        .expect("known forge") else {
+            panic!("expected Azure");
+        };
+        let forge = forge.with_api_url(server.uri());
+
+        let review = forge.create_review(&request(true)).await?;
+        assert_eq!(review.number, 9);
+        assert_eq!(
+            (review.head.as_str(), review.base.as_str()),
+            ("feature", "main"),
+            "the refs/heads/ prefix is removed"
+        );
+        assert_eq!(
+            review.url,
+            "https://dev.azure.com/org/project/_git/repo/pullrequest/9"
+        );
+        assert_eq!(review.status, ReviewStatus::Draft);
+        forge.update_review(9, "Updated", "develop").await?;
+        let reviews = forge.list_reviews_for_branch("feature").await?;
+        assert_eq!(reviews[0].status, ReviewStatus::Merged);
+        assert_eq!(forge.review_status(9).await?, ReviewStatus::Closed);
+        Ok(())
+    }
+}
@@ -337,6 +337,9 @@ where
 /// Any prompts for the user are passed to the asynchronous callback `on_prompt`,
 /// which should return the user's response or `None` if the operation should be
 /// aborted, in which case an `Err` value is returned from this function.
+///
+/// On success, the standard error output of the push is returned, which contains
+/// the messages sent by the remote as `remote: ` lines.
 #[expect(clippy::too_many_arguments)]
 pub async fn push<P, F, Fut, E, Extra>(
     repo_path: P,
@@ -347,7 +350,7 @@ pub async fn push<P, F, Fut, E, Extra>(
     force_push_protection: bool,
     on_prompt: F,
     extra: Extra,
-) -> Result<(), crate::Error<Error<E>>>
+) -> Result<String, crate::Error<Error<E>>>
 where
     P: AsRef<Path>,
     E: GitExecutor,
@@ -375,7 +378,7 @@ where
         execute_with_auth_harness(repo_path, &executor, &args, None, on_prompt, extra).await?;
 
     if status == 0 {
-        return Ok(());
+        return Ok(stderr);
     }
 
     let base_error = Error::<E>::Failed {
@@ -0,0 +1,235 @@
+use std::{path::Path, process::Command};
+
+use anyhow::{bail, Context, Result};
+use but_db::{DbHandle, HunkAssignment, WorkspaceRule};
+use gitbutler_command_context::CommandContext;
+use gitbutler_project::{access::WorktreeReadPermission, Project};
+use gitbutler_repo::SignaturePurpose;
+use gitbutler_stack::VirtualBranchesHandle;
+
+use crate::{
+    reflog::{set_reference_to_oplog, ReflogCommits},
+    state::OplogHandle,
+};
+
+/// The reference pointing to the oplog head in a bundle.
+const OPLOG_REF: &str = "refs/gitbutler/export/oplog";
+/// The reference pointing to a commit with the GitButler state that isn't part of snapshots in a bundle.
+const STATE_REF: &str = "refs/gitbutler/export/state";
+const WORKSPACE_REF: &str = "refs/heads/gitbutler/workspace";
+
+const VIRTUAL_BRANCHES_FILE: &str = "virtual_branches.toml";
+const WORKSPACE_RULES_FILE: &str = "workspace_rules.json";
+const HUNK_ASSIGNMENTS_FILE: &str = "hunk_assignments.json";
+
+/// Write the oplog of the project behind `ctx` into a git bundle at `bundle_path`, to be imported
+/// into a scratch repository with [`import_bundle()`] to reproduce the workspace with all of its snapshots.
+///
+/// Besides the chain of snapshots, the bundle contains the workspace commit, the target branch and the branches
+/// of all stacks, applied or not, along with their history, the current `virtual_branches.toml` as well as the
+/// workspace rules and hunk assignments. Nothing else of the repository is included, and nothing that was written
+/// to create the bundle is left behind.
+pub fn export_bundle(
+    ctx: &mut CommandContext,
+    bundle_path: &Path,
+    _perm: &WorktreeReadPermission,
+) -> Result<()> {
+    let project = ctx.project().clone();
+    let oplog_head = OplogHandle::new(&project.gb_dir())
+        .oplog_head()?
+        .context("There are no snapshots to export")?;
+
+    let rules = ctx.db()?.workspace_rules().list()?;
+    let assignments = ctx.db()?.hunk_assignments().list_all()?;
+    let repo = ctx.repo();
+    let vb_toml = std::fs::read(project.gb_dir().join(VIRTUAL_BRANCHES_FILE))
+        .context("Failed to read virtual_branches.toml")?;
+
+    let mut export = ExportState::new(repo);
+    let mut tree = Vec::new();
+    for (name, content) in [
+        (
+            HUNK_ASSIGNMENTS_FILE,
+            serde_json::to_vec_pretty(&assignments)?,
+        ),
+        (VIRTUAL_BRANCHES_FILE, vb_toml),
+        (WORKSPACE_RULES_FILE, serde_json::to_vec_pretty(&rules)?),
+    ] {
+        // The names are sorted, as git expects them to be in a tree.
+        let blob = export.write(git2::ObjectType::Blob, &content)?;
+        tree.extend_from_slice(format!("100644 {name}\0").as_bytes());
+        tree.extend_from_slice(blob.as_bytes());
+    }
+    let tree = repo.find_tree(export.write(git2::ObjectType::Tree, &tree)?)?;
+    let signature = gitbutler_repo::signature(SignaturePurpose::Committer)?;
+    let state_commit =
+        repo.commit_create_buffer(&signature, &signature, "GitButler state export", &tree, &[])?;
+    let state_commit = export.write(git2::ObjectType::Commit, &state_commit)?;
+
+    let mut refs = vec![OPLOG_REF.to_owned(), STATE_REF.to_owned()];
+    if repo.find_reference(WORKSPACE_REF).is_ok() {
+        refs.push(WORKSPACE_REF.to_owned());
+    }
+    let vb_state = VirtualBranchesHandle::new(project.gb_dir());
+    if let Ok(target) = vb_state.get_default_target() {
+        let target_ref = target.branch.to_string();
+        if repo.find_reference(&target_ref).is_ok() {
+            refs.push(target_ref);
+        }
+    }
+    for stack in vb_state.list_all_stacks()? {
+        for branch in &stack.heads {
+            let branch_ref = branch.full_name()?.to_string();
+            if repo.find_reference(&branch_ref).is_ok() && !refs.contains(&branch_ref) {
+                refs.push(branch_ref);
+            }
+        }
+    }
+
+    // Bundles can only be created from references, which are removed again when `export` is dropped.
+    export.reference(OPLOG_REF, oplog_head)?;
+    export.reference(STATE_REF, state_commit)?;
+    git(&project.path, |cmd| {
+        cmd.arg("bundle").arg("create").arg(bundle_path).args(&refs)
+    })
+}
+
+/// The references and objects that were created only to be able to create a bundle, which are removed
+/// once this instance is dropped, no matter if the bundle could be created or not.
+struct ExportState<'repo> {
+    repo: &'repo git2::Repository,
+    refs: Vec<&'static str>,
+    /// Objects that didn't exist in the repository before they were written for the export.
+    new_objects: Vec<git2::Oid>,
+}
+
+impl<'repo> ExportState<'repo> {
+    fn new(repo: &'repo git2::Repository) -> Self {
+        ExportState {
+            repo,
+            refs: Vec::new(),
+            new_objects: Vec::new(),
+        }
+    }
+
+    /// Write an object of `kind` with `data`, remembering it for removal if it didn't exist yet.
+    fn write(&mut self, kind: git2::ObjectType, data: &[u8]) -> Result<git2::Oid> {
+        let odb = self.repo.odb()?;
+        let id = git2::Oid::hash_object(kind, data)?;
+        if !odb.exists(id) {
+            self.new_objects.push(odb.write(kind, data)?);
+        }
+        Ok(id)
+    }
+
+    /// Create the reference `name` pointing to `target`, remembering it for removal.
+    fn reference(&mut self, name: &'static str, target: git2::Oid) -> Result<()> {
+        self.refs.push(name);
+        self.repo.reference(name, target, true, "oplog export")?;
+        Ok(())
+    }
+}
+
+impl Drop for ExportState<'_> {
+    fn drop(&mut self) {
+        for name in &self.refs {
+            match self.repo.find_reference(name) {
+                Ok(mut reference) => {
+                    if let Err(err) = reference.delete() {
+                        tracing::warn!("Failed to remove temporary reference '{name}': {err}");
+                    }
+                }
+                Err(err) if err.code() == git2::ErrorCode::NotFound => {}
+                Err(err) => tracing::warn!("Failed to find temporary reference '{name}': {err}"),
+            }
+        }
+        // Objects are always written loose, so they can be removed without leaving anything dangling.
+        let objects_dir = self.repo.path().join("objects");
+        for id in &self.new_objects {
+            let hex = id.to_string();
+            let path = objects_dir.join(&hex[..2]).join(&hex[2..]);
+            match std::fs::remove_file(&path) {
+                Ok(()) => {}
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => tracing::warn!("Failed to remove temporary object {id}: {err}"),
+            }
+        }
+    }
+}
+
+/// Replay the bundle at `bundle_path`, created with [`export_bundle()`], into a new repository in `worktree_dir`
+/// so any of its snapshots can be restored there.
+///
+/// The repository is checked out at the workspace commit, and the GitButler state is set up as it was when the bundle
+/// was created. Returns the id of the most recent snapshot.
+pub fn import_bundle(bundle_path: &Path, worktree_dir: &Path) -> Result<git2::Oid> {
+    if worktree_dir.exists() && worktree_dir.read_dir()?.next().is_some() {
+        bail!(
+            "Refusing to import into '{}' as it's not empty",
+            worktree_dir.display()
+        );
+    }
+    let bundle_path = std::path::absolute(bundle_path)?;
+    let repo = git2::Repository::init(worktree_dir)?;
+    git(worktree_dir, |cmd| {
+        cmd.arg("fetch")
+            .arg("--quiet")
+            .arg(&bundle_path)
+            .arg("+refs/*:refs/*")
+    })?;
+
+    let oplog_head = repo
+        .find_reference(OPLOG_REF)
+        .context("The bundle doesn't contain an oplog")?
+        .peel_to_commit()?
+        .id();
+    let state_tree = repo.find_reference(STATE_REF)?.peel_to_tree()?;
+    let read = |name: &str| -> Result<Vec<u8>> {
+        let entry = state_tree
+            .get_name(name)
+            .with_context(|| format!("The bundle doesn't contain '{name}'"))?;
+        Ok(repo.find_blob(entry.id())?.content().to_owned())
+    };
+
+    let project = Project::from_path(worktree_dir)?;
+    let gb_dir = project.gb_dir();
+    std::fs::create_dir_all(&gb_dir)?;
+    std::fs::write(
+        gb_dir.join(VIRTUAL_BRANCHES_FILE),
+        read(VIRTUAL_BRANCHES_FILE)?,
+    )?;
+    let rules: Vec<WorkspaceRule> = serde_json::from_slice(&read(WORKSPACE_RULES_FILE)?)?;
+    let assignments: Vec<HunkAssignment> = serde_json::from_slice(&read(HUNK_ASSIGNMENTS_FILE)?)?;
+    let mut db = DbHandle::new_in_directory(&gb_dir)?;
+    for rule in rules {
+        db.workspace_rules().insert(rule)?;
+    }
+    db.hunk_assignments().set_all(assignments)?;
+
+    OplogHandle::new(&gb_dir).set_oplog_head(oplog_head)?;
+    set_reference_to_oplog(&project.path, ReflogCommits::new(&project)?)?;
+    for name in [OPLOG_REF, STATE_REF] {
+        repo.find_reference(name)?.delete()?;
+    }
+
+    if repo.find_reference(WORKSPACE_REF).is_ok() {
+        repo.set_head(WORKSPACE_REF)?;
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))?;
+    }
+    Ok(oplog_head)
+}
+
+/// Run `git` in `dir` with the arguments `args` adds, failing with its error output if it fails.
+fn git(dir: &Path, args: impl FnOnce(&mut Command) -> &mut Command) -> Result<()> {
+    let mut cmd = Command::new(gix::path::env::exe_invocation());
+    cmd.current_dir(dir);
+    let output = args(&mut cmd).output()?;
+    if !output.status.success() {
+        bail!(
+            "git failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(())
+}
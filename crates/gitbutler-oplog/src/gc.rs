@@ -0,0 +1,204 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{bail, Context, Result};
+use gitbutler_command_context::CommandContext;
+use gitbutler_project::{access::WorktreeWritePermission, OplogRetention};
+use serde::Serialize;
+
+use crate::{
+    entry::Snapshot,
+    reflog::{set_reference_to_oplog, ReflogCommits},
+    state::OplogHandle,
+    OplogExt,
+};
+
+/// The key of the trailer with which a restore refers to the snapshot it restored.
+const RESTORED_FROM_TRAILER: &str = "restored_from";
+
+/// The result of compacting the operations log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcOutcome {
+    /// The amount of snapshots before compaction.
+    pub snapshots_before: usize,
+    /// The amount of snapshots that were removed.
+    pub snapshots_removed: usize,
+    /// The amount of bytes of loose objects of removed or rewritten snapshots that were deleted from the object database.
+    pub bytes_removed: u64,
+}
+
+/// Return, for each snapshot created at `times` in seconds since the Unix epoch and ordered from the most recent
+/// to the oldest, if it should be kept under `retention` assuming it's `now`.
+///
+/// Beyond the most recent snapshots and those still young enough to be kept in full, a snapshot is only kept if
+/// it's in a different hour or day than the previously kept one, which is why the most recent snapshot of each
+/// hour or day survives.
+pub fn retained_snapshots(times: &[i64], now: i64, retention: &OplogRetention) -> Vec<bool> {
+    const HOUR: i64 = 60 * 60;
+    const DAY: i64 = 24 * HOUR;
+    let keep_all_since = now - i64::from(retention.keep_all_days) * DAY;
+    let keep_hourly_since = now - i64::from(retention.keep_hourly_days) * DAY;
+
+    let mut last_kept: Option<i64> = None;
+    times
+        .iter()
+        .enumerate()
+        .map(|(idx, &time)| {
+            let keep = if idx < retention.keep_latest || time >= keep_all_since {
+                true
+            } else {
+                let bucket_size = if time >= keep_hourly_since { HOUR } else { DAY };
+                last_kept
+                    .is_none_or(|last| last.div_euclid(bucket_size) != time.div_euclid(bucket_size))
+            };
+            if keep {
+                last_kept = Some(time);
+            }
+            keep
+        })
+        .collect()
+}
+
+pub(crate) fn compact(
+    ctx: &CommandContext,
+    retention: &OplogRetention,
+    _exclusive_access: &mut WorktreeWritePermission,
+) -> Result<GcOutcome> {
+    let project = ctx.project();
+    let repo = ctx.repo();
+    let snapshots = ctx.list_snapshots(usize::MAX, None, Vec::new())?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs() as i64;
+    let times: Vec<_> = snapshots.iter().map(|s| s.created_at.seconds()).collect();
+    let mut keep = retained_snapshots(&times, now, retention);
+    // Restores refer to the snapshot they restored, which thus has to survive as well.
+    // Restored snapshots are always older than the restore, so one pass from the most recent one suffices.
+    for idx in 0..snapshots.len() {
+        if !keep[idx] {
+            continue;
+        }
+        for restored_from in restored_from(&snapshots[idx]) {
+            if let Some(target) = snapshots
+                .iter()
+                .position(|snapshot| snapshot.commit_id == restored_from)
+            {
+                keep[target] = true;
+            }
+        }
+    }
+    let snapshots_removed = keep.iter().filter(|keep| !**keep).count();
+    if snapshots_removed == 0 {
+        return Ok(GcOutcome {
+            snapshots_before: snapshots.len(),
+            snapshots_removed,
+            bytes_removed: 0,
+        });
+    }
+
+    // The chain continues below the oldest snapshot we know, if there is anything there.
+    let mut parent = match snapshots.last() {
+        Some(oldest) => repo.find_commit(oldest.commit_id)?.parent_ids().next(),
+        None => None,
+    };
+    // The ids of rewritten snapshots, by their previous id.
+    let mut rewritten = HashMap::<git2::Oid, git2::Oid>::new();
+    // The objects that nothing refers to anymore once the chain was rewritten.
+    let mut unreferenced = Vec::new();
+    let kept_trees: HashSet<_> = snapshots
+        .iter()
+        .zip(&keep)
+        .filter(|(_, keep)| **keep)
+        .map(|(snapshot, _)| Ok(repo.find_commit(snapshot.commit_id)?.tree_id()))
+        .collect::<Result<_>>()?;
+    for (snapshot, keep) in snapshots.iter().zip(keep).rev() {
+        let commit = repo.find_commit(snapshot.commit_id)?;
+        if !keep {
+            unreferenced.push(commit.id());
+            if !kept_trees.contains(&commit.tree_id()) {
+                unreferenced.push(commit.tree_id());
+            }
+            continue;
+        }
+        if unreferenced.is_empty() {
+            // Nothing was removed below this snapshot yet, so it can stay as is.
+            parent = Some(snapshot.commit_id);
+            continue;
+        }
+        let message = commit.message_raw().with_context(|| {
+            format!(
+                "Snapshot {} has a message that isn't valid UTF-8",
+                commit.id()
+            )
+        })?;
+        let message = remap_restored_from(message, &rewritten);
+        let parents = parent.map(|id| repo.find_commit(id)).transpose()?;
+        let new_id = repo.commit(
+            None,
+            &commit.author(),
+            &commit.committer(),
+            &message,
+            &commit.tree()?,
+            parents.iter().collect::<Vec<_>>().as_slice(),
+        )?;
+        rewritten.insert(commit.id(), new_id);
+        unreferenced.push(commit.id());
+        parent = Some(new_id);
+    }
+
+    let Some(new_head) = parent else {
+        bail!("BUG: the most recent snapshot is always retained");
+    };
+    OplogHandle::new(&project.gb_dir()).set_oplog_head(new_head)?;
+    set_reference_to_oplog(&project.path, ReflogCommits::new(project)?)?;
+
+    // Only loose objects can be deleted, the packed ones are left to `git gc`.
+    let objects_dir = repo.path().join("objects");
+    let mut bytes_removed = 0;
+    for id in unreferenced {
+        let hex = id.to_string();
+        let path = objects_dir.join(&hex[..2]).join(&hex[2..]);
+        let Ok(metadata) = std::fs::metadata(&path) else {
+            continue;
+        };
+        std::fs::remove_file(&path)?;
+        bytes_removed += metadata.len();
+    }
+
+    Ok(GcOutcome {
+        snapshots_before: snapshots.len(),
+        snapshots_removed,
+        bytes_removed,
+    })
+}
+
+/// Return the ids of the snapshots that `snapshot` restored.
+fn restored_from(snapshot: &Snapshot) -> impl Iterator<Item = git2::Oid> + '_ {
+    snapshot
+        .details
+        .iter()
+        .flat_map(|details| &details.trailers)
+        .filter(|trailer| trailer.key == RESTORED_FROM_TRAILER)
+        .filter_map(|trailer| git2::Oid::from_str(&trailer.value).ok())
+}
+
+/// Return `message` with the snapshot ids in its `restored_from` trailers replaced with their `rewritten` ids.
+fn remap_restored_from(message: &str, rewritten: &HashMap<git2::Oid, git2::Oid>) -> String {
+    let prefix = format!("{RESTORED_FROM_TRAILER}: ");
+    message
+        .split_inclusive('\n')
+        .map(|line| {
+            let new_id = line
+                .strip_prefix(&prefix)
+                .and_then(|value| git2::Oid::from_str(value.trim_end()).ok())
+                .and_then(|id| rewritten.get(&id));
+            match new_id {
+                Some(new_id) => {
+                    let line_end = if line.ends_with('\n') { "\n" } else { "" };
+                    format!("{prefix}{new_id}{line_end}")
+                }
+                None => line.to_owned(),
+            }
+        })
+        .collect()
+}
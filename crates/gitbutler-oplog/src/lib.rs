@@ -1,10 +1,18 @@
+mod bundle;
+pub use bundle::{export_bundle, import_bundle};
 pub mod entry;
+mod gc;
+pub use gc::{retained_snapshots, GcOutcome};
 mod oplog;
 pub use oplog::OplogExt;
+mod query;
+pub use query::SnapshotQuery;
 pub mod reflog;
 mod snapshot;
 pub use snapshot::SnapshotExt;
 mod state;
+mod undo;
+pub use undo::{redo_target, undo_target, RestoreAction, RESTORE_ACTION_TRAILER};
 
 /// The name of the file holding our state, useful for watching for changes.
 pub const OPLOG_FILE_NAME: &str = "operations-log.toml";
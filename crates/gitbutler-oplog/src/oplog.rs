@@ -5,7 +5,13 @@ use std::{
     time::Duration,
 };
 
-use crate::{entry::Version, reflog::ReflogCommits};
+use crate::{
+    entry::Version,
+    gc::GcOutcome,
+    query::SnapshotQuery,
+    reflog::ReflogCommits,
+    undo::{redo_target, undo_target, RestoreAction},
+};
 
 use super::{
     entry::{OperationKind, Snapshot, SnapshotDetails, Trailer},
@@ -24,7 +30,7 @@ use gitbutler_oxidize::{
 };
 use gitbutler_project::{
     access::{WorktreeReadPermission, WorktreeWritePermission},
-    Project, AUTO_TRACK_LIMIT_BYTES,
+    OplogRetention, Project, AUTO_TRACK_LIMIT_BYTES,
 };
 use gitbutler_repo::RepositoryExt;
 use gitbutler_repo::SignaturePurpose;
@@ -106,6 +112,14 @@ pub trait OplogExt {
         exclude_kind: Vec<OperationKind>,
     ) -> Result<Vec<Snapshot>>;
 
+    /// Lists up to `limit` snapshots that match all criteria of `query`, with the most recent snapshot first.
+    ///
+    /// As snapshots are traversed from the oplog head, the traversal stops at the first snapshot older than
+    /// [`SnapshotQuery::since`], which keeps time-bounded queries cheap even in a long oplog.
+    /// Matching by [path](SnapshotQuery::path) is the most expensive criterion as it needs the changes of each snapshot
+    /// (see [`snapshot_diff()`](Self::snapshot_diff)), so it's only checked for snapshots that match otherwise.
+    fn query_snapshots(&self, query: &SnapshotQuery, limit: usize) -> Result<Vec<Snapshot>>;
+
     /// Reverts to a previous state of the working directory, virtual branches and commits.
     /// The provided `snapshot_commit_id` must refer to a valid snapshot commit, as returned by [`create_snapshot`](Self::create_snapshot).
     /// Upon success, a new snapshot is created representing the state right before this call.
@@ -123,6 +137,30 @@ pub trait OplogExt {
         guard: &mut WorktreeWritePermission,
     ) -> Result<git2::Oid>;
 
+    /// Undoes the most recent operation that wasn't undone yet by restoring the snapshot taken right before it,
+    /// and returns that snapshot, or `None` if there is nothing left to undo.
+    ///
+    /// The restore is marked as undo, so calling this repeatedly steps further back in history, one operation at a time.
+    /// See [`undo_target()`](crate::undo_target) for details.
+    fn undo(&self, perm: &mut WorktreeWritePermission) -> Result<Option<Snapshot>>;
+
+    /// Reverts the most recent undo that wasn't redone yet, and returns the snapshot that was restored to do so,
+    /// or `None` if there is nothing to redo, which is also the case once another operation was performed after undoing.
+    /// See [`redo_target()`](crate::redo_target) for details.
+    fn redo(&self, perm: &mut WorktreeWritePermission) -> Result<Option<Snapshot>>;
+
+    /// Removes all snapshots that `retention` doesn't keep by rewriting the chain of snapshot commits, which preserves
+    /// the details of all remaining snapshots, and deletes the loose objects of the removed and replaced snapshot commits.
+    /// Snapshots that a retained restore refers to are always kept.
+    ///
+    /// Note that snapshot ids change for all snapshots more recent than the oldest removed snapshot, which is also
+    /// reflected in the `restored_from` trailers of restores.
+    fn compact(
+        &self,
+        retention: &OplogRetention,
+        perm: &mut WorktreeWritePermission,
+    ) -> Result<GcOutcome>;
+
     /// Determines if a new snapshot should be created due to file changes being created since the last snapshot.
     /// The needs for the automatic snapshotting are:
     ///  - It needs to facilitate backup of work in progress code
@@ -248,13 +286,85 @@ impl OplogExt for CommandContext {
         Ok(snapshots)
     }
 
+    #[instrument(skip(self), err(Debug))]
+    fn query_snapshots(&self, query: &SnapshotQuery, limit: usize) -> Result<Vec<Snapshot>> {
+        const PAGE_SIZE: usize = 100;
+        let mut snapshots = Vec::new();
+        let mut next_page_start = None;
+        'pages: loop {
+            let page = self.list_snapshots(PAGE_SIZE, next_page_start, Vec::new())?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            next_page_start = Some(last.commit_id);
+            let is_last_page = page.len() < PAGE_SIZE;
+            for snapshot in page {
+                if snapshots.len() == limit
+                    || query
+                        .since
+                        .is_some_and(|since| snapshot.created_at.seconds() < since)
+                {
+                    break 'pages;
+                }
+                if !query.matches_details(&snapshot) {
+                    continue;
+                }
+                if query.path.is_some()
+                    && !self
+                        .snapshot_diff(snapshot.commit_id)?
+                        .iter()
+                        .any(|change| {
+                            query.matches_path(change.path.as_bstr())
+                                || change
+                                    .previous_path()
+                                    .is_some_and(|path| query.matches_path(path))
+                        })
+                {
+                    continue;
+                }
+                snapshots.push(snapshot);
+            }
+            if is_last_page {
+                break;
+            }
+        }
+        Ok(snapshots)
+    }
+
     fn restore_snapshot(
         &self,
         snapshot_commit_id: git2::Oid,
         guard: &mut WorktreeWritePermission,
     ) -> Result<git2::Oid> {
         // let mut guard = self.exclusive_worktree_access();
-        restore_snapshot(self, snapshot_commit_id, guard)
+        restore_snapshot(self, snapshot_commit_id, None, guard)
+    }
+
+    #[instrument(skip(self, perm), err(Debug))]
+    fn undo(&self, perm: &mut WorktreeWritePermission) -> Result<Option<Snapshot>> {
+        let Some(target) = find_in_history(self, undo_target)? else {
+            return Ok(None);
+        };
+        restore_snapshot(self, target.commit_id, Some(RestoreAction::Undo), perm)?;
+        Ok(Some(target))
+    }
+
+    #[instrument(skip(self, perm), err(Debug))]
+    fn redo(&self, perm: &mut WorktreeWritePermission) -> Result<Option<Snapshot>> {
+        let Some(target) = find_in_history(self, redo_target)? else {
+            return Ok(None);
+        };
+        restore_snapshot(self, target.commit_id, Some(RestoreAction::Redo), perm)?;
+        Ok(Some(target))
+    }
+
+    #[instrument(skip(self, perm), err(Debug))]
+    fn compact(
+        &self,
+        retention: &OplogRetention,
+        perm: &mut WorktreeWritePermission,
+    ) -> Result<GcOutcome> {
+        crate::gc::compact(self, retention, perm)
     }
 
     #[instrument(level = tracing::Level::DEBUG, skip(self), err(Debug))]
@@ -520,9 +630,28 @@ fn commit_snapshot(
     Ok(snapshot_commit_id)
 }
 
+/// Return the snapshot `find` picks from all snapshots, most recent first, while trying to avoid listing all of them
+/// as undo and redo usually don't have to look far.
+fn find_in_history(
+    ctx: &CommandContext,
+    find: fn(&[Snapshot]) -> Option<&Snapshot>,
+) -> Result<Option<Snapshot>> {
+    for limit in [100, usize::MAX] {
+        let snapshots = ctx.list_snapshots(limit, None, Vec::new())?;
+        if let Some(snapshot) = find(&snapshots) {
+            return Ok(Some(snapshot.clone()));
+        }
+        if snapshots.len() < limit {
+            break;
+        }
+    }
+    Ok(None)
+}
+
 fn restore_snapshot(
     ctx: &CommandContext,
     snapshot_commit_id: git2::Oid,
+    action: Option<RestoreAction>,
     exclusive_access: &mut WorktreeWritePermission,
 ) -> Result<git2::Oid> {
     let worktree_dir = ctx.project().path.as_path();
@@ -667,7 +796,7 @@ fn restore_snapshot(
     // create new snapshot
     let before_restore_snapshot_tree_id = before_restore_snapshot_result?;
     let restored_date_ms = snapshot_commit.time().seconds() * 1000;
-    let details = SnapshotDetails {
+    let mut details = SnapshotDetails {
         version: Default::default(),
         operation: OperationKind::RestoreFromSnapshot,
         title: "Restored from snapshot".to_string(),
@@ -687,6 +816,9 @@ fn restore_snapshot(
             },
         ],
     };
+    if let Some(action) = action {
+        details.trailers.push(action.to_trailer());
+    }
     commit_snapshot(
         ctx.project(),
         before_restore_snapshot_tree_id,
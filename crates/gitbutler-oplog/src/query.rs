@@ -0,0 +1,72 @@
+use gix::bstr::BStr;
+use serde::Deserialize;
+
+use crate::entry::{OperationKind, Snapshot};
+
+/// Criteria to select snapshots by, all of which have to match for a snapshot to be selected.
+///
+/// The default query matches all snapshots.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotQuery {
+    /// If set, only snapshots created at or after this time, in seconds since the Unix epoch, are selected.
+    pub since: Option<i64>,
+    /// If set, only snapshots created at or before this time, in seconds since the Unix epoch, are selected.
+    pub until: Option<i64>,
+    /// If not empty, only snapshots of one of these kinds are selected.
+    #[serde(default)]
+    pub kinds: Vec<OperationKind>,
+    /// If set, only snapshots whose worktree changes touch this path, or anything inside of it if it's a directory,
+    /// are selected. The path is relative to the root of the worktree.
+    pub path: Option<String>,
+    /// If set, only snapshots with a trailer of the given key are selected, and if a value is given, the
+    /// trailer value must match as well.
+    pub trailer: Option<(String, Option<String>)>,
+}
+
+impl SnapshotQuery {
+    /// Return `true` if `snapshot` matches everything but the `path` of this query, which can't be
+    /// determined without computing its changes.
+    pub(crate) fn matches_details(&self, snapshot: &Snapshot) -> bool {
+        let time = snapshot.created_at.seconds();
+        if self.since.is_some_and(|since| time < since)
+            || self.until.is_some_and(|until| time > until)
+        {
+            return false;
+        }
+        if !self.kinds.is_empty()
+            && !snapshot
+                .details
+                .as_ref()
+                .is_some_and(|details| self.kinds.contains(&details.operation))
+        {
+            return false;
+        }
+        if let Some((key, value)) = &self.trailer {
+            let has_trailer = snapshot.details.as_ref().is_some_and(|details| {
+                details
+                    .trailers
+                    .iter()
+                    .any(|t| &t.key == key && value.as_ref().is_none_or(|v| &t.value == v))
+            });
+            if !has_trailer {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Return `true` if `changed_path` is the path of this query or inside of it, or if there is no path to match.
+    pub(crate) fn matches_path(&self, changed_path: &BStr) -> bool {
+        let Some(wanted) = self.path.as_ref() else {
+            return true;
+        };
+        let wanted = wanted.trim_end_matches('/');
+        if wanted.is_empty() {
+            return true;
+        }
+        changed_path
+            .strip_prefix(wanted.as_bytes())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(b"/"))
+    }
+}
@@ -0,0 +1,89 @@
+use crate::entry::{OperationKind, Snapshot, Trailer};
+
+/// The key of the trailer that marks a [restore](OperationKind::RestoreFromSnapshot) as undo or redo.
+pub const RESTORE_ACTION_TRAILER: &str = "restore_action";
+
+/// The reason for restoring a snapshot, recorded on the snapshot of the restore to be able to reconstruct
+/// the undo history from the operations log alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreAction {
+    /// The restore undid the operation of the snapshot it restored from.
+    Undo,
+    /// The restore reverted an undo, by restoring the snapshot taken right before it.
+    Redo,
+}
+
+impl RestoreAction {
+    /// Return the action that created `snapshot`, or `None` if it's not a restore made to undo or redo.
+    pub fn of(snapshot: &Snapshot) -> Option<Self> {
+        let details = snapshot.details.as_ref()?;
+        if details.operation != OperationKind::RestoreFromSnapshot {
+            return None;
+        }
+        let trailer = details
+            .trailers
+            .iter()
+            .find(|t| t.key == RESTORE_ACTION_TRAILER)?;
+        match trailer.value.as_str() {
+            "undo" => Some(RestoreAction::Undo),
+            "redo" => Some(RestoreAction::Redo),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn to_trailer(self) -> Trailer {
+        Trailer {
+            key: RESTORE_ACTION_TRAILER.to_owned(),
+            value: match self {
+                RestoreAction::Undo => "undo",
+                RestoreAction::Redo => "redo",
+            }
+            .to_owned(),
+        }
+    }
+}
+
+/// Return the snapshot to restore to undo the most recent operation that wasn't undone yet, given `snapshots`
+/// with the most recent one first, or `None` if everything in `snapshots` was undone already.
+///
+/// Each undo makes the snapshot below the one it restored the next one to undo to, so repeated undos walk further
+/// back in history instead of undoing the previous undo. A redo can be undone like any other operation.
+pub fn undo_target(snapshots: &[Snapshot]) -> Option<&Snapshot> {
+    // The amount of snapshots below the current one that were restored by undos more recent than it.
+    let mut undone = 0usize;
+    for snapshot in snapshots {
+        match RestoreAction::of(snapshot) {
+            Some(RestoreAction::Undo) => undone += 1,
+            Some(RestoreAction::Redo) | None => {
+                if undone == 0 {
+                    return Some(snapshot);
+                }
+                undone -= 1;
+            }
+        }
+    }
+    None
+}
+
+/// Return the snapshot to restore to redo the most recent undo that wasn't redone yet, given `snapshots` with the
+/// most recent one first, or `None` if there is nothing to redo.
+///
+/// The snapshot of an undo captures the state right before it, so restoring it reverts the undo.
+/// Any operation other than undo and redo discards all undos before it, just like editing after undoing in an editor.
+pub fn redo_target(snapshots: &[Snapshot]) -> Option<&Snapshot> {
+    // The amount of undos below the current snapshot that were redone by redos more recent than it.
+    let mut redone = 0usize;
+    for snapshot in snapshots {
+        match RestoreAction::of(snapshot) {
+            Some(RestoreAction::Redo) => redone += 1,
+            Some(RestoreAction::Undo) => {
+                if redone == 0 {
+                    return Some(snapshot);
+                }
+                redone -= 1;
+            }
+            None => return None,
+        }
+    }
+    None
+}
@@ -172,3 +172,186 @@ mod snapshot_details {
         }
     }
 }
+
+mod retained_snapshots {
+    use gitbutler_oplog::retained_snapshots;
+    use gitbutler_project::OplogRetention;
+
+    const HOUR: i64 = 60 * 60;
+    const DAY: i64 = 24 * HOUR;
+    const NOW: i64 = 1000 * DAY;
+
+    fn retention(keep_all_days: u32, keep_hourly_days: u32, keep_latest: usize) -> OplogRetention {
+        OplogRetention {
+            keep_all_days,
+            keep_hourly_days,
+            keep_latest,
+        }
+    }
+
+    #[test]
+    fn young_snapshots_are_all_kept() {
+        let times = [NOW, NOW - 1, NOW - 2, NOW - DAY];
+        assert_eq!(
+            retained_snapshots(&times, NOW, &retention(2, 10, 0)),
+            [true; 4]
+        );
+    }
+
+    #[test]
+    fn older_snapshots_are_thinned_to_one_per_hour_and_then_per_day() {
+        let hourly = NOW - 5 * DAY;
+        let daily = NOW - 20 * DAY;
+        let times = [
+            NOW,
+            hourly + 30 * 60,
+            hourly + 10 * 60,
+            hourly - HOUR,
+            daily + 10 * HOUR,
+            daily + 5 * HOUR,
+            daily - DAY,
+        ];
+        assert_eq!(
+            retained_snapshots(&times, NOW, &retention(1, 10, 0)),
+            [true, true, false, true, true, false, true],
+            "the most recent snapshot of each hour, and later of each day, is kept"
+        );
+    }
+
+    #[test]
+    fn the_latest_snapshots_are_kept_regardless_of_age() {
+        let times = [NOW - 100 * DAY + 2, NOW - 100 * DAY + 1, NOW - 100 * DAY];
+        assert_eq!(
+            retained_snapshots(&times, NOW, &retention(1, 10, 2)),
+            [true, true, false]
+        );
+        assert_eq!(
+            retained_snapshots(&times, NOW, &retention(1, 10, 0)),
+            [true, false, false],
+            "the most recent snapshot is always kept"
+        );
+    }
+
+    #[test]
+    fn empty() {
+        assert!(retained_snapshots(&[], NOW, &OplogRetention::default()).is_empty());
+    }
+}
+
+mod undo_redo {
+    use gitbutler_oplog::{
+        entry::{OperationKind, Snapshot, SnapshotDetails, Trailer},
+        redo_target, undo_target, RestoreAction, RESTORE_ACTION_TRAILER,
+    };
+
+    fn snapshot(id: u8, operation: OperationKind, action: Option<RestoreAction>) -> Snapshot {
+        let mut details = SnapshotDetails::new(operation);
+        if let Some(action) = action {
+            details.trailers.push(Trailer {
+                key: RESTORE_ACTION_TRAILER.to_owned(),
+                value: match action {
+                    RestoreAction::Undo => "undo",
+                    RestoreAction::Redo => "redo",
+                }
+                .to_owned(),
+            });
+        }
+        Snapshot {
+            commit_id: git2::Oid::from_bytes(&[id; 20]).unwrap(),
+            created_at: git2::Time::new(i64::from(id), 0),
+            details: Some(details),
+        }
+    }
+
+    fn op(id: u8) -> Snapshot {
+        snapshot(id, OperationKind::CreateCommit, None)
+    }
+
+    fn restore(id: u8, action: Option<RestoreAction>) -> Snapshot {
+        snapshot(id, OperationKind::RestoreFromSnapshot, action)
+    }
+
+    fn id(snapshot: Option<&Snapshot>) -> Option<u8> {
+        snapshot.map(|s| s.commit_id.as_bytes()[0])
+    }
+
+    #[test]
+    fn undo_restores_the_snapshot_before_the_last_operation() {
+        let history = [op(2), op(1)];
+        assert_eq!(id(undo_target(&history)), Some(2));
+        assert_eq!(id(redo_target(&history)), None, "nothing was undone yet");
+    }
+
+    #[test]
+    fn repeated_undo_steps_further_back() {
+        let history = [restore(3, Some(RestoreAction::Undo)), op(2), op(1)];
+        assert_eq!(id(undo_target(&history)), Some(1));
+
+        let history = [
+            restore(4, Some(RestoreAction::Undo)),
+            restore(3, Some(RestoreAction::Undo)),
+            op(2),
+            op(1),
+        ];
+        assert_eq!(id(undo_target(&history)), None, "everything was undone");
+        assert_eq!(
+            id(redo_target(&history)),
+            Some(4),
+            "the last undo is redone first"
+        );
+    }
+
+    #[test]
+    fn redo_walks_forward_again() {
+        let history = [
+            restore(5, Some(RestoreAction::Redo)),
+            restore(4, Some(RestoreAction::Undo)),
+            restore(3, Some(RestoreAction::Undo)),
+            op(2),
+            op(1),
+        ];
+        assert_eq!(id(redo_target(&history)), Some(3));
+        assert_eq!(
+            id(undo_target(&history)),
+            Some(5),
+            "undoing the redo restores the state before it"
+        );
+
+        let history = [
+            restore(6, Some(RestoreAction::Redo)),
+            restore(5, Some(RestoreAction::Redo)),
+            restore(4, Some(RestoreAction::Undo)),
+            restore(3, Some(RestoreAction::Undo)),
+            op(2),
+            op(1),
+        ];
+        assert_eq!(id(redo_target(&history)), None, "everything was redone");
+    }
+
+    #[test]
+    fn new_operations_discard_what_could_be_redone() {
+        let history = [op(4), restore(3, Some(RestoreAction::Undo)), op(2), op(1)];
+        assert_eq!(id(redo_target(&history)), None);
+        assert_eq!(id(undo_target(&history)), Some(4));
+
+        let history = [
+            restore(5, Some(RestoreAction::Undo)),
+            op(4),
+            restore(3, Some(RestoreAction::Undo)),
+            op(2),
+            op(1),
+        ];
+        assert_eq!(
+            id(undo_target(&history)),
+            Some(1),
+            "the operation undone before is skipped"
+        );
+    }
+
+    #[test]
+    fn plain_restores_are_operations_like_any_other() {
+        let history = [restore(3, None), op(2), op(1)];
+        assert_eq!(id(undo_target(&history)), Some(3));
+        assert_eq!(id(redo_target(&history)), None);
+    }
+}
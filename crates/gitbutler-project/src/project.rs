@@ -72,6 +72,32 @@ pub struct CodePushState {
 
 pub type ProjectId = Id<Project>;
 
+/// Determines which snapshots of the operations log are kept when it's compacted.
+///
+/// Snapshots are thinned out with their age: all recent snapshots are kept, then one per hour,
+/// and beyond that only one per day.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OplogRetention {
+    /// All snapshots younger than this many days are kept.
+    pub keep_all_days: u32,
+    /// Snapshots younger than this many days, but older than `keep_all_days`, are thinned to one per hour.
+    /// Older snapshots are thinned to one per day.
+    pub keep_hourly_days: u32,
+    /// The amount of most recent snapshots to keep, no matter their age.
+    pub keep_latest: usize,
+}
+
+impl Default for OplogRetention {
+    fn default() -> Self {
+        OplogRetention {
+            keep_all_days: 7,
+            keep_hourly_days: 30,
+            keep_latest: 100,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct Project {
     pub id: ProjectId,
@@ -103,6 +129,9 @@ pub struct Project {
     pub snapshot_lines_threshold: Option<usize>,
     #[serde(default)]
     pub forge_override: Option<String>,
+    /// The policy to apply when compacting the operations log, or `None` to use the default.
+    #[serde(default)]
+    pub oplog_retention: Option<OplogRetention>,
 }
 
 /// Instantiation
@@ -178,6 +207,10 @@ impl Project {
         self.snapshot_lines_threshold.unwrap_or(20)
     }
 
+    pub fn oplog_retention(&self) -> OplogRetention {
+        self.oplog_retention.unwrap_or_default()
+    }
+
     pub fn worktree_path(&self) -> PathBuf {
         self.path.clone()
     }
@@ -3,7 +3,7 @@ use std::path::PathBuf;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::{ApiProject, AuthKey, CodePushState, FetchResult, Project, ProjectId};
+use crate::{ApiProject, AuthKey, CodePushState, FetchResult, OplogRetention, Project, ProjectId};
 
 const PROJECTS_FILE: &str = "projects.json";
 
@@ -30,6 +30,7 @@ pub struct UpdateRequest {
     pub omit_certificate_check: Option<bool>,
     pub use_diff_context: Option<bool>,
     pub snapshot_lines_threshold: Option<usize>,
+    pub oplog_retention: Option<OplogRetention>,
     pub forge_override: Option<String>,
     #[serde(default = "default_false")]
     pub unset_forge_override: bool,
@@ -149,6 +150,10 @@ impl Storage {
             project.snapshot_lines_threshold = Some(snapshot_lines_threshold);
         }
 
+        if let Some(oplog_retention) = update_request.oplog_retention {
+            project.oplog_retention = Some(oplog_retention);
+        }
+
         self.inner
             .write(PROJECTS_FILE, &serde_json::to_string_pretty(&projects)?)?;
 
@@ -17,6 +17,7 @@ use gitbutler_repo::{
 };
 pub trait RepoActionsExt {
     fn fetch(&self, remote_name: &str, askpass: Option<String>) -> Result<()>;
+    /// Push `head` to `branch` and return the messages the remote sent while doing so.
     fn push(
         &self,
         head: git2::Oid,
@@ -25,7 +26,7 @@ pub trait RepoActionsExt {
         force_push_protection: bool,
         refspec: Option<String>,
         askpass_broker: Option<Option<StackId>>,
-    ) -> Result<()>;
+    ) -> Result<String>;
     fn commit(
         &self,
         message: &str,
@@ -67,13 +68,13 @@ impl RepoActionsExt for CommandContext {
             RemoteRefname::from_str(&format!("refs/remotes/{remote_name}/{branch_name}",))?;
 
         match self.push(commit_id, &refname, false, false, None, askpass) {
-            Ok(()) => Ok(()),
+            Ok(_) => Ok(()),
             Err(e) => Err(anyhow::anyhow!(e.to_string())),
         }?;
 
         let empty_refspec = Some(format!(":refs/heads/{branch_name}"));
         match self.push(commit_id, &refname, false, false, empty_refspec, askpass) {
-            Ok(()) => Ok(()),
+            Ok(_) => Ok(()),
             Err(e) => Err(anyhow::anyhow!(e.to_string())),
         }?;
 
@@ -163,7 +164,7 @@ impl RepoActionsExt for CommandContext {
         force_push_protection: bool,
         refspec: Option<String>,
         askpass_broker: Option<Option<StackId>>,
-    ) -> Result<()> {
+    ) -> Result<String> {
         let use_git_executable = self.project().preferred_key == AuthKey::SystemExecutable;
         if !use_git_executable && force_push_protection {
             bail!("Force push protection is only supported when 'Using the Git executable'");
@@ -218,12 +219,22 @@ impl RepoActionsExt for CommandContext {
             for (mut remote, callbacks) in auth_flows {
                 let mut update_refs_error: Option<git2::Error> = None;
                 for callback in callbacks {
+                    // Only the messages of the attempt that succeeds are of interest.
+                    let mut remote_output = String::new();
                     let mut cbs: git2::RemoteCallbacks = callback.into();
                     if self.project().omit_certificate_check.unwrap_or(false) {
                         cbs.certificate_check(|_, _| {
                             Ok(git2::CertificateCheckStatus::CertificateOk)
                         });
                     }
+                    cbs.sideband_progress(|data| {
+                        for line in String::from_utf8_lossy(data).lines() {
+                            remote_output.push_str("remote: ");
+                            remote_output.push_str(line);
+                            remote_output.push('\n');
+                        }
+                        true
+                    });
                     cbs.push_update_reference(|_reference: &str, status: Option<&str>| {
                         if let Some(status) = status {
                             update_refs_error = Some(git2::Error::from_str(status));
@@ -245,7 +256,7 @@ impl RepoActionsExt for CommandContext {
                                 branch = branch.branch(),
                                 "pushed git branch"
                             );
-                            return Ok(());
+                            return Ok(remote_output);
                         }
                         Err(err) => match err.class() {
                             git2::ErrorClass::Net | git2::ErrorClass::Http => {
@@ -7,6 +7,7 @@ use gitbutler_cherry_pick::{ConflictedTreeKey, RepositoryExt};
 use gitbutler_command_context::gix_repo_for_merging;
 use gitbutler_commit::commit_headers::CommitHeadersV2;
 use gitbutler_oxidize::{GixRepositoryExt as _, ObjectIdExt as _, OidExt as _};
+use gix::prelude::ObjectIdExt as _;
 
 fn extract_conflicted_files(
     merged_tree_id: gix::Id<'_>,
@@ -74,6 +75,44 @@ fn extract_conflicted_files(
     Ok(out)
 }
 
+/// Merge the trees of `target_commit` and `incoming_commit` in `gix_repo` like [`merge_commits()`] does, but
+/// without creating the merge commit, and return the id of the merged tree along with the entries that
+/// are conflicted in it, if there are any.
+///
+/// If there is a merge conflict, we will **auto-resolve** to favor *our* side, the `incoming_commit`.
+pub fn merge_commit_trees(
+    gix_repo: &gix::Repository,
+    target_commit: gix::ObjectId,
+    incoming_commit: gix::ObjectId,
+) -> Result<(gix::ObjectId, Option<ConflictEntries>)> {
+    let merge_base = gix_repo.merge_base(target_commit, incoming_commit)?;
+    let real_tree = |id: gix::ObjectId| -> Result<gix::ObjectId> {
+        Ok(but_core::Commit::from_id(id.attach(gix_repo))?
+            .tree_id_or_auto_resolution()?
+            .detach())
+    };
+    let mut merge_result = gix_repo.merge_trees(
+        real_tree(merge_base.detach())?,
+        real_tree(incoming_commit)?,
+        real_tree(target_commit)?,
+        gix_repo.default_merge_labels(),
+        gix_repo.merge_options_force_ours()?,
+    )?;
+    let merged_tree_id = merge_result.tree.write()?;
+
+    let forced_resolution = gix::merge::tree::TreatAsUnresolved::forced_resolution();
+    let conflicted_files = if merge_result.has_unresolved_conflicts(forced_resolution) {
+        Some(extract_conflicted_files(
+            merged_tree_id,
+            merge_result,
+            forced_resolution,
+        )?)
+    } else {
+        None
+    };
+    Ok((merged_tree_id.detach(), conflicted_files))
+}
+
 /// Merge two commits together
 ///
 /// The `target_commit` and `incoming_commit` must have a common ancestor.
@@ -97,24 +136,15 @@ pub fn merge_commits(
     let target_tree = repo.find_real_tree(&target_commit, ConflictedTreeKey::Theirs)?;
     let incoming_tree = repo.find_real_tree(&incoming_commit, ConflictedTreeKey::Ours)?;
 
-    let target_merge_tree = repo.find_real_tree(&target_commit, Default::default())?;
-    let incoming_merge_tree = repo.find_real_tree(&incoming_commit, Default::default())?;
     let gix_repo = gix_repo_for_merging(repo.path())?;
-    let mut merge_result = gix_repo.merge_trees(
-        base_tree.id().to_gix(),
-        incoming_merge_tree.id().to_gix(),
-        target_merge_tree.id().to_gix(),
-        gix_repo.default_merge_labels(),
-        gix_repo.merge_options_force_ours()?,
+    let (merged_tree_id, conflicted_files) = merge_commit_trees(
+        &gix_repo,
+        target_commit.id().to_gix(),
+        incoming_commit.id().to_gix(),
     )?;
-    let merged_tree_id = merge_result.tree.write()?;
 
     let tree_oid;
-    let forced_resolution = gix::merge::tree::TreatAsUnresolved::forced_resolution();
-    let commit_headers = if merge_result.has_unresolved_conflicts(forced_resolution) {
-        let conflicted_files =
-            extract_conflicted_files(merged_tree_id, merge_result, forced_resolution)?;
-
+    let commit_headers = if let Some(conflicted_files) = conflicted_files {
         // convert files into a string and save as a blob
         let conflicted_files_string = toml::to_string(&conflicted_files)?;
         let conflicted_files_blob = repo.blob(conflicted_files_string.as_bytes())?;
@@ -217,6 +217,7 @@ fn main() {
                     zip::commands::get_logs_archive_path,
                     zip::commands::get_project_archive_path,
                     zip::commands::get_anonymous_graph_path,
+                    zip::commands::get_oplog_bundle_path,
                     users::set_user,
                     users::delete_user,
                     users::get_user,
@@ -255,6 +256,7 @@ fn main() {
                     virtual_branches::insert_blank_commit,
                     virtual_branches::reorder_stack,
                     virtual_branches::update_commit_message,
+                    virtual_branches::acknowledge_auto_resolution,
                     virtual_branches::find_git_branches,
                     virtual_branches::list_branches,
                     virtual_branches::get_branch_listing_details,
@@ -265,6 +267,7 @@ fn main() {
                     virtual_branches::move_branch,
                     virtual_branches::normalize_branch_name,
                     virtual_branches::upstream_integration_statuses,
+                    virtual_branches::upstream_integration_dry_run,
                     virtual_branches::integrate_upstream,
                     virtual_branches::resolve_upstream_integration,
                     virtual_branches::find_commit,
@@ -279,8 +282,12 @@ fn main() {
                     secret::secret_get_global,
                     secret::secret_set_global,
                     undo::list_snapshots,
+                    undo::query_snapshots,
                     undo::restore_snapshot,
+                    undo::undo_operation,
+                    undo::redo_operation,
                     undo::snapshot_diff,
+                    undo::compact_oplog,
                     config::get_gb_config,
                     config::set_gb_config,
                     config::store_author_globally_if_unset,
@@ -297,6 +304,10 @@ fn main() {
                     modes::abort_edit_and_return_to_workspace,
                     modes::edit_initial_index_state,
                     modes::edit_changes_from_initial,
+                    modes::edit_conflicted_paths,
+                    modes::edit_take_conflict_side,
+                    modes::edit_write_conflict_markers,
+                    modes::finish_edit_resolution_and_return_to_workspace,
                     open::open_url,
                     open::show_in_finder,
                     forge::pr_templates,
@@ -309,6 +320,7 @@ fn main() {
                     settings::update_claude,
                     settings::update_fetch,
                     settings::update_reviews,
+                    settings::update_hunk_dependencies,
                     action::list_actions,
                     action::handle_changes,
                     action::list_workflows,
@@ -341,6 +353,7 @@ fn main() {
                     diff::commit_details,
                     diff::changes_in_branch,
                     diff::tree_change_diffs,
+                    diff::explain_hunk_locks,
                     diff::assign_hunk,
                     // Debug-only - not for production!
                     #[cfg(debug_assertions)]
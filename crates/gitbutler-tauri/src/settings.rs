@@ -1,7 +1,8 @@
 #![allow(deprecated)]
 use but_api::commands::settings;
 use but_settings::api::{
-    ClaudeUpdate, FeatureFlagsUpdate, FetchUpdate, ReviewsUpdate, TelemetryUpdate,
+    ClaudeUpdate, FeatureFlagsUpdate, FetchUpdate, HunkDependenciesUpdate, ReviewsUpdate,
+    TelemetryUpdate,
 };
 use but_settings::AppSettingsWithDiskSync;
 use tauri::State;
@@ -83,3 +84,15 @@ pub fn update_reviews(
 ) -> Result<(), Error> {
     settings::update_reviews(&app_settings_sync, settings::UpdateReviewsParams { update })
 }
+
+#[tauri::command(async)]
+#[instrument(skip(app_settings_sync), err(Debug))]
+pub fn update_hunk_dependencies(
+    app_settings_sync: State<'_, AppSettingsWithDiskSync>,
+    update: HunkDependenciesUpdate,
+) -> Result<(), Error> {
+    settings::update_hunk_dependencies(
+        &app_settings_sync,
+        settings::UpdateHunkDependenciesParams { update },
+    )
+}
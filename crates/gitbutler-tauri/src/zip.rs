@@ -1,7 +1,8 @@
 pub mod commands {
     #![allow(clippy::used_underscore_binding)]
     use but_api::commands::zip::{
-        self, GetAnonymousGraphPathParams, GetLogsArchivePathParams, GetProjectArchivePathParams,
+        self, GetAnonymousGraphPathParams, GetLogsArchivePathParams, GetOplogBundlePathParams,
+        GetProjectArchivePathParams,
     };
     use but_api::error::Error;
     use std::path::PathBuf;
@@ -36,6 +37,20 @@ pub mod commands {
         )
     }
 
+    #[tauri::command(async)]
+    #[instrument(skip(app), err(Debug))]
+    pub fn get_oplog_bundle_path(
+        app: State<'_, but_api::App>,
+        project_id: &str,
+    ) -> Result<PathBuf, Error> {
+        zip::get_oplog_bundle_path(
+            &app,
+            GetOplogBundlePathParams {
+                project_id: project_id.to_string(),
+            },
+        )
+    }
+
     #[tauri::command(async)]
     #[instrument(skip(app), err(Debug))]
     pub fn get_logs_archive_path(app: State<'_, but_api::App>) -> Result<PathBuf, Error> {
@@ -85,6 +85,7 @@ impl Handler {
             &ctx.project().path,
             &ctx.project().gb_dir(),
             Some(wt_changes.changes.clone()),
+            ctx.app_settings().hunk_dependencies.into(),
         );
 
         let (assignments, assignments_error) =